@@ -0,0 +1,235 @@
+//! Screen-following ambient lighting.
+//!
+//! The application samples its screen (or video) and supplies one dominant
+//! color per region, e.g. `"left"`, `"top"`, `"right"`. [`Ambilight`]
+//! corrects each color for the capture's white point, smooths it over time to
+//! avoid flicker, and sends it to the lights assigned to that region through a
+//! [`RateLimiter`] so the bulbs are never flooded.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::ambilight::{Ambilight, AmbilightConfig};
+//!
+//! let mut ambilight = Ambilight::new(AmbilightConfig::default());
+//! ambilight.assign("left", left_lamp);
+//! ambilight.assign("right", right_lamp);
+//!
+//! // frames: impl Stream<Item = Vec<(String, Color)>>
+//! ambilight.run(frames).await;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt, future};
+use log::debug;
+
+use crate::light::Light;
+use crate::payload::Payload;
+use crate::ratelimit::RateLimiter;
+use crate::types::Color;
+
+/// Tuning for [`Ambilight`].
+#[derive(Debug, Clone)]
+pub struct AmbilightConfig {
+    /// Weight of the previous color when blending in a new one, in
+    /// `0.0..1.0`. Higher values are smoother but slower to react.
+    pub smoothing: f32,
+    /// The color the capture reports for pure white. Channels are scaled so
+    /// this color maps to full white on the bulbs.
+    pub white_point: Color,
+    /// Skip updates where no channel moved by more than this amount.
+    pub threshold: u8,
+    /// Upper bound on updates per second sent to each light.
+    pub max_updates_per_second: f64,
+}
+
+impl Default for AmbilightConfig {
+    fn default() -> Self {
+        AmbilightConfig {
+            smoothing: 0.6,
+            white_point: Color::rgb(255, 255, 255),
+            threshold: 4,
+            max_updates_per_second: RateLimiter::DEFAULT_MESSAGES_PER_SECOND,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Region {
+    lights: Vec<Light>,
+    smoothed: Option<[f32; 3]>,
+    sent: Option<Color>,
+}
+
+/// Maps per-region screen colors onto assigned lights.
+pub struct Ambilight {
+    config: AmbilightConfig,
+    regions: HashMap<String, Region>,
+}
+
+impl Ambilight {
+    pub fn new(config: AmbilightConfig) -> Self {
+        Ambilight {
+            config,
+            regions: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &AmbilightConfig {
+        &self.config
+    }
+
+    /// Assign a light to a screen region.
+    ///
+    /// Lights without a rate limiter get one based on
+    /// [`AmbilightConfig::max_updates_per_second`].
+    pub fn assign(&mut self, region: &str, mut light: Light) {
+        if light.rate_limiter().is_none() {
+            light.set_rate_limiter(Some(Arc::new(RateLimiter::new(
+                self.config.max_updates_per_second,
+            ))));
+        }
+        self.regions
+            .entry(region.to_string())
+            .or_default()
+            .lights
+            .push(light);
+    }
+
+    pub fn regions(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(String::as_str)
+    }
+
+    /// Blend `color` into the region's state.
+    ///
+    /// Returns the color to send, or `None` if the region is unknown or the
+    /// change is below the threshold.
+    pub fn update(&mut self, region: &str, color: &Color) -> Option<Color> {
+        let config = &self.config;
+        let region = self.regions.get_mut(region)?;
+
+        let target = correct_white_point(color, &config.white_point);
+        let smoothing = config.smoothing.clamp(0.0, 0.99);
+        let smoothed = match region.smoothed {
+            Some(prev) => {
+                std::array::from_fn(|i| prev[i] * smoothing + target[i] * (1.0 - smoothing))
+            }
+            None => target,
+        };
+        region.smoothed = Some(smoothed);
+
+        let out = Color::rgb(
+            smoothed[0].round() as u8,
+            smoothed[1].round() as u8,
+            smoothed[2].round() as u8,
+        );
+        let changed = region.sent.as_ref().is_none_or(|sent| {
+            sent.red().abs_diff(out.red()) > config.threshold
+                || sent.green().abs_diff(out.green()) > config.threshold
+                || sent.blue().abs_diff(out.blue()) > config.threshold
+        });
+        if !changed {
+            return None;
+        }
+        region.sent = Some(out.clone());
+        Some(out)
+    }
+
+    /// Update a region and send the result to its lights.
+    pub async fn apply(&mut self, region: &str, color: &Color) {
+        let Some(color) = self.update(region, color) else {
+            return;
+        };
+        let Some(region) = self.regions.get(region) else {
+            return;
+        };
+
+        let payload = Payload::from(&color);
+        let results = future::join_all(
+            region
+                .lights
+                .iter()
+                .map(|light| light.set_coalesced(&payload)),
+        )
+        .await;
+        for (light, result) in region.lights.iter().zip(results) {
+            if let Err(e) = result {
+                debug!("Ambilight update for {} failed: {}", light.ip(), e);
+            }
+        }
+    }
+
+    /// Apply every frame from `frames` until the stream ends.
+    ///
+    /// Each frame lists the dominant color of one or more regions.
+    pub async fn run<S>(&mut self, frames: S)
+    where
+        S: Stream<Item = Vec<(String, Color)>>,
+    {
+        let mut frames = std::pin::pin!(frames);
+        while let Some(frame) = frames.next().await {
+            for (region, color) in frame {
+                self.apply(&region, &color).await;
+            }
+        }
+    }
+}
+
+fn correct_white_point(color: &Color, white_point: &Color) -> [f32; 3] {
+    let channel = |value: u8, white: u8| {
+        let white = f32::from(white.max(1));
+        (f32::from(value) * 255.0 / white).min(255.0)
+    };
+    [
+        channel(color.red(), white_point.red()),
+        channel(color.green(), white_point.green()),
+        channel(color.blue(), white_point.blue()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn ambilight(config: AmbilightConfig) -> Ambilight {
+        let mut ambilight = Ambilight::new(config);
+        ambilight.assign("left", Light::new(Ipv4Addr::new(192, 168, 1, 10), None));
+        ambilight
+    }
+
+    #[test]
+    fn test_smooths_and_thresholds() {
+        let mut ambilight = ambilight(AmbilightConfig {
+            smoothing: 0.5,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            ambilight.update("left", &Color::rgb(0, 0, 0)),
+            Some(Color::rgb(0, 0, 0))
+        );
+        assert_eq!(
+            ambilight.update("left", &Color::rgb(200, 0, 0)),
+            Some(Color::rgb(100, 0, 0))
+        );
+        // 100 -> 102 is below the threshold
+        assert_eq!(ambilight.update("left", &Color::rgb(104, 0, 0)), None);
+        assert_eq!(ambilight.update("right", &Color::rgb(0, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_white_point_correction() {
+        let mut ambilight = ambilight(AmbilightConfig {
+            smoothing: 0.0,
+            white_point: Color::rgb(255, 240, 200),
+            ..Default::default()
+        });
+        assert_eq!(
+            ambilight.update("left", &Color::rgb(255, 240, 200)),
+            Some(Color::rgb(255, 255, 255))
+        );
+    }
+}
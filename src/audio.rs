@@ -0,0 +1,314 @@
+//! Music synchronisation from user-supplied audio.
+//!
+//! The crate does not capture audio itself. Feed PCM samples or FFT
+//! magnitudes from whatever source the application uses into an
+//! [`AudioSync`]; it tracks loudness and detects beats, then maps them to
+//! brightness and color pulses on a group of lights.
+//!
+//! Requires the `audio-sync` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::audio::{AudioSync, AudioSyncConfig};
+//!
+//! let mut sync = AudioSync::new(lights, AudioSyncConfig::default());
+//! while let Some(samples) = capture.next_block().await {
+//!     sync.feed_samples(&samples).await;
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::debug;
+
+use crate::cancel::CancelToken;
+use crate::light::Light;
+use crate::payload::Payload;
+use crate::runtime::{self, JoinHandle};
+use crate::types::{Brightness, Color};
+
+/// Loudness and beat information for one block of audio.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AudioAnalysis {
+    /// Loudness relative to the recent peak, in `0.0..=1.0`.
+    pub level: f32,
+    /// Whether this block is markedly louder than the recent average.
+    pub beat: bool,
+}
+
+/// Energy-based beat detector.
+///
+/// Each block's energy is compared against a rolling average of the
+/// previous blocks; a block more than `sensitivity` times louder is a beat.
+#[derive(Debug, Clone)]
+pub struct BeatDetector {
+    history: VecDeque<f32>,
+    history_len: usize,
+    sensitivity: f32,
+    peak: f32,
+}
+
+impl Default for BeatDetector {
+    fn default() -> Self {
+        Self::new(1.4)
+    }
+}
+
+impl BeatDetector {
+    /// Roughly one second of history at typical block sizes.
+    const HISTORY_LEN: usize = 43;
+    /// How quickly the loudness reference forgets an old peak, per block.
+    const PEAK_DECAY: f32 = 0.995;
+
+    pub fn new(sensitivity: f32) -> Self {
+        BeatDetector {
+            history: VecDeque::with_capacity(Self::HISTORY_LEN),
+            history_len: Self::HISTORY_LEN,
+            sensitivity,
+            peak: 0.0,
+        }
+    }
+
+    /// Analyse a block of PCM samples in `-1.0..=1.0`.
+    pub fn process_samples(&mut self, samples: &[f32]) -> AudioAnalysis {
+        if samples.is_empty() {
+            return AudioAnalysis::default();
+        }
+        let energy = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+        self.process_energy(energy.sqrt())
+    }
+
+    /// Analyse one frame of FFT magnitudes, ordered from low to high frequency.
+    ///
+    /// Only the lowest quarter of the bands is used, where kick drums and bass
+    /// lines carry the beat.
+    pub fn process_spectrum(&mut self, magnitudes: &[f32]) -> AudioAnalysis {
+        let bass = &magnitudes[..magnitudes.len().div_ceil(4)];
+        if bass.is_empty() {
+            return AudioAnalysis::default();
+        }
+        self.process_energy(bass.iter().sum::<f32>() / bass.len() as f32)
+    }
+
+    fn process_energy(&mut self, energy: f32) -> AudioAnalysis {
+        let average = if self.history.is_empty() {
+            energy
+        } else {
+            self.history.iter().sum::<f32>() / self.history.len() as f32
+        };
+        let beat = self.history.len() >= self.history_len / 4
+            && energy > average * self.sensitivity
+            && energy > f32::EPSILON;
+
+        if self.history.len() == self.history_len {
+            self.history.pop_front();
+        }
+        self.history.push_back(energy);
+
+        self.peak = (self.peak * Self::PEAK_DECAY).max(energy);
+        let level = if self.peak > f32::EPSILON {
+            (energy / self.peak).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        AudioAnalysis { level, beat }
+    }
+}
+
+/// How audio analysis is mapped onto the lights.
+#[derive(Debug, Clone)]
+pub struct AudioSyncConfig {
+    /// Dimming at silence. The two bounds may be given in either order.
+    pub min_brightness: Brightness,
+    /// Dimming at full level and on beats.
+    pub max_brightness: Brightness,
+    /// Colors stepped through on every detected beat.
+    pub palette: Vec<Color>,
+    /// See [`BeatDetector::new`].
+    pub sensitivity: f32,
+}
+
+impl Default for AudioSyncConfig {
+    fn default() -> Self {
+        AudioSyncConfig {
+            min_brightness: Brightness::create_or(10),
+            max_brightness: Brightness::create_or(100),
+            palette: vec![
+                Color::rgb(255, 0, 0),
+                Color::rgb(255, 0, 255),
+                Color::rgb(0, 0, 255),
+                Color::rgb(0, 255, 255),
+            ],
+            sensitivity: 1.4,
+        }
+    }
+}
+
+/// Drives a group of lights from an audio feed.
+///
+/// Updates are sent in the background with [`Light::set_coalesced`], so
+/// feeding audio never waits for the bulbs: a light that is still busy with
+/// an earlier frame only gets the latest one once it is done. Attach a
+/// [`RateLimiter`](crate::RateLimiter) to the lights to pace them further.
+///
+/// Dropping the `AudioSync` cancels the frames still being sent, whatever
+/// the runtime.
+pub struct AudioSync {
+    lights: Vec<Light>,
+    config: AudioSyncConfig,
+    detector: BeatDetector,
+    color_index: usize,
+    /// Frames being sent, with a flag set once each is done.
+    sending: Vec<(Arc<AtomicBool>, JoinHandle<()>)>,
+    /// Stops frames on runtimes where aborting a task is a no-op.
+    cancel: CancelToken,
+}
+
+impl AudioSync {
+    pub fn new(lights: Vec<Light>, config: AudioSyncConfig) -> Self {
+        AudioSync {
+            lights,
+            detector: BeatDetector::new(config.sensitivity),
+            config,
+            color_index: 0,
+            sending: Vec::new(),
+            cancel: CancelToken::new(),
+        }
+    }
+
+    pub fn config(&self) -> &AudioSyncConfig {
+        &self.config
+    }
+
+    /// Analyse PCM samples and update the lights.
+    pub async fn feed_samples(&mut self, samples: &[f32]) -> AudioAnalysis {
+        let analysis = self.detector.process_samples(samples);
+        self.apply(analysis);
+        analysis
+    }
+
+    /// Analyse FFT magnitudes and update the lights.
+    pub async fn feed_spectrum(&mut self, magnitudes: &[f32]) -> AudioAnalysis {
+        let analysis = self.detector.process_spectrum(magnitudes);
+        self.apply(analysis);
+        analysis
+    }
+
+    /// The payload the lights would receive for `analysis`.
+    ///
+    /// Advances to the next palette color on beats.
+    pub fn payload_for(&mut self, analysis: AudioAnalysis) -> Payload {
+        if analysis.beat && !self.config.palette.is_empty() {
+            self.color_index = (self.color_index + 1) % self.config.palette.len();
+        }
+
+        let (min, max) = {
+            let a = self.config.min_brightness.value();
+            let b = self.config.max_brightness.value();
+            (f32::from(a.min(b)), f32::from(a.max(b)))
+        };
+        let level = if analysis.beat { 1.0 } else { analysis.level };
+        let dimming = (min + (max - min) * level).round() as u8;
+
+        let mut payload = Payload::new();
+        payload.brightness(&Brightness::create_or(dimming));
+        if let Some(color) = self.config.palette.get(self.color_index) {
+            payload.color(color);
+        }
+        payload
+    }
+
+    fn apply(&mut self, analysis: AudioAnalysis) {
+        let payload = self.payload_for(analysis);
+        self.sending
+            .retain(|(done, _)| !done.load(Ordering::SeqCst));
+        for light in &self.lights {
+            // A shared handle shares the coalescer, so a busy light keeps
+            // only the newest frame and the extra task returns right away
+            let light = light.share();
+            let payload = payload.clone();
+            let done = Arc::new(AtomicBool::new(false));
+            let task_done = Arc::clone(&done);
+            let cancel = self.cancel.clone();
+            let handle = runtime::spawn(async move {
+                if let Ok(Err(e)) = cancel.run(light.set_coalesced(&payload)).await {
+                    debug!("Audio frame for {} failed: {}", light.ip(), e);
+                }
+                task_done.store(true, Ordering::SeqCst);
+            });
+            self.sending.push((done, handle));
+        }
+    }
+}
+
+impl Drop for AudioSync {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        for (_, handle) in &self.sending {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_beat_after_quiet_blocks() {
+        let mut detector = BeatDetector::default();
+        let quiet = vec![0.05f32; 512];
+        let loud = vec![0.8f32; 512];
+
+        for _ in 0..20 {
+            assert!(!detector.process_samples(&quiet).beat);
+        }
+        let analysis = detector.process_samples(&loud);
+        assert!(analysis.beat);
+        assert_eq!(analysis.level, 1.0);
+    }
+
+    #[test]
+    fn test_payload_follows_level_and_beats() {
+        let mut sync = AudioSync::new(Vec::new(), AudioSyncConfig::default());
+
+        let payload = sync.payload_for(AudioAnalysis {
+            level: 0.5,
+            beat: false,
+        });
+        assert_eq!(payload.dimming, Some(55));
+        assert_eq!(payload.red, Some(255));
+        assert_eq!(payload.blue, Some(0));
+
+        let payload = sync.payload_for(AudioAnalysis {
+            level: 0.2,
+            beat: true,
+        });
+        assert_eq!(payload.dimming, Some(100));
+        assert_eq!(payload.blue, Some(255));
+    }
+
+    #[test]
+    fn test_payload_orders_brightness_bounds() {
+        let config = AudioSyncConfig {
+            min_brightness: Brightness::create_or(80),
+            max_brightness: Brightness::create_or(20),
+            ..AudioSyncConfig::default()
+        };
+        let mut sync = AudioSync::new(Vec::new(), config);
+
+        let quiet = AudioAnalysis {
+            level: 0.0,
+            beat: false,
+        };
+        assert_eq!(sync.payload_for(quiet).dimming, Some(20));
+        let loud = AudioAnalysis {
+            level: 1.0,
+            beat: false,
+        };
+        assert_eq!(sync.payload_for(loud).dimming, Some(80));
+    }
+}
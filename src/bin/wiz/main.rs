@@ -0,0 +1,757 @@
+//! Command-line interface for controlling Wiz lights.
+//!
+//! Install with: cargo install wiz-lights-rs --features cli
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{Value, json};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use wiz_lights_rs::{
+    Brightness, Color, FanDirection, FanMode, FanSpeed, Home, Kelvin, Light, LightStatus, Payload,
+    PowerMode, Preset, SceneMode, Snapshot, discover_bulbs, push::PushManager,
+};
+
+#[cfg(feature = "tui")]
+mod tui;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(name = "wiz", version)]
+#[command(about = "Control Wiz smart lights from the command line", long_about = None)]
+struct Cli {
+    /// IP address of the Wiz light (not required for discover, rooms and scenes)
+    #[arg(short, long, global = true)]
+    ip: Option<Ipv4Addr>,
+
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// File presets are stored in (default: ~/.config/wiz/presets.json)
+    #[arg(long, global = true)]
+    presets: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Discover all Wiz lights on the network
+    Discover {
+        /// Discovery timeout in seconds (default: 5)
+        #[arg(short, long, default_value = "5")]
+        timeout: u64,
+    },
+
+    /// List rooms as configured in the WiZ app
+    Rooms {
+        /// Discovery timeout in seconds (default: 5)
+        #[arg(short, long, default_value = "5")]
+        timeout: u64,
+    },
+
+    /// Control every light in a room
+    Room {
+        /// Room id, as shown by `wiz rooms`
+        id: u64,
+
+        /// Discovery timeout in seconds (default: 5)
+        #[arg(short, long, default_value = "5")]
+        timeout: u64,
+
+        #[command(subcommand)]
+        action: RoomAction,
+    },
+
+    /// List the available scenes (only those the light supports with --ip)
+    Scenes,
+
+    #[command(flatten)]
+    Light(LightAction),
+
+    /// Control the fan of a fan-equipped fixture
+    Fan {
+        #[command(subcommand)]
+        action: FanAction,
+    },
+
+    /// Save and apply named presets
+    Preset {
+        #[command(subcommand)]
+        action: PresetAction,
+    },
+
+    /// Run a command after a delay, optionally repeating it
+    Schedule {
+        /// Delay before the first run (e.g. 90, 30s, 15m, 2h)
+        #[arg(long, value_parser = parse_duration)]
+        after: Option<Duration>,
+
+        /// Repeat at this interval until interrupted
+        #[arg(long, value_parser = parse_duration)]
+        every: Option<Duration>,
+
+        #[command(subcommand)]
+        action: LightAction,
+    },
+
+    /// Get detailed diagnostics
+    Diagnostics,
+
+    /// Interactive dashboard of every light on the network
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Discovery timeout in seconds (default: 5)
+        #[arg(short, long, default_value = "5")]
+        timeout: u64,
+
+        /// Local IP address for live push updates (IP of this machine on the network)
+        #[arg(short, long)]
+        local_ip: Option<Ipv4Addr>,
+    },
+
+    /// Listen for push notifications from a light
+    Listen {
+        /// Local IP address for registration (IP of this machine on the network)
+        #[arg(short, long)]
+        local_ip: Ipv4Addr,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum LightAction {
+    /// Get the current status of the light
+    Status,
+
+    /// Turn the light on
+    On,
+
+    /// Turn the light off
+    Off,
+
+    /// Toggle the light on/off
+    Toggle,
+
+    /// Set RGB color (0-255 for each component)
+    Color {
+        /// Red component (0-255)
+        red: u8,
+        /// Green component (0-255)
+        green: u8,
+        /// Blue component (0-255)
+        blue: u8,
+    },
+
+    /// Set brightness (10-100)
+    Brightness {
+        /// Brightness level (10-100)
+        #[arg(value_parser = clap::value_parser!(u8).range(10..=100))]
+        level: u8,
+    },
+
+    /// Set color temperature in Kelvin (1000-8000)
+    Temperature {
+        /// Temperature in Kelvin (1000-8000)
+        #[arg(value_parser = clap::value_parser!(u16).range(1000..=8000))]
+        kelvin: u16,
+    },
+
+    /// Set a preset scene (see `wiz scenes`)
+    Scene {
+        /// Scene name or id (e.g., Ocean, Romance, Sunset, Party, etc.)
+        scene: String,
+    },
+
+    /// Apply a saved preset
+    Apply {
+        /// Preset name
+        name: String,
+    },
+
+    /// Reset the light
+    Reset,
+}
+
+#[derive(Subcommand)]
+enum RoomAction {
+    /// Get the status of every light in the room
+    Status,
+    /// Turn the room on
+    On,
+    /// Turn the room off
+    Off,
+    /// Set brightness (10-100)
+    Brightness {
+        #[arg(value_parser = clap::value_parser!(u8).range(10..=100))]
+        level: u8,
+    },
+    /// Set a preset scene
+    Scene { scene: String },
+    /// Apply a saved preset
+    Apply { name: String },
+}
+
+#[derive(Subcommand)]
+enum FanAction {
+    /// Turn the fan on
+    On,
+    /// Turn the fan off
+    Off,
+    /// Toggle the fan on/off
+    Toggle,
+    /// Set the fan speed (1 up to the fixture's maximum)
+    Speed { speed: u8 },
+    /// Set the fan mode
+    Mode { mode: FanModeArg },
+    /// Set the fan direction
+    Direction { direction: FanDirectionArg },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum FanModeArg {
+    Normal,
+    Breeze,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum FanDirectionArg {
+    Forward,
+    Reverse,
+}
+
+#[derive(Subcommand)]
+enum PresetAction {
+    /// List saved presets
+    List,
+    /// Save the light's current state as a preset
+    Save { name: String },
+    /// Apply a preset to the light
+    Apply { name: String },
+    /// Delete a saved preset
+    Delete { name: String },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if json {
+                println!("{}", json!({ "error": e.to_string() }));
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let out = Output { json: cli.json };
+    let presets = cli.presets.unwrap_or_else(default_presets_path);
+
+    match cli.command {
+        Commands::Discover { timeout } => {
+            out.progress(format!(
+                "Discovering Wiz lights on the network (timeout: {}s)...",
+                timeout
+            ));
+            let bulbs = discover_bulbs(Duration::from_secs(timeout)).await?;
+            let list: Vec<_> = bulbs
+                .iter()
+                .map(|bulb| json!({ "ip": bulb.ip, "mac": bulb.mac }))
+                .collect();
+            out.print(json!(list), || {
+                if bulbs.is_empty() {
+                    return "No lights found on the network.".to_string();
+                }
+                let mut text = format!("\nFound {} light(s):", bulbs.len());
+                for bulb in &bulbs {
+                    text += &format!("\n  IP: {:15}  MAC: {}", bulb.ip.to_string(), bulb.mac);
+                }
+                text
+            });
+        }
+
+        Commands::Rooms { timeout } => {
+            out.progress(format!("Discovering rooms (timeout: {}s)...", timeout));
+            let home = Home::discover(Duration::from_secs(timeout)).await?;
+            let rooms: Vec<_> = home
+                .rooms()
+                .map(|(id, room)| json!({ "id": id, "lights": room_ips(room) }))
+                .collect();
+            out.print(json!(rooms), || {
+                let mut text = String::from("Rooms:");
+                for (id, room) in home.rooms() {
+                    let ips: Vec<_> = room_ips(room).iter().map(|ip| ip.to_string()).collect();
+                    text += &format!("\n  {:>8}  {}", id, ips.join(", "));
+                }
+                if !home.unassigned().is_empty() {
+                    let ips: Vec<_> = home
+                        .unassigned()
+                        .iter()
+                        .map(|l| l.ip().to_string())
+                        .collect();
+                    text += &format!("\n  (no room)  {}", ips.join(", "));
+                }
+                text
+            });
+        }
+
+        Commands::Room {
+            id,
+            timeout,
+            action,
+        } => {
+            out.progress(format!(
+                "Discovering room {} (timeout: {}s)...",
+                id, timeout
+            ));
+            let home = Home::discover(Duration::from_secs(timeout)).await?;
+            let room = home
+                .room(id)
+                .ok_or_else(|| format!("Room {} not found", id))?;
+            let lights: Vec<&Light> = room
+                .list()
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|light_id| room.read(light_id))
+                .collect();
+
+            let action = match action {
+                RoomAction::Status => LightAction::Status,
+                RoomAction::On => LightAction::On,
+                RoomAction::Off => LightAction::Off,
+                RoomAction::Brightness { level } => LightAction::Brightness { level },
+                RoomAction::Scene { scene } => LightAction::Scene { scene },
+                RoomAction::Apply { name } => LightAction::Apply { name },
+            };
+            let results = futures::future::join_all(
+                lights
+                    .iter()
+                    .map(|light| light_action(light, &action, &presets)),
+            )
+            .await;
+
+            let mut report = Vec::new();
+            for (light, result) in lights.iter().zip(results) {
+                report.push(match result {
+                    Ok(value) => json!({ "ip": light.ip(), "result": value }),
+                    Err(e) => json!({ "ip": light.ip(), "error": e.to_string() }),
+                });
+            }
+            out.print(json!(report), || {
+                let mut text = format!("Room {} ({} light(s)):", id, lights.len());
+                for entry in &report {
+                    let outcome = match entry.get("error") {
+                        Some(e) => format!("error: {}", e.as_str().unwrap_or_default()),
+                        None => "ok".to_string(),
+                    };
+                    text += &format!(
+                        "\n  {:15}  {}",
+                        entry["ip"].as_str().unwrap_or_default(),
+                        outcome
+                    );
+                }
+                text
+            });
+        }
+
+        Commands::Scenes => {
+            let scenes = match cli.ip {
+                Some(ip) => SceneMode::supported_by(&Light::new(ip, None).get_bulb_type().await?),
+                None => SceneMode::all(),
+            };
+            let list: Vec<_> = scenes
+                .iter()
+                .map(|s| json!({ "id": s.id(), "name": s.name(), "dynamic": s.is_dynamic() }))
+                .collect();
+            out.print(json!(list), || {
+                let mut text = String::from("Scenes:");
+                for scene in &scenes {
+                    let kind = if scene.is_dynamic() {
+                        "dynamic"
+                    } else {
+                        "static"
+                    };
+                    text += &format!("\n  {:>4}  {:16} {}", scene.id(), scene.name(), kind);
+                }
+                text
+            });
+        }
+
+        Commands::Light(action) => {
+            let light = Light::new(require_ip(cli.ip)?, None);
+            let value = light_action(&light, &action, &presets).await?;
+            out.print(value.clone(), || describe(&action, &value));
+        }
+
+        Commands::Fan { action } => {
+            let light = Light::new(require_ip(cli.ip)?, None);
+            let (text, value) = match action {
+                FanAction::On => {
+                    light.fan_turn_on(None, None).await?;
+                    ("Fan turned ON", json!({ "fanState": 1 }))
+                }
+                FanAction::Off => {
+                    light.fan_turn_off().await?;
+                    ("Fan turned OFF", json!({ "fanState": 0 }))
+                }
+                FanAction::Toggle => {
+                    light.fan_toggle().await?;
+                    ("Fan toggled", json!({ "toggled": true }))
+                }
+                FanAction::Speed { speed } => {
+                    let max = light.get_fan_speed_range().await?;
+                    let speed = FanSpeed::create(speed, max).ok_or_else(|| {
+                        format!(
+                            "Invalid fan speed. Must be between 1 and {}.",
+                            max.unwrap_or(FanSpeed::DEFAULT_MAX)
+                        )
+                    })?;
+                    light.set_fan_speed(speed).await?;
+                    ("Fan speed set", json!({ "fanSpeed": speed.value() }))
+                }
+                FanAction::Mode { mode } => {
+                    let mode = match mode {
+                        FanModeArg::Normal => FanMode::Normal,
+                        FanModeArg::Breeze => FanMode::Breeze,
+                    };
+                    light.set_fan_mode(mode).await?;
+                    ("Fan mode set", json!({ "fanMode": mode as u8 }))
+                }
+                FanAction::Direction { direction } => {
+                    let direction = match direction {
+                        FanDirectionArg::Forward => FanDirection::Forward,
+                        FanDirectionArg::Reverse => FanDirection::Reverse,
+                    };
+                    light.set_fan_direction(direction).await?;
+                    ("Fan direction set", json!({ "fanRevrs": direction as u8 }))
+                }
+            };
+            out.print(value, || text.to_string());
+        }
+
+        Commands::Preset { action } => match action {
+            PresetAction::List => {
+                let saved = load_presets(&presets)?;
+                out.print(serde_json::to_value(&saved)?, || {
+                    if saved.is_empty() {
+                        return format!("No presets saved in {}", presets.display());
+                    }
+                    let mut text = String::from("Presets:");
+                    for preset in &saved {
+                        text += &format!(
+                            "\n  {:16} {}",
+                            preset.name,
+                            serde_json::to_string(&preset.payload).unwrap_or_default()
+                        );
+                    }
+                    text
+                });
+            }
+            PresetAction::Save { name } => {
+                let light = Light::new(require_ip(cli.ip)?, None);
+                let snapshot = Snapshot::capture(&light).await?;
+                let mut saved = load_presets(&presets)?;
+                saved.retain(|preset| preset.name != name);
+                let preset = Preset::new(&name, snapshot.payload);
+                let value = serde_json::to_value(&preset)?;
+                saved.push(preset);
+                save_presets(&presets, &saved)?;
+                out.print(value, || format!("Saved preset '{}'", name));
+            }
+            PresetAction::Apply { name } => {
+                let action = LightAction::Apply { name };
+                let light = Light::new(require_ip(cli.ip)?, None);
+                let value = light_action(&light, &action, &presets).await?;
+                out.print(value.clone(), || describe(&action, &value));
+            }
+            PresetAction::Delete { name } => {
+                let mut saved = load_presets(&presets)?;
+                let before = saved.len();
+                saved.retain(|preset| preset.name != name);
+                if saved.len() == before {
+                    return Err(format!("Unknown preset '{}'", name).into());
+                }
+                save_presets(&presets, &saved)?;
+                out.print(json!({ "deleted": name }), || {
+                    format!("Deleted preset '{}'", name)
+                });
+            }
+        },
+
+        Commands::Schedule {
+            after,
+            every,
+            action,
+        } => {
+            let light = Light::new(require_ip(cli.ip)?, None);
+            if let Some(delay) = after {
+                out.progress(format!("Waiting {:?}...", delay));
+                tokio::time::sleep(delay).await;
+            }
+            loop {
+                match light_action(&light, &action, &presets).await {
+                    Ok(value) => out.print(value.clone(), || describe(&action, &value)),
+                    // A repeating schedule keeps going past failed runs
+                    Err(e) if every.is_some() => out
+                        .print(json!({ "error": e.to_string() }), || {
+                            format!("Error: {}", e)
+                        }),
+                    Err(e) => return Err(e),
+                }
+                let Some(interval) = every else { break };
+                tokio::time::sleep(interval).await;
+            }
+        }
+
+        Commands::Diagnostics => {
+            let ip = require_ip(cli.ip)?;
+            out.progress(format!("Getting diagnostics for light at {}...", ip));
+            let diag = Light::new(ip, None).diagnostics().await.to_json();
+            out.print(diag.clone(), || {
+                format!(
+                    "\nDiagnostics:\n{}",
+                    serde_json::to_string_pretty(&diag).unwrap_or_default()
+                )
+            });
+        }
+
+        #[cfg(feature = "tui")]
+        Commands::Tui { timeout, local_ip } => {
+            tui::run(Duration::from_secs(timeout), local_ip).await?;
+        }
+
+        Commands::Listen { local_ip } => {
+            let ip = require_ip(cli.ip)?;
+            let light = Light::new(ip, None);
+            out.progress(format!(
+                "Setting up push notification listener for light at {}...",
+                ip
+            ));
+            out.progress(format!("Local IP: {}", local_ip));
+
+            // Get the light's MAC address first
+            let config = light.get_system_config().await?;
+            let mac = config.mac.clone();
+            out.progress(format!("Light MAC: {}\n", mac));
+
+            // Create and start push manager
+            let push_manager = PushManager::new();
+
+            // Subscribe to notifications from this light
+            let display_mac = mac.to_string();
+            push_manager
+                .subscribe(&mac, move |event| {
+                    out.print(event.raw().clone(), || {
+                        format!(
+                            "[{}] State update received:\n{}\n",
+                            display_mac,
+                            serde_json::to_string_pretty(event.raw())
+                                .unwrap_or_else(|_| format!("{:?}", event.raw()))
+                        )
+                    });
+                })
+                .await;
+
+            // Start listening for push notifications
+            push_manager.start(local_ip).await?;
+            out.progress("Push manager started on port 38900".to_string());
+
+            // Register with the bulb
+            push_manager.register_bulb(ip).await?;
+            out.progress(format!("Registered with light at {}", ip));
+            out.progress(
+                "\nListening for push notifications... (Press Ctrl+C to stop)\n".to_string(),
+            );
+
+            // Keep the program running
+            loop {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints results either as text or as one JSON document per line.
+#[derive(Clone, Copy)]
+struct Output {
+    json: bool,
+}
+
+impl Output {
+    fn print(&self, value: Value, text: impl FnOnce() -> String) {
+        if self.json {
+            println!("{}", value);
+        } else {
+            println!("{}", text());
+        }
+    }
+
+    /// Status messages are only shown in text mode, on stderr.
+    fn progress(&self, message: String) {
+        if !self.json {
+            eprintln!("{}", message);
+        }
+    }
+}
+
+fn require_ip(ip: Option<Ipv4Addr>) -> Result<Ipv4Addr> {
+    ip.ok_or_else(|| "IP address is required for this command. Use --ip <IP>".into())
+}
+
+fn room_ips(room: &wiz_lights_rs::Room) -> Vec<Ipv4Addr> {
+    let mut ips: Vec<_> = room
+        .list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|id| room.read(id).map(Light::ip))
+        .collect();
+    ips.sort();
+    ips
+}
+
+/// Runs a single-light action and returns its result as JSON.
+async fn light_action(light: &Light, action: &LightAction, presets: &Path) -> Result<Value> {
+    let payload = match action {
+        LightAction::Status => return Ok(status_json(&light.get_status().await?)),
+        LightAction::On => {
+            light.set_power(&PowerMode::On).await?;
+            return Ok(json!({ "state": true }));
+        }
+        LightAction::Off => {
+            light.set_power(&PowerMode::Off).await?;
+            return Ok(json!({ "state": false }));
+        }
+        LightAction::Toggle => {
+            light.toggle().await?;
+            return Ok(json!({ "toggled": true }));
+        }
+        LightAction::Reset => {
+            light.reset().await?;
+            return Ok(json!({ "reset": true }));
+        }
+        LightAction::Apply { name } => {
+            let preset = load_presets(presets)?
+                .into_iter()
+                .find(|preset| preset.name == *name)
+                .ok_or_else(|| format!("Unknown preset '{}'", name))?;
+            light.apply_preset(&preset).await?;
+            preset.payload
+        }
+        LightAction::Color { red, green, blue } => Payload::from(&Color::rgb(*red, *green, *blue)),
+        LightAction::Brightness { level } => {
+            let brightness = Brightness::create(*level)
+                .ok_or("Invalid brightness value. Must be between 10 and 100.")?;
+            Payload::from(&brightness)
+        }
+        LightAction::Temperature { kelvin } => {
+            let temp = Kelvin::create(*kelvin)
+                .ok_or("Invalid temperature value. Must be between 1000 and 8000K.")?;
+            Payload::from(&temp)
+        }
+        LightAction::Scene { scene } => {
+            let scene = scene
+                .parse::<SceneMode>()
+                .map_err(|_| "Unknown scene name. Use `wiz scenes` to list them.")?;
+            Payload::from(&scene)
+        }
+    };
+
+    if !matches!(action, LightAction::Apply { .. }) {
+        light.set(&payload).await?;
+    }
+    Ok(serde_json::to_value(&payload)?)
+}
+
+fn status_json(status: &LightStatus) -> Value {
+    json!({
+        "state": status.emitting(),
+        "color": status.color().map(|c| [c.red(), c.green(), c.blue()]),
+        "dimming": status.brightness().map(|b| b.value()),
+        "temp": status.temp().map(|t| t.kelvin()),
+        "scene": status.scene().map(|s| s.name()),
+    })
+}
+
+/// Human-readable outcome of a light action.
+fn describe(action: &LightAction, value: &Value) -> String {
+    match action {
+        LightAction::Status => {
+            let mut text = String::from("Light Status:");
+            let on = value["state"].as_bool().unwrap_or_default();
+            text += &format!("\n  Power: {}", if on { "ON" } else { "OFF" });
+            if let Some([r, g, b]) = value["color"].as_array().map(|c| [&c[0], &c[1], &c[2]]) {
+                text += &format!("\n  Color: RGB({}, {}, {})", r, g, b);
+            }
+            if let Some(dimming) = value["dimming"].as_u64() {
+                text += &format!("\n  Brightness: {}%", dimming);
+            }
+            if let Some(temp) = value["temp"].as_u64() {
+                text += &format!("\n  Temperature: {}K", temp);
+            }
+            if let Some(scene) = value["scene"].as_str() {
+                text += &format!("\n  Scene: {}", scene);
+            }
+            text
+        }
+        LightAction::On => "Light turned ON".to_string(),
+        LightAction::Off => "Light turned OFF".to_string(),
+        LightAction::Toggle => "Light toggled".to_string(),
+        LightAction::Reset => "Light reset successfully".to_string(),
+        LightAction::Apply { name } => format!("Preset '{}' applied", name),
+        LightAction::Color { .. } => "Color set successfully".to_string(),
+        LightAction::Brightness { .. } => "Brightness set successfully".to_string(),
+        LightAction::Temperature { .. } => "Temperature set successfully".to_string(),
+        LightAction::Scene { .. } => "Scene set successfully".to_string(),
+    }
+}
+
+fn default_presets_path() -> PathBuf {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .unwrap_or_default();
+    config.join("wiz").join("presets.json")
+}
+
+fn load_presets(path: &Path) -> Result<Vec<Preset>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(serde_json::from_str(&text)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save_presets(path: &Path, presets: &[Preset]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(presets)?)?;
+    Ok(())
+}
+
+/// Parses `90`, `30s`, `15m` or `2h`.
+fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", s))?;
+    let secs = match unit {
+        "" | "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        _ => return Err(format!("unknown unit '{}' (use s, m or h)", unit)),
+    };
+    Ok(Duration::from_secs(secs))
+}
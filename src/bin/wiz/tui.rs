@@ -0,0 +1,355 @@
+//! Interactive terminal dashboard (`wiz tui`).
+//!
+//! Lists discovered bulbs with their live state and controls the selected
+//! one from the keyboard. State is refreshed by polling and, when a local IP
+//! is given, by push notifications. Drawn with ratatui on crossterm, so it
+//! runs in any terminal crossterm supports, Windows included.
+
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use futures::StreamExt;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color as TermColor, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Paragraph, Row as TableRow, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use wiz_lights_rs::push::{PushManager, SyncPilotEvent};
+use wiz_lights_rs::{Color, Kelvin, Light, LightStatus, Payload, discover_bulbs};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// How often every light is polled, on top of push updates.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+const PALETTE: [(u8, u8, u8); 8] = [
+    (255, 0, 0),
+    (255, 128, 0),
+    (255, 255, 0),
+    (0, 255, 0),
+    (0, 255, 255),
+    (0, 0, 255),
+    (128, 0, 255),
+    (255, 0, 128),
+];
+
+const WHITES: [u16; 3] = [2700, 4000, 6500];
+
+const HELP: &str = "↑/↓ select  space toggle  +/- dim  c color  w white  r refresh  q quit";
+
+enum Key {
+    Up,
+    Down,
+    Toggle,
+    Brighter,
+    Dimmer,
+    Color,
+    White,
+    Refresh,
+    Quit,
+}
+
+impl Key {
+    fn from_event(event: &KeyEvent) -> Option<Key> {
+        // Windows reports releases too; act on presses only
+        if event.kind != KeyEventKind::Press {
+            return None;
+        }
+        let key = match event.code {
+            KeyCode::Up | KeyCode::Char('k') => Key::Up,
+            KeyCode::Down | KeyCode::Char('j') => Key::Down,
+            KeyCode::Char(' ') | KeyCode::Char('t') => Key::Toggle,
+            KeyCode::Char('+') | KeyCode::Char('=') => Key::Brighter,
+            KeyCode::Char('-') => Key::Dimmer,
+            KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => Key::Quit,
+            KeyCode::Char('c') => Key::Color,
+            KeyCode::Char('w') => Key::White,
+            KeyCode::Char('r') => Key::Refresh,
+            KeyCode::Char('q') | KeyCode::Esc => Key::Quit,
+            _ => return None,
+        };
+        Some(key)
+    }
+}
+
+enum Msg {
+    Status(usize, std::result::Result<LightStatus, String>),
+    Push(Box<SyncPilotEvent>),
+}
+
+/// What the dashboard shows for one bulb.
+struct Row {
+    light: Light,
+    mac: String,
+    on: Option<bool>,
+    dimming: Option<u8>,
+    color: Option<(u8, u8, u8)>,
+    temp: Option<u16>,
+    scene: Option<&'static str>,
+    error: Option<String>,
+    palette: usize,
+    white: usize,
+}
+
+impl Row {
+    fn apply_status(&mut self, status: &LightStatus) {
+        self.on = Some(status.emitting());
+        self.dimming = status.brightness().map(|b| b.value());
+        self.color = status.color().map(|c| (c.red(), c.green(), c.blue()));
+        self.temp = status.temp().map(|t| t.kelvin());
+        self.scene = status.scene().map(|s| s.name());
+        self.error = None;
+    }
+
+    fn apply_push(&mut self, event: &SyncPilotEvent) {
+        self.on = event.state.or(self.on);
+        if let Some(dimming) = &event.dimming {
+            self.dimming = Some(dimming.value());
+        }
+        // A push carries whichever mode is active, so drop the others
+        if event.color.is_some() || event.temp.is_some() || event.scene.is_some() {
+            self.color = event.color.as_ref().map(|c| (c.red(), c.green(), c.blue()));
+            self.temp = event.temp.as_ref().map(|t| t.kelvin());
+            self.scene = event.scene.as_ref().map(|s| s.name());
+        }
+        self.error = None;
+    }
+
+    fn render(&self) -> TableRow<'_> {
+        let power = match self.on {
+            Some(true) => "ON",
+            Some(false) => "OFF",
+            None => "?",
+        };
+        let dimming = self
+            .dimming
+            .map(|d| format!("{:>3}%", d))
+            .unwrap_or_else(|| "   -".to_string());
+        let swatch = match self.color {
+            Some((r, g, b)) => Cell::from("").bg(TermColor::Rgb(r, g, b)),
+            None => Cell::from(""),
+        };
+        let mode = match (self.scene, self.temp, self.color) {
+            (Some(scene), _, _) => scene.to_string(),
+            (None, Some(temp), _) => format!("{}K", temp),
+            (None, None, Some((r, g, b))) => format!("rgb({}, {}, {})", r, g, b),
+            _ => String::new(),
+        };
+        let error = self.error.as_deref().unwrap_or_default();
+        TableRow::new(vec![
+            Cell::from(self.light.ip().to_string()),
+            Cell::from(self.mac.as_str()),
+            Cell::from(power),
+            Cell::from(dimming),
+            swatch,
+            Cell::from(mode),
+            Cell::from(error).red(),
+        ])
+    }
+}
+
+/// Restores the terminal when dropped, even on error.
+struct Terminal(DefaultTerminal);
+
+impl Terminal {
+    fn enable() -> Self {
+        // Also installs a panic hook that restores the terminal first
+        Terminal(ratatui::init())
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+fn refresh(tx: &UnboundedSender<Msg>, index: usize, light: &Light) {
+    let tx = tx.clone();
+    let light = light.clone();
+    tokio::spawn(async move {
+        let status = light.get_status().await.map_err(|e| e.to_string());
+        let _ = tx.send(Msg::Status(index, status));
+    });
+}
+
+/// Sends a command to the selected light, then refreshes its row.
+fn command(tx: &UnboundedSender<Msg>, index: usize, row: &mut Row, key: &Key) {
+    let payload = match key {
+        Key::Color => {
+            row.palette = (row.palette + 1) % PALETTE.len();
+            let (r, g, b) = PALETTE[row.palette];
+            Some(Payload::from(&Color::rgb(r, g, b)))
+        }
+        Key::White => {
+            row.white = (row.white + 1) % WHITES.len();
+            Kelvin::create(WHITES[row.white]).map(|k| Payload::from(&k))
+        }
+        _ => None,
+    };
+    let delta = match key {
+        Key::Brighter => 10,
+        Key::Dimmer => -10,
+        _ => 0,
+    };
+    let toggle = matches!(key, Key::Toggle);
+
+    let tx = tx.clone();
+    let light = row.light.clone();
+    tokio::spawn(async move {
+        let result = if toggle {
+            light.toggle().await.map(drop)
+        } else if delta != 0 {
+            light.adjust_brightness(delta).await.map(drop)
+        } else if let Some(payload) = payload {
+            light.set(&payload).await.map(drop)
+        } else {
+            Ok(())
+        };
+        let status = match result {
+            Ok(()) => light.get_status().await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let _ = tx.send(Msg::Status(index, status));
+    });
+}
+
+fn draw(frame: &mut Frame, rows: &[Row], state: &mut TableState, push: bool) {
+    let [body, help] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let title = Line::from(vec![
+        Span::from("Wiz lights").bold(),
+        Span::from(if push { " (live)" } else { " (polling)" }),
+    ]);
+    let block = Block::bordered().title(title);
+
+    if rows.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No lights found on the network.").block(block),
+            body,
+        );
+    } else {
+        let table = Table::new(
+            rows.iter().map(Row::render),
+            [
+                Constraint::Length(15),
+                Constraint::Length(12),
+                Constraint::Length(3),
+                Constraint::Length(4),
+                Constraint::Length(4),
+                Constraint::Length(20),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(
+            TableRow::new(["IP", "MAC", "", "DIM", "", "MODE", ""])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .block(block)
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+        frame.render_stateful_widget(table, body, state);
+    }
+    frame.render_widget(Paragraph::new(HELP).dim(), help);
+}
+
+/// Discover bulbs and run the dashboard until `q` is pressed.
+pub async fn run(timeout: Duration, local_ip: Option<Ipv4Addr>) -> Result<()> {
+    eprintln!(
+        "Discovering Wiz lights on the network (timeout: {}s)...",
+        timeout.as_secs()
+    );
+    let bulbs = discover_bulbs(timeout).await?;
+    let mut rows: Vec<Row> = bulbs
+        .into_iter()
+        .map(|bulb| Row {
+            mac: bulb.mac.clone(),
+            light: bulb.into_light(None),
+            on: None,
+            dimming: None,
+            color: None,
+            temp: None,
+            scene: None,
+            error: None,
+            palette: PALETTE.len() - 1,
+            white: WHITES.len() - 1,
+        })
+        .collect();
+    rows.sort_by_key(|row| row.light.ip());
+
+    let (tx, mut rx) = unbounded_channel();
+
+    let push = PushManager::new();
+    if let Some(local_ip) = local_ip {
+        let events = tx.clone();
+        push.subscribe_all(move |event| {
+            let _ = events.send(Msg::Push(Box::new(event.clone())));
+        })
+        .await;
+        push.start(local_ip).await?;
+        for row in &rows {
+            push.register_bulb(row.light.ip()).await?;
+        }
+    }
+
+    let mut terminal = Terminal::enable();
+    let mut input = EventStream::new();
+
+    let mut state = TableState::default().with_selected(0);
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        terminal
+            .0
+            .draw(|frame| draw(frame, &rows, &mut state, push.is_running()))?;
+        tokio::select! {
+            _ = poll.tick() => {
+                for (i, row) in rows.iter().enumerate() {
+                    refresh(&tx, i, &row.light);
+                }
+            }
+            event = input.next() => {
+                let key = match event {
+                    Some(Ok(Event::Key(event))) => Key::from_event(&event),
+                    Some(Ok(_)) => None,
+                    Some(Err(e)) => return Err(e.into()),
+                    None => Some(Key::Quit),
+                };
+                let selected = state.selected().unwrap_or(0);
+                match key {
+                    None => {}
+                    Some(Key::Quit) => break,
+                    Some(Key::Up) => state.select(Some(selected.saturating_sub(1))),
+                    Some(Key::Down) => {
+                        state.select(Some((selected + 1).min(rows.len().saturating_sub(1))));
+                    }
+                    Some(Key::Refresh) => {
+                        for (i, row) in rows.iter().enumerate() {
+                            refresh(&tx, i, &row.light);
+                        }
+                    }
+                    Some(key) => {
+                        if let Some(row) = rows.get_mut(selected) {
+                            command(&tx, selected, row, &key);
+                        }
+                    }
+                }
+            }
+            Some(msg) = rx.recv() => match msg {
+                Msg::Status(i, Ok(status)) => rows[i].apply_status(&status),
+                Msg::Status(i, Err(error)) => rows[i].error = Some(error),
+                Msg::Push(event) => {
+                    let mac = event.mac.to_lowercase();
+                    if let Some(row) = rows.iter_mut().find(|row| row.mac.to_lowercase() == mac) {
+                        row.apply_push(&event);
+                    }
+                }
+            },
+        }
+    }
+
+    drop(terminal);
+    push.stop().await;
+    Ok(())
+}
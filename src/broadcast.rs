@@ -0,0 +1,191 @@
+//! Sending one payload to many bulbs at once.
+//!
+//! [`Light::set`](crate::Light::set) opens a socket per command and waits for
+//! each reply, so setting fifty bulbs one by one takes a while even when the
+//! calls run concurrently. [`broadcast_set`] sends the same `setPilot` to
+//! every address from a single socket straight away and then collects the
+//! replies as they arrive, until all bulbs answered or the deadline passed.
+//!
+//! This is a fast path: there are no retries, no rate limiting and no
+//! calibration. It also bypasses each light's command queue, so a broadcast
+//! can land in between the packets of a command already running for the
+//! same bulb. [`Room::set_fast`](crate::Room::set_fast) wraps it for the
+//! lights of a room and updates their cached status.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::{Brightness, Payload, broadcast_set};
+//!
+//! let ips: Vec<_> = room.iter().map(|(_, light)| light.ip()).collect();
+//! let results = broadcast_set(&ips, &Payload::from(&Brightness::create(60).unwrap())).await?;
+//! let missing: Vec<_> = results.failures().map(|(ip, _)| *ip).collect();
+//! ```
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::light::next_request_id;
+use crate::payload::Payload;
+use crate::protocol::{self, PORT};
+use crate::response::{BatchResults, LightingResponse};
+use crate::runtime::{self, Instant, Transport};
+use crate::stats;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Options for [`broadcast_set_with`].
+#[derive(Debug, Clone)]
+pub struct BroadcastOptions {
+    /// How long to wait for replies after the last datagram was sent.
+    pub timeout: Duration,
+    /// Creates the socket every datagram is sent from.
+    pub transport: Arc<dyn Transport>,
+}
+
+impl Default for BroadcastOptions {
+    fn default() -> Self {
+        BroadcastOptions {
+            timeout: Duration::from_secs(1),
+            transport: runtime::default_transport(),
+        }
+    }
+}
+
+/// Sends `payload` to every bulb in `ips` from one socket and waits up to a
+/// second for their replies.
+///
+/// Returns a result per address, in the order given with duplicates
+/// dropped: a bulb that did not answer in time fails with a receive
+/// timeout, or with the socket error that stopped the wait early. Fails as
+/// a whole only if the payload is empty or no socket could be bound.
+pub async fn broadcast_set(ips: &[Ipv4Addr], payload: &Payload) -> Result<BatchResults<Ipv4Addr>> {
+    broadcast_set_with(ips, payload, &BroadcastOptions::default()).await
+}
+
+/// Like [`broadcast_set`], with a custom deadline and transport.
+pub async fn broadcast_set_with(
+    ips: &[Ipv4Addr],
+    payload: &Payload,
+    options: &BroadcastOptions,
+) -> Result<BatchResults<Ipv4Addr>> {
+    let mut targets = Vec::with_capacity(ips.len());
+    for ip in ips {
+        if !targets.contains(&(*ip, *ip)) {
+            targets.push((*ip, *ip));
+        }
+    }
+    broadcast_keyed(targets, payload, options).await
+}
+
+/// Sends `payload` to the address of every target and reports the results
+/// by key, in the order given.
+///
+/// Every target gets its own request id, so keys sharing an address (e.g.
+/// two handles to one bulb) each get the reply to their own datagram.
+pub(crate) async fn broadcast_keyed<K>(
+    targets: Vec<(K, Ipv4Addr)>,
+    payload: &Payload,
+    options: &BroadcastOptions,
+) -> Result<BatchResults<K>> {
+    if !payload.is_valid() {
+        return Err(Error::NoAttribute);
+    }
+    let params = serde_json::to_value(payload).map_err(Error::JsonDump)?;
+
+    let socket = options
+        .transport
+        .bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
+        .await
+        .map_err(|e| Error::socket("bind", e))?;
+
+    // Every target starts out waiting; send failures settle it right away
+    let mut requests = Vec::with_capacity(targets.len());
+    let mut results: Vec<Option<Result<()>>> = Vec::with_capacity(targets.len());
+    for (_, ip) in &targets {
+        let request = protocol::with_id(
+            &protocol::request("setPilot", Some(params.clone())),
+            next_request_id(),
+        );
+        let msg = protocol::encode(&request)?;
+        stats::record_command(*ip, "setPilot");
+        let result = match socket.send_to(&msg, SocketAddr::from((*ip, PORT))).await {
+            Ok(_) => {
+                stats::record_sent(msg.len());
+                None
+            }
+            Err(e) => Some(Err(Error::socket("send", e))),
+        };
+        requests.push(request);
+        results.push(result);
+    }
+    let sent_at = Instant::now();
+
+    let mut buffer = [0u8; 4096];
+    let mut failed = None;
+    let receive = async {
+        while results.iter().any(Option::is_none) {
+            let (bytes, from) = match socket.recv_from(&mut buffer).await {
+                Ok(received) => received,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    continue;
+                }
+                // Anything else would fail again straight away, e.g. WSAECONNRESET
+                Err(e) => {
+                    failed = Some(e);
+                    break;
+                }
+            };
+            let SocketAddr::V4(from) = from else {
+                continue;
+            };
+            let Ok(resp) = protocol::parse(&buffer[..bytes]) else {
+                continue;
+            };
+            let waiting = (0..targets.len()).find(|&i| {
+                results[i].is_none()
+                    && targets[i].1 == *from.ip()
+                    && protocol::is_reply_to(&resp, &requests[i])
+            });
+            let Some(i) = waiting else {
+                continue;
+            };
+            stats::record_reply(*from.ip(), bytes, sent_at.elapsed());
+            results[i] = Some(match protocol::bulb_error("setPilot", &resp) {
+                Some(err) => Err(err),
+                None => Ok(()),
+            });
+        }
+    };
+    // Targets still waiting at the deadline are reported as timed out below
+    let _ = runtime::timeout(options.timeout, receive).await;
+
+    Ok(targets
+        .into_iter()
+        .zip(results)
+        .map(|((key, ip), result)| {
+            let result = match result {
+                Some(result) => result.map(|()| LightingResponse::payload(ip, payload.clone())),
+                None => Err(Error::socket(
+                    "receive",
+                    match &failed {
+                        Some(e) => io::Error::new(e.kind(), e.to_string()),
+                        None => {
+                            stats::record_timeout(ip);
+                            io::Error::new(io::ErrorKind::TimedOut, "receive timeout")
+                        }
+                    },
+                )),
+            };
+            (key, result)
+        })
+        .collect())
+}
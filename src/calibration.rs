@@ -0,0 +1,104 @@
+//! Per-light color calibration.
+
+use serde::{Deserialize, Serialize};
+
+use crate::payload::Payload;
+
+/// Corrections applied to every payload sent to a light.
+///
+/// Bulbs render mid-range RGB values nonlinearly and differ in their white
+/// balance. A calibration profile compensates with a gamma curve, per-channel
+/// scaling and an optional brightness cap. Profiles serialize with serde so
+/// they can be stored alongside the light.
+///
+/// # Example
+///
+/// ```
+/// use wiz_lights_rs::{Calibration, Color, Payload};
+///
+/// let calibration = Calibration {
+///     gamma: 2.2,
+///     blue_scale: 0.9,
+///     ..Default::default()
+/// };
+/// let mut payload = Payload::new();
+/// payload.color(&Color::rgb(128, 128, 128));
+/// let corrected = calibration.apply(&payload);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Calibration {
+    /// Exponent applied to each RGB channel; 1.0 leaves values unchanged.
+    pub gamma: f32,
+    pub red_scale: f32,
+    pub green_scale: f32,
+    pub blue_scale: f32,
+    /// Upper bound for the `dimming` value, in percent.
+    pub max_brightness: Option<u8>,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration {
+            gamma: 1.0,
+            red_scale: 1.0,
+            green_scale: 1.0,
+            blue_scale: 1.0,
+            max_brightness: None,
+        }
+    }
+}
+
+impl Calibration {
+    /// Returns a copy of `payload` with the corrections applied.
+    pub fn apply(&self, payload: &Payload) -> Payload {
+        let mut out = payload.clone();
+        out.red = payload.red.map(|v| self.channel(v, self.red_scale));
+        out.green = payload.green.map(|v| self.channel(v, self.green_scale));
+        out.blue = payload.blue.map(|v| self.channel(v, self.blue_scale));
+        if let (Some(dimming), Some(max)) = (payload.dimming, self.max_brightness) {
+            out.dimming = Some(dimming.min(max));
+        }
+        out
+    }
+
+    fn channel(&self, value: u8, scale: f32) -> u8 {
+        let normalized = f32::from(value) / 255.0;
+        let corrected = normalized.powf(self.gamma.max(0.0)) * scale.max(0.0);
+        (corrected * 255.0).round().clamp(0.0, 255.0) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Brightness, Color};
+
+    #[test]
+    fn test_default_is_identity() {
+        let mut payload = Payload::new();
+        payload.color(&Color::rgb(10, 128, 250));
+        payload.brightness(&Brightness::create(80).unwrap());
+
+        let out = Calibration::default().apply(&payload);
+        assert_eq!(out.get_color(), Some(Color::rgb(10, 128, 250)));
+        assert_eq!(out.dimming, Some(80));
+    }
+
+    #[test]
+    fn test_gamma_scale_and_cap() {
+        let calibration = Calibration {
+            gamma: 2.0,
+            blue_scale: 0.5,
+            max_brightness: Some(60),
+            ..Default::default()
+        };
+        let mut payload = Payload::new();
+        payload.color(&Color::rgb(255, 128, 255));
+        payload.brightness(&Brightness::create(80).unwrap());
+
+        let out = calibration.apply(&payload);
+        assert_eq!(out.get_color(), Some(Color::rgb(255, 64, 128)));
+        assert_eq!(out.dimming, Some(60));
+    }
+}
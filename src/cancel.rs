@@ -0,0 +1,151 @@
+//! Cooperative cancellation that works the same on every runtime.
+//!
+//! Aborting a task is a no-op on smol and tears futures down mid-message on
+//! the others. A [`CancelToken`] is checked by long-running operations
+//! instead, which stop at the next safe point and return
+//! [`Error::Cancelled`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::{CancelToken, DiscoveryOptions, discover_bulbs_with};
+//!
+//! let cancel = CancelToken::new();
+//! let options = DiscoveryOptions {
+//!     cancel: Some(cancel.clone()),
+//!     ..Default::default()
+//! };
+//! // Elsewhere, e.g. when the user closes a dialog:
+//! cancel.cancel();
+//!
+//! // Any other future can be raced against the token
+//! let responses = cancel.run(room.flash(&color, 3, Duration::from_millis(300))).await;
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures::future::{self, Either};
+
+use crate::errors::Error;
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+/// A shared flag that asks operations to stop.
+///
+/// Clones share the same flag, so one clone can be handed to an operation
+/// and another kept to cancel it.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every operation holding a clone of this token.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut *self.wakers());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completes once the token is cancelled.
+    pub fn cancelled(&self) -> Cancelled {
+        Cancelled {
+            token: self.clone(),
+        }
+    }
+
+    /// Run `future` until it completes or the token is cancelled.
+    ///
+    /// On cancellation the future is dropped and [`Error::Cancelled`] is
+    /// returned.
+    pub async fn run<F, T>(&self, future: F) -> Result<T>
+    where
+        F: Future<Output = T>,
+    {
+        if self.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        futures::pin_mut!(future);
+        match future::select(future, self.cancelled()).await {
+            Either::Left((value, _)) => Ok(value),
+            Either::Right(((), _)) => Err(Error::Cancelled),
+        }
+    }
+
+    fn wakers(&self) -> std::sync::MutexGuard<'_, Vec<Waker>> {
+        self.inner.wakers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Future returned by [`CancelToken::cancelled`].
+#[derive(Debug)]
+pub struct Cancelled {
+    token: CancelToken,
+}
+
+impl Future for Cancelled {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.token.wakers();
+        // Checked again under the lock so a concurrent cancel() is not missed
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_run_until_cancelled() {
+        let token = CancelToken::new();
+        assert_eq!(token.run(async { 7 }).await.unwrap(), 7);
+
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+        let result = token.run(future::pending::<()>()).await;
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(token.is_cancelled());
+
+        // An already cancelled token does not start the future
+        assert!(matches!(
+            token.run(async { 7 }).await,
+            Err(Error::Cancelled)
+        ));
+        token.cancelled().await;
+    }
+}
@@ -1,6 +1,18 @@
 //! Bulb configuration and type detection.
 
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::time::Duration;
+
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use serde_with::{DefaultOnError, serde_as};
+
+use crate::errors::Error;
+use crate::types::Brightness;
+
+type Result<T> = core::result::Result<T, Error>;
 
 /// System configuration of a Wiz bulb.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,14 +35,240 @@ pub struct SystemConfig {
     pub ping: Option<u32>,
 }
 
+#[cfg(feature = "std")]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub(crate) struct SystemConfigResponse {
     pub method: String,
     pub result: SystemConfig,
 }
 
+/// User configuration of a Wiz bulb, from `getUserConfig`.
+///
+/// Which fields are present depends on the model and firmware. A field with
+/// an unexpected shape reads as `None`; fields not listed here are kept in
+/// `extra`.
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserConfig {
+    /// Fade-in time when turning on, in milliseconds.
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_in: Option<u32>,
+    /// Fade-out time when turning off, in milliseconds.
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_out: Option<u32>,
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fade_night: Option<bool>,
+    /// Default brightness, in percent.
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dft_dim: Option<u8>,
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pwm_range: Option<Vec<u32>>,
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub white_range: Option<Vec<f32>>,
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ext_range: Option<Vec<f32>>,
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cct_range: Option<Vec<f32>>,
+    /// Number of fan speeds, for fan-equipped fixtures.
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fan_speed: Option<u8>,
+    /// Name given to the bulb, on firmware that stores one.
+    #[serde_as(deserialize_as = "DefaultOnError")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Changes to a bulb's user configuration, written with
+/// [`Light::set_user_config`](crate::Light::set_user_config).
+///
+/// Only the values set are sent. Values are checked when set; the write is
+/// refused until [`UserConfigUpdate::dangerous`] is called, since a bad
+/// configuration survives reboots and can only be fixed from the app.
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserConfigUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fade_in: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fade_out: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dft_dim: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    white_range: Option<[u16; 2]>,
+    #[serde(rename = "po", skip_serializing_if = "Option::is_none")]
+    power_on_restore: Option<bool>,
+    #[serde(skip)]
+    dangerous: bool,
+}
+
+impl UserConfigUpdate {
+    /// Longest fade bulbs accept.
+    pub const MAX_FADE: Duration = Duration::from_secs(60);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long the light fades in when turned on.
+    pub fn fade_in(&mut self, fade: Duration) -> Result<&mut Self> {
+        self.fade_in = Some(fade_millis(fade)?);
+        Ok(self)
+    }
+
+    /// How long the light fades out when turned off.
+    pub fn fade_out(&mut self, fade: Duration) -> Result<&mut Self> {
+        self.fade_out = Some(fade_millis(fade)?);
+        Ok(self)
+    }
+
+    /// Brightness used when none is given.
+    pub fn default_brightness(&mut self, brightness: &Brightness) -> &mut Self {
+        self.dft_dim = Some(brightness.value());
+        self
+    }
+
+    /// The white temperature range the bulb maps its channels to.
+    pub fn white_range(&mut self, range: KelvinRange) -> Result<&mut Self> {
+        const LIMITS: core::ops::RangeInclusive<u16> = 1000..=10000;
+        if !LIMITS.contains(&range.min) || !LIMITS.contains(&range.max) || range.min >= range.max {
+            return Err(Error::InvalidConfig(format!(
+                "white range {}-{}K must be increasing and within 1000-10000K",
+                range.min, range.max
+            )));
+        }
+        self.white_range = Some([range.min, range.max]);
+        Ok(self)
+    }
+
+    /// Whether the light comes back in its last state after a power cut,
+    /// rather than the default.
+    pub fn power_on_restore(&mut self, restore: bool) -> &mut Self {
+        self.power_on_restore = Some(restore);
+        self
+    }
+
+    /// Confirm that the write may leave the bulb misconfigured.
+    pub fn dangerous(&mut self) -> &mut Self {
+        self.dangerous = true;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn check(&self) -> Result<()> {
+        check_write("setUserConfig", self.dangerous, self)
+    }
+}
+
+/// Changes to a bulb's system configuration, written with
+/// [`Light::set_system_config`](crate::Light::set_system_config).
+///
+/// Like [`UserConfigUpdate`], the write must be confirmed with
+/// [`SystemConfigUpdate::dangerous`]. A wrong PWM frequency can make the
+/// light flicker or whine until it is changed back.
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemConfigUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pwm_freq: Option<u32>,
+    #[serde(skip)]
+    dangerous: bool,
+}
+
+impl SystemConfigUpdate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// PWM frequency of the LED drivers, 100 Hz to 20 kHz.
+    pub fn pwm_freq(&mut self, hz: u32) -> Result<&mut Self> {
+        if !(100..=20_000).contains(&hz) {
+            return Err(Error::InvalidConfig(format!(
+                "pwm frequency {hz} Hz must be within 100-20000 Hz"
+            )));
+        }
+        self.pwm_freq = Some(hz);
+        Ok(self)
+    }
+
+    /// Confirm that the write may leave the bulb misconfigured.
+    pub fn dangerous(&mut self) -> &mut Self {
+        self.dangerous = true;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn check(&self) -> Result<()> {
+        check_write("setSystemConfig", self.dangerous, self)
+    }
+}
+
+fn fade_millis(fade: Duration) -> Result<u32> {
+    if fade > UserConfigUpdate::MAX_FADE {
+        return Err(Error::InvalidConfig(format!(
+            "fade of {fade:?} is longer than {:?}",
+            UserConfigUpdate::MAX_FADE
+        )));
+    }
+    Ok(fade.as_millis() as u32)
+}
+
+/// Refuses empty or unconfirmed writes.
+#[cfg(feature = "std")]
+fn check_write(method: &str, dangerous: bool, update: &impl Serialize) -> Result<()> {
+    let empty = serde_json::to_value(update)
+        .map_err(Error::JsonDump)?
+        .as_object()
+        .is_none_or(Map::is_empty);
+    if empty {
+        return Err(Error::NoAttribute);
+    }
+    if !dangerous {
+        return Err(Error::DangerousNotConfirmed(method.into()));
+    }
+    Ok(())
+}
+
+/// Device information, from `getDevInfo`.
+///
+/// Fields not listed here are kept in `extra`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DevInfo {
+    #[serde(default, alias = "devMac", skip_serializing_if = "Option::is_none")]
+    pub mac: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// WiFi settings the bulb is using, from `getWifiConfig`.
+///
+/// The password is never reported. Fields not listed here are kept in
+/// `extra`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WifiConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bssid: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
 /// Classification of Wiz bulb types.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BulbClass {
     TW,     // Tunable White
     DW,     // Dimmable White
@@ -40,7 +278,7 @@ pub enum BulbClass {
 }
 
 /// Feature flags for a Wiz bulb.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Features {
     pub color: bool,
     pub color_tmp: bool,
@@ -52,15 +290,47 @@ pub struct Features {
     pub fan_reverse: bool,
 }
 
+impl Features {
+    /// The features every bulb of `bulb_class` has.
+    pub fn for_class(bulb_class: BulbClass, dual_head: bool) -> Self {
+        let mut features = Features {
+            dual_head,
+            ..Default::default()
+        };
+        match bulb_class {
+            BulbClass::RGB => {
+                features.color = true;
+                features.color_tmp = true;
+                features.effect = true;
+                features.brightness = true;
+            }
+            BulbClass::TW => {
+                features.color_tmp = true;
+                features.effect = true;
+                features.brightness = true;
+            }
+            BulbClass::DW => features.brightness = true,
+            BulbClass::Socket => {}
+            BulbClass::FanDim => {
+                features.brightness = true;
+                features.fan = true;
+                features.fan_breeze_mode = true;
+                features.fan_reverse = true;
+            }
+        }
+        features
+    }
+}
+
 /// Color temperature range (Kelvin).
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct KelvinRange {
     pub min: u16,
     pub max: u16,
 }
 
 /// White range values from user config.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WhiteRange {
     pub values: Vec<f32>,
 }
@@ -72,7 +342,7 @@ impl WhiteRange {
 }
 
 /// Extended white range (CCT range) - typically [warm_min, warm_max, cool_min, cool_max].
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtendedWhiteRange {
     pub values: Vec<f32>,
 }
@@ -84,7 +354,7 @@ impl ExtendedWhiteRange {
 }
 
 /// Complete type information for a Wiz bulb.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BulbType {
     pub features: Features,
     pub name: String,
@@ -96,59 +366,67 @@ pub struct BulbType {
 
 impl BulbType {
     /// Parse bulb type from module name (e.g., "ESP01_SHRGB1C_31").
+    ///
+    /// Known modules come from the [`BulbDatabase`](crate::BulbDatabase);
+    /// others are classified by the type token in their name.
     pub fn from_module_name(module_name: &str, fw_version: Option<&str>) -> Self {
-        let parts: Vec<&str> = module_name.split('_').collect();
-        let mut features = Features::default();
-        let mut bulb_class = BulbClass::DW;
-        let mut kelvin_range = KelvinRange {
-            min: 2700,
-            max: 6500,
-        };
-        let mut white_channels = 0u8;
+        crate::database::lookup(module_name, fw_version)
+    }
+}
 
-        if let Some(type_part) = parts.get(1) {
-            features.dual_head = type_part.starts_with("DH");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
 
-            if type_part.contains("RGB") {
-                bulb_class = BulbClass::RGB;
-                features.color = true;
-                features.color_tmp = true;
-                features.effect = true;
-                features.brightness = true;
-                white_channels = 2;
-                kelvin_range = KelvinRange {
-                    min: 2200,
-                    max: 6500,
-                };
-            } else if type_part.contains("TW") {
-                bulb_class = BulbClass::TW;
-                features.color_tmp = true;
-                features.brightness = true;
-                features.effect = true;
-                white_channels = 2;
-            } else if type_part.contains("DW") || type_part.contains("SHDW") {
-                bulb_class = BulbClass::DW;
-                features.brightness = true;
-                white_channels = 1;
-            } else if type_part.contains("SOCKET") {
-                bulb_class = BulbClass::Socket;
-            } else if type_part.contains("FANDIM") {
-                bulb_class = BulbClass::FanDim;
-                features.brightness = true;
-                features.fan = true;
-                features.fan_breeze_mode = true;
-                features.fan_reverse = true;
-                white_channels = 1;
-            }
-        }
+    #[test]
+    fn test_user_config_lenient() {
+        let config: UserConfig = serde_json::from_value(json!({
+            "fadeIn": 500,
+            "whiteRange": [2700, 6500],
+            "fanSpeed": "fast",
+            "po": false,
+        }))
+        .unwrap();
+        assert_eq!(config.fade_in, Some(500));
+        assert_eq!(config.white_range, Some(vec![2700.0, 6500.0]));
+        // Unexpected shapes read as missing rather than failing the whole config
+        assert_eq!(config.fan_speed, None);
+        assert_eq!(config.extra["po"], false);
 
-        BulbType {
-            features,
-            name: module_name.to_string(),
-            kelvin_range,
-            bulb_class,
-            fw_version: fw_version.map(String::from),
-            white_channels,
-        }
+        let info: DevInfo = serde_json::from_value(json!({"devMac": "a8bb50aabbcc"})).unwrap();
+        assert_eq!(info.mac.as_deref(), Some("a8bb50aabbcc"));
+    }
+
+    #[test]
+    fn test_config_update_guards() {
+        let mut update = UserConfigUpdate::new();
+        assert!(matches!(update.check(), Err(Error::NoAttribute)));
+
+        update
+            .fade_in(Duration::from_millis(800))
+            .unwrap()
+            .power_on_restore(true);
+        assert!(matches!(
+            update.check(),
+            Err(Error::DangerousNotConfirmed(_))
+        ));
+        update.dangerous();
+        update.check().unwrap();
+        assert_eq!(
+            serde_json::to_value(&update).unwrap(),
+            json!({"fadeIn": 800, "po": true})
+        );
+
+        assert!(update.fade_out(Duration::from_secs(120)).is_err());
+        let reversed = KelvinRange {
+            min: 6500,
+            max: 2700,
+        };
+        assert!(matches!(
+            update.white_range(reversed),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(SystemConfigUpdate::new().pwm_freq(50).is_err());
     }
 }
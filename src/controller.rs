@@ -0,0 +1,227 @@
+//! A common interface for anything that can be switched and colored.
+//!
+//! [`LightController`] is implemented by [`Light`], [`Plug`], [`Room`] and
+//! [`Group`], so automation code can work on a mixed collection without
+//! caring which kind of device each entry is. The methods return boxed
+//! futures, which keeps the trait usable as `Box<dyn LightController>`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::{Light, LightController, Plug, PowerMode, Room};
+//!
+//! let devices: Vec<Box<dyn LightController>> = vec![
+//!     Box::new(Light::new("192.168.1.20".parse()?, None)),
+//!     Box::new(Plug::new("192.168.1.40".parse()?, None)),
+//!     Box::new(living_room),
+//! ];
+//! for device in &devices {
+//!     device.set_power(&PowerMode::Off).await?;
+//! }
+//! ```
+
+use futures::future;
+
+use crate::config::Features;
+use crate::errors::Error;
+use crate::group::Group;
+use crate::light::Light;
+use crate::payload::Payload;
+use crate::plug::Plug;
+use crate::response::LightingResponse;
+use crate::room::Room;
+use crate::runtime::BoxFuture;
+use crate::status::LightStatus;
+use crate::types::PowerMode;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Device-agnostic control of one or more lights.
+///
+/// Methods report one response or status per light, so a single bulb
+/// returns one-element vectors and a room one entry per member.
+pub trait LightController: Send + Sync {
+    /// Applies lighting settings from a payload.
+    fn set<'a>(&'a self, payload: &'a Payload) -> BoxFuture<'a, Result<Vec<LightingResponse>>>;
+
+    fn set_power<'a>(
+        &'a self,
+        power: &'a PowerMode,
+    ) -> BoxFuture<'a, Result<Vec<LightingResponse>>>;
+
+    /// Queries the current state of every light.
+    fn get_status(&self) -> BoxFuture<'_, Result<Vec<LightStatus>>>;
+
+    /// What the device can do. For rooms and groups, the features every
+    /// member shares.
+    fn capabilities(&self) -> BoxFuture<'_, Result<Features>>;
+}
+
+impl LightController for Light {
+    fn set<'a>(&'a self, payload: &'a Payload) -> BoxFuture<'a, Result<Vec<LightingResponse>>> {
+        Box::pin(async move { Ok(vec![Light::set(self, payload).await?]) })
+    }
+
+    fn set_power<'a>(
+        &'a self,
+        power: &'a PowerMode,
+    ) -> BoxFuture<'a, Result<Vec<LightingResponse>>> {
+        Box::pin(async move { Ok(vec![Light::set_power(self, power).await?]) })
+    }
+
+    fn get_status(&self) -> BoxFuture<'_, Result<Vec<LightStatus>>> {
+        Box::pin(async move { Ok(vec![Light::get_status(self).await?]) })
+    }
+
+    fn capabilities(&self) -> BoxFuture<'_, Result<Features>> {
+        Box::pin(async move { Ok(self.get_bulb_type().await?.features) })
+    }
+}
+
+impl LightController for Plug {
+    /// Plugs only switch power, so any payload setting more than the power
+    /// state fails with [`Error::UnsupportedFeature`].
+    fn set<'a>(&'a self, payload: &'a Payload) -> BoxFuture<'a, Result<Vec<LightingResponse>>> {
+        Box::pin(async move {
+            let mut rest = payload.clone();
+            let state = rest.state.take();
+            let power_only = serde_json::to_value(&rest)
+                .is_ok_and(|value| value.as_object().is_some_and(|map| map.is_empty()));
+            if !power_only {
+                return Err(Error::UnsupportedFeature {
+                    feature: "lighting".to_string(),
+                    module: "socket".to_string(),
+                });
+            }
+            match state {
+                Some(on) => Ok(vec![self.set_on(on).await?]),
+                None => Err(Error::NoAttribute),
+            }
+        })
+    }
+
+    fn set_power<'a>(
+        &'a self,
+        power: &'a PowerMode,
+    ) -> BoxFuture<'a, Result<Vec<LightingResponse>>> {
+        Box::pin(async move { Ok(vec![self.light().set_power(power).await?]) })
+    }
+
+    fn get_status(&self) -> BoxFuture<'_, Result<Vec<LightStatus>>> {
+        Box::pin(async move { Ok(vec![self.light().get_status().await?]) })
+    }
+
+    fn capabilities(&self) -> BoxFuture<'_, Result<Features>> {
+        Box::pin(async move { Ok(self.light().get_bulb_type().await?.features) })
+    }
+}
+
+impl LightController for Room {
+    fn set<'a>(&'a self, payload: &'a Payload) -> BoxFuture<'a, Result<Vec<LightingResponse>>> {
+        Box::pin(async move { Room::set(self, payload).await.into_result() })
+    }
+
+    fn set_power<'a>(
+        &'a self,
+        power: &'a PowerMode,
+    ) -> BoxFuture<'a, Result<Vec<LightingResponse>>> {
+        Box::pin(async move { Room::set_power(self, power).await.into_result() })
+    }
+
+    fn get_status(&self) -> BoxFuture<'_, Result<Vec<LightStatus>>> {
+        Box::pin(async move {
+            let results = future::join_all(self.members().map(Light::get_status)).await;
+            results.into_iter().collect()
+        })
+    }
+
+    fn capabilities(&self) -> BoxFuture<'_, Result<Features>> {
+        Box::pin(async move {
+            let results = future::join_all(self.members().map(Light::get_bulb_type)).await;
+            let mut features = Vec::new();
+            for result in results {
+                features.push(result?.features);
+            }
+            Ok(common(features))
+        })
+    }
+}
+
+impl LightController for Group {
+    fn set<'a>(&'a self, payload: &'a Payload) -> BoxFuture<'a, Result<Vec<LightingResponse>>> {
+        Box::pin(Group::set(self, payload))
+    }
+
+    fn set_power<'a>(
+        &'a self,
+        power: &'a PowerMode,
+    ) -> BoxFuture<'a, Result<Vec<LightingResponse>>> {
+        Box::pin(Group::set_power(self, power))
+    }
+
+    fn get_status(&self) -> BoxFuture<'_, Result<Vec<LightStatus>>> {
+        Box::pin(Group::get_status(self))
+    }
+
+    fn capabilities(&self) -> BoxFuture<'_, Result<Features>> {
+        Box::pin(async move {
+            let lights: Vec<Light> = self
+                .ips()?
+                .into_iter()
+                .map(|ip| Light::new(ip, None))
+                .collect();
+            let results = future::join_all(lights.iter().map(Light::get_bulb_type)).await;
+            let mut features = Vec::new();
+            for result in results {
+                features.push(result?.features);
+            }
+            Ok(common(features))
+        })
+    }
+}
+
+/// Features every entry has. Empty input has none.
+fn common(features: Vec<Features>) -> Features {
+    features
+        .into_iter()
+        .reduce(|a, b| Features {
+            color: a.color && b.color,
+            color_tmp: a.color_tmp && b.color_tmp,
+            effect: a.effect && b.effect,
+            brightness: a.brightness && b.brightness,
+            dual_head: a.dual_head && b.dual_head,
+            fan: a.fan && b.fan,
+            fan_breeze_mode: a.fan_breeze_mode && b.fan_breeze_mode,
+            fan_reverse: a.fan_reverse && b.fan_reverse,
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BulbClass;
+
+    #[test]
+    fn test_common_features() {
+        let rgb = Features::for_class(BulbClass::RGB, false);
+        let dw = Features::for_class(BulbClass::DW, false);
+        let shared = common(vec![rgb.clone(), dw]);
+        assert!(shared.brightness);
+        assert!(!shared.color && !shared.color_tmp);
+
+        assert!(common(vec![rgb]).color);
+        assert!(!common(Vec::new()).brightness);
+    }
+
+    #[tokio::test]
+    async fn test_empty_collections() {
+        let devices: Vec<Box<dyn LightController>> =
+            vec![Box::new(Room::new("Empty")), Box::new(Group::new("Empty"))];
+        for device in &devices {
+            assert!(device.set_power(&PowerMode::Off).await.unwrap().is_empty());
+            assert!(device.get_status().await.unwrap().is_empty());
+            assert!(!device.capabilities().await.unwrap().color);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Known WiZ modules and their capabilities.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::sync::{OnceLock, RwLock};
+
+use crate::config::{BulbClass, BulbType, Features, KelvinRange};
+
+/// Capabilities of one module, as reported in `moduleName`.
+#[derive(Debug, Clone)]
+pub struct KnownModel {
+    /// Exact module name, e.g. `ESP01_SHRGB1C_31`.
+    pub module_name: String,
+    /// Short human-readable description.
+    pub description: String,
+    pub bulb_class: BulbClass,
+    pub kelvin_range: KelvinRange,
+    pub white_channels: u8,
+    pub dual_head: bool,
+}
+
+impl KnownModel {
+    /// A model with the defaults of its class.
+    pub fn new(module_name: &str, description: &str, bulb_class: BulbClass) -> Self {
+        let (kelvin_range, white_channels) = class_defaults(bulb_class);
+        KnownModel {
+            module_name: module_name.to_string(),
+            description: description.to_string(),
+            bulb_class,
+            kelvin_range,
+            white_channels,
+            dual_head: false,
+        }
+    }
+
+    pub fn with_kelvin_range(mut self, min: u16, max: u16) -> Self {
+        self.kelvin_range = KelvinRange { min, max };
+        self
+    }
+
+    pub fn with_white_channels(mut self, channels: u8) -> Self {
+        self.white_channels = channels;
+        self
+    }
+
+    pub fn with_dual_head(mut self, dual_head: bool) -> Self {
+        self.dual_head = dual_head;
+        self
+    }
+
+    /// The full type information for a bulb of this model.
+    pub fn bulb_type(&self, fw_version: Option<&str>) -> BulbType {
+        BulbType {
+            features: Features::for_class(self.bulb_class, self.dual_head),
+            name: self.module_name.clone(),
+            kelvin_range: self.kelvin_range,
+            bulb_class: self.bulb_class,
+            fw_version: fw_version.map(String::from),
+            white_channels: self.white_channels,
+        }
+    }
+}
+
+/// Lookup table from module names to capabilities.
+///
+/// [`BulbType::from_module_name`] consults the global database first and
+/// falls back to guessing from the tokens in the name. Add models the crate
+/// does not know yet with [`BulbDatabase::register`].
+///
+/// # Example
+///
+/// ```
+/// use wiz_lights_rs::{BulbClass, BulbDatabase, BulbType, KnownModel};
+///
+/// BulbDatabase::register(
+///     KnownModel::new("ESP99_CUSTOM_01", "Custom strip", BulbClass::RGB)
+///         .with_kelvin_range(2700, 6000),
+/// );
+/// let bulb = BulbType::from_module_name("ESP99_CUSTOM_01", None);
+/// assert_eq!(bulb.kelvin_range.max, 6000);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BulbDatabase {
+    models: BTreeMap<String, KnownModel>,
+}
+
+impl BulbDatabase {
+    /// An empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The models known to the crate.
+    pub fn builtin() -> Self {
+        let mut db = Self::new();
+        for model in builtin_models() {
+            db.insert(model);
+        }
+        db
+    }
+
+    /// Adds or replaces a model.
+    pub fn insert(&mut self, model: KnownModel) {
+        self.models.insert(model.module_name.to_uppercase(), model);
+    }
+
+    /// Looks up a module name, ignoring case.
+    pub fn get(&self, module_name: &str) -> Option<&KnownModel> {
+        self.models.get(&module_name.to_uppercase())
+    }
+
+    pub fn models(&self) -> impl Iterator<Item = &KnownModel> {
+        self.models.values()
+    }
+
+    /// Type information for a module, guessed from its name when unknown.
+    pub fn lookup(&self, module_name: &str, fw_version: Option<&str>) -> BulbType {
+        match self.get(module_name) {
+            Some(model) => model.bulb_type(fw_version),
+            None => guess(module_name).bulb_type(fw_version),
+        }
+    }
+
+    /// Adds a model to the global database used by
+    /// [`BulbType::from_module_name`].
+    #[cfg(feature = "std")]
+    pub fn register(model: KnownModel) {
+        global()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(model);
+    }
+
+    /// Looks up a module name in the global database.
+    #[cfg(feature = "std")]
+    pub fn known(module_name: &str) -> Option<KnownModel> {
+        global()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(module_name)
+            .cloned()
+    }
+}
+
+#[cfg(feature = "std")]
+fn global() -> &'static RwLock<BulbDatabase> {
+    static DATABASE: OnceLock<RwLock<BulbDatabase>> = OnceLock::new();
+    DATABASE.get_or_init(|| RwLock::new(BulbDatabase::builtin()))
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn lookup(module_name: &str, fw_version: Option<&str>) -> BulbType {
+    global()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .lookup(module_name, fw_version)
+}
+
+/// Without `std` there is no global database to register models in, so
+/// only the built-in ones are known.
+#[cfg(not(feature = "std"))]
+pub(crate) fn lookup(module_name: &str, fw_version: Option<&str>) -> BulbType {
+    BulbDatabase::builtin().lookup(module_name, fw_version)
+}
+
+fn class_defaults(bulb_class: BulbClass) -> (KelvinRange, u8) {
+    let range = |min, max| KelvinRange { min, max };
+    match bulb_class {
+        BulbClass::RGB => (range(2200, 6500), 2),
+        BulbClass::TW => (range(2700, 6500), 2),
+        BulbClass::DW | BulbClass::FanDim => (range(2700, 6500), 1),
+        BulbClass::Socket => (range(2700, 6500), 0),
+    }
+}
+
+/// Guesses capabilities from the type token, e.g. `SHRGB1C` in
+/// `ESP01_SHRGB1C_31`.
+fn guess(module_name: &str) -> KnownModel {
+    let type_part = module_name.split('_').nth(1).unwrap_or_default();
+    let bulb_class = if type_part.contains("RGB") {
+        BulbClass::RGB
+    } else if type_part.contains("TW") {
+        BulbClass::TW
+    } else if type_part.contains("SOCKET") {
+        BulbClass::Socket
+    } else if type_part.contains("FANDIM") {
+        BulbClass::FanDim
+    } else {
+        BulbClass::DW
+    };
+    KnownModel::new(module_name, "Unknown model", bulb_class)
+        .with_dual_head(type_part.starts_with("DH"))
+}
+
+fn builtin_models() -> Vec<KnownModel> {
+    use BulbClass::*;
+
+    vec![
+        // A-shape and spot bulbs
+        KnownModel::new("ESP01_SHRGB1C_31", "Color bulb", RGB),
+        KnownModel::new("ESP01_SHRGB_03", "Color bulb", RGB),
+        KnownModel::new("ESP01_SHTW1C_31", "Tunable white bulb", TW),
+        KnownModel::new("ESP01_SHDW1_31", "Dimmable white bulb", DW),
+        KnownModel::new("ESP01_SHDW1C_31", "Dimmable white bulb", DW),
+        KnownModel::new("ESP01_SHDW_01", "Dimmable white bulb", DW),
+        KnownModel::new("ESP03_SHRGB1C_01", "Color bulb", RGB),
+        KnownModel::new("ESP03_SHRGB1W_01", "Color bulb", RGB),
+        KnownModel::new("ESP03_SHRGB3_01ABI", "Color bulb", RGB),
+        KnownModel::new("ESP14_SHRGB1C_01", "Color bulb", RGB),
+        KnownModel::new("ESP15_SHTW1_01I", "Tunable white bulb", TW),
+        KnownModel::new("ESP24_SHRGB_01", "Color bulb", RGB),
+        // Filament bulbs
+        KnownModel::new("ESP06_SHDW9_01", "Dimmable white filament bulb", DW),
+        KnownModel::new("ESP17_SHTW9_01", "Tunable white filament bulb", TW)
+            .with_kelvin_range(2000, 5000),
+        KnownModel::new("ESP56_SHTW3_01", "Tunable white filament bulb", TW)
+            .with_kelvin_range(2000, 5000),
+        // Light strips
+        KnownModel::new("ESP05_SHRGBL_21", "Color light strip", RGB),
+        KnownModel::new("ESP20_SHRGBC_01", "Color light strip", RGB),
+        // Dual-head fixtures
+        KnownModel::new("ESP20_DHRGB_01B", "Dual-head color floor lamp", RGB).with_dual_head(true),
+        // Ceiling fans
+        KnownModel::new(
+            "ESP03_FANDIMS_01",
+            "Ceiling fan with dimmable light",
+            FanDim,
+        ),
+        KnownModel::new(
+            "ESP03_FANDIMS_31",
+            "Ceiling fan with dimmable light",
+            FanDim,
+        ),
+        // Smart plugs
+        KnownModel::new("ESP10_SOCKET_06", "Smart plug", Socket),
+        KnownModel::new("ESP25_SOCKET_01", "Smart plug", Socket),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_models() {
+        let db = BulbDatabase::builtin();
+        let filament = db.lookup("esp56_shtw3_01", Some("1.25.0"));
+        assert_eq!(filament.bulb_class, BulbClass::TW);
+        assert_eq!(filament.kelvin_range.min, 2000);
+        assert_eq!(filament.fw_version.as_deref(), Some("1.25.0"));
+
+        let lamp = db.lookup("ESP20_DHRGB_01B", None);
+        assert!(lamp.features.dual_head && lamp.features.color);
+    }
+
+    #[test]
+    fn test_guess_unknown_models() {
+        let db = BulbDatabase::new();
+        assert_eq!(
+            db.lookup("ESP99_SHRGB9_01", None).bulb_class,
+            BulbClass::RGB
+        );
+        assert_eq!(db.lookup("ESP99_SOCKET_01", None).white_channels, 0);
+
+        let fan = db.lookup("ESP99_FANDIMS_01", None);
+        assert!(fan.features.fan && fan.features.brightness);
+
+        let unknown = db.lookup("Unknown", None);
+        assert_eq!(unknown.bulb_class, BulbClass::DW);
+    }
+}
@@ -0,0 +1,232 @@
+//! Typed diagnostics reports.
+//!
+//! [`Light::diagnostics`](crate::Light::diagnostics) gathers what is known
+//! about a bulb into a [`Diagnostics`] report;
+//! [`Room::diagnostics`](crate::Room::diagnostics) and
+//! [`Home::diagnostics`](crate::Home::diagnostics) collect those reports with
+//! totals. Every report serializes, and `to_json()` renders it for display
+//! or for a support bundle.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let diag = light.diagnostics().await;
+//! if !diag.reachable {
+//!     println!("{} did not answer", diag.ip);
+//! }
+//! println!("{}", serde_json::to_string_pretty(&diag.to_json())?);
+//! ```
+
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::config::{BulbType, ExtendedWhiteRange, SystemConfig, WhiteRange};
+use crate::errors::Error;
+use crate::history::HistorySummary;
+use crate::runtime;
+use crate::status::LightStatus;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// What is known about one light.
+///
+/// Sections the bulb did not answer for are `None`, with the reason in
+/// [`errors`](Diagnostics::errors). Sections the bulb does not support,
+/// such as the fan speed range of a bulb without a fan, are `None` too but
+/// are not errors.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub ip: Ipv4Addr,
+    pub name: Option<String>,
+    /// Whether the bulb answered `getPilot`.
+    pub reachable: bool,
+    /// WiFi signal strength in dBm.
+    pub rssi: Option<i64>,
+    /// Cached status, refreshed if the bulb answered.
+    pub status: Option<LightStatus>,
+    pub system_config: Option<SystemConfig>,
+    pub white_range: Option<WhiteRange>,
+    pub extended_white_range: Option<ExtendedWhiteRange>,
+    pub fan_speed_range: Option<u8>,
+    pub bulb_type: Option<BulbType>,
+    pub history: HistorySummary,
+    /// Why each missing section is missing, keyed by field name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, String>,
+}
+
+impl Diagnostics {
+    /// Whether every section could be queried.
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Firmware version, from the system config or the bulb type.
+    pub fn fw_version(&self) -> Option<&str> {
+        self.system_config
+            .as_ref()
+            .and_then(|config| config.fw_version.as_deref())
+            .or_else(|| self.bulb_type.as_ref()?.fw_version.as_deref())
+    }
+
+    pub fn to_json(&self) -> Value {
+        to_json(self)
+    }
+}
+
+/// Totals over several [`Diagnostics`] reports.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticsSummary {
+    pub lights: usize,
+    pub reachable: usize,
+    pub unreachable: usize,
+    /// Lights with at least one section missing.
+    pub incomplete: usize,
+    /// Errors logged in the history of every light.
+    pub error_count: usize,
+    /// Number of lights running each firmware version.
+    pub fw_versions: BTreeMap<String, usize>,
+}
+
+impl DiagnosticsSummary {
+    pub fn from_reports<'a>(reports: impl IntoIterator<Item = &'a Diagnostics>) -> Self {
+        let mut summary = DiagnosticsSummary::default();
+        for report in reports {
+            summary.lights += 1;
+            if report.reachable {
+                summary.reachable += 1;
+            } else {
+                summary.unreachable += 1;
+            }
+            if !report.is_complete() {
+                summary.incomplete += 1;
+            }
+            summary.error_count += report.history.error_count;
+            if let Some(version) = report.fw_version() {
+                *summary.fw_versions.entry(version.to_string()).or_default() += 1;
+            }
+        }
+        summary
+    }
+}
+
+/// Reports for every light of a room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomDiagnostics {
+    pub id: Uuid,
+    pub name: String,
+    pub summary: DiagnosticsSummary,
+    pub lights: BTreeMap<Uuid, Diagnostics>,
+}
+
+impl RoomDiagnostics {
+    pub fn to_json(&self) -> Value {
+        to_json(self)
+    }
+}
+
+/// Reports for every room of a home and its unassigned lights.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HomeDiagnostics {
+    pub home_id: Option<u64>,
+    /// Totals over every light of the home.
+    pub summary: DiagnosticsSummary,
+    pub rooms: BTreeMap<u64, RoomDiagnostics>,
+    pub unassigned: Vec<Diagnostics>,
+}
+
+impl HomeDiagnostics {
+    pub fn to_json(&self) -> Value {
+        to_json(self)
+    }
+}
+
+/// Runs one section query, turning a failure or a timeout into the
+/// message recorded in [`Diagnostics::errors`].
+pub(crate) async fn section<T>(
+    timeout: Duration,
+    query: impl Future<Output = Result<T>>,
+) -> std::result::Result<T, String> {
+    match runtime::timeout(timeout, query).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("timed out after {timeout:?}")),
+    }
+}
+
+/// The value of a section, or `None` with its error recorded under `name`.
+pub(crate) fn take<T>(
+    errors: &mut BTreeMap<String, String>,
+    name: &str,
+    result: std::result::Result<T, String>,
+) -> Option<T> {
+    result.map_err(|e| errors.insert(name.to_string(), e)).ok()
+}
+
+fn to_json(report: &impl Serialize) -> Value {
+    // Every field serializes to plain JSON, so this cannot fail
+    serde_json::to_value(report).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn report(reachable: bool, fw_version: Option<&str>, error_count: usize) -> Diagnostics {
+        let system_config = fw_version.map(|v| {
+            serde_json::from_value(json!({"mac": "a8bb50000001", "fwVersion": v})).unwrap()
+        });
+        let history = HistorySummary {
+            send_count: 0,
+            receive_count: 0,
+            push_count: 0,
+            total_entries: 0,
+            error_count,
+            last_error: None,
+        };
+        Diagnostics {
+            ip: Ipv4Addr::new(192, 168, 1, 20),
+            name: None,
+            reachable,
+            rssi: None,
+            status: None,
+            system_config,
+            white_range: None,
+            extended_white_range: None,
+            fan_speed_range: None,
+            bulb_type: None,
+            history,
+            errors: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut reports = [
+            report(true, Some("1.25.0"), 0),
+            report(true, Some("1.25.0"), 2),
+            report(false, None, 3),
+        ];
+        take::<()>(&mut reports[2].errors, "status", Err("timed out".into()));
+        let summary = DiagnosticsSummary::from_reports(&reports);
+        assert_eq!(
+            (summary.lights, summary.reachable, summary.unreachable),
+            (3, 2, 1)
+        );
+        assert_eq!(summary.incomplete, 1);
+        assert_eq!(summary.error_count, 5);
+        assert_eq!(summary.fw_versions["1.25.0"], 2);
+
+        let json = reports[2].to_json();
+        assert_eq!(json["reachable"], false);
+        assert!(json.get("system_config").is_none());
+        assert_eq!(json["errors"]["status"], "timed out");
+        assert!(reports[0].to_json().get("errors").is_none());
+    }
+}
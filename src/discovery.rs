@@ -2,39 +2,413 @@
 
 use std::collections::HashMap;
 use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
 use std::time::Duration;
 
+use futures::StreamExt;
+use futures::future;
+use futures::stream::{self, BoxStream};
+#[cfg(not(target_arch = "wasm32"))]
+use if_addrs::IfAddr;
+use ipnet::Ipv4Net;
+use log::debug;
 use serde_json::{Value, json};
 
+use crate::cancel::CancelToken;
+use crate::config::BulbType;
 use crate::errors::Error;
 use crate::light::Light;
-use crate::runtime::{self, AsyncUdpSocket, Instant, UdpSocket};
+use crate::plug::Plug;
+use crate::protocol;
+use crate::runtime::{self, Datagram, Instant, Transport};
+use crate::status::{BulbStatusResult, LightStatus};
 
 type Result<T> = std::result::Result<T, Error>;
 
+const PORT: u16 = protocol::PORT;
+const SWEEP_CONCURRENCY: usize = 32;
+/// How often the discovery probe is resent by default.
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+
 /// A discovered Wiz bulb.
+///
+/// The configuration fields are only populated when discovery was run with
+/// [`DiscoveryOptions::probe_config`] enabled and the bulb answered the
+/// follow-up `getSystemConfig` query.
 #[derive(Debug, Clone)]
 pub struct DiscoveredBulb {
     pub ip: Ipv4Addr,
     pub mac: String,
+    pub module_name: Option<String>,
+    pub fw_version: Option<String>,
+    pub home_id: Option<u64>,
+    /// Room the bulb is assigned to in the WiZ app.
+    pub room_id: Option<u64>,
+    /// Group the bulb is assigned to in the WiZ app.
+    pub group_id: Option<u64>,
+    pub bulb_type: Option<BulbType>,
+    /// State the bulb reported in its reply, from firmware that answers the
+    /// probe with a getPilot-style body.
+    pub status: Option<LightStatus>,
+    /// WiFi signal strength in dBm, if the reply included it.
+    pub rssi: Option<i64>,
 }
 
 impl DiscoveredBulb {
+    pub(crate) fn new(ip: Ipv4Addr, mac: String) -> Self {
+        DiscoveredBulb {
+            ip,
+            mac,
+            module_name: None,
+            fw_version: None,
+            home_id: None,
+            room_id: None,
+            group_id: None,
+            bulb_type: None,
+            status: None,
+            rssi: None,
+        }
+    }
+
+    /// Creates a light for this bulb that follows it across IP changes.
+    ///
+    /// State captured during discovery seeds the light's cached status.
     pub fn into_light(self, name: Option<&str>) -> Light {
-        Light::new(self.ip, name)
+        let mut light = Light::new(self.ip, name);
+        light.set_mac(Some(&self.mac));
+        if let Some(status) = &self.status {
+            light.update_status(status);
+        }
+        light
+    }
+
+    /// Creates a plug for this bulb, for devices known to be smart plugs.
+    pub fn into_plug(self, name: Option<&str>) -> Plug {
+        Plug::from(self.into_light(name))
+    }
+
+    /// Queries `getSystemConfig` and fills in the configuration fields.
+    pub async fn probe_config(&mut self) -> Result<()> {
+        self.probe_config_via(runtime::default_transport()).await
+    }
+
+    async fn probe_config_via(&mut self, transport: Arc<dyn Transport>) -> Result<()> {
+        let mut light = Light::new(self.ip, None);
+        light.set_transport(transport);
+        let config = light.get_system_config().await?;
+        self.bulb_type = config
+            .module_name
+            .as_deref()
+            .map(|name| BulbType::from_module_name(name, config.fw_version.as_deref()));
+        self.module_name = config.module_name;
+        self.fw_version = config.fw_version;
+        self.home_id = config.home_id;
+        self.room_id = config.room_id;
+        self.group_id = config.group_id;
+        Ok(())
+    }
+}
+
+/// Options controlling a discovery pass.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use wiz_lights_rs::DiscoveryOptions;
+///
+/// let options = DiscoveryOptions {
+///     timeout: Duration::from_secs(3),
+///     probe_config: true,
+///     ..Default::default()
+/// };
+/// assert!(options.probe_config);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    /// How long to listen for replies.
+    pub timeout: Duration,
+    /// Follow up each reply with `getSystemConfig` to fill in model details.
+    pub probe_config: bool,
+    /// Local address to send the probe from. `None` lets the OS pick.
+    pub bind_addr: Option<Ipv4Addr>,
+    /// Address the probe is broadcast to.
+    pub broadcast_addr: Ipv4Addr,
+    /// More addresses to send the probe to, e.g. the directed broadcast
+    /// addresses of other subnets such as `192.168.2.255`.
+    pub extra_addrs: Vec<Ipv4Addr>,
+    /// Port bulbs listen on for the probe.
+    pub port: u16,
+    /// Send the probe again this often until the timeout, for bulbs that
+    /// were busy or missed the first one on lossy WiFi. Defaults to every
+    /// second; `None` sends it once.
+    pub probe_interval: Option<Duration>,
+    /// Creates the probe socket. Also used to query config when
+    /// `probe_config` is set.
+    pub transport: Arc<dyn Transport>,
+    /// Stops listening early. Bulbs that already replied are still returned.
+    pub cancel: Option<CancelToken>,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        DiscoveryOptions {
+            timeout: Duration::from_secs(5),
+            probe_config: false,
+            bind_addr: None,
+            broadcast_addr: Ipv4Addr::BROADCAST,
+            extra_addrs: Vec::new(),
+            port: PORT,
+            probe_interval: Some(PROBE_INTERVAL),
+            transport: runtime::default_transport(),
+            cancel: None,
+        }
     }
 }
 
+impl DiscoveryOptions {
+    /// Options bound to the IPv4 address of the named network interface.
+    ///
+    /// The probe is sent to the interface's directed broadcast address when
+    /// one is known, so it leaves through that interface on multi-homed hosts.
+    ///
+    /// Not available on `wasm32`, where there are no interfaces to list.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn for_interface(name: &str) -> Result<Self> {
+        let addr = interface_addr(name)?;
+        Ok(DiscoveryOptions {
+            bind_addr: Some(addr.ip),
+            broadcast_addr: addr.broadcast.unwrap_or(Ipv4Addr::BROADCAST),
+            ..Default::default()
+        })
+    }
+}
+
+/// The first IPv4 address of the named network interface.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn interface_addr(name: &str) -> Result<if_addrs::Ifv4Addr> {
+    let interfaces = if_addrs::get_if_addrs().map_err(|e| Error::socket("list interfaces", e))?;
+    interfaces
+        .into_iter()
+        .filter(|iface| iface.name == name)
+        .find_map(|iface| match iface.addr {
+            IfAddr::V4(v4) => Some(v4),
+            IfAddr::V6(_) => None,
+        })
+        .ok_or_else(|| Error::InterfaceNotFound(name.to_string()))
+}
+
 /// Discovers Wiz bulbs using UDP broadcast.
 pub async fn discover_bulbs(discovery_timeout: Duration) -> Result<Vec<DiscoveredBulb>> {
-    let socket = UdpSocket::bind("0.0.0.0:0")
-        .await
-        .map_err(|e| Error::socket("bind", e))?;
+    discover_bulbs_with(&DiscoveryOptions {
+        timeout: discovery_timeout,
+        ..Default::default()
+    })
+    .await
+}
+
+/// Discovers Wiz bulbs using UDP broadcast with the given options.
+///
+/// When [`DiscoveryOptions::probe_config`] is set, every discovered bulb is
+/// queried concurrently once the listen window closes. Bulbs that fail to
+/// answer are still returned, just without configuration details.
+pub async fn discover_bulbs_with(options: &DiscoveryOptions) -> Result<Vec<DiscoveredBulb>> {
+    let stream = discover_bulbs_stream_with(options).await?;
+    let mut bulbs = latest_per_mac(stream.collect().await);
+
+    let cancelled = options
+        .cancel
+        .as_ref()
+        .is_some_and(CancelToken::is_cancelled);
+    if options.probe_config && !cancelled {
+        future::join_all(bulbs.iter_mut().map(|bulb| async move {
+            if let Err(e) = bulb.probe_config_via(Arc::clone(&options.transport)).await {
+                debug!("Failed to probe config for {}: {}", bulb.ip, e);
+            }
+        }))
+        .await;
+    }
+
+    Ok(bulbs)
+}
+
+/// Discovers Wiz bulbs using UDP broadcast, yielding each bulb as soon as it replies.
+///
+/// The probe is sent before this function returns, so socket errors surface
+/// immediately, and is resent every second while listening. The stream ends
+/// once `discovery_timeout` has elapsed. Bulbs are deduplicated by MAC address:
+/// a bulb is yielded again only when it re-announces itself with a different
+/// IP or state, so the last item for a MAC is the most recent.
+///
+/// # Example
+///
+/// ```ignore
+/// use futures::StreamExt;
+/// use wiz_lights_rs::discover_bulbs_stream;
+///
+/// let mut bulbs = discover_bulbs_stream(Duration::from_secs(5)).await?;
+/// while let Some(bulb) = bulbs.next().await {
+///     println!("Found {} at {}", bulb.mac, bulb.ip);
+/// }
+/// ```
+pub async fn discover_bulbs_stream(
+    discovery_timeout: Duration,
+) -> Result<BoxStream<'static, DiscoveredBulb>> {
+    discover_bulbs_stream_with(&DiscoveryOptions {
+        timeout: discovery_timeout,
+        ..Default::default()
+    })
+    .await
+}
+
+/// Streaming discovery with the given options.
+///
+/// [`DiscoveryOptions::probe_config`] is ignored here; call
+/// [`DiscoveredBulb::probe_config`] on the bulbs you are interested in.
+pub async fn discover_bulbs_stream_with(
+    options: &DiscoveryOptions,
+) -> Result<BoxStream<'static, DiscoveredBulb>> {
+    let socket = bind_probe_socket(&options.transport, options.bind_addr).await?;
 
     socket
         .set_broadcast(true)
         .map_err(|e| Error::socket("set_broadcast", e))?;
 
+    let probe = Probe {
+        msg: probe_message()?,
+        targets: std::iter::once(options.broadcast_addr)
+            .chain(options.extra_addrs.iter().copied())
+            .map(|addr| SocketAddr::from((addr, options.port)))
+            .collect(),
+        interval: options.probe_interval,
+    };
+    probe.send(socket.as_ref()).await?;
+
+    Ok(responses(
+        socket,
+        options.timeout,
+        options.cancel.clone(),
+        Some(probe),
+    ))
+}
+
+/// Discovers Wiz bulbs by unicasting the probe to every host in `cidr`.
+///
+/// Useful on networks where access points filter broadcast traffic. Probes are
+/// sent with bounded concurrency while replies are collected until
+/// `discovery_timeout` elapses.
+///
+/// # Example
+///
+/// ```ignore
+/// use wiz_lights_rs::{Ipv4Net, discover_in_subnet};
+///
+/// let bulbs = discover_in_subnet("192.168.1.0/24".parse()?, Duration::from_secs(5)).await?;
+/// ```
+pub async fn discover_in_subnet(
+    cidr: Ipv4Net,
+    discovery_timeout: Duration,
+) -> Result<Vec<DiscoveredBulb>> {
+    discover_in_subnet_with(
+        cidr,
+        &DiscoveryOptions {
+            timeout: discovery_timeout,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Like [`discover_in_subnet`], with the given options.
+///
+/// The probe is unicast to every host, so [`DiscoveryOptions::broadcast_addr`],
+/// [`DiscoveryOptions::extra_addrs`] and [`DiscoveryOptions::probe_interval`]
+/// are ignored. Cancelling stops both the sweep and listening.
+pub async fn discover_in_subnet_with(
+    cidr: Ipv4Net,
+    options: &DiscoveryOptions,
+) -> Result<Vec<DiscoveredBulb>> {
+    let socket = bind_probe_socket(&options.transport, options.bind_addr).await?;
+    let msg = probe_message()?;
+    let cancelled = || {
+        options
+            .cancel
+            .as_ref()
+            .is_some_and(CancelToken::is_cancelled)
+    };
+
+    let send_probes = stream::iter(cidr.hosts()).for_each_concurrent(SWEEP_CONCURRENCY, |host| {
+        let socket = &socket;
+        let msg = &msg;
+        async move {
+            if cancelled() {
+                return;
+            }
+            let target = SocketAddr::from((host, options.port));
+            if let Err(e) = socket.send_to(msg, target).await {
+                debug!("Failed to probe {}: {}", host, e);
+            }
+        }
+    });
+    let collect = responses(
+        Arc::clone(&socket),
+        options.timeout,
+        options.cancel.clone(),
+        None,
+    )
+    .collect::<Vec<_>>();
+
+    let ((), bulbs) = future::join(send_probes, collect).await;
+    let mut bulbs = latest_per_mac(bulbs);
+
+    if options.probe_config && !cancelled() {
+        future::join_all(bulbs.iter_mut().map(|bulb| async move {
+            if let Err(e) = bulb.probe_config_via(Arc::clone(&options.transport)).await {
+                debug!("Failed to probe config for {}: {}", bulb.ip, e);
+            }
+        }))
+        .await;
+    }
+
+    Ok(bulbs)
+}
+
+/// Keeps the last reply of each bulb, in the order bulbs first answered.
+fn latest_per_mac(bulbs: Vec<DiscoveredBulb>) -> Vec<DiscoveredBulb> {
+    let mut latest: Vec<DiscoveredBulb> = Vec::with_capacity(bulbs.len());
+    for bulb in bulbs {
+        match latest.iter_mut().find(|known| known.mac == bulb.mac) {
+            Some(known) => *known = bulb,
+            None => latest.push(bulb),
+        }
+    }
+    latest
+}
+
+/// Whether `bulb` is news compared to the last reply seen from its MAC.
+fn is_update(seen: &mut HashMap<String, DiscoveredBulb>, bulb: &DiscoveredBulb) -> bool {
+    let changed = seen
+        .get(&bulb.mac)
+        .is_none_or(|last| last.ip != bulb.ip || last.status != bulb.status);
+    if changed {
+        seen.insert(bulb.mac.clone(), bulb.clone());
+    }
+    changed
+}
+
+async fn bind_probe_socket(
+    transport: &Arc<dyn Transport>,
+    bind_addr: Option<Ipv4Addr>,
+) -> Result<Arc<dyn Datagram>> {
+    let bind_addr = bind_addr.unwrap_or(Ipv4Addr::UNSPECIFIED);
+    transport
+        .bind(SocketAddr::from((bind_addr, 0)))
+        .await
+        .map(Arc::from)
+        .map_err(|e| Error::socket("bind", e))
+}
+
+fn probe_message() -> Result<Vec<u8>> {
     let msg = json!({
         "method": "registration",
         "params": {
@@ -44,44 +418,189 @@ pub async fn discover_bulbs(discovery_timeout: Duration) -> Result<Vec<Discovere
             "id": "1"
         }
     });
-    let msg_bytes = serde_json::to_vec(&msg).map_err(Error::JsonDump)?;
+    serde_json::to_vec(&msg).map_err(Error::JsonDump)
+}
 
-    socket
-        .send_to(&msg_bytes, "255.255.255.255:38899")
-        .await
-        .map_err(|e| Error::socket("send_to", e))?;
-
-    let mut discovered: HashMap<String, DiscoveredBulb> = HashMap::new();
-    let start = Instant::now();
-    let mut buffer = [0u8; 4096];
-    let recv_timeout = Duration::from_millis(500);
-
-    while start.elapsed() < discovery_timeout {
-        // Use runtime-agnostic timeout for each recv_from operation
-        match runtime::timeout(recv_timeout, socket.recv_from(&mut buffer)).await {
-            Ok(Ok((size, addr))) => {
-                if let Ok(response) = String::from_utf8(buffer[..size].to_vec())
-                    && let Ok(json) = serde_json::from_str::<Value>(&response)
-                    && let Some(mac) = extract_mac(&json)
-                {
-                    let ip = match addr {
-                        SocketAddr::V4(v4) => *v4.ip(),
-                        SocketAddr::V6(_) => continue,
-                    };
-                    discovered.insert(mac.clone(), DiscoveredBulb { ip, mac });
+/// A discovery probe and where it is sent.
+struct Probe {
+    msg: Vec<u8>,
+    targets: Vec<SocketAddr>,
+    /// How often the probe is resent while listening.
+    interval: Option<Duration>,
+}
+
+impl Probe {
+    /// Sends the probe to every target.
+    ///
+    /// Fails only if no target could be sent to, so one unreachable subnet
+    /// does not stop discovery on the others.
+    async fn send(&self, socket: &dyn Datagram) -> Result<()> {
+        let mut last_error = None;
+        let mut sent = false;
+        for target in &self.targets {
+            match socket.send_to(&self.msg, *target).await {
+                Ok(_) => sent = true,
+                Err(e) => {
+                    debug!("Failed to send probe to {}: {}", target, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if !sent => Err(Error::socket("send_to", e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Yields new and changed bulb replies received on `socket` until the timeout
+/// elapses or `cancel` is cancelled, resending `probe` on its interval.
+fn responses(
+    socket: Arc<dyn Datagram>,
+    discovery_timeout: Duration,
+    cancel: Option<CancelToken>,
+    probe: Option<Probe>,
+) -> BoxStream<'static, DiscoveredBulb> {
+    let cancel = cancel.unwrap_or_default();
+    let probe = Arc::new(probe);
+    let interval = probe.as_ref().as_ref().and_then(|p| p.interval);
+    let state = (socket, Instant::now(), HashMap::new(), interval);
+    let stream = stream::unfold(state, move |(socket, start, mut seen, mut next_probe)| {
+        let cancel = cancel.clone();
+        let probe = Arc::clone(&probe);
+        async move {
+            let mut buffer = [0u8; 4096];
+            let mut recv_timeout = Duration::from_millis(500);
+
+            while start.elapsed() < discovery_timeout {
+                if let (Some(probe), Some(at)) = (probe.as_ref(), next_probe) {
+                    let elapsed = start.elapsed();
+                    if elapsed >= at {
+                        if let Err(e) = probe.send(socket.as_ref()).await {
+                            debug!("Failed to resend discovery probe: {}", e);
+                        }
+                        next_probe = probe.interval.map(|interval| at + interval);
+                    }
+                    // Wake up in time for the next probe
+                    if let Some(at) = next_probe {
+                        recv_timeout = recv_timeout.min(at.saturating_sub(elapsed));
+                    }
+                }
+
+                let remaining = discovery_timeout.saturating_sub(start.elapsed());
+                // Use runtime-agnostic timeout for each recv_from operation
+                let recv =
+                    runtime::timeout(recv_timeout.min(remaining), socket.recv_from(&mut buffer));
+                match cancel.run(recv).await {
+                    Err(_) => return None,
+                    Ok(Ok(Ok((size, addr)))) => {
+                        if let Some(bulb) = parse_response(&buffer[..size], addr)
+                            && is_update(&mut seen, &bulb)
+                        {
+                            return Some((bulb, (socket, start, seen, next_probe)));
+                        }
+                    }
+                    // Timeout elapsed - continue loop to check overall timeout
+                    Ok(Ok(Err(_)) | Err(_)) => continue,
                 }
             }
-            // Timeout elapsed - continue loop to check overall timeout
-            Ok(Err(_)) | Err(_) => continue,
+            None
         }
+    });
+
+    stream.boxed()
+}
+
+fn parse_response(data: &[u8], addr: SocketAddr) -> Option<DiscoveredBulb> {
+    let SocketAddr::V4(v4) = addr else {
+        return None;
+    };
+    let response = std::str::from_utf8(data).ok()?;
+    let json = serde_json::from_str::<Value>(response).ok()?;
+    let body = reply_body(&json)?;
+    let mac = body.get("mac")?.as_str()?;
+
+    let mut bulb = DiscoveredBulb::new(*v4.ip(), mac.to_string());
+    bulb.rssi = body.get("rssi").and_then(Value::as_i64);
+    if body.get("state").is_some() {
+        bulb.status = serde_json::from_value::<BulbStatusResult>(body.clone())
+            .ok()
+            .map(|result| LightStatus::from(&result));
     }
+    Some(bulb)
+}
 
-    Ok(discovered.into_values().collect())
+/// The object holding the bulb's fields in a discovery reply.
+///
+/// Most firmware answers the probe with `result.mac`, next to the state when
+/// the reply is shaped like `getPilot`. Some sends a `syncPilot` or
+/// `firstBeat` with the fields under `params`, and some puts them at the top
+/// level.
+fn reply_body(json: &Value) -> Option<&Value> {
+    ["result", "params"]
+        .iter()
+        .filter_map(|key| json.get(key))
+        .chain(std::iter::once(json))
+        .find(|body| body.get("mac").is_some_and(Value::is_string))
 }
 
-fn extract_mac(json: &Value) -> Option<String> {
-    json.get("result")
-        .and_then(|r| r.get("mac"))
-        .and_then(|m| m.as_str())
-        .map(String::from)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(reply: Value) -> Option<DiscoveredBulb> {
+        let addr = SocketAddr::from(([192, 168, 1, 20], PORT));
+        parse_response(reply.to_string().as_bytes(), addr)
+    }
+
+    #[test]
+    fn test_parse_response_shapes() {
+        let bulb = parse(
+            json!({"method": "registration", "result": {"mac": "a8bb50aabbcc", "success": true}}),
+        )
+        .unwrap();
+        assert_eq!(bulb.mac, "a8bb50aabbcc");
+        assert!(bulb.status.is_none());
+
+        let bulb = parse(json!({
+            "method": "getPilot",
+            "env": "pro",
+            "result": {"mac": "a8bb50aabbcc", "rssi": -60, "state": true, "sceneId": 0, "dimming": 40}
+        }))
+        .unwrap();
+        assert_eq!(bulb.rssi, Some(-60));
+        let status = bulb.status.unwrap();
+        assert!(status.emitting());
+        assert_eq!(status.brightness().map(|b| b.value()), Some(40));
+
+        let bulb = parse(
+            json!({"method": "syncPilot", "params": {"mac": "a8bb50aabbcc", "state": false}}),
+        )
+        .unwrap();
+        assert!(!bulb.status.unwrap().emitting());
+
+        assert_eq!(
+            parse(json!({"mac": "a8bb50aabbcc"})).unwrap().mac,
+            "a8bb50aabbcc"
+        );
+        assert!(parse(json!({"result": {"success": true}})).is_none());
+        assert!(parse_response(b"not json", SocketAddr::from(([192, 168, 1, 20], PORT))).is_none());
+    }
+
+    #[test]
+    fn test_keeps_latest_reply() {
+        let mut seen = HashMap::new();
+        let first = DiscoveredBulb::new(Ipv4Addr::new(192, 168, 1, 20), "a8bb50aabbcc".into());
+        let other = DiscoveredBulb::new(Ipv4Addr::new(192, 168, 1, 21), "a8bb50ddeeff".into());
+        let moved = DiscoveredBulb::new(Ipv4Addr::new(192, 168, 1, 30), "a8bb50aabbcc".into());
+
+        assert!(is_update(&mut seen, &first));
+        assert!(!is_update(&mut seen, &first.clone()));
+        assert!(is_update(&mut seen, &other));
+        assert!(is_update(&mut seen, &moved));
+
+        let bulbs = latest_per_mac(vec![first, other, moved]);
+        let ips: Vec<_> = bulbs.iter().map(|bulb| bulb.ip.octets()[3]).collect();
+        assert_eq!(ips, [30, 21]);
+    }
 }
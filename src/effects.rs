@@ -0,0 +1,849 @@
+//! Software effects built from keyframe timelines.
+//!
+//! An [`Effect`] is a list of [`Keyframe`]s, each giving a color, brightness
+//! and/or temperature at an offset from the start of the effect. Frames in
+//! between are interpolated with the target keyframe's [`Easing`] and sent to
+//! the bulbs as ordinary `setPilot` commands, so any combination of values can
+//! be animated, not just the built-in [`SceneMode`](crate::SceneMode)s.
+//!
+//! Ready-made effects are available as [`Effect::breathe`],
+//! [`Effect::rainbow`], [`Effect::candle`] and [`Effect::strobe`].
+//!
+//! A [`Sequencer`] choreographs several lights instead: each gets its own
+//! steps, timed against one shared clock.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use wiz_lights_rs::Color;
+//! use wiz_lights_rs::effects::{Easing, Effect, Keyframe, Playback};
+//!
+//! let effect = Effect::new()
+//!     .keyframe(Keyframe::new(Duration::ZERO).color(Color::rgb(255, 0, 0)))
+//!     .keyframe(
+//!         Keyframe::new(Duration::from_secs(2))
+//!             .color(Color::rgb(0, 0, 255))
+//!             .easing(Easing::EaseInOut),
+//!     )
+//!     .playback(Playback::PingPong);
+//!
+//! let handle = effect.run_on_light(&light);
+//! // ... later ...
+//! handle.stop().await;
+//! ```
+
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use futures::future;
+use log::debug;
+
+use crate::cancel::CancelToken;
+use crate::light::Light;
+use crate::payload::Payload;
+use crate::room::Room;
+use crate::runtime::{self, Instant, JoinHandle, Mutex};
+use crate::types::{Brightness, Color, Kelvin};
+
+/// How progress between two keyframes maps to interpolated values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// Hold the previous keyframe until this one is reached.
+    Step,
+}
+
+impl Easing {
+    /// Map linear progress `t` in `0.0..=1.0` to eased progress.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
+
+/// What happens when the timeline reaches its last keyframe.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Playback {
+    /// Stop on the last keyframe.
+    #[default]
+    Once,
+    /// Jump back to the first keyframe.
+    Loop,
+    /// Play backwards to the first keyframe, then forwards again.
+    PingPong,
+}
+
+/// Target values at a point on an effect's timeline.
+#[derive(Debug, Clone, Default)]
+pub struct Keyframe {
+    pub at: Duration,
+    pub color: Option<Color>,
+    pub brightness: Option<Brightness>,
+    pub temp: Option<Kelvin>,
+    /// Easing used when approaching this keyframe from the previous one.
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub fn new(at: Duration) -> Self {
+        Keyframe {
+            at,
+            ..Default::default()
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn brightness(mut self, brightness: Brightness) -> Self {
+        self.brightness = Some(brightness);
+        self
+    }
+
+    pub fn temp(mut self, temp: Kelvin) -> Self {
+        self.temp = Some(temp);
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+}
+
+/// A keyframe timeline that can be played on lights.
+#[derive(Debug, Clone)]
+pub struct Effect {
+    keyframes: Vec<Keyframe>,
+    playback: Playback,
+    frame_interval: Duration,
+}
+
+impl Default for Effect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect {
+    /// Default time between frames; roughly what bulbs handle reliably.
+    pub const DEFAULT_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+    pub fn new() -> Self {
+        Effect {
+            keyframes: Vec::new(),
+            playback: Playback::Once,
+            frame_interval: Self::DEFAULT_FRAME_INTERVAL,
+        }
+    }
+
+    /// Add a keyframe. Keyframes are kept ordered by their offset.
+    pub fn keyframe(mut self, keyframe: Keyframe) -> Self {
+        let index = self.keyframes.partition_point(|k| k.at <= keyframe.at);
+        self.keyframes.insert(index, keyframe);
+        self
+    }
+
+    pub fn playback(mut self, playback: Playback) -> Self {
+        self.playback = playback;
+        self
+    }
+
+    pub fn frame_interval(mut self, interval: Duration) -> Self {
+        self.frame_interval = interval;
+        self
+    }
+
+    /// Slowly pulse `color` between dim and full brightness.
+    ///
+    /// `period` is the time for one full dim-bright-dim cycle.
+    pub fn breathe(color: Color, period: Duration) -> Self {
+        Effect::new()
+            .keyframe(
+                Keyframe::new(Duration::ZERO)
+                    .color(color.clone())
+                    .brightness(Brightness::create_or(10)),
+            )
+            .keyframe(
+                Keyframe::new(period / 2)
+                    .color(color)
+                    .brightness(Brightness::create_or(100))
+                    .easing(Easing::EaseInOut),
+            )
+            .playback(Playback::PingPong)
+    }
+
+    /// Cycle through the color wheel once every `period`.
+    pub fn rainbow(period: Duration) -> Self {
+        const HUES: [(u8, u8, u8); 7] = [
+            (255, 0, 0),
+            (255, 255, 0),
+            (0, 255, 0),
+            (0, 255, 255),
+            (0, 0, 255),
+            (255, 0, 255),
+            (255, 0, 0),
+        ];
+        HUES.iter()
+            .enumerate()
+            .fold(Effect::new(), |effect, (i, &(r, g, b))| {
+                effect.keyframe(Keyframe::new(period * i as u32 / 6).color(Color::rgb(r, g, b)))
+            })
+            .playback(Playback::Loop)
+    }
+
+    /// Warm, irregular flicker resembling a candle flame.
+    pub fn candle() -> Self {
+        // Fixed pattern of (offset ms, brightness); irregular enough not to read as a loop
+        const FLICKER: [(u64, u8); 12] = [
+            (0, 60),
+            (150, 45),
+            (250, 70),
+            (450, 55),
+            (600, 80),
+            (700, 50),
+            (950, 65),
+            (1100, 40),
+            (1300, 75),
+            (1450, 60),
+            (1700, 50),
+            (1900, 60),
+        ];
+        FLICKER
+            .iter()
+            .fold(Effect::new(), |effect, &(ms, dimming)| {
+                effect.keyframe(
+                    Keyframe::new(Duration::from_millis(ms))
+                        .color(Color::rgb(255, 120, 20))
+                        .brightness(Brightness::create_or(dimming))
+                        .easing(Easing::EaseOut),
+                )
+            })
+            .playback(Playback::Loop)
+    }
+
+    /// Alternate between full and minimum brightness `hz` times per second.
+    ///
+    /// `setPilot` cannot dim below 10%, so the off phase is dim rather than
+    /// dark. Bulbs drop commands above roughly 10 messages per second, so
+    /// rates above 5 Hz will not be rendered faithfully.
+    pub fn strobe(hz: f64) -> Self {
+        let period = Duration::from_secs_f64(1.0 / hz.max(0.1));
+        let bright = |at| {
+            Keyframe::new(at)
+                .brightness(Brightness::create_or(100))
+                .easing(Easing::Step)
+        };
+        Effect::new()
+            .keyframe(bright(Duration::ZERO))
+            .keyframe(
+                Keyframe::new(period / 2)
+                    .brightness(Brightness::create_or(10))
+                    .easing(Easing::Step),
+            )
+            .keyframe(bright(period))
+            .playback(Playback::Loop)
+            .frame_interval(period / 2)
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Length of one pass through the timeline.
+    pub fn duration(&self) -> Duration {
+        self.keyframes.last().map(|k| k.at).unwrap_or_default()
+    }
+
+    /// The payload to show `elapsed` after the effect started.
+    ///
+    /// Returns `None` when there are no keyframes or the first keyframe has
+    /// not been reached yet. A [`Playback::Once`] effect holds its last
+    /// keyframe once it is over.
+    pub fn sample(&self, elapsed: Duration) -> Option<Payload> {
+        let position = self.position(elapsed);
+
+        let index = self.keyframes.partition_point(|k| k.at <= position);
+        let (from, to) = match self.keyframes.get(index) {
+            Some(_) if index == 0 => return None,
+            Some(to) => (&self.keyframes[index - 1], to),
+            None => {
+                let last = self.keyframes.last()?;
+                (last, last)
+            }
+        };
+
+        let span = to.at.saturating_sub(from.at).as_secs_f64();
+        let t = if span > 0.0 {
+            to.easing.apply((position - from.at).as_secs_f64() / span)
+        } else {
+            1.0
+        };
+
+        let mut payload = Payload::new();
+        if let Some(color) = lerp_field(&from.color, &to.color, t, lerp_color) {
+            payload.color(&color);
+        }
+        if let Some(brightness) = lerp_field(&from.brightness, &to.brightness, t, |a, b, t| {
+            Brightness::create_or(lerp(a.value() as f64, b.value() as f64, t) as u8)
+        }) {
+            payload.brightness(&brightness);
+        }
+        if let Some(temp) = lerp_field(&from.temp, &to.temp, t, |a, b, t| {
+            Kelvin::create(lerp(a.kelvin() as f64, b.kelvin() as f64, t) as u16)
+                .unwrap_or_else(|| b.clone())
+        }) {
+            payload.temp(&temp);
+        }
+        Some(payload)
+    }
+
+    /// Whether a one-shot effect has reached its last keyframe.
+    ///
+    /// Looping effects never finish.
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        self.playback == Playback::Once && elapsed >= self.duration()
+    }
+
+    /// Position on the timeline for the playback mode.
+    fn position(&self, elapsed: Duration) -> Duration {
+        let duration = self.duration();
+        if duration.is_zero() {
+            return duration;
+        }
+
+        match self.playback {
+            Playback::Once => elapsed.min(duration),
+            Playback::Loop => {
+                Duration::from_nanos((elapsed.as_nanos() % duration.as_nanos()) as u64)
+            }
+            Playback::PingPong => {
+                let cycle = (elapsed.as_nanos() % (2 * duration.as_nanos())) as u64;
+                let cycle = Duration::from_nanos(cycle);
+                if cycle <= duration {
+                    cycle
+                } else {
+                    2 * duration - cycle
+                }
+            }
+        }
+    }
+
+    /// Play the effect on a single light.
+    pub fn run_on_light(self, light: &Light) -> EffectHandle {
+        self.run(vec![light.clone()])
+    }
+
+    /// Play the effect in sync on every light in the room.
+    pub fn run_on_room(self, room: &Room) -> EffectHandle {
+        let lights = room
+            .list()
+            .into_iter()
+            .flatten()
+            .filter_map(|id| room.read(id))
+            .cloned()
+            .collect();
+        self.run(lights)
+    }
+
+    /// Play the effect in sync on the given lights.
+    ///
+    /// Every light follows the same clock, so a light that is slow to answer
+    /// skips frames instead of falling behind the others. Each frame is sent
+    /// once, without retries, and is not recorded in the light's history.
+    pub fn run(self, lights: Vec<Light>) -> EffectHandle {
+        self.run_with_cancel(lights, CancelToken::new())
+    }
+
+    /// Like [`Effect::run`], but playback also stops once `cancel` is
+    /// cancelled, so one token can stop effects together with other work.
+    pub fn run_with_cancel(self, lights: Vec<Light>, cancel: CancelToken) -> EffectHandle {
+        let running = Arc::new(AtomicBool::new(true));
+        let effect = Arc::new(self);
+
+        let task_running = Arc::clone(&running);
+        let handle = runtime::spawn(async move {
+            let start = Instant::now();
+            future::join_all(lights.iter().map(|light| {
+                let effect = &effect;
+                let running = &task_running;
+                let cancel = &cancel;
+                async move {
+                    if effect.keyframes.is_empty() {
+                        return;
+                    }
+                    while running.load(Ordering::SeqCst) && !cancel.is_cancelled() {
+                        let frame_start = Instant::now();
+                        let elapsed = start.elapsed();
+                        // Nothing is sent until the first keyframe is reached
+                        if let Some(payload) = effect.sample(elapsed) {
+                            if let Err(e) = light.send_frame(&payload, effect.frame_interval).await
+                            {
+                                debug!("Effect frame for {} failed: {}", light.ip(), e);
+                            }
+                        }
+                        if effect.is_finished(elapsed) {
+                            break;
+                        }
+                        let spent = frame_start.elapsed();
+                        if spent < effect.frame_interval {
+                            let _ = cancel
+                                .run(runtime::sleep(effect.frame_interval - spent))
+                                .await;
+                        }
+                    }
+                }
+            }))
+            .await;
+            task_running.store(false, Ordering::SeqCst);
+        });
+
+        EffectHandle {
+            running,
+            task: Mutex::new(Some(handle)),
+        }
+    }
+}
+
+/// Timed steps across several lights, played on one shared clock.
+///
+/// Each step is a [`Keyframe`] for one light, or for a group of lights with
+/// [`Sequencer::step_all`]. Every light follows its own timeline with the
+/// keyframes' easing, and steps at the same offset land together. One pass
+/// lasts until the last step, or [`Sequencer::length`] if longer, and is
+/// repeated [`Sequencer::loops`] times.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::time::Duration;
+/// use wiz_lights_rs::Color;
+/// use wiz_lights_rs::effects::{Keyframe, Sequencer};
+///
+/// let red = |at| Keyframe::new(at).color(Color::rgb(255, 0, 0));
+/// let blue = |at| Keyframe::new(at).color(Color::rgb(0, 0, 255));
+/// let handle = Sequencer::new()
+///     .step(&porch, red(Duration::ZERO))
+///     .step(&tree, blue(Duration::from_millis(500)))
+///     .step_all([&porch, &tree], blue(Duration::from_secs(1)))
+///     .length(Duration::from_secs(2))
+///     .forever()
+///     .run();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sequencer {
+    tracks: Vec<Track>,
+    length: Duration,
+    /// Number of passes; `None` repeats forever.
+    loops: Option<u32>,
+    frame_interval: Duration,
+}
+
+/// The timeline of one light in a [`Sequencer`].
+#[derive(Debug, Clone)]
+struct Track {
+    light: Light,
+    timeline: Effect,
+}
+
+impl Default for Sequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sequencer {
+    pub fn new() -> Self {
+        Sequencer {
+            tracks: Vec::new(),
+            length: Duration::ZERO,
+            loops: Some(1),
+            frame_interval: Effect::DEFAULT_FRAME_INTERVAL,
+        }
+    }
+
+    /// Add a step for `light`.
+    pub fn step(mut self, light: &Light, keyframe: Keyframe) -> Self {
+        let index = match self.tracks.iter().position(|t| t.light.ip() == light.ip()) {
+            Some(index) => index,
+            None => {
+                self.tracks.push(Track {
+                    light: light.clone(),
+                    timeline: Effect::new(),
+                });
+                self.tracks.len() - 1
+            }
+        };
+        let track = &mut self.tracks[index];
+        track.timeline = std::mem::take(&mut track.timeline).keyframe(keyframe);
+        self
+    }
+
+    /// Add the same step for every light in `lights`, so they change together.
+    pub fn step_all<'a>(
+        self,
+        lights: impl IntoIterator<Item = &'a Light>,
+        keyframe: Keyframe,
+    ) -> Self {
+        lights.into_iter().fold(self, |sequencer, light| {
+            sequencer.step(light, keyframe.clone())
+        })
+    }
+
+    /// Minimum length of a pass, to pause after the last step before looping.
+    pub fn length(mut self, length: Duration) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Play the sequence `count` times.
+    pub fn loops(mut self, count: u32) -> Self {
+        self.loops = Some(count);
+        self
+    }
+
+    /// Repeat the sequence until stopped.
+    pub fn forever(mut self) -> Self {
+        self.loops = None;
+        self
+    }
+
+    pub fn frame_interval(mut self, interval: Duration) -> Self {
+        self.frame_interval = interval;
+        self
+    }
+
+    /// Length of one pass.
+    pub fn duration(&self) -> Duration {
+        self.tracks
+            .iter()
+            .map(|track| track.timeline.duration())
+            .fold(self.length, Duration::max)
+    }
+
+    /// Whether every pass has been played.
+    ///
+    /// Sequences repeated forever never finish.
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        self.loops
+            .is_some_and(|loops| elapsed >= self.duration().saturating_mul(loops))
+    }
+
+    /// The payload for each light `elapsed` after the sequence started.
+    ///
+    /// Lights without a step yet are left out, and lights hold their last
+    /// step once the sequence is finished.
+    pub fn sample(&self, elapsed: Duration) -> Vec<(Ipv4Addr, Payload)> {
+        let position = self.position(elapsed);
+        self.tracks
+            .iter()
+            .filter_map(|track| Some((track.light.ip(), track.timeline.sample(position)?)))
+            .collect()
+    }
+
+    /// Position within the current pass.
+    fn position(&self, elapsed: Duration) -> Duration {
+        let duration = self.duration();
+        if duration.is_zero() || self.is_finished(elapsed) {
+            return duration;
+        }
+        Duration::from_nanos((elapsed.as_nanos() % duration.as_nanos()) as u64)
+    }
+
+    /// Play the sequence in the background.
+    pub fn run(self) -> EffectHandle {
+        self.run_with_cancel(CancelToken::new())
+    }
+
+    /// Like [`Sequencer::run`], but playback also stops once `cancel` is
+    /// cancelled.
+    ///
+    /// As with [`Effect::run`], a light that is slow to answer skips frames
+    /// instead of falling behind the others. A frame is only sent when it
+    /// differs from the previous one, so held steps cost no traffic.
+    pub fn run_with_cancel(self, cancel: CancelToken) -> EffectHandle {
+        let running = Arc::new(AtomicBool::new(true));
+        let sequencer = Arc::new(self);
+
+        let task_running = Arc::clone(&running);
+        let handle = runtime::spawn(async move {
+            let start = Instant::now();
+            future::join_all(sequencer.tracks.iter().map(|track| {
+                let sequencer = &sequencer;
+                let running = &task_running;
+                let cancel = &cancel;
+                async move {
+                    let mut last = None;
+                    while running.load(Ordering::SeqCst) && !cancel.is_cancelled() {
+                        let frame_start = Instant::now();
+                        let elapsed = start.elapsed();
+                        let payload = track.timeline.sample(sequencer.position(elapsed));
+                        // A light is left alone until its first step
+                        if let Some(payload) = payload.filter(|p| last.as_ref() != Some(p)) {
+                            match track
+                                .light
+                                .send_frame(&payload, sequencer.frame_interval)
+                                .await
+                            {
+                                Ok(_) => last = Some(payload),
+                                Err(e) => {
+                                    debug!("Sequence step for {} failed: {}", track.light.ip(), e)
+                                }
+                            }
+                        }
+                        if sequencer.is_finished(elapsed) {
+                            break;
+                        }
+                        let spent = frame_start.elapsed();
+                        if spent < sequencer.frame_interval {
+                            let _ = cancel
+                                .run(runtime::sleep(sequencer.frame_interval - spent))
+                                .await;
+                        }
+                    }
+                }
+            }))
+            .await;
+            task_running.store(false, Ordering::SeqCst);
+        });
+
+        EffectHandle {
+            running,
+            task: Mutex::new(Some(handle)),
+        }
+    }
+}
+
+/// Controls an effect playing in the background.
+///
+/// Dropping the handle cancels the effect after its current frame.
+pub struct EffectHandle {
+    running: Arc<AtomicBool>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl EffectHandle {
+    /// Whether the effect is still playing.
+    ///
+    /// One-shot effects stop on their own after the last keyframe.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Cancel the effect and wait for the background task to finish.
+    pub async fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.wait().await;
+    }
+
+    /// Wait for the effect to finish without cancelling it.
+    pub async fn wait(&self) {
+        if let Some(h) = self.task.lock().await.take() {
+            let _ = h.await;
+        }
+    }
+}
+
+impl Drop for EffectHandle {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    (a + (b - a) * t).round()
+}
+
+fn lerp_color(a: &Color, b: &Color, t: f64) -> Color {
+    Color::rgb(
+        lerp(a.red() as f64, b.red() as f64, t) as u8,
+        lerp(a.green() as f64, b.green() as f64, t) as u8,
+        lerp(a.blue() as f64, b.blue() as f64, t) as u8,
+    )
+}
+
+/// Interpolates when both ends are set, otherwise holds whichever is.
+fn lerp_field<T: Clone>(
+    from: &Option<T>,
+    to: &Option<T>,
+    t: f64,
+    f: impl Fn(&T, &T, f64) -> T,
+) -> Option<T> {
+    match (from, to) {
+        (Some(a), Some(b)) => Some(f(a, b, t)),
+        (Some(a), None) => Some(a.clone()),
+        (None, Some(b)) => Some(b.clone()),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn red_to_blue(playback: Playback) -> Effect {
+        Effect::new()
+            .keyframe(Keyframe::new(Duration::from_secs(2)).color(Color::rgb(0, 0, 255)))
+            .keyframe(Keyframe::new(Duration::ZERO).color(Color::rgb(255, 0, 0)))
+            .playback(playback)
+    }
+
+    fn rgb(payload: &Payload) -> (u8, u8, u8) {
+        (
+            payload.red.unwrap(),
+            payload.green.unwrap(),
+            payload.blue.unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_interpolates_between_keyframes() {
+        let effect = red_to_blue(Playback::Once);
+        assert_eq!(effect.duration(), Duration::from_secs(2));
+
+        let mid = effect.sample(Duration::from_secs(1)).unwrap();
+        assert_eq!(rgb(&mid), (128, 0, 128));
+        let end = effect.sample(Duration::from_secs(2)).unwrap();
+        assert_eq!(rgb(&end), (0, 0, 255));
+        let after = effect.sample(Duration::from_secs(3)).unwrap();
+        assert_eq!(rgb(&after), (0, 0, 255));
+        assert!(!effect.is_finished(Duration::from_secs(1)));
+        assert!(effect.is_finished(Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn test_loop_and_ping_pong() {
+        let looped = red_to_blue(Playback::Loop);
+        let sample = looped.sample(Duration::from_millis(2500)).unwrap();
+        assert_eq!(rgb(&sample), (191, 0, 64));
+
+        let ping_pong = red_to_blue(Playback::PingPong);
+        let sample = ping_pong.sample(Duration::from_secs(3)).unwrap();
+        assert_eq!(rgb(&sample), (128, 0, 128));
+        let sample = ping_pong.sample(Duration::from_secs(4)).unwrap();
+        assert_eq!(rgb(&sample), (255, 0, 0));
+    }
+
+    #[test]
+    fn test_breathe_pulses_brightness() {
+        let effect = Effect::breathe(Color::rgb(0, 255, 0), Duration::from_secs(4));
+        let dim = effect.sample(Duration::ZERO).unwrap();
+        let bright = effect.sample(Duration::from_secs(2)).unwrap();
+        let dim_again = effect.sample(Duration::from_secs(4)).unwrap();
+        assert_eq!(dim.dimming, Some(10));
+        assert_eq!(bright.dimming, Some(100));
+        assert_eq!(dim_again.dimming, Some(10));
+        assert_eq!(rgb(&bright), (0, 255, 0));
+    }
+
+    #[test]
+    fn test_strobe_alternates() {
+        let effect = Effect::strobe(2.0);
+        assert_eq!(
+            effect.sample(Duration::from_millis(100)).unwrap().dimming,
+            Some(100)
+        );
+        assert_eq!(
+            effect.sample(Duration::from_millis(300)).unwrap().dimming,
+            Some(10)
+        );
+        assert_eq!(
+            effect.sample(Duration::from_millis(600)).unwrap().dimming,
+            Some(100)
+        );
+    }
+
+    #[test]
+    fn test_sequencer_steps_and_loops() {
+        let (a, b) = (
+            Light::new(Ipv4Addr::new(192, 168, 1, 20), None),
+            Light::new(Ipv4Addr::new(192, 168, 1, 21), None),
+        );
+        let red = Keyframe::new(Duration::ZERO).color(Color::rgb(255, 0, 0));
+        let blue = |at| Keyframe::new(at).color(Color::rgb(0, 0, 255));
+        let sequencer = Sequencer::new()
+            .step(&a, red.clone())
+            .step(&b, red)
+            .step(&b, blue(Duration::from_millis(500)).easing(Easing::Step))
+            .step_all([&a, &b], blue(Duration::from_secs(1)))
+            .length(Duration::from_secs(2))
+            .loops(2);
+        assert_eq!(sequencer.duration(), Duration::from_secs(2));
+
+        let colors = |ms| -> Vec<_> {
+            sequencer
+                .sample(Duration::from_millis(ms))
+                .iter()
+                .map(|(_, payload)| rgb(payload))
+                .collect()
+        };
+        assert_eq!(colors(250), [(191, 0, 64), (255, 0, 0)]);
+        assert_eq!(colors(600), [(102, 0, 153), (0, 0, 255)]);
+        assert_eq!(colors(1500), [(0, 0, 255), (0, 0, 255)]);
+        // The second pass starts over, then the last step is held
+        assert_eq!(colors(2000), [(255, 0, 0), (255, 0, 0)]);
+        assert!(!sequencer.is_finished(Duration::from_secs(3)));
+        assert!(sequencer.is_finished(Duration::from_secs(4)));
+        assert_eq!(colors(9000), [(0, 0, 255), (0, 0, 255)]);
+    }
+
+    #[test]
+    fn test_light_untouched_before_first_step() {
+        let (a, b) = (
+            Light::new(Ipv4Addr::new(192, 168, 1, 20), None),
+            Light::new(Ipv4Addr::new(192, 168, 1, 21), None),
+        );
+        let red = |at| Keyframe::new(at).color(Color::rgb(255, 0, 0));
+        let sequencer = Sequencer::new()
+            .step(&a, red(Duration::ZERO))
+            .step(&b, red(Duration::from_secs(1)));
+
+        let lights = |ms| -> Vec<_> {
+            sequencer
+                .sample(Duration::from_millis(ms))
+                .iter()
+                .map(|(ip, _)| *ip)
+                .collect()
+        };
+        assert_eq!(lights(500), [a.ip()]);
+        assert_eq!(lights(1000), [a.ip(), b.ip()]);
+
+        let effect = Effect::new().keyframe(red(Duration::from_secs(1)));
+        assert!(effect.sample(Duration::from_millis(500)).is_none());
+        assert!(effect.sample(Duration::from_secs(1)).is_some());
+    }
+
+    #[test]
+    fn test_step_easing_holds_previous_value() {
+        let effect = Effect::new()
+            .keyframe(Keyframe::new(Duration::ZERO).brightness(Brightness::create(10).unwrap()))
+            .keyframe(
+                Keyframe::new(Duration::from_secs(1))
+                    .brightness(Brightness::create(100).unwrap())
+                    .easing(Easing::Step),
+            );
+        let sample = effect.sample(Duration::from_millis(900)).unwrap();
+        assert_eq!(sample.dimming, Some(10));
+    }
+}
@@ -0,0 +1,354 @@
+//! Power monitoring and energy estimation.
+//!
+//! [`EnergyMeter`] integrates wattage samples into kWh per light, bucketed by
+//! UTC day. [`EnergyMonitor`] polls [`Light::get_power`] on an interval and
+//! feeds a shared meter in the background.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use wiz_lights_rs::energy::EnergyMonitor;
+//!
+//! let monitor = EnergyMonitor::start(vec![light], Duration::from_secs(30));
+//! // ... later ...
+//! let report = monitor.meter().await.total_report();
+//! println!("{:.3} kWh today", report.today_kwh);
+//! monitor.stop().await;
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::future;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::Error;
+use crate::light::Light;
+use crate::room::Room;
+use crate::runtime::{self, JoinHandle, Mutex};
+
+type Result<T> = std::result::Result<T, Error>;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Accumulated energy for a single light.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LightEnergy {
+    /// Last sample as (unix seconds, watts).
+    last_sample: Option<(f64, f32)>,
+    /// kWh keyed by UTC day number (days since the Unix epoch).
+    daily: BTreeMap<u64, f64>,
+}
+
+/// Energy used on a single UTC day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyEnergy {
+    /// Start of the day as Unix seconds.
+    pub day_start: u64,
+    pub kwh: f64,
+}
+
+/// Energy usage summary for a light, a room, or everything metered.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnergyReport {
+    pub total_kwh: f64,
+    pub today_kwh: f64,
+    /// Energy over the last seven days, including today.
+    pub last_7_days_kwh: f64,
+    /// Per-day totals in chronological order.
+    pub daily: Vec<DailyEnergy>,
+}
+
+/// Integrates wattage samples into energy totals.
+///
+/// Power is assumed to change linearly between consecutive samples of the
+/// same light. Gaps longer than the maximum gap (e.g. while monitoring was
+/// stopped) are not integrated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnergyMeter {
+    lights: HashMap<Ipv4Addr, LightEnergy>,
+    max_gap: Duration,
+}
+
+impl Default for EnergyMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnergyMeter {
+    pub const DEFAULT_MAX_GAP: Duration = Duration::from_secs(600);
+
+    pub fn new() -> Self {
+        Self {
+            lights: HashMap::new(),
+            max_gap: Self::DEFAULT_MAX_GAP,
+        }
+    }
+
+    pub fn with_max_gap(max_gap: Duration) -> Self {
+        Self {
+            max_gap,
+            ..Self::new()
+        }
+    }
+
+    /// Record a wattage sample for the light at `ip` taken at `at`.
+    pub fn record(&mut self, ip: Ipv4Addr, watts: f32, at: SystemTime) {
+        let now = unix_secs(at);
+        let entry = self.lights.entry(ip).or_default();
+
+        if let Some((last_time, last_watts)) = entry.last_sample {
+            let elapsed = now - last_time;
+            if elapsed > 0.0 && elapsed <= self.max_gap.as_secs_f64() {
+                integrate(
+                    &mut entry.daily,
+                    (last_time, f64::from(last_watts)),
+                    (now, f64::from(watts)),
+                );
+            }
+        }
+        entry.last_sample = Some((now, watts));
+    }
+
+    /// Query the light's current wattage and record it.
+    ///
+    /// Lights that do not support power metering are ignored.
+    pub async fn poll(&mut self, light: &Light) -> Result<()> {
+        if let Some(watts) = light.get_power().await? {
+            self.record(light.ip(), watts, SystemTime::now());
+        }
+        Ok(())
+    }
+
+    /// Energy report for a single light, if it has been sampled.
+    pub fn report(&self, ip: Ipv4Addr) -> Option<EnergyReport> {
+        self.lights
+            .get(&ip)
+            .map(|light| build_report([&light.daily]))
+    }
+
+    /// Energy report summed over the lights in a room.
+    pub fn room_report(&self, room: &Room) -> EnergyReport {
+        let ips: Vec<Ipv4Addr> = room
+            .list()
+            .into_iter()
+            .flatten()
+            .filter_map(|id| room.read(id))
+            .map(Light::ip)
+            .collect();
+        build_report(
+            ips.iter()
+                .filter_map(|ip| self.lights.get(ip))
+                .map(|light| &light.daily),
+        )
+    }
+
+    /// Energy report summed over every metered light.
+    pub fn total_report(&self) -> EnergyReport {
+        build_report(self.lights.values().map(|light| &light.daily))
+    }
+
+    pub fn clear(&mut self) {
+        self.lights.clear();
+    }
+}
+
+/// Polls lights for wattage in the background and meters their energy use.
+pub struct EnergyMonitor {
+    meter: Arc<Mutex<EnergyMeter>>,
+    running: Arc<AtomicBool>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl EnergyMonitor {
+    /// Start polling `lights` every `interval`.
+    pub fn start(lights: Vec<Light>, interval: Duration) -> Self {
+        Self::start_with_meter(lights, interval, EnergyMeter::new())
+    }
+
+    /// Start polling into an existing meter, e.g. one restored from disk.
+    pub fn start_with_meter(lights: Vec<Light>, interval: Duration, meter: EnergyMeter) -> Self {
+        let meter = Arc::new(Mutex::new(meter));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let task_meter = Arc::clone(&meter);
+        let task_running = Arc::clone(&running);
+        let handle = runtime::spawn(async move {
+            while task_running.load(Ordering::SeqCst) {
+                let samples = future::join_all(
+                    lights
+                        .iter()
+                        .map(|light| async move { (light.ip(), light.get_power().await) }),
+                )
+                .await;
+
+                let now = SystemTime::now();
+                let mut meter = task_meter.lock().await;
+                for (ip, sample) in samples {
+                    match sample {
+                        Ok(Some(watts)) => meter.record(ip, watts, now),
+                        Ok(None) => {}
+                        Err(e) => debug!("Power poll of {} failed: {}", ip, e),
+                    }
+                }
+                drop(meter);
+
+                // Sleep in short slices so stop() is not delayed by the interval
+                let start = runtime::Instant::now();
+                while task_running.load(Ordering::SeqCst) && start.elapsed() < interval {
+                    runtime::sleep(interval.min(Duration::from_millis(500))).await;
+                }
+            }
+        });
+
+        Self {
+            meter,
+            running,
+            task: Mutex::new(Some(handle)),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Snapshot of the meter's current totals.
+    pub async fn meter(&self) -> EnergyMeter {
+        self.meter.lock().await.clone()
+    }
+
+    /// Stop polling and wait for the background task to finish.
+    pub async fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(h) = self.task.lock().await.take() {
+            let _ = h.await;
+        }
+    }
+}
+
+impl Drop for EnergyMonitor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+fn unix_secs(at: SystemTime) -> f64 {
+    at.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// Adds the energy between two samples to `daily`, splitting the interval at
+/// every UTC midnight it crosses so each day gets only its own share.
+fn integrate(daily: &mut BTreeMap<u64, f64>, from: (f64, f64), to: (f64, f64)) {
+    let (start, start_watts) = from;
+    let (end, end_watts) = to;
+    let watts_at = |t: f64| start_watts + (end_watts - start_watts) * (t - start) / (end - start);
+
+    let mut t = start;
+    while t < end {
+        let day = t as u64 / SECS_PER_DAY;
+        let until = end.min(((day + 1) * SECS_PER_DAY) as f64);
+        let avg_watts = (watts_at(t) + watts_at(until)) / 2.0;
+        *daily.entry(day).or_default() += avg_watts * (until - t) / 3_600_000.0;
+        t = until;
+    }
+}
+
+fn build_report<'a>(sources: impl IntoIterator<Item = &'a BTreeMap<u64, f64>>) -> EnergyReport {
+    let mut daily: BTreeMap<u64, f64> = BTreeMap::new();
+    for source in sources {
+        for (day, kwh) in source {
+            *daily.entry(*day).or_default() += kwh;
+        }
+    }
+
+    let today = unix_secs(SystemTime::now()) as u64 / SECS_PER_DAY;
+    EnergyReport {
+        total_kwh: daily.values().sum(),
+        today_kwh: daily.get(&today).copied().unwrap_or(0.0),
+        last_7_days_kwh: daily
+            .range(today.saturating_sub(6)..=today)
+            .map(|(_, kwh)| kwh)
+            .sum(),
+        daily: daily
+            .into_iter()
+            .map(|(day, kwh)| DailyEnergy {
+                day_start: day * SECS_PER_DAY,
+                kwh,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integrates_samples() {
+        let ip = Ipv4Addr::new(192, 168, 1, 10);
+        let start = SystemTime::now();
+        let mut meter = EnergyMeter::new();
+
+        meter.record(ip, 10.0, start);
+        meter.record(ip, 10.0, start + Duration::from_secs(360));
+
+        // 10 W for 6 minutes = 1 Wh
+        let report = meter.report(ip).unwrap();
+        assert!((report.total_kwh - 0.001).abs() < 1e-9);
+        assert!((meter.total_report().total_kwh - 0.001).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_skips_long_gaps() {
+        let ip = Ipv4Addr::new(192, 168, 1, 10);
+        let start = SystemTime::now();
+        let mut meter = EnergyMeter::with_max_gap(Duration::from_secs(60));
+
+        meter.record(ip, 10.0, start);
+        meter.record(ip, 10.0, start + Duration::from_secs(3600));
+
+        assert_eq!(meter.report(ip).unwrap().total_kwh, 0.0);
+    }
+
+    #[test]
+    fn test_splits_interval_at_midnight() {
+        let ip = Ipv4Addr::new(192, 168, 1, 10);
+        let midnight = UNIX_EPOCH + Duration::from_secs(20_000 * SECS_PER_DAY);
+        let mut meter = EnergyMeter::new();
+
+        // 10 W from 3 minutes before midnight to 6 minutes after
+        meter.record(ip, 10.0, midnight - Duration::from_secs(180));
+        meter.record(ip, 10.0, midnight + Duration::from_secs(360));
+
+        let daily = meter.report(ip).unwrap().daily;
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].day_start, 19_999 * SECS_PER_DAY);
+        assert!((daily[0].kwh - 0.0005).abs() < 1e-9);
+        assert_eq!(daily[1].day_start, 20_000 * SECS_PER_DAY);
+        assert!((daily[1].kwh - 0.001).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_meter_roundtrips_through_json() {
+        let ip = Ipv4Addr::new(192, 168, 1, 10);
+        let start = SystemTime::now();
+        let mut meter = EnergyMeter::new();
+        meter.record(ip, 5.0, start);
+        meter.record(ip, 5.0, start + Duration::from_secs(60));
+
+        let json = serde_json::to_string(&meter).unwrap();
+        let restored: EnergyMeter = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            restored.report(ip).unwrap().total_kwh,
+            meter.report(ip).unwrap().total_kwh
+        );
+    }
+}
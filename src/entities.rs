@@ -0,0 +1,328 @@
+//! Normalized entity model for home-automation integrations.
+//!
+//! Bulbs, sockets and fan fixtures are described as [`Entity`] values in the
+//! shape Home Assistant and similar hubs use: a domain (`light`, `fan`,
+//! `switch`), an `on`/`off` state and a flat attribute map with brightness on
+//! a 0-255 scale, the active color mode and the supported ones. Serialized
+//! with serde, an entity can be forwarded to such a hub as-is.
+//!
+//! # Example
+//!
+//! ```ignore
+//! for entity in wiz_lights_rs::entities::entities(&light).await? {
+//!     println!("{}", serde_json::to_string(&entity)?);
+//!     // {"entity_id":"light.wiz_a8bb50aabbcc","state":"on","attributes":{...}}
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{BulbClass, BulbType};
+use crate::errors::Error;
+use crate::light::Light;
+use crate::types::{FanSpeed, SceneMode};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// What kind of device an entity represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Domain {
+    Light,
+    Fan,
+    Switch,
+}
+
+/// How a light's color is currently being set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMode {
+    /// On/off only.
+    Onoff,
+    /// Dimmable, single white.
+    Brightness,
+    /// Tunable white.
+    ColorTemp,
+    /// Full color.
+    Rgb,
+}
+
+/// On/off state of an entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum State {
+    On,
+    Off,
+}
+
+/// Attributes of an entity. Fields that do not apply are omitted.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Attributes {
+    pub friendly_name: Option<String>,
+    /// Brightness from 0 to 255.
+    pub brightness: Option<u8>,
+    pub color_mode: Option<ColorMode>,
+    pub supported_color_modes: Option<Vec<ColorMode>>,
+    pub rgb_color: Option<[u8; 3]>,
+    pub color_temp_kelvin: Option<u16>,
+    pub min_color_temp_kelvin: Option<u16>,
+    pub max_color_temp_kelvin: Option<u16>,
+    /// Name of the running scene.
+    pub effect: Option<String>,
+    pub effect_list: Option<Vec<String>>,
+    /// Fan speed from 0 to 100.
+    pub percentage: Option<u8>,
+    /// Fan speed step as a percentage.
+    pub percentage_step: Option<f64>,
+    pub preset_mode: Option<String>,
+    pub preset_modes: Option<Vec<String>>,
+    /// `forward` or `reverse`.
+    pub direction: Option<String>,
+}
+
+/// A light, fan or socket in a home-automation friendly form.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    /// `<domain>.wiz_<mac>`, e.g. `light.wiz_a8bb50aabbcc`.
+    pub entity_id: String,
+    /// Stable id: the MAC, suffixed with `_fan` for a fixture's fan.
+    pub unique_id: String,
+    pub domain: Domain,
+    pub state: State,
+    pub attributes: Attributes,
+}
+
+impl Entity {
+    fn new(domain: Domain, unique_id: String, state: State) -> Self {
+        let mac = unique_id.split('_').next().unwrap_or_default();
+        let domain_name = match domain {
+            Domain::Light => "light",
+            Domain::Fan => "fan",
+            Domain::Switch => "switch",
+        };
+        Entity {
+            entity_id: format!("{}.wiz_{}", domain_name, mac),
+            unique_id,
+            domain,
+            state,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Whether the entity is on.
+    pub fn is_on(&self) -> bool {
+        self.state == State::On
+    }
+}
+
+/// Color modes a bulb type can be set to.
+pub fn supported_color_modes(bulb_type: &BulbType) -> Vec<ColorMode> {
+    let features = &bulb_type.features;
+    let mut modes = Vec::new();
+    if features.color_tmp {
+        modes.push(ColorMode::ColorTemp);
+    }
+    if features.color {
+        modes.push(ColorMode::Rgb);
+    }
+    if modes.is_empty() {
+        modes.push(if features.brightness {
+            ColorMode::Brightness
+        } else {
+            ColorMode::Onoff
+        });
+    }
+    modes
+}
+
+/// Describe a bulb from its `getPilot` result.
+///
+/// Returns a `switch` for sockets and a `light` otherwise, followed by a
+/// `fan` for fan fixtures. Fan speed is scaled assuming
+/// [`FanSpeed::DEFAULT_MAX`] steps; [`entities`] asks the bulb instead.
+pub fn from_pilot(mac: &str, bulb_type: &BulbType, pilot: &Value) -> Vec<Entity> {
+    from_pilot_with(mac, bulb_type, pilot, FanSpeed::DEFAULT_MAX)
+}
+
+/// Query a light and describe it as entities.
+///
+/// The light's name, if any, becomes the `friendly_name`.
+pub async fn entities(light: &Light) -> Result<Vec<Entity>> {
+    let config = light.get_system_config().await?;
+    let bulb_type = BulbType::from_module_name(
+        config.module_name.as_deref().unwrap_or_default(),
+        config.fw_version.as_deref(),
+    );
+    let fan_max = if bulb_type.features.fan {
+        light.get_fan_speed_range().await?
+    } else {
+        None
+    };
+    let resp = light.send_raw_method("getPilot", Value::Null).await?;
+    let pilot = resp.get("result").unwrap_or(&Value::Null);
+
+    let mut entities = from_pilot_with(
+        &config.mac,
+        &bulb_type,
+        pilot,
+        fan_max.unwrap_or(FanSpeed::DEFAULT_MAX),
+    );
+    if let Some(name) = light.name() {
+        for entity in &mut entities {
+            entity.attributes.friendly_name = Some(match entity.domain {
+                Domain::Fan => format!("{} Fan", name),
+                _ => name.to_string(),
+            });
+        }
+    }
+    Ok(entities)
+}
+
+fn from_pilot_with(mac: &str, bulb_type: &BulbType, pilot: &Value, fan_max: u8) -> Vec<Entity> {
+    let mac = mac.to_lowercase();
+    let get = |key: &str| pilot.get(key).and_then(Value::as_u64);
+    let state = |on: bool| if on { State::On } else { State::Off };
+    let features = &bulb_type.features;
+
+    let on = pilot.get("state").and_then(Value::as_bool).unwrap_or(false);
+    let mut entities = Vec::new();
+
+    if bulb_type.bulb_class == BulbClass::Socket {
+        entities.push(Entity::new(Domain::Switch, mac.clone(), state(on)));
+    } else {
+        let mut light = Entity::new(Domain::Light, mac.clone(), state(on));
+        let attrs = &mut light.attributes;
+        let modes = supported_color_modes(bulb_type);
+
+        if features.brightness {
+            attrs.brightness = get("dimming").map(|d| (d.min(100) * 255 / 100) as u8);
+        }
+        if features.color_tmp {
+            attrs.min_color_temp_kelvin = Some(bulb_type.kelvin_range.min);
+            attrs.max_color_temp_kelvin = Some(bulb_type.kelvin_range.max);
+        }
+
+        let rgb = match (get("r"), get("g"), get("b")) {
+            (Some(r), Some(g), Some(b)) => Some([r, g, b].map(|c| c.min(255) as u8)),
+            _ => None,
+        };
+        let scene = get("sceneId")
+            .filter(|id| *id != 0)
+            .and_then(|id| SceneMode::create(id as u16));
+
+        attrs.color_mode = Some(if rgb.is_some() && features.color {
+            ColorMode::Rgb
+        } else if get("temp").is_some() && features.color_tmp {
+            ColorMode::ColorTemp
+        } else {
+            modes[0]
+        });
+        if attrs.color_mode == Some(ColorMode::Rgb) {
+            attrs.rgb_color = rgb;
+        }
+        if attrs.color_mode == Some(ColorMode::ColorTemp) {
+            attrs.color_temp_kelvin = get("temp").map(|t| t as u16);
+        }
+        if features.effect {
+            attrs.effect = scene.map(|s| s.name().to_string());
+            attrs.effect_list = Some(
+                SceneMode::supported_by(bulb_type)
+                    .iter()
+                    .map(|s| s.name().to_string())
+                    .collect(),
+            );
+        }
+        attrs.supported_color_modes = Some(modes);
+        entities.push(light);
+    }
+
+    if features.fan {
+        let fan_on = get("fanState") == Some(1);
+        let mut fan = Entity::new(Domain::Fan, format!("{}_fan", mac), state(fan_on));
+        let attrs = &mut fan.attributes;
+        let fan_max = fan_max.max(1);
+
+        attrs.percentage = Some(match get("fanSpeed") {
+            Some(speed) if fan_on => (speed.min(fan_max as u64) * 100 / fan_max as u64) as u8,
+            _ => 0,
+        });
+        attrs.percentage_step = Some(100.0 / fan_max as f64);
+        if features.fan_breeze_mode {
+            attrs.preset_mode =
+                get("fanMode").map(|mode| if mode == 2 { "breeze" } else { "normal" }.to_string());
+            attrs.preset_modes = Some(vec!["normal".to_string(), "breeze".to_string()]);
+        }
+        if features.fan_reverse {
+            attrs.direction = get("fanRevrs")
+                .map(|reverse| if reverse == 1 { "reverse" } else { "forward" }.to_string());
+        }
+        entities.push(fan);
+    }
+
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rgb_light() {
+        let bulb_type = BulbType::from_module_name("ESP01_SHRGB1C_31", None);
+        let pilot = json!({"state": true, "dimming": 50, "r": 255, "g": 0, "b": 64});
+        let entities = from_pilot("A8BB50AABBCC", &bulb_type, &pilot);
+
+        assert_eq!(entities.len(), 1);
+        let light = &entities[0];
+        assert_eq!(light.entity_id, "light.wiz_a8bb50aabbcc");
+        assert!(light.is_on());
+        assert_eq!(light.attributes.brightness, Some(127));
+        assert_eq!(light.attributes.color_mode, Some(ColorMode::Rgb));
+        assert_eq!(light.attributes.rgb_color, Some([255, 0, 64]));
+        assert_eq!(
+            light.attributes.supported_color_modes,
+            Some(vec![ColorMode::ColorTemp, ColorMode::Rgb])
+        );
+
+        let value = serde_json::to_value(light).unwrap();
+        assert_eq!(value["state"], "on");
+        assert_eq!(value["attributes"]["color_mode"], "rgb");
+        assert!(value["attributes"].get("color_temp_kelvin").is_none());
+    }
+
+    #[test]
+    fn test_socket_is_switch() {
+        let bulb_type = BulbType::from_module_name("ESP10_SOCKET_06", None);
+        let entities = from_pilot("a8bb50aabbcc", &bulb_type, &json!({"state": false}));
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].domain, Domain::Switch);
+        assert_eq!(entities[0].state, State::Off);
+        assert_eq!(entities[0].attributes, Attributes::default());
+    }
+
+    #[test]
+    fn test_fan_fixture() {
+        let bulb_type = BulbType::from_module_name("ESP03_FANDIMS_01", None);
+        let pilot = json!({
+            "state": true, "dimming": 100,
+            "fanState": 1, "fanSpeed": 3, "fanMode": 2, "fanRevrs": 0
+        });
+        let entities = from_pilot("a8bb50aabbcc", &bulb_type, &pilot);
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(
+            entities[0].attributes.color_mode,
+            Some(ColorMode::Brightness)
+        );
+        let fan = &entities[1];
+        assert_eq!(fan.entity_id, "fan.wiz_a8bb50aabbcc");
+        assert_eq!(fan.unique_id, "a8bb50aabbcc_fan");
+        assert_eq!(fan.attributes.percentage, Some(50));
+        assert_eq!(fan.attributes.preset_mode.as_deref(), Some("breeze"));
+        assert_eq!(fan.attributes.direction.as_deref(), Some("forward"));
+    }
+}
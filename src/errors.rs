@@ -1,4 +1,6 @@
-use std::{net::Ipv4Addr, string::FromUtf8Error};
+use alloc::string::{FromUtf8Error, String, ToString};
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
 
 use uuid::Uuid;
 
@@ -14,6 +16,7 @@ pub enum Error {
     JsonLoad(serde_json::Error),
 
     /// A network socket operation failed while communicating with a bulb.
+    #[cfg(feature = "std")]
     #[error("socket {action} error: {err:?}")]
     Socket { action: String, err: std::io::Error },
 
@@ -45,17 +48,100 @@ pub enum Error {
     #[error("no change for light {light_id:?} in room {room_id:?}")]
     NoChangeLight { room_id: Uuid, light_id: Uuid },
 
+    /// The room was already linked to an id; use [`crate::Room::relink`]
+    /// to overwrite it.
+    #[error("room already linked to {0}")]
+    AlreadyLinked(Uuid),
+
     /// Attempted to modify a light in a room that has no lights.
     #[error("no lights in room {0}")]
     NoLights(Uuid),
 
+    /// A light referenced by MAC has no known IP address yet.
+    #[error("no known ip for mac {0}")]
+    MacNotResolved(String),
+
+    /// The home has no room with the given WiZ room id.
+    #[error("room {0} not found in home")]
+    HomeRoomNotFound(u64),
+
+    /// The home has no floor with the given name.
+    #[error("floor not found: {0}")]
+    FloorNotFound(String),
+
+    /// The room has no scene with the given name.
+    #[error("scene not found: {0}")]
+    SceneNotFound(String),
+
+    /// The bulb answered a command with a JSON-RPC error.
+    #[error("bulb rejected {method}: {message} (code {code})")]
+    Bulb {
+        code: i64,
+        message: String,
+        method: String,
+    },
+
+    /// The bulb acknowledged a command without reporting success.
+    #[error("bulb did not apply {0}")]
+    NotApplied(String),
+
+    /// Only replies to other requests arrived before the timeout, e.g. late
+    /// answers to earlier commands.
+    #[error("no reply to {method} request {id}, only to other requests")]
+    ResponseMismatch { method: String, id: u64 },
+
+    /// The bulb's state after a command does not match what was requested.
+    #[error("bulb state does not match request for fields: {fields:?}")]
+    StateMismatch { fields: Vec<String> },
+
+    /// No network interface with an IPv4 address matches the given name.
+    #[error("network interface not found: {0}")]
+    InterfaceNotFound(String),
+
     /// Failed to parse a [`crate::Color`] from a string.
     #[error("invalid color string: {0}")]
     InvalidColorString(String),
+
+    /// Failed to parse a [`crate::SceneMode`] from a string.
+    #[error("unknown scene: {0}")]
+    UnknownScene(String),
+
+    /// The bulb model lacks a feature the command relies on.
+    #[error("{feature} is not supported by {module}")]
+    UnsupportedFeature { feature: String, module: String },
+
+    /// The operation was stopped through a [`crate::CancelToken`].
+    #[error("operation cancelled")]
+    Cancelled,
+
+    /// WiFi credentials for provisioning are not valid.
+    #[error("invalid wifi credentials: {0}")]
+    InvalidCredentials(String),
+
+    /// A configuration value is out of the range bulbs accept.
+    #[error("invalid bulb configuration: {0}")]
+    InvalidConfig(String),
+
+    /// A configuration write was not confirmed with `dangerous()`.
+    #[error("{0} can leave the bulb misconfigured; confirm it with dangerous()")]
+    DangerousNotConfirmed(String),
+
+    /// A value was outside the range its type accepts.
+    #[error(transparent)]
+    Range(#[from] crate::types::RangeError),
+
+    /// Too many commands were already queued for the light.
+    #[error("command queue of {ip} is full ({depth} queued)")]
+    QueueFull { ip: Ipv4Addr, depth: usize },
+
+    /// The bulb model cannot show the requested scene.
+    #[error("scene {scene} is not supported by {module}")]
+    UnsupportedScene { scene: String, module: String },
 }
 
 impl Error {
     /// Create a new socket error
+    #[cfg(feature = "std")]
     pub fn socket(action: &str, err: std::io::Error) -> Self {
         Error::Socket {
             action: action.to_string(),
@@ -63,6 +149,15 @@ impl Error {
         }
     }
 
+    /// Create a new bulb error
+    pub fn bulb(code: i64, message: &str, method: &str) -> Self {
+        Error::Bulb {
+            code,
+            message: message.to_string(),
+            method: method.to_string(),
+        }
+    }
+
     /// Create a new light not found error
     pub fn light_not_found(room_id: &Uuid, light_id: &Uuid) -> Self {
         Error::LightNotFound {
@@ -0,0 +1,143 @@
+//! Over-the-air firmware updates.
+//!
+//! [`Light::check_firmware_update`] asks the bulb whether newer firmware is
+//! available and [`Light::start_firmware_update`] tells it to install it.
+//! Bulbs report nothing while flashing, so progress is followed by polling
+//! `getSystemConfig` until the firmware version changes.
+//!
+//! **Experimental.** WiZ does not document the update RPCs, and the method
+//! names `checkUpdate` and `updateOta` have no published source. Firmware
+//! that does not know them answers with [`Error::Bulb`](crate::Error::Bulb)
+//! (method not found), and the API may change once their behavior is
+//! confirmed.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use futures::StreamExt;
+//! use wiz_lights_rs::FirmwareProgress;
+//!
+//! if light.check_firmware_update().await?.is_available() {
+//!     let mut progress = light.start_firmware_update(Duration::from_secs(600)).await?;
+//!     while let Some(step) = progress.next().await {
+//!         println!("{step:?}");
+//!     }
+//! }
+//! ```
+
+use std::time::Duration;
+
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::light::Light;
+use crate::runtime::{self, Instant};
+
+/// Method asking the bulb for available firmware.
+pub(crate) const CHECK_METHOD: &str = "checkUpdate";
+/// Method starting the download and install.
+pub(crate) const UPDATE_METHOD: &str = "updateOta";
+
+/// How often the bulb is polled while it updates.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Result of [`Light::check_firmware_update`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirmwareUpdate {
+    /// Version the bulb is running.
+    pub current: Option<String>,
+    /// Newest version the bulb can install, if it reported one.
+    pub available: Option<String>,
+}
+
+impl FirmwareUpdate {
+    pub(crate) fn from_reply(current: Option<String>, result: &Value) -> Self {
+        let available = ["newFwVersion", "fwVersion", "version"]
+            .iter()
+            .find_map(|key| result.get(key).and_then(Value::as_str))
+            .map(String::from);
+        FirmwareUpdate { current, available }
+    }
+
+    /// Whether a version other than the running one is available.
+    pub fn is_available(&self) -> bool {
+        self.available.is_some() && self.available != self.current
+    }
+}
+
+/// A step of an update started with [`Light::start_firmware_update`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FirmwareProgress {
+    /// The bulb accepted the update while running `from`.
+    Started { from: Option<String> },
+    /// The bulb did not answer, as expected while it flashes and reboots.
+    Unreachable,
+    /// The bulb answered and still runs the old version.
+    Pending,
+    /// The bulb came back with new firmware. Ends the stream.
+    Updated { from: Option<String>, to: String },
+    /// The version did not change in time. Ends the stream.
+    TimedOut,
+}
+
+enum Poll {
+    Start,
+    Waiting,
+    Finished,
+}
+
+/// Polls `light` until its firmware version moves away from `from`.
+pub(crate) fn progress(
+    light: Light,
+    from: Option<String>,
+    timeout: Duration,
+) -> BoxStream<'static, FirmwareProgress> {
+    let started = Instant::now();
+    stream::unfold(Poll::Start, move |state| {
+        let light = light.clone();
+        let from = from.clone();
+        async move {
+            match state {
+                Poll::Start => Some((FirmwareProgress::Started { from }, Poll::Waiting)),
+                Poll::Finished => None,
+                Poll::Waiting => {
+                    if started.elapsed() >= timeout {
+                        return Some((FirmwareProgress::TimedOut, Poll::Finished));
+                    }
+                    runtime::sleep(POLL_INTERVAL).await;
+                    match light.get_system_config().await {
+                        Ok(config) => match config.fw_version {
+                            Some(to) if Some(&to) != from.as_ref() => {
+                                // Capabilities may differ on the new firmware
+                                let _ = light.refresh_capabilities().await;
+                                Some((FirmwareProgress::Updated { from, to }, Poll::Finished))
+                            }
+                            _ => Some((FirmwareProgress::Pending, Poll::Waiting)),
+                        },
+                        Err(_) => Some((FirmwareProgress::Unreachable, Poll::Waiting)),
+                    }
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_update_available() {
+        let current = Some("1.30.0".to_string());
+        let update =
+            FirmwareUpdate::from_reply(current.clone(), &json!({"newFwVersion": "1.31.0"}));
+        assert!(update.is_available());
+
+        let same = FirmwareUpdate::from_reply(current.clone(), &json!({"fwVersion": "1.30.0"}));
+        assert!(!same.is_available());
+        assert!(!FirmwareUpdate::from_reply(current, &json!({})).is_available());
+    }
+}
@@ -0,0 +1,78 @@
+//! Float functions that `core` lacks, backed by `libm` without `std`.
+//!
+//! With `std` the inherent methods are used and this module is not built.
+
+pub(crate) trait FloatExt: Sized {
+    fn floor(self) -> Self;
+    fn round(self) -> Self;
+    fn ln(self) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+}
+
+impl FloatExt for f32 {
+    fn floor(self) -> Self {
+        libm::floorf(self)
+    }
+
+    fn round(self) -> Self {
+        libm::roundf(self)
+    }
+
+    fn ln(self) -> Self {
+        libm::logf(self)
+    }
+
+    fn powf(self, n: Self) -> Self {
+        libm::powf(self, n)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        libm::powf(self, n as f32)
+    }
+
+    fn rem_euclid(self, rhs: Self) -> Self {
+        let r = self % rhs;
+        if r >= 0.0 {
+            r
+        } else if rhs < 0.0 {
+            r - rhs
+        } else {
+            r + rhs
+        }
+    }
+}
+
+impl FloatExt for f64 {
+    fn floor(self) -> Self {
+        libm::floor(self)
+    }
+
+    fn round(self) -> Self {
+        libm::round(self)
+    }
+
+    fn ln(self) -> Self {
+        libm::log(self)
+    }
+
+    fn powf(self, n: Self) -> Self {
+        libm::pow(self, n)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        libm::pow(self, f64::from(n))
+    }
+
+    fn rem_euclid(self, rhs: Self) -> Self {
+        let r = self % rhs;
+        if r >= 0.0 {
+            r
+        } else if rhs < 0.0 {
+            r - rhs
+        } else {
+            r + rhs
+        }
+    }
+}
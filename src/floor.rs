@@ -0,0 +1,123 @@
+//! Floors and nested zones of rooms within a home.
+
+use serde::{Deserialize, Serialize};
+
+/// A named part of a [`Home`](crate::Home), such as "Upstairs", made of
+/// rooms and nested floors.
+///
+/// Floors refer to rooms by their WiZ room id, so they survive rebuilding
+/// the home from discovery. Nest floors to model wings or zones within a
+/// level; a batch operation on a floor covers every floor nested in it.
+///
+/// # Example
+///
+/// ```
+/// use wiz_lights_rs::Floor;
+///
+/// let mut kids = Floor::new("Kids' wing");
+/// kids.add_room(3);
+///
+/// let mut upstairs = Floor::new("Upstairs");
+/// upstairs.add_room(1).add_room(2).add_child(kids);
+/// assert_eq!(upstairs.room_ids().collect::<Vec<_>>(), [1, 2, 3]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Floor {
+    name: String,
+    #[serde(default)]
+    rooms: Vec<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<Floor>,
+}
+
+impl Floor {
+    pub fn new(name: &str) -> Self {
+        Floor {
+            name: name.to_string(),
+            rooms: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Adds a room by its WiZ room id, if not already on this floor.
+    pub fn add_room(&mut self, room_id: u64) -> &mut Self {
+        if !self.rooms.contains(&room_id) {
+            self.rooms.push(room_id);
+        }
+        self
+    }
+
+    /// Removes a room from this floor. Nested floors are not searched.
+    pub fn remove_room(&mut self, room_id: u64) -> bool {
+        let before = self.rooms.len();
+        self.rooms.retain(|id| *id != room_id);
+        self.rooms.len() != before
+    }
+
+    /// Rooms directly on this floor.
+    pub fn rooms(&self) -> &[u64] {
+        &self.rooms
+    }
+
+    /// Nests a floor, replacing any direct child with the same name.
+    pub fn add_child(&mut self, floor: Floor) -> &mut Self {
+        match self.children.iter_mut().find(|c| c.name == floor.name) {
+            Some(existing) => *existing = floor,
+            None => self.children.push(floor),
+        }
+        self
+    }
+
+    pub fn remove_child(&mut self, name: &str) -> Option<Floor> {
+        let index = self.children.iter().position(|c| c.name == name)?;
+        Some(self.children.remove(index))
+    }
+
+    /// Floors directly nested in this one.
+    pub fn children(&self) -> &[Floor] {
+        &self.children
+    }
+
+    /// This floor and every floor nested in it, depth first.
+    pub fn walk(&self) -> impl Iterator<Item = &Floor> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let floor = stack.pop()?;
+            stack.extend(floor.children.iter().rev());
+            Some(floor)
+        })
+    }
+
+    /// Rooms on this floor and every floor nested in it, each once.
+    pub fn room_ids(&self) -> impl Iterator<Item = u64> {
+        let mut seen = Vec::new();
+        self.walk()
+            .flat_map(|floor| floor.rooms.iter().copied())
+            .filter(move |id| {
+                let new = !seen.contains(id);
+                if new {
+                    seen.push(*id);
+                }
+                new
+            })
+    }
+
+    /// Finds this floor or a floor nested in it by name.
+    pub fn find(&self, name: &str) -> Option<&Floor> {
+        self.walk().find(|floor| floor.name == name)
+    }
+
+    /// Mutable version of [`Floor::find`].
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut Floor> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children
+            .iter_mut()
+            .find_map(|child| child.find_mut(name))
+    }
+}
@@ -0,0 +1,220 @@
+//! Groups of lights that span rooms.
+
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use futures::future;
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::DiscoveredBulb;
+use crate::errors::Error;
+use crate::light::Light;
+use crate::payload::Payload;
+use crate::response::LightingResponse;
+use crate::status::LightStatus;
+use crate::types::PowerMode;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// How a group refers to one of its lights.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GroupMember {
+    Ip(Ipv4Addr),
+    /// MAC address, resolved to an IP through [`Group::learn`].
+    Mac(String),
+}
+
+/// A lightweight zone of lights, e.g. "hallway + stairs".
+///
+/// Unlike a [`Room`](crate::Room), a group does not own its lights; it only
+/// refers to them by IP or MAC, so the same bulb can be in a room and in any
+/// number of groups. MAC members survive DHCP changes once their current IP
+/// is learned from discovery.
+///
+/// Commands go to a fresh [`Light`] per member unless the light was added
+/// with [`Group::attach`]. Attach a handle from [`Light::share`] to control
+/// a bulb that is also in a room through one shared cached state.
+///
+/// # Example
+///
+/// ```
+/// use std::net::Ipv4Addr;
+/// use wiz_lights_rs::{Group, GroupMember};
+///
+/// let mut hallway = Group::new("Hallway");
+/// hallway.add(GroupMember::Ip(Ipv4Addr::new(192, 168, 1, 20)));
+/// hallway.add(GroupMember::Mac("a8bb50aabbcc".into()));
+/// assert_eq!(hallway.members().len(), 2);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    name: String,
+    members: Vec<GroupMember>,
+    #[serde(default)]
+    resolved: HashMap<String, Ipv4Addr>,
+    #[serde(skip)]
+    handles: Vec<Handle>,
+}
+
+/// A light attached to a group. Cloning shares the handle, so a cloned
+/// group still controls the same lights.
+#[derive(Debug)]
+struct Handle(Light);
+
+impl Clone for Handle {
+    fn clone(&self) -> Self {
+        Handle(self.0.share())
+    }
+}
+
+impl Group {
+    pub fn new(name: &str) -> Self {
+        Group {
+            name: name.to_string(),
+            members: Vec::new(),
+            resolved: HashMap::new(),
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn members(&self) -> &[GroupMember] {
+        &self.members
+    }
+
+    /// Adds a member. Returns false if it was already in the group.
+    pub fn add(&mut self, member: GroupMember) -> bool {
+        let member = normalize(member);
+        if self.members.contains(&member) {
+            return false;
+        }
+        self.members.push(member);
+        true
+    }
+
+    /// Adds a light as a member and sends the group's commands through it.
+    ///
+    /// The member is the light's MAC if it has one, otherwise its IP.
+    /// Returns false if the member was already in the group; its handle is
+    /// replaced either way.
+    pub fn attach(&mut self, light: Light) -> bool {
+        let member = match light.mac() {
+            Some(mac) => GroupMember::Mac(mac.to_string()),
+            None => GroupMember::Ip(light.ip()),
+        };
+        self.handles.retain(|handle| !handle.matches(&member));
+        self.handles.push(Handle(light));
+        self.add(member)
+    }
+
+    /// Removes a member and any light attached for it. Returns false if it
+    /// was not in the group.
+    pub fn remove(&mut self, member: &GroupMember) -> bool {
+        let member = normalize(member.clone());
+        let before = self.members.len();
+        self.members.retain(|m| *m != member);
+        self.handles.retain(|handle| !handle.matches(&member));
+        self.members.len() != before
+    }
+
+    /// Records the current IP of a MAC member.
+    pub fn learn(&mut self, mac: &str, ip: Ipv4Addr) {
+        self.resolved.insert(mac.to_lowercase(), ip);
+    }
+
+    /// Records the IPs of any discovered bulbs that are MAC members.
+    pub fn learn_discovered(&mut self, bulbs: &[DiscoveredBulb]) {
+        for bulb in bulbs {
+            self.learn(&bulb.mac, bulb.ip);
+        }
+    }
+
+    /// Current IPs of every member.
+    ///
+    /// Fails with [`Error::MacNotResolved`] if a MAC member's IP is unknown.
+    pub fn ips(&self) -> Result<Vec<Ipv4Addr>> {
+        self.members.iter().map(|member| self.ip(member)).collect()
+    }
+
+    /// Applies a payload to every member concurrently.
+    pub async fn set(&self, payload: &Payload) -> Result<Vec<LightingResponse>> {
+        let lights = self.lights()?;
+        let results = future::join_all(lights.iter().map(|light| light.set(payload))).await;
+        results.into_iter().collect()
+    }
+
+    pub async fn set_power(&self, power: &PowerMode) -> Result<Vec<LightingResponse>> {
+        let lights = self.lights()?;
+        let results = future::join_all(lights.iter().map(|light| light.set_power(power))).await;
+        results.into_iter().collect()
+    }
+
+    /// Switches the whole group off if any member is on, otherwise on.
+    ///
+    /// Toggling members individually would leave a mixed group mixed.
+    pub async fn toggle(&self) -> Result<Vec<LightingResponse>> {
+        let any_on = self.get_status().await?.iter().any(LightStatus::emitting);
+        let power = if any_on {
+            PowerMode::Off
+        } else {
+            PowerMode::On
+        };
+        self.set_power(&power).await
+    }
+
+    pub async fn get_status(&self) -> Result<Vec<LightStatus>> {
+        let lights = self.lights()?;
+        let results = future::join_all(lights.iter().map(Light::get_status)).await;
+        results.into_iter().collect()
+    }
+
+    fn ip(&self, member: &GroupMember) -> Result<Ipv4Addr> {
+        if let Some(handle) = self.handle(member) {
+            return Ok(handle.ip());
+        }
+        match member {
+            GroupMember::Ip(ip) => Ok(*ip),
+            GroupMember::Mac(mac) => self
+                .resolved
+                .get(mac)
+                .copied()
+                .ok_or_else(|| Error::MacNotResolved(mac.clone())),
+        }
+    }
+
+    fn handle(&self, member: &GroupMember) -> Option<&Light> {
+        self.handles
+            .iter()
+            .find(|handle| handle.matches(member))
+            .map(|handle| &handle.0)
+    }
+
+    fn lights(&self) -> Result<Vec<Light>> {
+        self.members
+            .iter()
+            .map(|member| match self.handle(member) {
+                Some(light) => Ok(light.share()),
+                None => self.ip(member).map(|ip| Light::new(ip, None)),
+            })
+            .collect()
+    }
+}
+
+impl Handle {
+    fn matches(&self, member: &GroupMember) -> bool {
+        match member {
+            GroupMember::Ip(ip) => self.0.mac().is_none() && self.0.ip() == *ip,
+            GroupMember::Mac(mac) => self.0.mac() == Some(mac.as_str()),
+        }
+    }
+}
+
+fn normalize(member: GroupMember) -> GroupMember {
+    match member {
+        GroupMember::Mac(mac) => GroupMember::Mac(mac.to_lowercase()),
+        other => other,
+    }
+}
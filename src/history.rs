@@ -1,7 +1,8 @@
 //! Message history tracking for debugging and diagnostics.
 
-use std::collections::HashMap;
-use std::time::Instant;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -24,11 +25,50 @@ pub struct HistoryEntry {
     pub timestamp: f64,
 }
 
+/// A failed exchange recorded in the history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorEntry {
+    /// The method being sent, if known.
+    pub method: Option<String>,
+    pub message: String,
+    /// Seconds since history creation
+    pub timestamp: f64,
+}
+
+/// Selects history entries. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub method: Option<String>,
+    pub msg_type: Option<MessageType>,
+    /// Earliest timestamp, inclusive, in seconds since history creation.
+    pub since: Option<f64>,
+    /// Latest timestamp, inclusive, in seconds since history creation.
+    pub until: Option<f64>,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.method.as_ref().is_none_or(|m| *m == entry.method)
+            && self.msg_type.is_none_or(|t| t == entry.msg_type)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+/// Round-trip times of one method, from sends paired with their replies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodLatency {
+    pub count: usize,
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+}
+
 /// Tracks message history for debugging.
 #[derive(Debug, Clone)]
 pub struct MessageHistory {
     history: HashMap<MessageType, HashMap<String, Value>>,
-    last_error: Option<String>,
+    errors: VecDeque<ErrorEntry>,
     start_time: Instant,
     entries: Vec<HistoryEntry>,
     max_entries: usize,
@@ -42,6 +82,8 @@ impl Default for MessageHistory {
 
 impl MessageHistory {
     pub const DEFAULT_MAX_ENTRIES: usize = 100;
+    /// How many errors are kept; older ones are dropped first.
+    pub const MAX_ERRORS: usize = 20;
 
     pub fn new() -> Self {
         Self {
@@ -50,7 +92,7 @@ impl MessageHistory {
                 (MessageType::Receive, HashMap::new()),
                 (MessageType::Push, HashMap::new()),
             ]),
-            last_error: None,
+            errors: VecDeque::new(),
             start_time: Instant::now(),
             entries: Vec::new(),
             max_entries: Self::DEFAULT_MAX_ENTRIES,
@@ -64,10 +106,13 @@ impl MessageHistory {
         }
     }
 
+    /// Records a message, with secrets such as a WiFi passphrase replaced
+    /// by `<redacted>`, since the history ends up in exports and diagnostics.
     pub fn record(&mut self, msg_type: MessageType, message: &Value) {
         let Some(method) = message.get("method").and_then(|m| m.as_str()) else {
             return;
         };
+        let message = &redact(message);
 
         if let Some(type_map) = self.history.get_mut(&msg_type) {
             type_map.insert(method.to_string(), message.clone());
@@ -86,17 +131,100 @@ impl MessageHistory {
     }
 
     pub fn record_error(&mut self, error: &str) {
-        self.last_error = Some(error.to_string());
+        self.push_error(None, error);
+    }
+
+    /// Records an error that happened while sending `method`.
+    pub fn record_method_error(&mut self, method: &str, error: &str) {
+        self.push_error(Some(method), error);
+    }
+
+    fn push_error(&mut self, method: Option<&str>, error: &str) {
+        if self.errors.len() == Self::MAX_ERRORS {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(ErrorEntry {
+            method: method.map(String::from),
+            message: error.to_string(),
+            timestamp: self.start_time.elapsed().as_secs_f64(),
+        });
     }
 
     pub fn last_error(&self) -> Option<&str> {
-        self.last_error.as_deref()
+        self.errors.back().map(|e| e.message.as_str())
+    }
+
+    /// Recorded errors, oldest first.
+    pub fn errors(&self) -> impl Iterator<Item = &ErrorEntry> {
+        self.errors.iter()
     }
 
     pub fn entries(&self) -> &[HistoryEntry] {
         &self.entries
     }
 
+    /// Entries selected by `filter`, oldest first.
+    pub fn filter<'a>(
+        &'a self,
+        filter: &'a HistoryFilter,
+    ) -> impl Iterator<Item = &'a HistoryEntry> {
+        self.entries.iter().filter(|entry| filter.matches(entry))
+    }
+
+    /// Writes every entry as one JSON object per line, oldest first.
+    pub fn export_jsonl<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        for entry in &self.entries {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()
+    }
+
+    /// Round-trip times per method.
+    ///
+    /// Each received message is paired with the oldest unanswered send of
+    /// the same method. Push messages are not counted.
+    pub fn latency_stats(&self) -> HashMap<String, MethodLatency> {
+        let mut pending: HashMap<&str, VecDeque<f64>> = HashMap::new();
+        let mut samples: HashMap<&str, Vec<f64>> = HashMap::new();
+        for entry in &self.entries {
+            match entry.msg_type {
+                MessageType::Send => pending
+                    .entry(&entry.method)
+                    .or_default()
+                    .push_back(entry.timestamp),
+                MessageType::Receive => {
+                    if let Some(sent) = pending
+                        .get_mut(entry.method.as_str())
+                        .and_then(VecDeque::pop_front)
+                    {
+                        samples
+                            .entry(&entry.method)
+                            .or_default()
+                            .push(entry.timestamp - sent);
+                    }
+                }
+                MessageType::Push => {}
+            }
+        }
+
+        samples
+            .into_iter()
+            .map(|(method, times)| {
+                let min = times.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = times.iter().copied().fold(0.0, f64::max);
+                let avg = times.iter().sum::<f64>() / times.len() as f64;
+                let stats = MethodLatency {
+                    count: times.len(),
+                    min: Duration::from_secs_f64(min),
+                    avg: Duration::from_secs_f64(avg),
+                    max: Duration::from_secs_f64(max),
+                };
+                (method.to_string(), stats)
+            })
+            .collect()
+    }
+
     pub fn len(&self) -> usize {
         self.entries.len()
     }
@@ -108,7 +236,7 @@ impl MessageHistory {
     pub fn clear(&mut self) {
         self.history.values_mut().for_each(|m| m.clear());
         self.entries.clear();
-        self.last_error = None;
+        self.errors.clear();
     }
 
     pub fn summary(&self) -> HistorySummary {
@@ -118,7 +246,8 @@ impl MessageHistory {
             receive_count: count(MessageType::Receive),
             push_count: count(MessageType::Push),
             total_entries: self.entries.len(),
-            last_error: self.last_error.clone(),
+            error_count: self.errors.len(),
+            last_error: self.last_error().map(String::from),
         }
     }
 }
@@ -130,9 +259,25 @@ pub struct HistorySummary {
     pub receive_count: usize,
     pub push_count: usize,
     pub total_entries: usize,
+    pub error_count: usize,
     pub last_error: Option<String>,
 }
 
+/// Params that are never stored, e.g. the passphrase of `setWifiConfig`.
+const SECRET_PARAMS: &[&str] = &["psk"];
+
+fn redact(message: &Value) -> Value {
+    let mut message = message.clone();
+    if let Some(params) = message.get_mut("params").and_then(Value::as_object_mut) {
+        for key in SECRET_PARAMS {
+            if let Some(value) = params.get_mut(*key) {
+                *value = Value::from("<redacted>");
+            }
+        }
+    }
+    message
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -149,11 +294,85 @@ mod tests {
         assert_eq!(history.len(), 1);
     }
 
+    #[test]
+    fn test_record_redacts_secrets() {
+        let mut history = MessageHistory::new();
+        history.record(
+            MessageType::Send,
+            &json!({"method": "setWifiConfig", "params": {"ssid": "Home", "psk": "password1"}}),
+        );
+
+        let mut export = Vec::new();
+        history.export_jsonl(&mut export).unwrap();
+        let export = String::from_utf8(export).unwrap();
+        assert!(!export.contains("password1"));
+        assert!(export.contains("<redacted>"));
+        assert_eq!(history.entries()[0].message["params"]["ssid"], "Home");
+    }
+
     #[test]
     fn test_record_error() {
         let mut history = MessageHistory::new();
         history.record_error("Connection timeout");
         assert_eq!(history.last_error(), Some("Connection timeout"));
+
+        // Errors are kept as a bounded log, not overwritten
+        history.record_method_error("getPilot", "Bulb error");
+        assert_eq!(history.last_error(), Some("Bulb error"));
+        assert_eq!(history.errors().count(), 2);
+        for _ in 0..MessageHistory::MAX_ERRORS {
+            history.record_error("again");
+        }
+        assert_eq!(history.errors().count(), MessageHistory::MAX_ERRORS);
+        assert!(history.errors().all(|e| e.method.is_none()));
+    }
+
+    #[test]
+    fn test_filter_and_export() {
+        let mut history = MessageHistory::new();
+        history.record(MessageType::Send, &json!({"method": "getPilot"}));
+        history.record(
+            MessageType::Receive,
+            &json!({"method": "getPilot", "result": {}}),
+        );
+        history.record(MessageType::Send, &json!({"method": "setPilot"}));
+
+        let filter = HistoryFilter {
+            method: Some("getPilot".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(history.filter(&filter).count(), 2);
+        let filter = HistoryFilter {
+            msg_type: Some(MessageType::Send),
+            since: Some(0.0),
+            ..Default::default()
+        };
+        assert_eq!(history.filter(&filter).count(), 2);
+
+        let mut out = Vec::new();
+        history.export_jsonl(&mut out).unwrap();
+        let lines: Vec<HistoryEntry> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2].method, "setPilot");
+    }
+
+    #[test]
+    fn test_latency_stats() {
+        let mut history = MessageHistory::new();
+        history.record(MessageType::Send, &json!({"method": "getPilot"}));
+        history.record(MessageType::Receive, &json!({"method": "getPilot"}));
+        // Unanswered sends have no latency
+        history.record(MessageType::Send, &json!({"method": "setPilot"}));
+
+        let stats = history.latency_stats();
+        assert_eq!(stats.len(), 1);
+        let get = &stats["getPilot"];
+        assert_eq!(get.count, 1);
+        assert!(get.min <= get.avg && get.avg <= get.max);
     }
 
     #[test]
@@ -0,0 +1,484 @@
+//! Homes assembled from the WiZ app's own room and group configuration.
+
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use futures::future;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::{DiagnosticsSummary, HomeDiagnostics};
+use crate::discovery::{DiscoveredBulb, DiscoveryOptions, discover_bulbs_with};
+use crate::errors::Error;
+use crate::floor::Floor;
+use crate::group::{Group, GroupMember};
+use crate::light::{IpChanged, Light};
+use crate::payload::Payload;
+use crate::response::LightingResponse;
+use crate::room::Room;
+use crate::types::PowerMode;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Rooms and groups matching how bulbs were set up in the WiZ app.
+///
+/// Bulbs report the `homeId`, `roomId` and `groupId` they were assigned in
+/// `getSystemConfig`. The names the app shows are stored in the cloud and are
+/// not available locally, so rooms and groups are named after their ids;
+/// rename rooms with [`Room::update`] as needed.
+///
+/// Rooms can be arranged into [`Floor`]s, so batch operations can target a
+/// [`Scope`] such as "Upstairs" or the whole house.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::time::Duration;
+/// use wiz_lights_rs::{Floor, Home, PowerMode, Scope};
+///
+/// let home = Home::discover(Duration::from_secs(5)).await?;
+/// for (id, room) in home.rooms() {
+///     println!("room {id}: {:?}", room.list());
+/// }
+///
+/// let mut upstairs = Floor::new("Upstairs");
+/// upstairs.add_room(1).add_room(2);
+/// home.add_floor(upstairs);
+/// home.set_power(Scope::Floor("Upstairs"), &PowerMode::Off).await?;
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Home {
+    home_id: Option<u64>,
+    rooms: BTreeMap<u64, Room>,
+    groups: BTreeMap<u64, Group>,
+    unassigned: Vec<Light>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    floors: Vec<Floor>,
+}
+
+impl Home {
+    /// Builds rooms and groups from discovered bulbs.
+    ///
+    /// Bulbs must have been discovered with
+    /// [`DiscoveryOptions::probe_config`] (or had
+    /// [`DiscoveredBulb::probe_config`] called) for their ids to be known.
+    /// If bulbs from several homes are present, the home id of the first one
+    /// is reported but all of them are grouped.
+    pub fn from_discovery(bulbs: Vec<DiscoveredBulb>) -> Self {
+        let mut home = Home {
+            home_id: bulbs.iter().find_map(|bulb| bulb.home_id),
+            ..Default::default()
+        };
+        for bulb in bulbs {
+            home.insert(bulb, None);
+        }
+        home
+    }
+
+    /// Merges a new discovery pass into the home.
+    ///
+    /// Bulbs are matched to known lights by MAC. Known lights found at a new
+    /// IP are updated; unknown bulbs are added to the room they reported,
+    /// creating it if needed, and named with `naming`. Known lights that did
+    /// not answer are only reported, since they may just be switched off.
+    /// Lights without a MAC cannot be matched and are left alone.
+    pub fn import_discovered(
+        &mut self,
+        bulbs: Vec<DiscoveredBulb>,
+        naming: NamingStrategy,
+    ) -> ImportReport {
+        let mut report = ImportReport::default();
+        if self.home_id.is_none() {
+            self.home_id = bulbs.iter().find_map(|bulb| bulb.home_id);
+        }
+
+        let found: HashSet<String> = bulbs.iter().map(|bulb| bulb.mac.to_lowercase()).collect();
+        for bulb in bulbs {
+            let (mac, ip) = (bulb.mac.to_lowercase(), bulb.ip);
+            let known = self
+                .lights_mut()
+                .find(|light| light.mac() == Some(mac.as_str()));
+            match known {
+                Some(light) if light.ip() != ip => {
+                    report.moved.push(IpChanged {
+                        mac: mac.clone(),
+                        old: light.ip(),
+                        new: ip,
+                    });
+                    light.set_ip(ip);
+                }
+                Some(_) => report.unchanged.push(mac.clone()),
+                None => {
+                    let name = naming.name(&bulb);
+                    if let Some(room_id) = self.insert(bulb, name.as_deref()) {
+                        report.rooms_created.push(room_id);
+                    }
+                    report.added.push(mac.clone());
+                }
+            }
+            for group in self.groups.values_mut() {
+                group.learn(&mac, ip);
+            }
+        }
+
+        report.missing = self
+            .lights()
+            .filter_map(Light::mac)
+            .filter(|mac| !found.contains(*mac))
+            .map(String::from)
+            .collect();
+        report
+    }
+
+    /// Discovers bulbs, probes their configuration and builds the home.
+    pub async fn discover(timeout: Duration) -> Result<Self> {
+        let bulbs = discover_bulbs_with(&DiscoveryOptions {
+            timeout,
+            probe_config: true,
+            ..Default::default()
+        })
+        .await?;
+        Ok(Self::from_discovery(bulbs))
+    }
+
+    pub fn home_id(&self) -> Option<u64> {
+        self.home_id
+    }
+
+    /// Rooms keyed by their WiZ room id.
+    pub fn rooms(&self) -> impl Iterator<Item = (u64, &Room)> {
+        self.rooms.iter().map(|(id, room)| (*id, room))
+    }
+
+    pub fn room(&self, room_id: u64) -> Option<&Room> {
+        self.rooms.get(&room_id)
+    }
+
+    pub fn room_mut(&mut self, room_id: u64) -> Option<&mut Room> {
+        self.rooms.get_mut(&room_id)
+    }
+
+    /// Groups keyed by their WiZ group id.
+    pub fn groups(&self) -> impl Iterator<Item = (u64, &Group)> {
+        self.groups.iter().map(|(id, group)| (*id, group))
+    }
+
+    pub fn group(&self, group_id: u64) -> Option<&Group> {
+        self.groups.get(&group_id)
+    }
+
+    /// Returns [`Room::diagnostics`] for every room and
+    /// [`Light::diagnostics`] for unassigned lights, with totals for the
+    /// whole home. Meant to be attached to support requests.
+    pub async fn diagnostics(&self) -> HomeDiagnostics {
+        let (rooms, unassigned) = future::join(
+            future::join_all(self.rooms.values().map(Room::diagnostics)),
+            future::join_all(self.unassigned.iter().map(Light::diagnostics)),
+        )
+        .await;
+        let rooms: BTreeMap<_, _> = self.rooms.keys().copied().zip(rooms).collect();
+
+        let reports = rooms
+            .values()
+            .flat_map(|room| room.lights.values())
+            .chain(&unassigned);
+        HomeDiagnostics {
+            home_id: self.home_id,
+            summary: DiagnosticsSummary::from_reports(reports),
+            rooms,
+            unassigned,
+        }
+    }
+
+    /// Lights that reported no room, e.g. because their config was not probed.
+    pub fn unassigned(&self) -> &[Light] {
+        &self.unassigned
+    }
+
+    /// Adds a top-level floor, replacing any with the same name.
+    pub fn add_floor(&mut self, floor: Floor) {
+        match self.floors.iter_mut().find(|f| f.name() == floor.name()) {
+            Some(existing) => *existing = floor,
+            None => self.floors.push(floor),
+        }
+    }
+
+    /// Removes a top-level floor. Remove nested floors with
+    /// [`Floor::remove_child`] on their parent.
+    pub fn remove_floor(&mut self, name: &str) -> Option<Floor> {
+        let index = self.floors.iter().position(|f| f.name() == name)?;
+        Some(self.floors.remove(index))
+    }
+
+    /// Top-level floors.
+    pub fn floors(&self) -> impl Iterator<Item = &Floor> {
+        self.floors.iter()
+    }
+
+    /// Finds a floor by name, however deeply nested.
+    pub fn floor(&self, name: &str) -> Option<&Floor> {
+        self.floors.iter().find_map(|floor| floor.find(name))
+    }
+
+    pub fn floor_mut(&mut self, name: &str) -> Option<&mut Floor> {
+        self.floors
+            .iter_mut()
+            .find_map(|floor| floor.find_mut(name))
+    }
+
+    /// Every light of the home: room members, then unassigned lights.
+    pub fn lights(&self) -> impl Iterator<Item = &Light> {
+        self.rooms
+            .values()
+            .flat_map(Room::members)
+            .chain(&self.unassigned)
+    }
+
+    /// Rooms covered by `scope`, keyed by their WiZ room id.
+    ///
+    /// Floors may list rooms the home does not have, e.g. before discovery
+    /// found them; those are skipped.
+    pub fn rooms_in(&self, scope: Scope) -> Result<Vec<(u64, &Room)>> {
+        match scope {
+            Scope::Home => Ok(self.rooms().collect()),
+            Scope::Floor(name) => {
+                let floor = self
+                    .floor(name)
+                    .ok_or_else(|| Error::FloorNotFound(name.to_string()))?;
+                Ok(floor
+                    .room_ids()
+                    .filter_map(|id| self.rooms.get(&id).map(|room| (id, room)))
+                    .collect())
+            }
+            Scope::Room(id) => {
+                let room = self.room(id).ok_or(Error::HomeRoomNotFound(id))?;
+                Ok(vec![(id, room)])
+            }
+        }
+    }
+
+    /// Lights covered by `scope`. Only [`Scope::Home`] includes unassigned
+    /// lights.
+    pub fn lights_in(&self, scope: Scope) -> Result<Vec<&Light>> {
+        if scope == Scope::Home {
+            return Ok(self.lights().collect());
+        }
+        Ok(self
+            .rooms_in(scope)?
+            .into_iter()
+            .flat_map(|(_, room)| room.members())
+            .collect())
+    }
+
+    /// Applies a payload to every light in `scope` concurrently.
+    pub async fn set(&self, scope: Scope<'_>, payload: &Payload) -> Result<Vec<LightingResponse>> {
+        let lights = self.lights_in(scope)?;
+        let results = future::join_all(lights.iter().map(|light| light.set(payload))).await;
+        results.into_iter().collect()
+    }
+
+    pub async fn set_power(
+        &self,
+        scope: Scope<'_>,
+        power: &PowerMode,
+    ) -> Result<Vec<LightingResponse>> {
+        let lights = self.lights_in(scope)?;
+        let results = future::join_all(lights.iter().map(|light| light.set_power(power))).await;
+        results.into_iter().collect()
+    }
+
+    /// Adds a bulb to its room and group, creating them as needed.
+    ///
+    /// Returns the id of the room if it was created.
+    fn insert(&mut self, bulb: DiscoveredBulb, name: Option<&str>) -> Option<u64> {
+        if let Some(group_id) = bulb.group_id.filter(|id| *id != 0) {
+            let group = self
+                .groups
+                .entry(group_id)
+                .or_insert_with(|| Group::new(&format!("Group {group_id}")));
+            group.add(GroupMember::Mac(bulb.mac.clone()));
+            group.learn(&bulb.mac, bulb.ip);
+        }
+
+        let Some(room_id) = bulb.room_id.filter(|id| *id != 0) else {
+            self.unassigned.push(bulb.into_light(name));
+            return None;
+        };
+        let created = !self.rooms.contains_key(&room_id);
+        let room = self
+            .rooms
+            .entry(room_id)
+            .or_insert_with(|| Room::new(&format!("Room {room_id}")));
+        let ip = bulb.ip;
+        if let Err(e) = room.new_light(bulb.into_light(name)) {
+            debug!("Skipping {} in room {}: {}", ip, room_id, e);
+        }
+        created.then_some(room_id)
+    }
+
+    fn lights_mut(&mut self) -> impl Iterator<Item = &mut Light> {
+        self.rooms
+            .values_mut()
+            .flat_map(Room::members_mut)
+            .chain(&mut self.unassigned)
+    }
+}
+
+/// Which part of a [`Home`] a batch operation targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope<'a> {
+    /// Every light, including unassigned ones.
+    Home,
+    /// The rooms of a floor, found by name, and of the floors nested in it.
+    Floor(&'a str),
+    /// A room by its WiZ room id.
+    Room(u64),
+}
+
+/// How [`Home::import_discovered`] names lights it adds.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum NamingStrategy {
+    /// Leave new lights unnamed.
+    None,
+    /// The bulb's MAC address.
+    Mac,
+    /// The bulb's IP address.
+    Ip,
+    /// The model followed by the end of the MAC, e.g. `ESP01_SHRGB1C_31 0a1b`,
+    /// or the MAC if the model is unknown.
+    #[default]
+    Model,
+    /// Computed from the discovered bulb.
+    Custom(fn(&DiscoveredBulb) -> Option<String>),
+}
+
+impl NamingStrategy {
+    fn name(&self, bulb: &DiscoveredBulb) -> Option<String> {
+        match self {
+            NamingStrategy::None => None,
+            NamingStrategy::Mac => Some(bulb.mac.clone()),
+            NamingStrategy::Ip => Some(bulb.ip.to_string()),
+            NamingStrategy::Model => {
+                let model = bulb
+                    .bulb_type
+                    .as_ref()
+                    .map(|t| t.name.as_str())
+                    .or(bulb.module_name.as_deref());
+                let suffix = bulb.mac.get(bulb.mac.len().saturating_sub(4)..);
+                match (model, suffix) {
+                    (Some(model), Some(suffix)) => Some(format!("{model} {suffix}")),
+                    _ => Some(bulb.mac.clone()),
+                }
+            }
+            NamingStrategy::Custom(name) => name(bulb),
+        }
+    }
+}
+
+/// What [`Home::import_discovered`] changed, by MAC address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Bulbs that were not known before.
+    pub added: Vec<String>,
+    /// Known bulbs found at a new IP.
+    pub moved: Vec<IpChanged>,
+    /// Known bulbs found at the IP they had.
+    pub unchanged: Vec<String>,
+    /// Known bulbs that did not answer.
+    pub missing: Vec<String>,
+    /// Ids of rooms created for added bulbs.
+    pub rooms_created: Vec<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn bulb(last: u8, mac: &str, room_id: Option<u64>) -> DiscoveredBulb {
+        let mut bulb = DiscoveredBulb::new(Ipv4Addr::new(192, 168, 1, last), mac.to_string());
+        bulb.room_id = room_id;
+        bulb.module_name = Some("ESP01_SHRGB1C_31".to_string());
+        bulb
+    }
+
+    #[test]
+    fn test_import_discovered() {
+        let mut home = Home::from_discovery(vec![
+            bulb(10, "a8bb50000001", Some(1)),
+            bulb(11, "a8bb50000002", Some(1)),
+            bulb(12, "a8bb50000003", None),
+        ]);
+
+        let report = home.import_discovered(
+            vec![
+                bulb(10, "a8bb50000001", Some(1)),
+                bulb(20, "A8BB50000003", None),
+                bulb(30, "a8bb50000004", Some(2)),
+            ],
+            NamingStrategy::Model,
+        );
+        assert_eq!(report.unchanged, ["a8bb50000001"]);
+        assert_eq!(
+            report.moved,
+            [IpChanged {
+                mac: "a8bb50000003".to_string(),
+                old: Ipv4Addr::new(192, 168, 1, 12),
+                new: Ipv4Addr::new(192, 168, 1, 20),
+            }]
+        );
+        assert_eq!(report.added, ["a8bb50000004"]);
+        assert_eq!(report.missing, ["a8bb50000002"]);
+        assert_eq!(report.rooms_created, [2]);
+
+        assert_eq!(home.unassigned()[0].ip(), Ipv4Addr::new(192, 168, 1, 20));
+        let added = home.room(2).unwrap().members().next().unwrap();
+        assert_eq!(added.name(), Some("ESP01_SHRGB1C_31 0004"));
+    }
+
+    #[test]
+    fn test_floors() {
+        let mut home = Home::from_discovery(vec![
+            bulb(10, "a8bb50000001", Some(1)),
+            bulb(11, "a8bb50000002", Some(2)),
+            bulb(12, "a8bb50000003", Some(3)),
+            bulb(13, "a8bb50000004", None),
+        ]);
+        let mut attic = Floor::new("Attic");
+        attic.add_room(3).add_room(99);
+        let mut upstairs = Floor::new("Upstairs");
+        upstairs.add_room(1).add_child(attic);
+        let mut downstairs = Floor::new("Downstairs");
+        downstairs.add_room(2);
+        home.add_floor(upstairs);
+        home.add_floor(downstairs);
+
+        let ips = |scope| -> Vec<u8> {
+            let mut ips: Vec<_> = home
+                .lights_in(scope)
+                .unwrap()
+                .iter()
+                .map(|light| light.ip().octets()[3])
+                .collect();
+            ips.sort();
+            ips
+        };
+        assert_eq!(ips(Scope::Home), [10, 11, 12, 13]);
+        assert_eq!(ips(Scope::Floor("Upstairs")), [10, 12]);
+        assert_eq!(ips(Scope::Floor("Attic")), [12]);
+        assert_eq!(ips(Scope::Room(2)), [11]);
+        assert!(matches!(
+            home.lights_in(Scope::Floor("Cellar")),
+            Err(Error::FloorNotFound(_))
+        ));
+
+        home.floor_mut("Attic").unwrap().remove_room(99);
+        let json = serde_json::to_value(&home).unwrap();
+        let restored: Home = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            restored.floors().collect::<Vec<_>>(),
+            home.floors().collect::<Vec<_>>()
+        );
+        assert_eq!(restored.floor("Attic").unwrap().rooms(), [3]);
+    }
+}
@@ -0,0 +1,423 @@
+//! Embedded REST server for controlling lights over HTTP.
+//!
+//! [`HttpApi`] serves a small JSON API on the selected async runtime so the
+//! crate can act as a local bridge for web dashboards. Requests are handled
+//! one per connection (`Connection: close`); there is no authentication, so
+//! bind it to a trusted interface.
+//!
+//! Requires the `http-api` feature.
+//!
+//! | Method | Path | Action |
+//! |--------|------|--------|
+//! | `GET`  | `/lights` | List lights |
+//! | `GET`  | `/lights/{ip}` | Query a light's status |
+//! | `POST` | `/lights/{ip}` | Apply a [`Payload`] given as JSON |
+//! | `GET`  | `/rooms` | List rooms and their lights |
+//! | `POST` | `/rooms/{name}` | Apply a [`Payload`] to every light in a room |
+//! | `POST` | `/rooms/{name}/scenes/{scene}` | Apply a [`RoomScene`](crate::RoomScene) |
+//! | `GET`  | `/scenes` | List built-in [`SceneMode`]s |
+//! | `GET`  | `/metrics` | OpenMetrics telemetry (with the `metrics` feature) |
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::http::HttpApi;
+//!
+//! let api = HttpApi::new().light(desk_lamp).room(living_room);
+//! api.serve("127.0.0.1:8080").await?;
+//! ```
+//!
+//! ```text
+//! curl -X POST localhost:8080/lights/192.168.1.20 -d '{"dimming": 40, "temp": 2700}'
+//! ```
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use log::debug;
+use serde_json::{Value, json};
+
+use crate::errors::Error;
+use crate::light::Light;
+use crate::payload::Payload;
+use crate::response::{BatchResults, LightingResponse};
+use crate::room::Room;
+use crate::runtime::{self, AsyncTcpListener, AsyncTcpStream, TcpListener};
+use crate::types::SceneMode;
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(feature = "metrics")]
+const METRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Largest request accepted, headers and body included.
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+
+/// A parsed HTTP request.
+#[derive(Debug, PartialEq)]
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+/// REST server over a fixed set of lights and rooms.
+#[derive(Debug, Default)]
+pub struct HttpApi {
+    lights: Vec<Light>,
+    rooms: Vec<Room>,
+}
+
+impl HttpApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expose a light, addressed by its IP.
+    pub fn light(mut self, light: Light) -> Self {
+        self.lights.push(light);
+        self
+    }
+
+    /// Expose a room, addressed by its name.
+    pub fn room(mut self, room: Room) -> Self {
+        self.rooms.push(room);
+        self
+    }
+
+    /// Bind to `addr` and serve requests until the listener fails.
+    pub async fn serve(self, addr: &str) -> Result<()> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| Error::socket("bind", e))?;
+        self.serve_on(listener).await
+    }
+
+    /// Serve requests on an already bound listener.
+    ///
+    /// Useful with port 0, to learn the chosen port from
+    /// [`AsyncTcpListener::local_addr`] first.
+    pub async fn serve_on(self, listener: TcpListener) -> Result<()> {
+        let api = Arc::new(self);
+        loop {
+            let (stream, peer) = listener
+                .accept()
+                .await
+                .map_err(|e| Error::socket("accept", e))?;
+            let api = Arc::clone(&api);
+            runtime::spawn(async move {
+                if let Err(e) = api.handle_connection(stream, peer).await {
+                    debug!("HTTP connection from {} failed: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    async fn handle_connection<S: AsyncTcpStream>(
+        &self,
+        mut stream: S,
+        peer: SocketAddr,
+    ) -> std::io::Result<()> {
+        let response = match read_request(&mut stream).await? {
+            Some(request) => {
+                debug!("HTTP {} {} from {}", request.method, request.path, peer);
+                #[cfg(feature = "metrics")]
+                if request.method == "GET" && request.path == "/metrics" {
+                    let text = crate::metrics::gather();
+                    return stream
+                        .write_all(&encode_response(200, METRICS_CONTENT_TYPE, &text))
+                        .await;
+                }
+                self.route(&request).await
+            }
+            None => (400, json!({"error": "malformed request"})),
+        };
+        let (status, body) = response;
+        stream
+            .write_all(&encode_response(
+                status,
+                "application/json",
+                &body.to_string(),
+            ))
+            .await
+    }
+
+    async fn route(&self, request: &Request) -> (u16, Value) {
+        let segments: Vec<String> = request
+            .path
+            .split('?')
+            .next()
+            .unwrap_or("")
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["lights"]) => (200, self.lights.iter().map(light_json).collect()),
+            ("GET", ["lights", ip]) => match self.find_light(ip) {
+                Some(light) => respond(light.get_status().await.map(|status| json!(status))),
+                None => not_found("light"),
+            },
+            ("POST" | "PUT", ["lights", ip]) => match self.find_light(ip) {
+                Some(light) => self.set_light(light, request).await,
+                None => not_found("light"),
+            },
+            ("GET", ["rooms"]) => (200, self.rooms.iter().map(room_json).collect()),
+            ("POST" | "PUT", ["rooms", name]) => match self.find_room(name) {
+                Some(room) => self.set_room(room, request).await,
+                None => not_found("room"),
+            },
+            ("POST" | "PUT", ["rooms", name, "scenes", scene]) => match self.find_room(name) {
+                Some(room) => respond(
+                    room.apply_scene(scene)
+                        .await
+                        .and_then(BatchResults::into_result)
+                        .map(|r| responses_json(&r)),
+                ),
+                None => not_found("room"),
+            },
+            ("GET", ["scenes"]) => (200, SceneMode::all().iter().map(scene_json).collect()),
+            _ => (404, json!({"error": "no such endpoint"})),
+        }
+    }
+
+    async fn set_light(&self, light: &Light, request: &Request) -> (u16, Value) {
+        match parse_payload(request) {
+            Ok(payload) => respond(light.set(&payload).await.map(|r| response_json(&r))),
+            Err(e) => (400, json!({"error": e})),
+        }
+    }
+
+    async fn set_room(&self, room: &Room, request: &Request) -> (u16, Value) {
+        let payload = match parse_payload(request) {
+            Ok(payload) => payload,
+            Err(e) => return (400, json!({"error": e})),
+        };
+        let results = room.set(&payload).await.into_result();
+        respond(results.map(|r| responses_json(&r)))
+    }
+
+    fn find_light(&self, ip: &str) -> Option<&Light> {
+        let ip: Ipv4Addr = ip.parse().ok()?;
+        self.lights.iter().find(|light| light.ip() == ip)
+    }
+
+    fn find_room(&self, name: &str) -> Option<&Room> {
+        self.rooms
+            .iter()
+            .find(|room| room.name().eq_ignore_ascii_case(name))
+    }
+}
+
+fn light_json(light: &Light) -> Value {
+    json!({
+        "ip": light.ip().to_string(),
+        "name": light.name(),
+        "mac": light.mac(),
+        "status": light.status(),
+    })
+}
+
+fn room_json(room: &Room) -> Value {
+    let lights: Vec<Value> = room
+        .list()
+        .into_iter()
+        .flatten()
+        .filter_map(|id| room.read(id))
+        .map(light_json)
+        .collect();
+    let scenes: Vec<&str> = room.scenes().map(|scene| scene.name()).collect();
+    json!({"name": room.name(), "lights": lights, "scenes": scenes})
+}
+
+fn scene_json(scene: &SceneMode) -> Value {
+    json!({"id": scene.id(), "name": scene.name(), "dynamic": scene.is_dynamic()})
+}
+
+fn response_json(resp: &LightingResponse) -> Value {
+    json!({"ip": resp.ip.to_string(), "ok": true})
+}
+
+fn responses_json(resps: &[LightingResponse]) -> Value {
+    resps.iter().map(response_json).collect()
+}
+
+fn respond(result: Result<Value>) -> (u16, Value) {
+    match result {
+        Ok(body) => (200, body),
+        Err(Error::SceneNotFound(name)) => {
+            (404, json!({"error": format!("scene not found: {name}")}))
+        }
+        Err(e) => (502, json!({"error": e.to_string()})),
+    }
+}
+
+fn not_found(what: &str) -> (u16, Value) {
+    (404, json!({"error": format!("{what} not found")}))
+}
+
+fn parse_payload(request: &Request) -> std::result::Result<Payload, String> {
+    let payload: Payload = serde_json::from_slice(&request.body).map_err(|e| e.to_string())?;
+    if !payload.is_valid() {
+        return Err("payload sets no attributes".into());
+    }
+    Ok(payload)
+}
+
+/// Reads one request; `None` if it is malformed or too large.
+async fn read_request<S: AsyncTcpStream>(stream: &mut S) -> std::io::Result<Option<Request>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        match parse_request(&buffer) {
+            Parse::Complete(request) => return Ok(Some(request)),
+            Parse::Invalid => return Ok(None),
+            Parse::Incomplete => {}
+        }
+        if buffer.len() > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// What the bytes received so far amount to.
+#[derive(Debug, PartialEq)]
+enum Parse {
+    Complete(Request),
+    /// More data is needed.
+    Incomplete,
+    /// The request is malformed or announces a body that would exceed
+    /// [`MAX_REQUEST_BYTES`].
+    Invalid,
+}
+
+/// Parses a complete request from `buffer`.
+fn parse_request(buffer: &[u8]) -> Parse {
+    let Some(header_end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") else {
+        return Parse::Incomplete;
+    };
+    let Some((method, path, content_length)) = parse_head(&buffer[..header_end]) else {
+        return Parse::Invalid;
+    };
+
+    let body_start = header_end + 4;
+    let body_end = match body_start.checked_add(content_length) {
+        Some(end) if end <= MAX_REQUEST_BYTES => end,
+        _ => return Parse::Invalid,
+    };
+    match buffer.get(body_start..body_end) {
+        Some(body) => Parse::Complete(Request {
+            method,
+            path,
+            body: body.to_vec(),
+        }),
+        None => Parse::Incomplete,
+    }
+}
+
+/// Method, path and `Content-Length` (0 if absent) of a request head.
+fn parse_head(head: &[u8]) -> Option<(String, String, usize)> {
+    let head = std::str::from_utf8(head).ok()?;
+    let mut lines = head.split("\r\n");
+
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_uppercase();
+    let path = request_line.next()?.to_string();
+
+    let content_length = match lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+    {
+        Some((_, value)) => value.trim().parse::<usize>().ok()?,
+        None => 0,
+    };
+    Some((method, path, content_length))
+}
+
+fn encode_response(status: u16, content_type: &str, body: &str) -> Vec<u8> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Bad Gateway",
+    };
+    format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .into_bytes()
+}
+
+/// Decodes `%XX` escapes in a path segment, e.g. room names with spaces.
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_waits_for_body() {
+        let raw = b"POST /rooms/Living%20Room HTTP/1.1\r\nContent-Length: 14\r\n\r\n{\"dimming\":";
+        assert_eq!(parse_request(raw), Parse::Incomplete);
+
+        let raw =
+            b"POST /rooms/Living%20Room HTTP/1.1\r\nContent-Length: 14\r\n\r\n{\"dimming\":40}";
+        let Parse::Complete(request) = parse_request(raw) else {
+            panic!("request should be complete");
+        };
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/rooms/Living%20Room");
+        assert_eq!(request.body, b"{\"dimming\":40}");
+    }
+
+    #[test]
+    fn test_parse_request_rejects_oversized_body() {
+        // Would overflow the body end instead of waiting for more data
+        let raw =
+            b"POST /lights/1.2.3.4 HTTP/1.1\r\nContent-Length: 18446744073709551615\r\n\r\n{}";
+        assert_eq!(parse_request(raw), Parse::Invalid);
+
+        let raw = format!(
+            "POST /lights/1.2.3.4 HTTP/1.1\r\nContent-Length: {MAX_REQUEST_BYTES}\r\n\r\n{{}}"
+        );
+        assert_eq!(parse_request(raw.as_bytes()), Parse::Invalid);
+
+        let raw = b"POST /lights/1.2.3.4 HTTP/1.1\r\nContent-Length: lots\r\n\r\n{}";
+        assert_eq!(parse_request(raw), Parse::Invalid);
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("Living%20Room"), "Living Room");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
@@ -34,10 +34,29 @@
 //! - **Color Temperature**: Set warm to cool white (1000K-8000K) using [`Kelvin`]
 //! - **Scenes**: Use preset lighting scenes with [`SceneMode`]
 //! - **Power Control**: Turn lights on/off or reboot with [`PowerMode`]
+//! - **Smart Plugs**: Switch and meter outlets with [`Plug`]
+//! - **Device-Agnostic Control**: Drive lights, plugs, rooms and groups through [`LightController`]
 //! - **Room Grouping**: Organize lights into [`Room`]s for batch operations
-//! - **Discovery**: Find bulbs on your network with [`discover_bulbs`]
+//! - **Fast Batches**: Send one payload to many bulbs from a single socket with [`broadcast_set`]
+//! - **Change Notifications**: Subscribe to cached status changes with [`Light::on_change`]
+//! - **Polling**: Keep cached status fresh with [`Light::start_polling`]
+//! - **Statistics**: Opt-in protocol counters and round-trip times with [`stats`]
+//! - **Zones**: Address lights across rooms with [`Group`]s
+//! - **Home Sync**: Rebuild the WiZ app's rooms and groups from discovery with [`Home`]
+//! - **Floors**: Arrange rooms into nested [`Floor`]s and target a [`Scope`] of the home
+//! - **Discovery**: Find bulbs on your network with [`discover_bulbs`] or [`discover_in_subnet`]
 //! - **Hue/Saturation**: Alternative color mode with [`HueSaturation`]
 //! - **Push Notifications**: Real-time state updates via [`push::PushManager`]
+//! - **Effects**: Animate keyframe timelines in software with [`effects::Effect`]
+//! - **Ambilight**: Follow on-screen colors with [`ambilight::Ambilight`]
+//! - **Cancellation**: Stop discovery, schedules and effects on any runtime with [`CancelToken`]
+//! - **Rate Limiting**: Keep animations from flooding bulbs with [`RateLimiter`]
+//! - **Retries**: Choose how lost replies are retried with [`retry::RetryPolicy`]
+//! - **Availability Monitoring**: Track bulbs coming and going with [`monitor::BulbMonitor`]
+//! - **Energy Monitoring**: Track power use over time with [`energy::EnergyMonitor`]
+//! - **Provisioning**: Onboard new bulbs onto WiFi with [`provisioning::provision`]
+//! - **Protocol Core**: Build and parse datagrams for your own network stack with [`protocol`]
+//! - **Entities**: Export state in a Home Assistant style model with [`entities::Entity`]
 //!
 //! ## Communication
 //!
@@ -74,36 +93,162 @@
 //!
 //! ## Feature Flags
 //!
+//! - `std` (default): Everything that needs an OS; without it only the protocol
+//!   core is built, see [`no_std`](#no_std)
 //! - `runtime-tokio` (default): Use the tokio async runtime
 //! - `runtime-async-std`: Use the async-std runtime
 //! - `runtime-smol`: Use the smol runtime
+//! - `wasm`: Delegate sockets, timers and tasks to a user-supplied `runtime::Bridge`, e.g. in a browser
+//! - `audio-sync`: Drive lights from user-supplied audio with [`audio::AudioSync`]
+//! - `http-api`: Serve a REST API for lights and rooms with [`http::HttpApi`]
+//! - `metrics`: Record per-light telemetry for Prometheus with [`metrics::gather`]
+//! - `testing`: Simulate bulbs without hardware with [`testing::MockBulb`]
+//! - `cli`: Build the `wiz` command-line tool
+//! - `tui`: Add the interactive `wiz tui` dashboard
+//!
+//! ## no_std
+//!
+//! With `default-features = false` and no runtime the crate builds on
+//! `core` and `alloc` alone, e.g. for a microcontroller with its own network
+//! stack. It then contains [`protocol`] and the types it works with:
+//! `Payload`, `LightStatus`, the value types such as `Color` and `Kelvin`,
+//! the bulb configuration types and model database, and `Error`. Datagrams
+//! go out and come back through whatever stack the application has. Without
+//! a clock, `LightStatus` carries no request or confirmation timestamps.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod ambilight;
+#[cfg(feature = "audio-sync")]
+pub mod audio;
+#[cfg(feature = "std")]
+mod broadcast;
+#[cfg(feature = "std")]
+mod calibration;
+#[cfg(feature = "std")]
+mod cancel;
 mod config;
+#[cfg(feature = "std")]
+mod controller;
+mod database;
+#[cfg(feature = "std")]
+mod diagnostics;
+#[cfg(feature = "std")]
 mod discovery;
+#[cfg(feature = "std")]
+pub mod effects;
+#[cfg(feature = "std")]
+pub mod energy;
+#[cfg(feature = "std")]
+pub mod entities;
 mod errors;
+#[cfg(feature = "std")]
+mod firmware;
+#[cfg(not(feature = "std"))]
+mod float;
+#[cfg(feature = "std")]
+mod floor;
+#[cfg(feature = "std")]
+mod group;
+#[cfg(feature = "std")]
 mod history;
+#[cfg(feature = "std")]
+mod home;
+#[cfg(feature = "http-api")]
+pub mod http;
+#[cfg(feature = "std")]
 mod light;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod monitor;
 mod payload;
+#[cfg(feature = "std")]
+mod plug;
+#[cfg(feature = "std")]
+pub mod polling;
+#[cfg(feature = "std")]
+mod presets;
+pub mod protocol;
+#[cfg(feature = "std")]
+pub mod provisioning;
+#[cfg(feature = "std")]
 pub mod push;
+#[cfg(feature = "std")]
+mod ratelimit;
+#[cfg(feature = "std")]
 mod response;
+#[cfg(feature = "std")]
+pub mod retry;
+#[cfg(feature = "std")]
 mod room;
+#[cfg(feature = "std")]
 pub mod runtime;
+#[cfg(feature = "std")]
+pub mod stats;
 mod status;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod types;
 
 // Re-export public API
+#[cfg(feature = "std")]
+pub use broadcast::{BroadcastOptions, broadcast_set, broadcast_set_with};
+#[cfg(feature = "std")]
+pub use calibration::Calibration;
+#[cfg(feature = "std")]
+pub use cancel::{CancelToken, Cancelled};
 pub use config::{
-    BulbClass, BulbType, ExtendedWhiteRange, Features, KelvinRange, SystemConfig, WhiteRange,
+    BulbClass, BulbType, DevInfo, ExtendedWhiteRange, Features, KelvinRange, SystemConfig,
+    SystemConfigUpdate, UserConfig, UserConfigUpdate, WhiteRange, WifiConfig,
+};
+#[cfg(feature = "std")]
+pub use controller::LightController;
+pub use database::{BulbDatabase, KnownModel};
+#[cfg(feature = "std")]
+pub use diagnostics::{Diagnostics, DiagnosticsSummary, HomeDiagnostics, RoomDiagnostics};
+#[cfg(feature = "std")]
+pub use discovery::{
+    DiscoveredBulb, DiscoveryOptions, discover_bulbs, discover_bulbs_stream,
+    discover_bulbs_stream_with, discover_bulbs_with, discover_in_subnet, discover_in_subnet_with,
 };
-pub use discovery::{DiscoveredBulb, discover_bulbs};
 pub use errors::Error;
-pub use history::{HistoryEntry, HistorySummary, MessageHistory, MessageType};
-pub use light::Light;
+#[cfg(feature = "std")]
+pub use firmware::{FirmwareProgress, FirmwareUpdate};
+#[cfg(feature = "std")]
+pub use floor::Floor;
+#[cfg(feature = "std")]
+pub use group::{Group, GroupMember};
+#[cfg(feature = "std")]
+pub use history::{
+    ErrorEntry, HistoryEntry, HistoryFilter, HistorySummary, MessageHistory, MessageType,
+    MethodLatency,
+};
+#[cfg(feature = "std")]
+pub use home::{Home, ImportReport, NamingStrategy, Scope};
+#[cfg(feature = "std")]
+pub use ipnet::Ipv4Net;
+#[cfg(feature = "std")]
+pub use light::{
+    ChangeSubscription, IpChangeCallback, IpChanged, LatencyReport, Light, StatusChange,
+    StatusChangeCallback, ToggleOptions,
+};
 pub use payload::Payload;
-pub use response::LightingResponse;
-pub use room::Room;
-pub use status::{LastSet, LightStatus};
+#[cfg(feature = "std")]
+pub use plug::Plug;
+#[cfg(feature = "std")]
+pub use presets::{Preset, Snapshot};
+#[cfg(feature = "std")]
+pub use ratelimit::RateLimiter;
+#[cfg(feature = "std")]
+pub use response::{Adjustment, BatchResults, LightingResponse};
+#[cfg(feature = "std")]
+pub use room::{Room, RoomIntoIter, RoomIter, RoomIterMut, RoomScene};
+pub use status::{FanStatus, LastSet, LightStatus};
 pub use types::{
-    Brightness, Color, ColorRGBW, ColorRGBWW, FanDirection, FanMode, FanSpeed, FanState,
-    HueSaturation, Kelvin, PowerMode, Ratio, SceneMode, Speed, White,
+    Brightness, BrightnessOrOff, Color, ColorRGBW, ColorRGBWW, FanDirection, FanMode, FanSpeed,
+    FanState, HueSaturation, Kelvin, PowerMode, RangeError, Ratio, SceneMode, Speed, White,
 };
@@ -1,25 +1,271 @@
 //! Individual light control.
 
-use std::net::Ipv4Addr;
-use std::sync::Arc;
-use std::time::Duration;
-
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::stream::{BoxStream, StreamExt};
 use log::debug;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{Value, json};
 
-use crate::runtime::{self, AsyncUdpSocket, Mutex, UdpSocket};
+use crate::runtime::{self, Instant, JoinHandle, Mutex, Transport};
 
-use crate::config::{BulbType, ExtendedWhiteRange, SystemConfig, SystemConfigResponse, WhiteRange};
+use crate::calibration::Calibration;
+use crate::cancel::CancelToken;
+use crate::config::{
+    BulbType, DevInfo, ExtendedWhiteRange, SystemConfig, SystemConfigResponse, SystemConfigUpdate,
+    UserConfig, UserConfigUpdate, WhiteRange, WifiConfig,
+};
+use crate::diagnostics::{self, Diagnostics};
+use crate::discovery::{DiscoveryOptions, discover_bulbs_with};
 use crate::errors::Error;
+use crate::firmware::{self, FirmwareProgress, FirmwareUpdate};
 use crate::history::{MessageHistory, MessageType};
+#[cfg(feature = "metrics")]
+use crate::metrics;
 use crate::payload::Payload;
-use crate::response::{LightingResponse, LightingResponseType};
-use crate::status::{BulbStatus, LightStatus};
-use crate::types::{FanDirection, FanMode, FanSpeed, FanState, PowerMode};
+use crate::polling::StatusPoller;
+use crate::presets::{Preset, Snapshot};
+use crate::protocol::{self, RetryClass};
+use crate::push::SyncPilotEvent;
+use crate::ratelimit::{Coalescer, CommandQueue, RateLimiter};
+use crate::response::{Adjustment, LightingResponse, LightingResponseType};
+use crate::retry::{self, RetryAttempt, RetryPolicy};
+use crate::stats;
+use crate::status::{FanStatus, LightStatus};
+use crate::types::{
+    Brightness, BrightnessOrOff, Color, FanDirection, FanMode, FanSpeed, FanState, Kelvin,
+    PowerMode, Ratio, SceneMode, Speed,
+};
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// Round-trip statistics from [`Light::measure_latency`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub sent: u32,
+    pub received: u32,
+    pub min: Option<Duration>,
+    pub avg: Option<Duration>,
+    pub max: Option<Duration>,
+}
+
+impl LatencyReport {
+    /// Fraction of probes that got no reply, from 0.0 to 1.0.
+    pub fn packet_loss(&self) -> f64 {
+        if self.sent == 0 {
+            return 0.0;
+        }
+        f64::from(self.sent - self.received) / f64::from(self.sent)
+    }
+}
+
+/// Reported when a light addressed by MAC is found at a new IP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpChanged {
+    pub mac: String,
+    pub old: Ipv4Addr,
+    pub new: Ipv4Addr,
+}
+
+/// Callback invoked with [`IpChanged`] events, see [`Light::on_ip_change`].
+pub type IpChangeCallback = Arc<dyn Fn(&IpChanged) + Send + Sync + 'static>;
+
+/// The light's current IP, updatable through `&self` when a MAC-addressed
+/// bulb moves.
+struct Address(Arc<AtomicU32>);
+
+impl Address {
+    fn new(ip: Ipv4Addr) -> Self {
+        Address(Arc::new(AtomicU32::new(ip.into())))
+    }
+
+    /// The same address, updated together with this one.
+    fn share(&self) -> Self {
+        Address(Arc::clone(&self.0))
+    }
+
+    fn get(&self) -> Ipv4Addr {
+        self.0.load(Ordering::Relaxed).into()
+    }
+
+    fn set(&self, ip: Ipv4Addr) {
+        self.0.store(ip.into(), Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ipv4Addr::deserialize(deserializer).map(Address::new)
+    }
+}
+
+/// Reported when a light's cached status changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusChange {
+    pub ip: Ipv4Addr,
+    /// The status before the change, `None` if nothing was cached yet.
+    pub previous: Option<LightStatus>,
+    pub current: LightStatus,
+}
+
+/// Callback invoked with [`StatusChange`] events, see [`Light::on_change`].
+pub type StatusChangeCallback = Arc<dyn Fn(&StatusChange) + Send + Sync + 'static>;
+
+/// Identifies a change listener so it can be removed later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChangeSubscription(u64);
+
+impl ChangeSubscription {
+    pub(crate) fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        ChangeSubscription(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[derive(Default)]
+struct Listeners {
+    callbacks: Vec<(ChangeSubscription, StatusChangeCallback)>,
+    senders: Vec<UnboundedSender<StatusChange>>,
+}
+
+#[derive(Default)]
+struct StatusCell {
+    status: RwLock<Option<LightStatus>>,
+    listeners: std::sync::Mutex<Listeners>,
+}
+
+/// The cached status, shared by clones and updatable through `&self`.
+///
+/// Plain locks are enough: they are only held to copy or merge a status and
+/// never across an await. Listeners run after the status lock is released.
+#[derive(Default)]
+struct SharedStatus(Arc<StatusCell>);
+
+impl SharedStatus {
+    fn get(&self) -> Option<LightStatus> {
+        self.0
+            .status
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn is_none(&self) -> bool {
+        self.0
+            .status
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_none()
+    }
+
+    /// Applies `update` and notifies listeners if the status changed.
+    fn modify(&self, ip: Ipv4Addr, update: impl FnOnce(&mut Option<LightStatus>)) {
+        let change = {
+            let mut cached = self.0.status.write().unwrap_or_else(|e| e.into_inner());
+            let previous = cached.clone();
+            update(&mut cached);
+            if let Some(status) = cached.as_mut() {
+                status.stamp(SystemTime::now());
+            }
+            match &*cached {
+                Some(current) if previous.as_ref() != Some(current) => Some(StatusChange {
+                    ip,
+                    previous,
+                    current: current.clone(),
+                }),
+                _ => None,
+            }
+        };
+        if let Some(change) = change {
+            self.notify(&change);
+        }
+    }
+
+    fn notify(&self, change: &StatusChange) {
+        // Clone the callbacks out so they may subscribe or unsubscribe
+        let callbacks: Vec<StatusChangeCallback> = {
+            let mut listeners = self.listeners();
+            listeners
+                .senders
+                .retain(|tx| tx.unbounded_send(change.clone()).is_ok());
+            listeners
+                .callbacks
+                .iter()
+                .map(|(_, cb)| Arc::clone(cb))
+                .collect()
+        };
+        for callback in callbacks {
+            callback(change);
+        }
+    }
+
+    fn listeners(&self) -> std::sync::MutexGuard<'_, Listeners> {
+        self.0.listeners.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fmt::Debug for SharedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl Serialize for SharedStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let status = Option::<LightStatus>::deserialize(deserializer)?;
+        Ok(SharedStatus(Arc::new(StatusCell {
+            status: RwLock::new(status),
+            listeners: Default::default(),
+        })))
+    }
+}
+
+/// A fresh JSON-RPC id for an outgoing request.
+pub(crate) fn next_request_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Wraps the IP change callback so [`Light`] can keep deriving `Debug`.
+#[derive(Clone)]
+struct IpChangeHandler(IpChangeCallback);
+
+impl fmt::Debug for IpChangeHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IpChangeHandler")
+    }
+}
+
+// Lights are shared between push listeners and controller tasks
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Light>();
+};
+
 /// Represents a single Wiz smart light bulb.
 ///
 /// A `Light` communicates with a physical Wiz bulb over UDP. Each light is
@@ -38,11 +284,79 @@ type Result<T> = std::result::Result<T, Error>;
 #[serde_with::skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Light {
-    ip: Ipv4Addr,
+    ip: Address,
+    mac: Option<String>,
     name: Option<String>,
-    status: Option<LightStatus>,
+    #[serde(default, skip_serializing_if = "SharedStatus::is_none")]
+    status: SharedStatus,
+    calibration: Option<Calibration>,
     #[serde(skip)]
     history: Arc<Mutex<MessageHistory>>,
+    #[serde(skip)]
+    rate_limiter: Option<Arc<RateLimiter>>,
+    #[serde(skip)]
+    coalescer: Arc<Coalescer>,
+    #[serde(skip, default = "default_queue")]
+    queue: Arc<CommandQueue>,
+    #[serde(skip)]
+    on_ip_change: Option<IpChangeHandler>,
+    #[serde(skip)]
+    capabilities: Arc<std::sync::Mutex<Capabilities>>,
+    #[serde(skip, default = "default_capability_ttl")]
+    capability_ttl: Duration,
+    #[serde(skip, default = "runtime::default_transport")]
+    transport: Arc<dyn Transport>,
+    #[serde(skip, default = "default_status_max_age")]
+    status_max_age: Duration,
+    #[serde(skip)]
+    last_toggle: Arc<std::sync::Mutex<Option<Instant>>>,
+    #[serde(skip)]
+    auto_off: Arc<std::sync::Mutex<Option<CancelToken>>>,
+    #[serde(skip)]
+    retry_policy: Option<Arc<dyn RetryPolicy>>,
+}
+
+/// Options for [`Light::toggle_with_cache`].
+#[derive(Debug, Clone, Copy)]
+pub struct ToggleOptions {
+    /// Oldest cached status used instead of querying the bulb.
+    pub max_age: Duration,
+    /// Ignore toggles this soon after the previous one.
+    pub debounce: Option<Duration>,
+    /// Power state to set when the bulb cannot be queried; `None` returns
+    /// the error instead.
+    pub fallback: Option<bool>,
+}
+
+impl Default for ToggleOptions {
+    fn default() -> Self {
+        ToggleOptions {
+            max_age: Light::STATUS_MAX_AGE,
+            debounce: None,
+            fallback: None,
+        }
+    }
+}
+
+/// Capabilities read from the bulb, with the time they were read.
+#[derive(Debug, Default)]
+struct Capabilities {
+    bulb_type: Option<(BulbType, Instant)>,
+    white_range: Option<(Option<WhiteRange>, Instant)>,
+    extended_white_range: Option<(Option<ExtendedWhiteRange>, Instant)>,
+    fan_speed_range: Option<(Option<u8>, Instant)>,
+}
+
+fn default_capability_ttl() -> Duration {
+    Light::CAPABILITY_TTL
+}
+
+fn default_status_max_age() -> Duration {
+    Light::STATUS_MAX_AGE
+}
+
+fn default_queue() -> Arc<CommandQueue> {
+    Arc::new(CommandQueue::new(Light::QUEUE_DEPTH))
 }
 
 impl Clone for Light {
@@ -51,51 +365,314 @@ impl Clone for Light {
         // This requires blocking to read the current history, which is acceptable for clone.
         // Note: try_lock API differs between runtimes:
         // - tokio returns Result<Guard, TryLockError>
-        // - async-std and async-lock (smol) return Option<Guard>
+        // - async-std, async-lock (smol) and futures (wasm) return Option<Guard>
         #[cfg(feature = "runtime-tokio")]
         let history_clone = match self.history.try_lock() {
             Ok(guard) => guard.clone(),
             Err(_) => MessageHistory::new(), // If locked, start fresh
         };
-        #[cfg(any(feature = "runtime-async-std", feature = "runtime-smol"))]
+        #[cfg(any(
+            feature = "runtime-async-std",
+            feature = "runtime-smol",
+            feature = "wasm"
+        ))]
         let history_clone = match self.history.try_lock() {
             Some(guard) => guard.clone(),
             None => MessageHistory::new(), // If locked, start fresh
         };
         Light {
-            ip: self.ip,
+            ip: Address::new(self.ip()),
+            mac: self.mac.clone(),
             name: self.name.clone(),
-            status: self.status.clone(),
+            status: SharedStatus(Arc::clone(&self.status.0)),
+            calibration: self.calibration.clone(),
             history: Arc::new(Mutex::new(history_clone)),
+            rate_limiter: self.rate_limiter.clone(),
+            coalescer: Arc::new(Coalescer::default()),
+            queue: Arc::clone(&self.queue),
+            on_ip_change: self.on_ip_change.clone(),
+            capabilities: Arc::clone(&self.capabilities),
+            capability_ttl: self.capability_ttl,
+            transport: Arc::clone(&self.transport),
+            status_max_age: self.status_max_age,
+            last_toggle: Arc::clone(&self.last_toggle),
+            auto_off: Arc::clone(&self.auto_off),
+            retry_policy: self.retry_policy.clone(),
         }
     }
 }
 
 impl Light {
-    const PORT: u16 = 38899;
+    const PORT: u16 = protocol::PORT;
     const TIMEOUT_MS: u64 = 1000;
-    const MAX_RETRIES: u32 = 3;
-    const RETRY_DELAYS_MS: [u64; 3] = [750, 1500, 3000];
+    const RESOLVE_TIMEOUT: Duration = Duration::from_secs(2);
+    /// How long bulb type and ranges are cached by default.
+    pub const CAPABILITY_TTL: Duration = Duration::from_secs(600);
+    /// How old the cached status may get before it counts as stale by default.
+    pub const STATUS_MAX_AGE: Duration = Duration::from_secs(30);
+    /// Longest alias accepted by [`Light::set_alias`].
+    pub const MAX_ALIAS_LEN: usize = 32;
+    /// How long each section of [`Light::diagnostics`] may take.
+    pub const DIAGNOSTICS_TIMEOUT: Duration = Duration::from_secs(5);
+    /// How many commands may be running or waiting for this light by default.
+    pub const QUEUE_DEPTH: usize = 64;
 
     pub fn new(ip: Ipv4Addr, name: Option<&str>) -> Self {
         Light {
-            ip,
+            ip: Address::new(ip),
+            mac: None,
             name: name.map(String::from),
-            status: None,
+            status: SharedStatus::default(),
+            calibration: None,
             history: Arc::new(Mutex::new(MessageHistory::new())),
+            rate_limiter: None,
+            coalescer: Arc::new(Coalescer::default()),
+            queue: default_queue(),
+            on_ip_change: None,
+            capabilities: Arc::default(),
+            capability_ttl: Self::CAPABILITY_TTL,
+            transport: runtime::default_transport(),
+            status_max_age: Self::STATUS_MAX_AGE,
+            last_toggle: Arc::default(),
+            auto_off: Arc::default(),
+            retry_policy: None,
+        }
+    }
+
+    /// Returns another handle to the same bulb.
+    ///
+    /// A clone shares the cached status, capabilities and timers but keeps
+    /// its own IP and message history. A shared handle shares those too, so
+    /// the same bulb can be a member of several rooms and groups and behave
+    /// as one light: a DHCP change found through one handle is seen by all
+    /// of them. Name, MAC and calibration stay per handle.
+    ///
+    /// Handles are serialized separately and come back independent.
+    pub fn share(&self) -> Self {
+        Light {
+            ip: self.ip.share(),
+            history: Arc::clone(&self.history),
+            coalescer: Arc::clone(&self.coalescer),
+            ..self.clone()
+        }
+    }
+
+    /// Whether `other` is a handle to this light made with [`Light::share`].
+    pub fn is_shared_with(&self, other: &Light) -> bool {
+        Arc::ptr_eq(&self.ip.0, &other.ip.0)
+    }
+
+    /// Finds the bulb with the given MAC address by broadcast discovery.
+    ///
+    /// Fails with [`Error::MacNotResolved`] if no bulb with that MAC answers.
+    pub async fn from_mac(mac: &str, name: Option<&str>) -> Result<Self> {
+        let mut light = Light::new(Ipv4Addr::UNSPECIFIED, name);
+        light.set_mac(Some(mac));
+        match light.resolve_ip().await? {
+            Some(ip) => {
+                light.ip.set(ip);
+                Ok(light)
+            }
+            None => Err(Error::MacNotResolved(mac.to_lowercase())),
         }
     }
 
     pub fn ip(&self) -> Ipv4Addr {
-        self.ip
+        self.ip.get()
+    }
+
+    pub fn mac(&self) -> Option<&str> {
+        self.mac.as_deref()
+    }
+
+    /// Sets the MAC address used to find the bulb again if its IP changes.
+    pub fn set_mac(&mut self, mac: Option<&str>) {
+        self.mac = mac.map(str::to_lowercase);
+    }
+
+    /// Registers a callback invoked when the bulb is found at a new IP.
+    ///
+    /// Clones of this light share the callback.
+    pub fn on_ip_change<F: Fn(&IpChanged) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.on_ip_change = Some(IpChangeHandler(Arc::new(callback)));
     }
 
     pub fn name(&self) -> Option<&str> {
         self.name.as_deref()
     }
 
-    pub fn status(&self) -> Option<&LightStatus> {
-        self.status.as_ref()
+    /// The last known state, from replies and push updates.
+    ///
+    /// Clones of a light share this cache, so an update applied through one
+    /// handle (e.g. by a push listener) is visible through all of them.
+    pub fn status(&self) -> Option<LightStatus> {
+        self.status.get()
+    }
+
+    /// Calls `callback` whenever the cached status changes, whether from
+    /// [`Light::process_reply`], [`Light::get_status`] or push updates
+    /// delivered to an attached light.
+    ///
+    /// Clones share listeners along with the status.
+    pub fn on_change<F: Fn(&StatusChange) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> ChangeSubscription {
+        let id = ChangeSubscription::next();
+        self.add_change_listener(id, Arc::new(callback));
+        id
+    }
+
+    pub(crate) fn add_change_listener(
+        &self,
+        id: ChangeSubscription,
+        callback: StatusChangeCallback,
+    ) {
+        self.status.listeners().callbacks.push((id, callback));
+    }
+
+    /// Removes a callback added with [`Light::on_change`].
+    ///
+    /// Returns false if it was not registered on this light.
+    pub fn remove_change_listener(&self, id: ChangeSubscription) -> bool {
+        let callbacks = &mut self.status.listeners().callbacks;
+        let before = callbacks.len();
+        callbacks.retain(|(cb_id, _)| *cb_id != id);
+        callbacks.len() != before
+    }
+
+    /// A stream of status changes. It ends when the light and all its
+    /// clones are dropped.
+    pub fn changes(&self) -> BoxStream<'static, StatusChange> {
+        let (tx, rx) = mpsc::unbounded();
+        self.status.listeners().senders.push(tx);
+        rx.boxed()
+    }
+
+    /// Polls the bulb about every `interval` in the background, keeping the
+    /// cached status of this light and its clones fresh.
+    ///
+    /// Useful for bulbs whose firmware does not push reliably. Polling stops
+    /// when the returned poller is stopped or dropped.
+    pub fn start_polling(&self, interval: Duration) -> StatusPoller {
+        StatusPoller::start(vec![self.clone()], interval)
+    }
+
+    pub fn calibration(&self) -> Option<&Calibration> {
+        self.calibration.as_ref()
+    }
+
+    /// Sets the correction applied to every payload sent by [`Light::set`]
+    /// and its variants. The light's recorded state keeps the requested,
+    /// uncorrected values.
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) {
+        self.calibration = calibration;
+    }
+
+    pub fn rate_limiter(&self) -> Option<&Arc<RateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Limits how fast datagrams are sent to this light.
+    ///
+    /// Pass the same limiter to several lights to cap their combined rate.
+    /// Clones of this light share the limiter.
+    pub fn set_rate_limiter(&mut self, limiter: Option<Arc<RateLimiter>>) {
+        self.rate_limiter = limiter;
+    }
+
+    /// Commands run one at a time and in order, so concurrent calls never
+    /// interleave their packets and retries. This is how many may be running
+    /// or waiting at once before further ones fail with
+    /// [`Error::QueueFull`].
+    pub fn queue_depth(&self) -> usize {
+        self.queue.depth()
+    }
+
+    /// Sets how many commands may be queued, at least 1.
+    ///
+    /// Clones of this light share the queue.
+    pub fn set_queue_depth(&mut self, depth: usize) {
+        self.queue.set_depth(depth);
+    }
+
+    /// Sets how commands without a reply are retried; `None` uses the
+    /// [global default](crate::retry::set_default_policy).
+    ///
+    /// Clones of this light share the policy.
+    pub fn set_retry_policy(&mut self, policy: Option<Arc<dyn RetryPolicy>>) {
+        self.retry_policy = policy;
+    }
+
+    /// The retry policy in effect for this light.
+    pub fn retry_policy(&self) -> Arc<dyn RetryPolicy> {
+        self.retry_policy
+            .clone()
+            .unwrap_or_else(retry::default_policy)
+    }
+
+    /// Sends commands through `transport` instead of the runtime's sockets.
+    pub fn set_transport(&mut self, transport: Arc<dyn Transport>) {
+        self.transport = transport;
+    }
+
+    /// Sets how long the bulb type, white ranges and fan speed range are
+    /// cached before being queried again. `Duration::ZERO` disables caching.
+    ///
+    /// Clones of this light share the cache.
+    pub fn set_capability_ttl(&mut self, ttl: Duration) {
+        self.capability_ttl = ttl;
+    }
+
+    pub fn capability_ttl(&self) -> Duration {
+        self.capability_ttl
+    }
+
+    /// Sets how old the cached status may get before
+    /// [`Light::is_status_stale`] reports it as stale.
+    pub fn set_status_max_age(&mut self, max_age: Duration) {
+        self.status_max_age = max_age;
+    }
+
+    pub fn status_max_age(&self) -> Duration {
+        self.status_max_age
+    }
+
+    /// Whether the cached status is missing or older than
+    /// [`Light::status_max_age`].
+    pub fn is_status_stale(&self) -> bool {
+        self.status
+            .get()
+            .is_none_or(|status| status.is_stale(self.status_max_age))
+    }
+
+    /// Re-reads the bulb type, white ranges and fan speed range, for example
+    /// after a firmware update.
+    pub async fn refresh_capabilities(&self) -> Result<()> {
+        *self.capabilities_lock() = Capabilities::default();
+        self.get_bulb_type().await?;
+        self.get_white_range().await?;
+        self.get_extended_white_range().await?;
+        self.get_fan_speed_range().await?;
+        Ok(())
+    }
+
+    fn capabilities_lock(&self) -> std::sync::MutexGuard<'_, Capabilities> {
+        self.capabilities.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cached<T: Clone>(
+        &self,
+        slot: fn(&mut Capabilities) -> &mut Option<(T, Instant)>,
+    ) -> Option<T> {
+        match slot(&mut self.capabilities_lock()) {
+            Some((value, at)) if at.elapsed() < self.capability_ttl => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn cache<T>(&self, slot: fn(&mut Capabilities) -> &mut Option<(T, Instant)>, value: T) {
+        *slot(&mut self.capabilities_lock()) = Some((value, Instant::now()));
     }
 
     pub async fn history(&self) -> MessageHistory {
@@ -107,92 +684,340 @@ impl Light {
     }
 
     /// Returns diagnostics including state, configuration, and history.
-    pub async fn diagnostics(&self) -> Value {
-        let mut diag = json!({
-            "ip": self.ip.to_string(),
-            "name": self.name,
-            "status": self.status.as_ref().map(|s| json!({
-                "emitting": s.emitting(),
-                "color": s.color().map(|c| format!("{},{},{}", c.red(), c.green(), c.blue())),
-                "brightness": s.brightness().map(|b| b.value()),
-                "temp": s.temp().map(|t| t.kelvin()),
-                "scene": s.scene().map(|sc| format!("{:?}", sc)),
-            })),
-        });
+    ///
+    /// The sections are queried concurrently, each limited to
+    /// [`Light::DIAGNOSTICS_TIMEOUT`]. Sections that failed are left empty
+    /// and the reason is recorded in [`Diagnostics::errors`].
+    pub async fn diagnostics(&self) -> Diagnostics {
+        let timeout = Self::DIAGNOSTICS_TIMEOUT;
+        let get_pilot = json!({"method": "getPilot"});
+        let (pilot, system_config, white, extended_white, fan_speed, bulb_type) = futures::join!(
+            diagnostics::section(timeout, self.send_command(&get_pilot)),
+            diagnostics::section(timeout, self.get_system_config()),
+            diagnostics::section(timeout, self.get_white_range()),
+            diagnostics::section(timeout, self.get_extended_white_range()),
+            diagnostics::section(timeout, self.get_fan_speed_range()),
+            diagnostics::section(timeout, self.get_bulb_type()),
+        );
+
+        let mut errors = BTreeMap::new();
+        let reachable = pilot.is_ok();
+        let rssi = pilot
+            .as_ref()
+            .ok()
+            .and_then(|resp| resp["result"]["rssi"].as_i64());
+        // A pilot that does not parse still proves the bulb is reachable
+        let status =
+            pilot.and_then(|resp| protocol::parse_status(&resp).map_err(|e| e.to_string()));
+        if let Some(status) = diagnostics::take(&mut errors, "status", status) {
+            self.update_status(&status);
+        }
 
-        // Add history summary
-        let history = self.history.lock().await;
-        diag["history"] = serde_json::to_value(history.summary()).unwrap_or(Value::Null);
-        drop(history); // Release lock before network operations
-
-        // Try to add configuration info (may fail if device is unreachable)
-        if let Ok(config) = self.get_system_config().await {
-            diag["system_config"] = json!({
-                "mac": config.mac,
-                "module_name": config.module_name,
-                "fw_version": config.fw_version,
-                "home_id": config.home_id,
-                "room_id": config.room_id,
-            });
+        Diagnostics {
+            ip: self.ip(),
+            name: self.name.clone(),
+            reachable,
+            rssi,
+            status: self.status.get(),
+            system_config: diagnostics::take(&mut errors, "system_config", system_config),
+            white_range: diagnostics::take(&mut errors, "white_range", white).flatten(),
+            extended_white_range: diagnostics::take(
+                &mut errors,
+                "extended_white_range",
+                extended_white,
+            )
+            .flatten(),
+            fan_speed_range: diagnostics::take(&mut errors, "fan_speed_range", fan_speed).flatten(),
+            bulb_type: diagnostics::take(&mut errors, "bulb_type", bulb_type),
+            history: self.history.lock().await.summary(),
+            errors,
         }
+    }
 
-        if let Ok(Some(white_range)) = self.get_white_range().await {
-            diag["white_range"] = json!(white_range.values);
+    /// Queries the bulb for current status (live network call).
+    pub async fn get_status(&self) -> Result<LightStatus> {
+        let resp = self.send_command(&json!({"method": "getPilot"})).await?;
+        let status = protocol::parse_status(&resp)?;
+        self.update_status(&status);
+        Ok(status)
+    }
+
+    /// Returns the cached status if the bulb confirmed it within `max_age`,
+    /// otherwise queries the bulb like [`Light::get_status`].
+    ///
+    /// Optimistic state from [`Light::set`] is never returned, since the
+    /// bulb may not have applied it.
+    pub async fn get_status_cached(&self, max_age: Duration) -> Result<LightStatus> {
+        match self.status.get() {
+            Some(status) if status.is_confirmed() && !status.is_stale(max_age) => Ok(status),
+            _ => self.get_status().await,
+        }
+    }
+
+    /// Applies lighting settings from a payload.
+    ///
+    /// The cached status shows the payload right away, unconfirmed until
+    /// the bulb next reports its state (see [`LightStatus::is_confirmed`]).
+    /// It stays unconfirmed if sending fails.
+    pub async fn set(&self, payload: &Payload) -> Result<LightingResponse> {
+        if !payload.is_valid() {
+            return Err(Error::NoAttribute);
         }
+        self.update_status_from_payload(payload);
+
+        let msg = self.wire_params(payload)?;
+        let response = self
+            .send_command(&json!({
+                "method": "setPilot",
+                "params": msg,
+            }))
+            .await?;
+
+        debug!("UDP response: {:?}", response);
+        Ok(LightingResponse::payload(self.ip(), payload.clone()))
+    }
 
-        if let Ok(Some(ext_range)) = self.get_extended_white_range().await {
-            diag["extended_white_range"] = json!(ext_range.values);
+    /// Applies lighting settings, collapsing rapid successive calls.
+    ///
+    /// While an earlier call is still waiting for its rate-limit slot, later
+    /// payloads replace the pending one and return `Ok(None)` immediately.
+    /// The call that is sending returns the response for the last payload
+    /// it delivered. Without a rate limiter this behaves like [`Light::set`].
+    pub async fn set_coalesced(&self, payload: &Payload) -> Result<Option<LightingResponse>> {
+        if !payload.is_valid() {
+            return Err(Error::NoAttribute);
+        }
+        if !self.coalescer.offer(payload.clone()).await {
+            return Ok(None);
         }
 
-        if let Ok(Some(fan_range)) = self.get_fan_speed_range().await {
-            diag["fan_speed_range"] = json!(fan_range);
+        // Hands the driver role back if this future is dropped mid-send
+        struct Release<'a>(Option<&'a Coalescer>);
+        impl Drop for Release<'_> {
+            fn drop(&mut self) {
+                if let Some(coalescer) = self.0 {
+                    coalescer.release();
+                }
+            }
+        }
+        let mut guard = Release(Some(&self.coalescer));
+
+        let mut last = None;
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.wait_ready().await;
+            }
+            let Some(next) = self.coalescer.next().await else {
+                guard.0 = None;
+                return Ok(last);
+            };
+            last = Some(self.set(&next).await?);
+        }
+    }
+
+    /// Applies a named preset.
+    pub async fn apply_preset(&self, preset: &Preset) -> Result<LightingResponse> {
+        self.set(&preset.payload).await
+    }
+
+    /// Puts the light back into a state captured with [`Snapshot::capture`].
+    ///
+    /// A light that was off is simply turned off again, so restoring does
+    /// not briefly show the old look. Snapshots hold the values the bulb
+    /// reported, so calibration is not applied a second time.
+    pub async fn restore(&self, snapshot: &Snapshot) -> Result<LightingResponse> {
+        if !snapshot.emitting {
+            return self.set_power(&PowerMode::Off).await;
+        }
+
+        let mut payload = snapshot.payload.clone();
+        payload.state = Some(true);
+        let params = serde_json::to_value(&payload).map_err(Error::JsonDump)?;
+        self.send_command(&json!({
+            "method": "setPilot",
+            "params": params,
+        }))
+        .await?;
+        Ok(LightingResponse::payload(self.ip(), payload))
+    }
+
+    /// Blinks the light `times` times in `color`, then restores its prior state.
+    ///
+    /// Each blink shows the color for `interval` and turns the light off for
+    /// `interval`. The previous state is restored even if a blink fails; the
+    /// first error is returned.
+    pub async fn flash(
+        &self,
+        color: &Color,
+        times: u32,
+        interval: Duration,
+    ) -> Result<LightingResponse> {
+        let snapshot = Snapshot::capture(self).await?;
+
+        let mut payload = Payload::from(color);
+        payload.brightness(&Brightness::new());
+        let blinked: Result<()> = async {
+            for _ in 0..times {
+                self.turn_on_with(&payload).await?;
+                runtime::sleep(interval).await;
+                self.set_power(&PowerMode::Off).await?;
+                runtime::sleep(interval).await;
+            }
+            Ok(())
+        }
+        .await;
+
+        let restored = self.restore(&snapshot).await;
+        blinked.and(restored)
+    }
+
+    /// Pulses the light between bright and dim white, then restores its
+    /// prior state.
+    ///
+    /// Lets setup tools show which physical bulb answers at this IP. Only
+    /// brightness and white temperature are used, so every bulb type can
+    /// show it.
+    ///
+    /// ```ignore
+    /// for bulb in discover_bulbs(Duration::from_secs(2)).await? {
+    ///     bulb.clone().into_light(None).identify().await?;
+    ///     println!("That was {}", bulb.ip);
+    /// }
+    /// ```
+    pub async fn identify(&self) -> Result<LightingResponse> {
+        const PULSES: u32 = 3;
+        const STEP: Duration = Duration::from_millis(300);
+
+        let snapshot = Snapshot::capture(self).await?;
+
+        let mut bright = Payload::from(&Kelvin::create(6500).unwrap_or_default());
+        bright.brightness(&Brightness::new());
+        let mut dim = bright.clone();
+        dim.brightness(&Brightness::create_or(10));
+        let pulsed: Result<()> = async {
+            for _ in 0..PULSES {
+                self.turn_on_with(&bright).await?;
+                runtime::sleep(STEP).await;
+                self.turn_on_with(&dim).await?;
+                runtime::sleep(STEP).await;
+            }
+            Ok(())
+        }
+        .await;
+
+        let restored = self.restore(&snapshot).await;
+        pulsed.and(restored)
+    }
+
+    /// Applies lighting settings after checking the bulb supports them.
+    ///
+    /// Queries the bulb type first and fails with
+    /// [`Error::UnsupportedScene`] instead of sending a scene the model
+    /// cannot show, or with [`Error::UnsupportedFeature`] for a ratio sent
+    /// to a single-head bulb. Temperatures outside the bulb's range are
+    /// clamped into it and reported in [`LightingResponse::adjustments`].
+    pub async fn set_checked(&self, payload: &Payload) -> Result<LightingResponse> {
+        let scene = payload.scene.and_then(SceneMode::create);
+        if scene.is_none() && payload.ratio.is_none() && payload.temp.is_none() {
+            return self.set(payload).await;
         }
 
-        if let Ok(bulb_type) = self.get_bulb_type().await {
-            diag["bulb_type"] = json!({
-                "name": bulb_type.name,
-                "class": format!("{:?}", bulb_type.bulb_class),
-                "kelvin_range": {
-                    "min": bulb_type.kelvin_range.min,
-                    "max": bulb_type.kelvin_range.max,
-                },
-                "features": {
-                    "color": bulb_type.features.color,
-                    "color_tmp": bulb_type.features.color_tmp,
-                    "effect": bulb_type.features.effect,
-                    "brightness": bulb_type.features.brightness,
-                    "fan": bulb_type.features.fan,
-                },
-                "fw_version": bulb_type.fw_version,
+        let bulb_type = self.get_bulb_type().await?;
+        if let Some(scene) = scene
+            && !scene.is_supported_by(&bulb_type)
+        {
+            return Err(Error::UnsupportedScene {
+                scene: scene.to_string(),
+                module: bulb_type.name,
+            });
+        }
+        if payload.ratio.is_some() && !bulb_type.features.dual_head {
+            return Err(Error::UnsupportedFeature {
+                feature: "ratio".to_string(),
+                module: bulb_type.name,
             });
         }
 
-        diag
+        let mut adjustments = Vec::new();
+        let mut payload = payload.clone();
+        if let Some(temp) = payload.temp.and_then(Kelvin::create) {
+            let clamped = temp.clamp_to(&bulb_type.kelvin_range);
+            if clamped != temp {
+                adjustments.push(Adjustment {
+                    field: "temp",
+                    requested: temp.kelvin(),
+                    applied: clamped.kelvin(),
+                });
+                payload.temp(&clamped);
+            }
+        }
+        Ok(self.set(&payload).await?.with_adjustments(adjustments))
     }
 
-    /// Queries the bulb for current status (live network call).
-    pub async fn get_status(&self) -> Result<LightStatus> {
-        let resp = self.send_command(&json!({"method": "getPilot"})).await?;
-        let status: BulbStatus = serde_json::from_value(resp).map_err(Error::JsonLoad)?;
-        Ok(LightStatus::from(&status))
+    /// Whether the fixture has separately lit up and down heads.
+    pub async fn is_dual_head(&self) -> Result<bool> {
+        Ok(self.get_bulb_type().await?.features.dual_head)
     }
 
-    /// Applies lighting settings from a payload.
-    pub async fn set(&self, payload: &Payload) -> Result<LightingResponse> {
+    /// Balances light between the up and down heads of a dual-head fixture.
+    ///
+    /// Fails with [`Error::UnsupportedFeature`] on single-head bulbs, which
+    /// would otherwise silently ignore the ratio.
+    pub async fn set_ratio(&self, ratio: Ratio) -> Result<LightingResponse> {
+        self.set_checked(&Payload::from(&ratio)).await
+    }
+
+    /// Applies lighting settings and checks that the bulb accepted them.
+    ///
+    /// Unlike [`Light::set`], the `setPilot` reply must report
+    /// `{"success": true}`.
+    /// When `verify_state` is true the bulb is queried with `getPilot`
+    /// afterwards and every requested field must match the reported state,
+    /// otherwise [`Error::StateMismatch`] lists the fields that differ.
+    ///
+    /// The cached status shows the payload once the bulb accepted it, or the
+    /// state the bulb reported, confirmed, when `verify_state` is true.
+    pub async fn set_with_confirmation(
+        &self,
+        payload: &Payload,
+        verify_state: bool,
+    ) -> Result<LightingResponse> {
         if !payload.is_valid() {
             return Err(Error::NoAttribute);
         }
 
-        let msg = serde_json::to_value(payload).map_err(Error::JsonDump)?;
-        let response = self
+        let params = self.wire_params(payload)?;
+        let resp = self
             .send_command(&json!({
                 "method": "setPilot",
-                "params": msg,
+                "params": params,
             }))
             .await?;
 
-        debug!("UDP response: {:?}", response);
-        Ok(LightingResponse::payload(self.ip, payload.clone()))
+        let success = resp
+            .get("result")
+            .and_then(|r| r.get("success"))
+            .and_then(Value::as_bool);
+        if success != Some(true) {
+            return Err(Error::NotApplied("setPilot".to_string()));
+        }
+
+        if !verify_state {
+            self.update_status_from_payload(payload);
+        } else {
+            let pilot = self.send_command(&json!({"method": "getPilot"})).await?;
+            // The read-back is the bulb's actual state, whether it matches or not
+            if let Ok(status) = protocol::parse_status(&pilot) {
+                self.update_status(&status);
+            }
+            let actual = pilot.get("result").unwrap_or(&Value::Null);
+            let fields = mismatched_fields(&params, actual);
+            if !fields.is_empty() {
+                return Err(Error::StateMismatch { fields });
+            }
+        }
+
+        Ok(LightingResponse::payload(self.ip(), payload.clone()))
     }
 
     pub async fn set_power(&self, power: &PowerMode) -> Result<LightingResponse> {
@@ -203,6 +1028,73 @@ impl Light {
         }
     }
 
+    /// Turns the light on directly into the state described by `payload`.
+    ///
+    /// Power and pilot attributes go out in a single `setPilot`, so the bulb
+    /// does not briefly show its previous color before switching.
+    pub async fn turn_on_with(&self, payload: &Payload) -> Result<LightingResponse> {
+        let mut payload = payload.clone();
+        payload.state = Some(true);
+        self.set(&payload).await
+    }
+
+    /// Sets the brightness from a 0-100 slider value where 0 means off.
+    ///
+    /// 0 switches the light off with `setState`; anything else turns it on
+    /// at that brightness, raised to 10% if lower. See
+    /// [`Brightness::from_percent_or_off`].
+    pub async fn set_brightness_or_off(&self, percent: u8) -> Result<LightingResponse> {
+        match Brightness::from_percent_or_off(percent) {
+            BrightnessOrOff::Off => self.set_power(&PowerMode::Off).await,
+            BrightnessOrOff::On(brightness) => self.turn_on_with(&Payload::from(&brightness)).await,
+        }
+    }
+
+    /// Shows `scene`, optionally at the given speed and brightness.
+    pub async fn set_scene(
+        &self,
+        scene: SceneMode,
+        speed: Option<Speed>,
+        brightness: Option<Brightness>,
+    ) -> Result<LightingResponse> {
+        let mut payload = Payload::from(&scene);
+        if let Some(speed) = &speed {
+            payload.speed(speed);
+        }
+        self.set_with_brightness(payload, brightness).await
+    }
+
+    /// Shows `color`, optionally at the given brightness.
+    pub async fn set_color(
+        &self,
+        color: Color,
+        brightness: Option<Brightness>,
+    ) -> Result<LightingResponse> {
+        self.set_with_brightness(Payload::from(&color), brightness)
+            .await
+    }
+
+    /// Shows white at `temp`, optionally at the given brightness.
+    pub async fn set_temperature(
+        &self,
+        temp: Kelvin,
+        brightness: Option<Brightness>,
+    ) -> Result<LightingResponse> {
+        self.set_with_brightness(Payload::from(&temp), brightness)
+            .await
+    }
+
+    async fn set_with_brightness(
+        &self,
+        mut payload: Payload,
+        brightness: Option<Brightness>,
+    ) -> Result<LightingResponse> {
+        if let Some(brightness) = &brightness {
+            payload.brightness(brightness);
+        }
+        self.set(&payload).await
+    }
+
     pub async fn toggle(&self) -> Result<LightingResponse> {
         let status = self.get_status().await?;
         if status.emitting() {
@@ -212,6 +1104,165 @@ impl Light {
         }
     }
 
+    /// Turns the light off after `duration`, e.g. once a room is left.
+    ///
+    /// The timer runs in a background task. Setting a new one on this light
+    /// or any clone of it replaces the previous timer, whose handle then
+    /// resolves to [`Error::Cancelled`], as it does after
+    /// [`Light::cancel_turn_off`].
+    pub fn turn_off_after(&self, duration: Duration) -> JoinHandle<Result<LightingResponse>> {
+        let cancel = CancelToken::new();
+        if let Some(previous) = self.auto_off_lock().replace(cancel.clone()) {
+            previous.cancel();
+        }
+        let light = self.clone();
+        runtime::spawn(async move {
+            cancel.run(runtime::sleep(duration)).await?;
+            // The timer is spent; cancelling it later is a no-op
+            cancel.cancel();
+            light.set_power_state(false).await
+        })
+    }
+
+    /// Cancels the timer set with [`Light::turn_off_after`].
+    ///
+    /// Returns whether a timer was pending.
+    pub fn cancel_turn_off(&self) -> bool {
+        match self.auto_off_lock().take() {
+            Some(cancel) => {
+                let pending = !cancel.is_cancelled();
+                cancel.cancel();
+                pending
+            }
+            None => false,
+        }
+    }
+
+    fn auto_off_lock(&self) -> std::sync::MutexGuard<'_, Option<CancelToken>> {
+        self.auto_off.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Toggles the light, trusting the cached status when it is fresh.
+    ///
+    /// Unlike [`Light::toggle`], no `getPilot` is sent while the cache is
+    /// younger than [`ToggleOptions::max_age`]. Since toggles update the
+    /// cache right away, rapid presses alternate instead of both reading the
+    /// bulb's old state. With [`ToggleOptions::debounce`] set, a toggle that
+    /// follows the previous one of this light or its clones too closely is
+    /// ignored and `None` is returned.
+    pub async fn toggle_with_cache(
+        &self,
+        options: &ToggleOptions,
+    ) -> Result<Option<LightingResponse>> {
+        if let Some(debounce) = options.debounce {
+            let mut last = self.last_toggle.lock().unwrap_or_else(|e| e.into_inner());
+            if last.is_some_and(|at| at.elapsed() < debounce) {
+                return Ok(None);
+            }
+            *last = Some(Instant::now());
+        }
+
+        let emitting = match self.status.get() {
+            Some(status) if !status.is_stale(options.max_age) => status.emitting(),
+            _ => match (self.get_status().await, options.fallback) {
+                (Ok(status), _) => status.emitting(),
+                // Setting the fallback state means turning to the other one
+                (Err(e), Some(on)) => {
+                    debug!("Toggling {} to fallback state: {}", self.ip(), e);
+                    !on
+                }
+                (Err(e), None) => return Err(e),
+            },
+        };
+        self.set_power_state(!emitting).await.map(Some)
+    }
+
+    /// Raises or lowers brightness by `delta` percentage points.
+    ///
+    /// Starts from the cached status when available, otherwise queries the
+    /// bulb. The result is clamped to the 10-100% range.
+    pub async fn adjust_brightness(&self, delta: i8) -> Result<LightingResponse> {
+        let cached = self
+            .status
+            .get()
+            .and_then(|s| s.brightness().map(Brightness::value));
+        let current = match cached {
+            Some(brightness) => brightness,
+            None => self
+                .get_status()
+                .await?
+                .brightness()
+                .map_or(Brightness::new().value(), Brightness::value),
+        };
+        let target = (i16::from(current) + i16::from(delta)).clamp(10, 100) as u8;
+        self.set(&Payload::from(&Brightness::create_or(target)))
+            .await
+    }
+
+    /// Checks whether the bulb answers a single `getPilot` probe within `timeout`.
+    ///
+    /// No retries are made and the probe is not recorded in the history.
+    pub async fn is_reachable(&self, timeout: Duration) -> bool {
+        self.probe(timeout).await.is_ok()
+    }
+
+    /// Sends `samples` sequential `getPilot` probes and reports round-trip times.
+    ///
+    /// Probes that time out count as lost. The state of the bulb is not changed.
+    pub async fn measure_latency(&self, samples: u32) -> LatencyReport {
+        let mut rtts = Vec::new();
+        for _ in 0..samples {
+            let start = runtime::Instant::now();
+            if self
+                .probe(Duration::from_millis(Self::TIMEOUT_MS))
+                .await
+                .is_ok()
+            {
+                rtts.push(start.elapsed());
+            }
+        }
+
+        LatencyReport {
+            sent: samples,
+            received: rtts.len() as u32,
+            min: rtts.iter().min().copied(),
+            avg: (!rtts.is_empty()).then(|| rtts.iter().sum::<Duration>() / rtts.len() as u32),
+            max: rtts.iter().max().copied(),
+        }
+    }
+
+    async fn probe(&self, timeout: Duration) -> Result<Value> {
+        let msg = protocol::with_id(&protocol::request("getPilot", None), next_request_id());
+        let msg_str = serde_json::to_string(&msg).map_err(Error::JsonDump)?;
+        self.send_udp(&msg, &msg_str, timeout).await
+    }
+
+    /// Sends one animation frame: a single `setPilot` attempt that waits at
+    /// most `timeout` for the reply.
+    ///
+    /// Frames skip the command queue, retries and the history, since a lost
+    /// frame is superseded by the next one anyway. The cached status is
+    /// updated as in [`Light::set`].
+    pub(crate) async fn send_frame(&self, payload: &Payload, timeout: Duration) -> Result<()> {
+        if !payload.is_valid() {
+            return Err(Error::NoAttribute);
+        }
+        self.update_status_from_payload(payload);
+
+        let params = self.wire_params(payload)?;
+        let msg = protocol::with_id(
+            &protocol::request("setPilot", Some(params)),
+            next_request_id(),
+        );
+        let msg_str = serde_json::to_string(&msg).map_err(Error::JsonDump)?;
+        stats::record_command(self.ip(), "setPilot");
+        let response = self.send_udp(&msg, &msg_str, timeout).await?;
+        match protocol::bulb_error("setPilot", &response) {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
     /// Factory resets the bulb (including WiFi configuration).
     pub async fn reset(&self) -> Result<()> {
         self.send_command(&json!({"method": "reset"})).await?;
@@ -236,11 +1287,157 @@ impl Light {
         Ok(config.result)
     }
 
-    pub async fn get_user_config(&self) -> Result<Value> {
+    pub async fn get_user_config(&self) -> Result<UserConfig> {
         let resp = self
             .send_command(&json!({"method": "getUserConfig"}))
             .await?;
-        Ok(resp.get("result").cloned().unwrap_or(Value::Null))
+        parse_result(resp)
+    }
+
+    /// Writes user configuration such as fades, the default brightness or
+    /// the white range.
+    ///
+    /// # Warning
+    ///
+    /// The configuration persists across reboots and a wrong value can only
+    /// be undone by writing it again or from the WiZ app. The update must be
+    /// confirmed with [`UserConfigUpdate::dangerous`].
+    pub async fn set_user_config(&self, update: &UserConfigUpdate) -> Result<()> {
+        update.check()?;
+        self.write_config("setUserConfig", update).await
+    }
+
+    /// Writes system configuration such as the PWM frequency.
+    ///
+    /// # Warning
+    ///
+    /// As with [`Light::set_user_config`], the change persists and must be
+    /// confirmed with [`SystemConfigUpdate::dangerous`].
+    pub async fn set_system_config(&self, update: &SystemConfigUpdate) -> Result<()> {
+        update.check()?;
+        self.write_config("setSystemConfig", update).await
+    }
+
+    /// Returns the name stored on the bulb, if its firmware keeps one.
+    pub async fn get_alias(&self) -> Result<Option<String>> {
+        Ok(self
+            .get_user_config()
+            .await?
+            .alias
+            .filter(|a| !a.is_empty()))
+    }
+
+    /// Stores `alias` on the bulb and uses it as this light's name, so other
+    /// apps reading the alias see the same name.
+    ///
+    /// Firmware that does not store aliases rejects the write with
+    /// [`Error::NotApplied`] or [`Error::Bulb`], and the name is not changed.
+    pub async fn set_alias(&mut self, alias: &str) -> Result<()> {
+        if alias.is_empty() || alias.chars().count() > Self::MAX_ALIAS_LEN {
+            return Err(Error::InvalidConfig(format!(
+                "alias must be 1-{} characters long",
+                Self::MAX_ALIAS_LEN
+            )));
+        }
+        self.write_config("setUserConfig", &json!({"alias": alias}))
+            .await?;
+        self.name = Some(alias.to_string());
+        Ok(())
+    }
+
+    /// Reconciles [`Light::name`] with the alias stored on the bulb.
+    ///
+    /// A stored alias wins and replaces the local name. If the bulb has none
+    /// and the light is named, the name is written to the bulb. Returns the
+    /// resulting name.
+    pub async fn sync_name(&mut self) -> Result<Option<&str>> {
+        match self.get_alias().await? {
+            Some(alias) => self.name = Some(alias),
+            None => {
+                if let Some(name) = self.name.clone() {
+                    self.set_alias(&name).await?;
+                }
+            }
+        }
+        Ok(self.name())
+    }
+
+    async fn write_config(&self, method: &str, update: &impl Serialize) -> Result<()> {
+        let params = serde_json::to_value(update).map_err(Error::JsonDump)?;
+        let resp = self
+            .send_command(&json!({"method": method, "params": params}))
+            .await?;
+        // Ranges read from the configuration may have changed
+        *self.capabilities_lock() = Capabilities::default();
+        let success = resp
+            .get("result")
+            .and_then(|r| r.get("success"))
+            .and_then(Value::as_bool);
+        if success != Some(true) {
+            return Err(Error::NotApplied(method.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Asks the bulb whether newer firmware is available.
+    ///
+    /// Experimental: WiZ does not document `checkUpdate`, see
+    /// [`Light::start_firmware_update`].
+    pub async fn check_firmware_update(&self) -> Result<FirmwareUpdate> {
+        let current = self.get_system_config().await?.fw_version;
+        let resp = self
+            .send_command(&json!({"method": firmware::CHECK_METHOD}))
+            .await?;
+        let result = resp.get("result").unwrap_or(&Value::Null);
+        Ok(FirmwareUpdate::from_reply(current, result))
+    }
+
+    /// Tells the bulb to download and install new firmware, then follows
+    /// the update until the reported version changes or `timeout` passes.
+    ///
+    /// # Warning
+    ///
+    /// The bulb goes dark and stops answering while it flashes. Cutting its
+    /// power during an update can leave it unusable, so only start one when
+    /// the bulb's power will stay on. Updates cannot be cancelled: dropping
+    /// the stream only stops following progress.
+    ///
+    /// # Experimental
+    ///
+    /// WiZ does not document `updateOta`; the method name has no published
+    /// source and bulbs that do not know it fail with [`Error::Bulb`]. The
+    /// request is never retried once it may have reached the bulb, so a lost
+    /// reply cannot trigger a second update.
+    pub async fn start_firmware_update(
+        &self,
+        timeout: Duration,
+    ) -> Result<BoxStream<'static, FirmwareProgress>> {
+        let from = self.get_system_config().await?.fw_version;
+        self.send_command(&json!({"method": firmware::UPDATE_METHOD}))
+            .await?;
+        Ok(firmware::progress(self.clone(), from, timeout))
+    }
+
+    pub async fn get_dev_info(&self) -> Result<DevInfo> {
+        let resp = self.send_command(&json!({"method": "getDevInfo"})).await?;
+        parse_result(resp)
+    }
+
+    pub async fn get_wifi_config(&self) -> Result<WifiConfig> {
+        let resp = self
+            .send_command(&json!({"method": "getWifiConfig"}))
+            .await?;
+        parse_result(resp)
+    }
+
+    /// Returns the MAC address the bulb reports, lowercased.
+    pub async fn get_mac(&self) -> Result<String> {
+        let resp = self.send_command(&json!({"method": "getMac"})).await?;
+        resp.get("result")
+            .and_then(|result| result.get("mac").or(Some(result)))
+            .and_then(Value::as_str)
+            .map(str::to_lowercase)
+            .ok_or(Error::NoAttribute)
     }
 
     /// Returns model configuration (firmware >= 1.22).
@@ -251,45 +1448,72 @@ impl Light {
         Ok(resp.get("result").cloned().unwrap_or(Value::Null))
     }
 
+    /// Sends an arbitrary method and returns the bulb's full JSON reply.
+    ///
+    /// For methods or parameters this crate does not wrap yet. `params` is
+    /// omitted from the request when it is `Value::Null`.
+    pub async fn send_raw_method(&self, method: &str, params: Value) -> Result<Value> {
+        let msg = if params.is_null() {
+            json!({"method": method})
+        } else {
+            json!({"method": method, "params": params})
+        };
+        self.send_command(&msg).await
+    }
+
+    /// Returns the bulb type, cached for [`Light::capability_ttl`].
     pub async fn get_bulb_type(&self) -> Result<BulbType> {
+        if let Some(bulb_type) = self.cached(|c| &mut c.bulb_type) {
+            return Ok(bulb_type);
+        }
         let config = self.get_system_config().await?;
         let module_name = config.module_name.as_deref().unwrap_or("Unknown");
         let fw_version = config.fw_version.as_deref();
-        Ok(BulbType::from_module_name(module_name, fw_version))
+        let bulb_type = BulbType::from_module_name(module_name, fw_version);
+        self.cache(|c| &mut c.bulb_type, bulb_type.clone());
+        Ok(bulb_type)
     }
 
+    /// Returns the white range, cached for [`Light::capability_ttl`].
     pub async fn get_white_range(&self) -> Result<Option<WhiteRange>> {
+        if let Some(range) = self.cached(|c| &mut c.white_range) {
+            return Ok(range);
+        }
         let config = self.get_user_config().await?;
-        Ok(parse_f32_array(&config, "whiteRange").map(WhiteRange::new))
+        let range = config.white_range.map(WhiteRange::new);
+        self.cache(|c| &mut c.white_range, range.clone());
+        Ok(range)
     }
 
+    /// Returns the extended white range, cached for [`Light::capability_ttl`].
     pub async fn get_extended_white_range(&self) -> Result<Option<ExtendedWhiteRange>> {
+        if let Some(range) = self.cached(|c| &mut c.extended_white_range) {
+            return Ok(range);
+        }
         // Try model config first (FW >= 1.22), then user config
         let model = self.get_model_config().await?;
         let user = self.get_user_config().await?;
 
-        for (config, key) in [
-            (&model, "cctRange"),
-            (&user, "extRange"),
-            (&user, "cctRange"),
-        ] {
-            if let Some(values) = parse_f32_array(config, key) {
-                return Ok(Some(ExtendedWhiteRange::new(values)));
-            }
-        }
-        Ok(None)
+        let range = parse_f32_array(&model, "cctRange")
+            .or(user.ext_range)
+            .or(user.cct_range)
+            .map(ExtendedWhiteRange::new);
+        self.cache(|c| &mut c.extended_white_range, range.clone());
+        Ok(range)
     }
 
+    /// Returns the number of fan speeds, cached for [`Light::capability_ttl`].
     pub async fn get_fan_speed_range(&self) -> Result<Option<u8>> {
-        let model = self.get_model_config().await?;
-        if let Some(v) = model.get("fanSpeed").and_then(|v| v.as_u64()) {
-            return Ok(Some(v as u8));
+        if let Some(range) = self.cached(|c| &mut c.fan_speed_range) {
+            return Ok(range);
         }
-        let user = self.get_user_config().await?;
-        Ok(user
-            .get("fanSpeed")
-            .and_then(|v| v.as_u64())
-            .map(|v| v as u8))
+        let model = self.get_model_config().await?;
+        let range = match model.get("fanSpeed").and_then(|v| v.as_u64()) {
+            Some(v) => Some(v as u8),
+            None => self.get_user_config().await?.fan_speed,
+        };
+        self.cache(|c| &mut c.fan_speed_range, range);
+        Ok(range)
     }
 
     pub async fn fan_set_state(
@@ -313,6 +1537,7 @@ impl Light {
         if let Some(d) = direction {
             payload.fan_direction(&d);
         }
+        self.update_status_from_payload(&payload);
 
         let msg = serde_json::to_value(&payload).map_err(Error::JsonDump)?;
         self.send_command(&json!({
@@ -321,7 +1546,7 @@ impl Light {
         }))
         .await?;
 
-        Ok(LightingResponse::payload(self.ip, payload))
+        Ok(LightingResponse::payload(self.ip(), payload))
     }
 
     pub async fn fan_turn_on(
@@ -339,22 +1564,42 @@ impl Light {
     }
 
     pub async fn fan_toggle(&self) -> Result<LightingResponse> {
-        // Check fan state from the raw response
-        let resp = self.send_command(&json!({"method": "getPilot"})).await?;
-        let fan_on = resp
-            .get("result")
-            .and_then(|r| r.get("fanState"))
-            .and_then(|s| s.as_u64())
-            .map(|s| s == 1)
-            .unwrap_or(false);
-
-        if fan_on {
+        let status = self.get_status().await?;
+        if status.fan().is_some_and(FanStatus::is_on) {
             self.fan_turn_off().await
         } else {
             self.fan_turn_on(None, None).await
         }
     }
 
+    /// Switches the fan to breeze mode with the given gust intensity.
+    pub async fn fan_breeze(&self, intensity: Option<FanSpeed>) -> Result<LightingResponse> {
+        self.fan_turn_on(Some(FanMode::Breeze), intensity).await
+    }
+
+    /// Turns the fan off after `duration`, like the sleep timer in the app.
+    ///
+    /// Fixtures do not expose their own timer over the local protocol, so
+    /// the timer runs in a background task here; abort the returned handle
+    /// to cancel it, or use [`Light::fan_set_timer_with_cancel`].
+    pub fn fan_set_timer(&self, duration: Duration) -> JoinHandle<Result<LightingResponse>> {
+        self.fan_set_timer_with_cancel(duration, CancelToken::new())
+    }
+
+    /// Like [`Light::fan_set_timer`], but the timer stops with
+    /// [`Error::Cancelled`] once `cancel` is cancelled, on every runtime.
+    pub fn fan_set_timer_with_cancel(
+        &self,
+        duration: Duration,
+        cancel: CancelToken,
+    ) -> JoinHandle<Result<LightingResponse>> {
+        let light = self.clone();
+        runtime::spawn(async move {
+            cancel.run(runtime::sleep(duration)).await?;
+            light.fan_turn_off().await
+        })
+    }
+
     pub async fn set_fan_speed(&self, speed: FanSpeed) -> Result<LightingResponse> {
         self.fan_set_state(None, None, Some(speed), None).await
     }
@@ -367,8 +1612,11 @@ impl Light {
         self.fan_set_state(None, None, None, Some(direction)).await
     }
 
-    pub fn process_reply(&mut self, resp: &LightingResponse) -> bool {
-        if resp.ip != self.ip {
+    /// Merges a response for this light into the cached status.
+    ///
+    /// Returns false if the response came from another light.
+    pub fn process_reply(&self, resp: &LightingResponse) -> bool {
+        if resp.ip != self.ip() {
             return false;
         }
 
@@ -376,128 +1624,337 @@ impl Light {
             LightingResponseType::Payload(payload) => self.update_status_from_payload(payload),
             LightingResponseType::Power(power) => self.update_status_from_power(power),
             LightingResponseType::Status(status) => self.update_status(status),
+            LightingResponseType::Push(event) => self.update_status_from_push(event),
         }
         true
     }
 
+    /// Records a push message in the history if it came from this bulb.
+    ///
+    /// The bulb is matched by MAC when known, otherwise by IP.
+    pub(crate) async fn record_push(&self, mac: &str, ip: Ipv4Addr, msg: &Value) -> bool {
+        let ours = match &self.mac {
+            Some(own) => own.eq_ignore_ascii_case(mac),
+            None => ip == self.ip(),
+        };
+        if ours {
+            self.history.lock().await.record(MessageType::Push, msg);
+        }
+        ours
+    }
+
+    pub(crate) fn set_ip(&mut self, ip: Ipv4Addr) {
+        self.ip.set(ip);
+    }
+
     pub(crate) fn update(&mut self, other: &Self) -> bool {
         let mut changed = false;
         if self.name != other.name {
             self.name.clone_from(&other.name);
             changed = true;
         }
-        if self.ip != other.ip {
-            self.ip = other.ip;
+        if self.ip() != other.ip() {
+            self.ip.set(other.ip());
+            changed = true;
+        }
+        if self.mac != other.mac {
+            self.mac.clone_from(&other.mac);
+            changed = true;
+        }
+        if self.calibration != other.calibration {
+            self.calibration.clone_from(&other.calibration);
             changed = true;
         }
         changed
     }
 
     async fn set_power_state(&self, on: bool) -> Result<LightingResponse> {
+        let power = if on { PowerMode::On } else { PowerMode::Off };
+        self.update_status_from_power(&power);
         self.send_command(&json!({"method": "setState", "params": {"state": on}}))
             .await?;
-        let power = if on { PowerMode::On } else { PowerMode::Off };
-        Ok(LightingResponse::power(self.ip, power))
+        Ok(LightingResponse::power(self.ip(), power))
     }
 
     async fn reboot_bulb(&self) -> Result<LightingResponse> {
         self.send_command(&json!({"method": "reboot"})).await?;
-        Ok(LightingResponse::power(self.ip, PowerMode::Reboot))
+        Ok(LightingResponse::power(self.ip(), PowerMode::Reboot))
     }
 
-    fn update_status(&mut self, status: &LightStatus) {
-        if let Some(current) = &mut self.status {
-            current.update(status);
-        } else {
-            self.status = Some(status.clone());
-        }
+    pub(crate) fn update_status(&self, status: &LightStatus) {
+        self.status.modify(self.ip(), |cached| match cached {
+            Some(current) => current.update(status),
+            None => *cached = Some(status.clone()),
+        });
     }
 
-    fn update_status_from_payload(&mut self, payload: &Payload) {
-        if let Some(status) = &mut self.status {
-            status.update_from_payload(payload);
-        } else {
-            self.status = Some(LightStatus::from(payload));
-        }
+    fn update_status_from_payload(&self, payload: &Payload) {
+        self.status.modify(self.ip(), |cached| match cached {
+            Some(status) => status.update_from_payload(payload),
+            None => *cached = Some(LightStatus::from(payload)),
+        });
     }
 
-    fn update_status_from_power(&mut self, power: &PowerMode) {
-        if let Some(status) = &mut self.status {
-            status.update_from_power(power);
-        } else {
-            self.status = Some(LightStatus::from(power));
+    fn update_status_from_push(&self, event: &SyncPilotEvent) {
+        self.status.modify(self.ip(), |cached| match cached {
+            Some(status) => status.update_from_push(event),
+            None => *cached = Some(LightStatus::from(event)),
+        });
+    }
+
+    fn update_status_from_power(&self, power: &PowerMode) {
+        self.status.modify(self.ip(), |cached| match cached {
+            Some(status) => status.update_from_power(power),
+            None => *cached = Some(LightStatus::from(power)),
+        });
+    }
+
+    /// Serializes `payload` for `setPilot`, applying calibration if set.
+    fn wire_params(&self, payload: &Payload) -> Result<Value> {
+        match &self.calibration {
+            Some(calibration) => serde_json::to_value(calibration.apply(payload)),
+            None => serde_json::to_value(payload),
         }
+        .map_err(Error::JsonDump)
     }
 
     async fn send_command(&self, msg: &Value) -> Result<Value> {
+        // Boxed, as batch operations join many of these futures on one stack
+        match self.queue.run(Box::pin(self.send_queued(msg))).await {
+            Some(result) => result,
+            None => Err(Error::QueueFull {
+                ip: self.ip(),
+                depth: self.queue.depth(),
+            }),
+        }
+    }
+
+    /// Sends `msg` once it is this light's turn in the queue.
+    async fn send_queued(&self, msg: &Value) -> Result<Value> {
+        // Retries reuse the id, so a late reply to an earlier attempt still counts
+        let msg = &protocol::with_id(msg, next_request_id());
+
         // Record the sent message
         self.history.lock().await.record(MessageType::Send, msg);
 
         let msg_str = serde_json::to_string(msg).map_err(Error::JsonDump)?;
-        let mut last_error = None;
+        #[cfg(feature = "metrics")]
+        metrics::record_command(self.ip());
+        let method = msg.get("method").and_then(Value::as_str).unwrap_or("");
+        stats::record_command(self.ip(), method);
+        match self.send_with_retries(msg, &msg_str).await {
+            // The bulb may have moved; look it up again by MAC and try once more
+            Err(Error::Socket { .. }) if self.mac.is_some() && self.reresolve().await => {
+                self.send_with_retries(msg, &msg_str).await
+            }
+            result => result,
+        }
+    }
 
-        for attempt in 0..=Self::MAX_RETRIES {
-            match self.send_udp(&msg_str).await {
+    /// Sends `msg`, retrying lost replies.
+    ///
+    /// Requests that are not idempotent (see [`protocol::retry_class`]) are
+    /// only retried if they never left this host, since a lost reply does not
+    /// mean the bulb did not act on them.
+    async fn send_with_retries(&self, msg: &Value, msg_str: &str) -> Result<Value> {
+        let class = protocol::retry_class(msg);
+        let method = msg.get("method").and_then(Value::as_str).unwrap_or("");
+        let policy = self.retry_policy();
+
+        let mut retry = 0;
+        let last_error = loop {
+            if retry > 0 {
+                #[cfg(feature = "metrics")]
+                metrics::record_retry(self.ip());
+                stats::record_retry(self.ip());
+            }
+            match self
+                .send_udp(msg, msg_str, Duration::from_millis(Self::TIMEOUT_MS))
+                .await
+            {
                 Ok(response) => {
                     // Record the received response
-                    self.history
-                        .lock()
-                        .await
-                        .record(MessageType::Receive, &response);
+                    let mut history = self.history.lock().await;
+                    history.record(MessageType::Receive, &response);
+
+                    // The bulb answered, so a JSON-RPC error is final and not retried
+                    if let Some(err) = protocol::bulb_error(method, &response) {
+                        history.record_method_error(method, &err.to_string());
+                        #[cfg(feature = "metrics")]
+                        metrics::record_error(self.ip());
+                        return Err(err);
+                    }
+                    #[cfg(feature = "metrics")]
+                    if let Some(result) = response.get("result") {
+                        metrics::observe(self.ip(), result);
+                    }
                     return Ok(response);
                 }
                 Err(e) => {
                     // Record the error
-                    self.history.lock().await.record_error(&e.to_string());
-                    last_error = Some(e);
-                    if attempt < Self::MAX_RETRIES {
-                        let delay_idx = (attempt as usize).min(Self::RETRY_DELAYS_MS.len() - 1);
-                        runtime::sleep(Duration::from_millis(Self::RETRY_DELAYS_MS[delay_idx]))
-                            .await;
+                    self.history
+                        .lock()
+                        .await
+                        .record_method_error(method, &e.to_string());
+                    if matches!(&e, Error::Socket { err, .. } if err.kind() == std::io::ErrorKind::TimedOut)
+                    {
+                        #[cfg(feature = "metrics")]
+                        metrics::record_timeout(self.ip());
+                        stats::record_timeout(self.ip());
                     }
+                    let sent = matches!(&e, Error::Socket { action, .. } if action == "receive")
+                        || matches!(&e, Error::ResponseMismatch { .. });
+                    if sent && class == RetryClass::NonIdempotent {
+                        break e;
+                    }
+                    retry += 1;
+                    let Some(delay) = policy.delay(retry) else {
+                        break e;
+                    };
+                    retry::notify(&RetryAttempt {
+                        ip: self.ip(),
+                        method: method.to_string(),
+                        retry,
+                        delay,
+                        error: e.to_string(),
+                    });
+                    runtime::sleep(delay).await;
                 }
             }
+        };
+
+        #[cfg(feature = "metrics")]
+        metrics::record_error(self.ip());
+        Err(last_error)
+    }
+
+    /// Looks the bulb up by MAC and updates the IP if it moved.
+    ///
+    /// Returns true if the IP changed.
+    async fn reresolve(&self) -> bool {
+        let Ok(Some(new)) = self.resolve_ip().await else {
+            return false;
+        };
+        let old = self.ip();
+        if new == old {
+            return false;
+        }
+        self.ip.set(new);
+        debug!("Bulb {:?} moved from {} to {}", self.mac, old, new);
+
+        if let (Some(handler), Some(mac)) = (&self.on_ip_change, &self.mac) {
+            (handler.0)(&IpChanged {
+                mac: mac.clone(),
+                old,
+                new,
+            });
         }
+        true
+    }
 
-        Err(last_error.unwrap_or(Error::NoAttribute))
+    /// Current IP of the bulb with this light's MAC, found by broadcast.
+    async fn resolve_ip(&self) -> Result<Option<Ipv4Addr>> {
+        let Some(mac) = &self.mac else {
+            return Ok(None);
+        };
+        let bulbs = discover_bulbs_with(&DiscoveryOptions {
+            timeout: Self::RESOLVE_TIMEOUT,
+            transport: Arc::clone(&self.transport),
+            ..Default::default()
+        })
+        .await?;
+        Ok(bulbs
+            .into_iter()
+            .find(|bulb| bulb.mac.eq_ignore_ascii_case(mac))
+            .map(|bulb| bulb.ip))
     }
 
-    async fn send_udp(&self, msg: &str) -> Result<Value> {
-        let socket = UdpSocket::bind("0.0.0.0:0")
-            .await
-            .map_err(|e| Error::socket("bind", e))?;
+    /// Sends `msg_str`, the encoding of `request`, and waits for the reply.
+    ///
+    /// Fails with [`Error::ResponseMismatch`] if only replies to other
+    /// requests arrive in time.
+    async fn send_udp(&self, request: &Value, msg_str: &str, timeout: Duration) -> Result<Value> {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
 
-        socket
-            .connect(&format!("{}:{}", self.ip, Self::PORT))
+        let socket = self
+            .transport
+            .bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)))
             .await
-            .map_err(|e| Error::socket("connect", e))?;
+            .map_err(|e| Error::socket("bind", e))?;
 
+        let ip = self.ip();
         socket
-            .send(msg.as_bytes())
+            .send_to(msg_str.as_bytes(), SocketAddr::from((ip, Self::PORT)))
             .await
             .map_err(|e| Error::socket("send", e))?;
+        stats::record_sent(msg_str.len());
+        let sent_at = Instant::now();
 
         let mut buffer = [0u8; 4096];
 
-        // Use runtime-agnostic timeout for the receive operation
-        let bytes = runtime::timeout(
-            Duration::from_millis(Self::TIMEOUT_MS),
-            socket.recv(&mut buffer),
-        )
-        .await
-        .map_err(|_| {
-            Error::socket(
+        // Skip stray datagrams from other hosts, and replies to other
+        // requests, until the bulb answers this one
+        let mismatched = AtomicBool::new(false);
+        let receive = async {
+            loop {
+                let (bytes, from) = socket
+                    .recv_from(&mut buffer)
+                    .await
+                    .map_err(|e| Error::socket("receive", e))?;
+                if from.ip() != ip {
+                    continue;
+                }
+                let resp = protocol::parse(&buffer[..bytes])?;
+                if !protocol::is_reply_to(&resp, request) {
+                    debug!("Dropping reply to another request from {}: {}", ip, resp);
+                    mismatched.store(true, Ordering::Relaxed);
+                    continue;
+                }
+                stats::record_reply(ip, bytes, sent_at.elapsed());
+                return Ok(resp);
+            }
+        };
+        match runtime::timeout(timeout, receive).await {
+            Ok(result) => result,
+            Err(_) if mismatched.load(Ordering::Relaxed) => Err(Error::ResponseMismatch {
+                method: request
+                    .get("method")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+                id: protocol::message_id(request).unwrap_or(0),
+            }),
+            Err(_) => Err(Error::socket(
                 "receive",
                 std::io::Error::new(std::io::ErrorKind::TimedOut, "receive timeout"),
-            )
-        })?
-        .map_err(|e| Error::socket("receive", e))?;
-
-        let response = String::from_utf8(buffer[..bytes].to_vec()).map_err(Error::Utf8Decode)?;
-        serde_json::from_str(&response).map_err(Error::JsonLoad)
+            )),
+        }
     }
 }
 
+/// Keys of `requested` whose values are missing from or differ in `actual`.
+fn mismatched_fields(requested: &Value, actual: &Value) -> Vec<String> {
+    // Transition parameters shape how a change happens, not the resulting state
+    const TRANSIENT: [&str; 3] = ["fadeIn", "fadeOut", "dimmingDelta"];
+
+    requested
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| !TRANSIENT.contains(&key.as_str()))
+        .filter(|(key, value)| actual.get(key.as_str()) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+/// Deserializes the `result` of a reply.
+fn parse_result<T: serde::de::DeserializeOwned>(resp: Value) -> Result<T> {
+    let result = resp.get("result").cloned().unwrap_or(Value::Null);
+    serde_json::from_value(result).map_err(Error::JsonLoad)
+}
+
 fn parse_f32_array(config: &Value, key: &str) -> Option<Vec<f32>> {
     config.get(key).and_then(|v| v.as_array()).map(|arr| {
         arr.iter()
@@ -0,0 +1,193 @@
+//! Prometheus/OpenMetrics telemetry for lights.
+//!
+//! With the `metrics` feature, every [`Light`](crate::Light) records how many
+//! commands it sent, how many were retried or timed out, and the latest
+//! signal strength, wattage and on/off state its bulb reported. Push updates
+//! from a [`PushManager`](crate::push::PushManager) are counted too.
+//! [`gather`] renders everything in the OpenMetrics text format for scraping;
+//! with the `http-api` feature it is also served at `GET /metrics`.
+//!
+//! Requires the `metrics` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! light.get_status().await?;
+//! print!("{}", wiz_lights_rs::metrics::gather());
+//! // # TYPE wiz_commands counter
+//! // wiz_commands_total{ip="192.168.1.20"} 1
+//! // ...
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Telemetry recorded for one light.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LightMetrics {
+    /// Commands sent, not counting retries.
+    pub commands: u64,
+    /// Extra attempts made after a command got no reply.
+    pub retries: u64,
+    /// Attempts that timed out waiting for a reply.
+    pub timeouts: u64,
+    /// Commands that failed after all retries, or that the bulb rejected.
+    pub errors: u64,
+    /// Latest WiFi signal strength in dBm.
+    pub rssi: Option<i64>,
+    /// Latest power draw in watts.
+    pub watts: Option<f64>,
+    /// Latest on/off state.
+    pub on: Option<bool>,
+}
+
+/// Per-light telemetry, keyed by IP.
+#[derive(Debug, Default)]
+struct Metrics {
+    lights: BTreeMap<Ipv4Addr, LightMetrics>,
+}
+
+impl Metrics {
+    fn entry(&mut self, ip: Ipv4Addr) -> &mut LightMetrics {
+        self.lights.entry(ip).or_default()
+    }
+
+    /// Picks gauges out of a `getPilot`/`getPower` result or push params.
+    fn observe(&mut self, ip: Ipv4Addr, values: &Value) {
+        let light = self.entry(ip);
+        if let Some(rssi) = values.get("rssi").and_then(Value::as_i64) {
+            light.rssi = Some(rssi);
+        }
+        if let Some(state) = values.get("state").and_then(Value::as_bool) {
+            light.on = Some(state);
+        }
+        if let Some(power) = values.get("power").and_then(Value::as_f64) {
+            light.watts = Some(power);
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, kind, help, value) in FAMILIES {
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let suffix = if kind == "counter" { "_total" } else { "" };
+            for (ip, light) in &self.lights {
+                if let Some(value) = value(light) {
+                    let _ = writeln!(out, "{name}{suffix}{{ip=\"{ip}\"}} {value}");
+                }
+            }
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+type Field = fn(&LightMetrics) -> Option<f64>;
+
+/// Exported metric families as (name, type, help, value).
+const FAMILIES: [(&str, &str, &str, Field); 7] = [
+    ("wiz_commands", "counter", "Commands sent.", |m| {
+        Some(m.commands as f64)
+    }),
+    ("wiz_retries", "counter", "Retried attempts.", |m| {
+        Some(m.retries as f64)
+    }),
+    ("wiz_timeouts", "counter", "Attempts that timed out.", |m| {
+        Some(m.timeouts as f64)
+    }),
+    ("wiz_errors", "counter", "Failed commands.", |m| {
+        Some(m.errors as f64)
+    }),
+    ("wiz_rssi_dbm", "gauge", "WiFi signal strength.", |m| {
+        m.rssi.map(|v| v as f64)
+    }),
+    ("wiz_power_watts", "gauge", "Power draw.", |m| m.watts),
+    ("wiz_light_on", "gauge", "1 if the light is on.", |m| {
+        m.on.map(f64::from)
+    }),
+];
+
+fn global() -> &'static Mutex<Metrics> {
+    static METRICS: OnceLock<Mutex<Metrics>> = OnceLock::new();
+    METRICS.get_or_init(Mutex::default)
+}
+
+fn with<T>(f: impl FnOnce(&mut Metrics) -> T) -> T {
+    let mut metrics = global().lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut metrics)
+}
+
+/// Render all recorded telemetry in the OpenMetrics text format.
+pub fn gather() -> String {
+    with(|metrics| metrics.render())
+}
+
+/// Telemetry recorded for every light seen so far.
+pub fn snapshot() -> BTreeMap<Ipv4Addr, LightMetrics> {
+    with(|metrics| metrics.lights.clone())
+}
+
+/// Forget all recorded telemetry.
+pub fn reset() {
+    with(|metrics| metrics.lights.clear());
+}
+
+pub(crate) fn record_command(ip: Ipv4Addr) {
+    with(|metrics| metrics.entry(ip).commands += 1);
+}
+
+pub(crate) fn record_retry(ip: Ipv4Addr) {
+    with(|metrics| metrics.entry(ip).retries += 1);
+}
+
+pub(crate) fn record_timeout(ip: Ipv4Addr) {
+    with(|metrics| metrics.entry(ip).timeouts += 1);
+}
+
+pub(crate) fn record_error(ip: Ipv4Addr) {
+    with(|metrics| metrics.entry(ip).errors += 1);
+}
+
+pub(crate) fn observe(ip: Ipv4Addr, values: &Value) {
+    with(|metrics| metrics.observe(ip, values));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_observe_gauges() {
+        let ip = Ipv4Addr::new(192, 168, 1, 20);
+        let mut metrics = Metrics::default();
+        metrics.observe(ip, &json!({"rssi": -60, "state": true, "dimming": 50}));
+        metrics.observe(ip, &json!({"power": 7.5}));
+
+        let light = &metrics.lights[&ip];
+        assert_eq!(light.rssi, Some(-60));
+        assert_eq!(light.on, Some(true));
+        assert_eq!(light.watts, Some(7.5));
+    }
+
+    #[test]
+    fn test_render_openmetrics() {
+        let ip = Ipv4Addr::new(192, 168, 1, 20);
+        let mut metrics = Metrics::default();
+        metrics.entry(ip).commands = 3;
+        metrics.observe(ip, &json!({"state": false}));
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE wiz_commands counter\n"));
+        assert!(text.contains("wiz_commands_total{ip=\"192.168.1.20\"} 3\n"));
+        assert!(text.contains("wiz_light_on{ip=\"192.168.1.20\"} 0\n"));
+        assert!(!text.contains("wiz_rssi_dbm{"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+}
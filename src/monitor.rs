@@ -0,0 +1,449 @@
+//! Bulb availability tracking.
+//!
+//! A [`Registry`] is the inventory of known bulbs, keyed by MAC address, with
+//! each bulb's last known IP and whether it is reachable. [`BulbMonitor`]
+//! keeps a registry current in the background: it periodically broadcasts a
+//! discovery probe, pings known bulbs that did not answer, and can follow
+//! `firstBeat` and `syncPilot` messages from a [`PushManager`]. Changes are
+//! reported as [`BulbEvent`]s.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use futures::StreamExt;
+//! use wiz_lights_rs::monitor::{BulbEvent, BulbMonitor, Registry};
+//!
+//! let monitor = BulbMonitor::start(Registry::new(), Duration::from_secs(30));
+//! let mut events = monitor.events().await;
+//! while let Some(event) = events.next().await {
+//!     match event {
+//!         BulbEvent::Online { mac, ip } => println!("{mac} is up at {ip}"),
+//!         BulbEvent::Offline { mac, .. } => println!("{mac} went away"),
+//!         BulbEvent::IpChanged(change) => println!("{} moved to {}", change.mac, change.new),
+//!     }
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::stream::BoxStream;
+use futures::{StreamExt, future};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::{DiscoveredBulb, discover_bulbs};
+use crate::light::{IpChanged, Light};
+use crate::push::{PushEvent, PushManager};
+use crate::runtime::{self, JoinHandle, Mutex};
+
+/// A change in a bulb's availability.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulbEvent {
+    /// The bulb answered after being unknown or offline.
+    Online { mac: String, ip: Ipv4Addr },
+    /// The bulb stopped answering.
+    Offline { mac: String, ip: Ipv4Addr },
+    /// The bulb answered from a different IP than before.
+    IpChanged(IpChanged),
+}
+
+/// What the registry knows about one bulb.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulbRecord {
+    /// MAC address, lowercased.
+    pub mac: String,
+    pub ip: Ipv4Addr,
+    pub online: bool,
+    pub last_seen: Option<SystemTime>,
+    /// Consecutive checks the bulb did not answer.
+    pub missed: u32,
+}
+
+/// Inventory of known bulbs.
+///
+/// # Example
+///
+/// ```
+/// use std::net::Ipv4Addr;
+/// use std::time::SystemTime;
+/// use wiz_lights_rs::monitor::{BulbEvent, Registry};
+///
+/// let mut registry = Registry::new();
+/// let events = registry.observe("A8BB50AABBCC", Ipv4Addr::new(192, 168, 1, 20), SystemTime::now());
+/// assert!(matches!(events[0], BulbEvent::Online { .. }));
+/// assert_eq!(registry.resolve("a8bb50aabbcc"), Some(Ipv4Addr::new(192, 168, 1, 20)));
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Registry {
+    bulbs: HashMap<String, BulbRecord>,
+    offline_after: u32,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    /// Missed checks before a bulb is reported offline.
+    pub const DEFAULT_OFFLINE_AFTER: u32 = 2;
+
+    pub fn new() -> Self {
+        Registry {
+            bulbs: HashMap::new(),
+            offline_after: Self::DEFAULT_OFFLINE_AFTER,
+        }
+    }
+
+    /// Report bulbs offline after `missed` consecutive unanswered checks.
+    pub fn with_offline_after(missed: u32) -> Self {
+        Registry {
+            offline_after: missed.max(1),
+            ..Self::new()
+        }
+    }
+
+    /// Adds a bulb to check without marking it online.
+    ///
+    /// Returns false if the MAC was already known.
+    pub fn add(&mut self, mac: &str, ip: Ipv4Addr) -> bool {
+        let mac = mac.to_lowercase();
+        if self.bulbs.contains_key(&mac) {
+            return false;
+        }
+        self.bulbs.insert(
+            mac.clone(),
+            BulbRecord {
+                mac,
+                ip,
+                online: false,
+                last_seen: None,
+                missed: 0,
+            },
+        );
+        true
+    }
+
+    /// Adds every discovered bulb as seen now.
+    pub fn add_discovered(&mut self, bulbs: &[DiscoveredBulb]) -> Vec<BulbEvent> {
+        let now = SystemTime::now();
+        bulbs
+            .iter()
+            .flat_map(|bulb| self.observe(&bulb.mac, bulb.ip, now))
+            .collect()
+    }
+
+    /// Records that the bulb answered from `ip` at `at`.
+    pub fn observe(&mut self, mac: &str, ip: Ipv4Addr, at: SystemTime) -> Vec<BulbEvent> {
+        let mac = mac.to_lowercase();
+        let mut events = Vec::new();
+        let record = self.bulbs.entry(mac.clone()).or_insert_with(|| BulbRecord {
+            mac: mac.clone(),
+            ip,
+            online: false,
+            last_seen: None,
+            missed: 0,
+        });
+
+        if record.ip != ip {
+            events.push(BulbEvent::IpChanged(IpChanged {
+                mac: mac.clone(),
+                old: record.ip,
+                new: ip,
+            }));
+            record.ip = ip;
+        }
+        if !record.online {
+            events.push(BulbEvent::Online { mac, ip });
+            record.online = true;
+        }
+        record.last_seen = Some(at);
+        record.missed = 0;
+        events
+    }
+
+    /// Records that the bulb did not answer a check.
+    ///
+    /// Returns [`BulbEvent::Offline`] when this pushes it over the limit.
+    pub fn missed(&mut self, mac: &str) -> Option<BulbEvent> {
+        let record = self.bulbs.get_mut(&mac.to_lowercase())?;
+        record.missed = record.missed.saturating_add(1);
+        if record.online && record.missed >= self.offline_after {
+            record.online = false;
+            return Some(BulbEvent::Offline {
+                mac: record.mac.clone(),
+                ip: record.ip,
+            });
+        }
+        None
+    }
+
+    pub fn remove(&mut self, mac: &str) -> Option<BulbRecord> {
+        self.bulbs.remove(&mac.to_lowercase())
+    }
+
+    pub fn get(&self, mac: &str) -> Option<&BulbRecord> {
+        self.bulbs.get(&mac.to_lowercase())
+    }
+
+    /// Last known IP of the bulb with this MAC.
+    pub fn resolve(&self, mac: &str) -> Option<Ipv4Addr> {
+        self.get(mac).map(|record| record.ip)
+    }
+
+    pub fn bulbs(&self) -> impl Iterator<Item = &BulbRecord> {
+        self.bulbs.values()
+    }
+
+    pub fn online(&self) -> impl Iterator<Item = &BulbRecord> {
+        self.bulbs.values().filter(|record| record.online)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bulbs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bulbs.is_empty()
+    }
+}
+
+type Senders = Arc<Mutex<Vec<UnboundedSender<BulbEvent>>>>;
+
+/// Keeps a [`Registry`] current in the background.
+pub struct BulbMonitor {
+    registry: Arc<Mutex<Registry>>,
+    senders: Senders,
+    running: Arc<AtomicBool>,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl BulbMonitor {
+    /// How long each discovery broadcast waits for replies.
+    const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Start checking bulbs every `interval`.
+    ///
+    /// Each check broadcasts a discovery probe, then pings the known bulbs
+    /// that did not reply to it.
+    pub fn start(registry: Registry, interval: Duration) -> Self {
+        let registry = Arc::new(Mutex::new(registry));
+        let senders: Senders = Arc::new(Mutex::new(Vec::new()));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let task_registry = Arc::clone(&registry);
+        let task_senders = Arc::clone(&senders);
+        let task_running = Arc::clone(&running);
+        let handle = runtime::spawn(async move {
+            while task_running.load(Ordering::SeqCst) {
+                check(&task_registry, &task_senders, interval).await;
+
+                // Sleep in short slices so stop() is not delayed by the interval
+                let start = runtime::Instant::now();
+                while task_running.load(Ordering::SeqCst) && start.elapsed() < interval {
+                    runtime::sleep(interval.min(Duration::from_millis(500))).await;
+                }
+            }
+        });
+
+        BulbMonitor {
+            registry,
+            senders,
+            running,
+            tasks: Mutex::new(vec![handle]),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Snapshot of the current inventory.
+    pub async fn registry(&self) -> Registry {
+        self.registry.lock().await.clone()
+    }
+
+    /// Last known IP of the bulb with this MAC.
+    pub async fn resolve(&self, mac: &str) -> Option<Ipv4Addr> {
+        self.registry.lock().await.resolve(mac)
+    }
+
+    /// Get a stream of availability changes.
+    ///
+    /// The stream ends when the monitor is stopped.
+    pub async fn events(&self) -> BoxStream<'static, BulbEvent> {
+        let (tx, rx) = mpsc::unbounded();
+        self.senders.lock().await.push(tx);
+        rx.boxed()
+    }
+
+    /// Also track bulbs from push notifications.
+    ///
+    /// `firstBeat` and `syncPilot` messages count as the bulb answering, so
+    /// bulbs coming online or changing IP are noticed before the next check.
+    pub async fn follow_push(&self, push: &PushManager) {
+        let mut events = push.events().await;
+        let registry = Arc::clone(&self.registry);
+        let senders = Arc::clone(&self.senders);
+        let running = Arc::clone(&self.running);
+        let handle = runtime::spawn(async move {
+            while running.load(Ordering::SeqCst) {
+                // Wake up periodically so stop() does not wait for the next push
+                let next = runtime::timeout(Duration::from_millis(500), events.next()).await;
+                let event = match next {
+                    Ok(Some(event)) => event,
+                    Ok(None) => break,
+                    Err(_) => continue,
+                };
+                let ip = match &event {
+                    PushEvent::State(state) => state.ip,
+                    PushEvent::Discovered(bulb) => bulb.ip,
+                    PushEvent::Accessory(_) => continue,
+                };
+                let changes = registry
+                    .lock()
+                    .await
+                    .observe(event.mac(), ip, SystemTime::now());
+                send_events(&senders, changes).await;
+            }
+        });
+        self.tasks.lock().await.push(handle);
+    }
+
+    /// Stop checking and wait for the background tasks to finish.
+    pub async fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        let tasks: Vec<_> = self.tasks.lock().await.drain(..).collect();
+        self.senders.lock().await.clear();
+        future::join_all(tasks).await;
+    }
+}
+
+impl Drop for BulbMonitor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// One monitoring pass: broadcast, then ping whoever stayed quiet.
+async fn check(registry: &Mutex<Registry>, senders: &Senders, interval: Duration) {
+    let mut seen = HashSet::new();
+    let mut changes = Vec::new();
+
+    match discover_bulbs(BulbMonitor::DISCOVERY_TIMEOUT.min(interval)).await {
+        Ok(bulbs) => {
+            let mut registry = registry.lock().await;
+            for bulb in &bulbs {
+                seen.insert(bulb.mac.to_lowercase());
+            }
+            changes.extend(registry.add_discovered(&bulbs));
+        }
+        Err(e) => debug!("Monitor discovery failed: {}", e),
+    }
+
+    let quiet: Vec<(String, Ipv4Addr)> = registry
+        .lock()
+        .await
+        .bulbs()
+        .filter(|record| !seen.contains(&record.mac))
+        .map(|record| (record.mac.clone(), record.ip))
+        .collect();
+    let pings =
+        future::join_all(quiet.iter().map(|(_, ip)| async move {
+            Light::new(*ip, None).measure_latency(1).await.received > 0
+        }))
+        .await;
+
+    let now = SystemTime::now();
+    let mut registry = registry.lock().await;
+    for ((mac, ip), answered) in quiet.into_iter().zip(pings) {
+        if answered {
+            changes.extend(registry.observe(&mac, ip, now));
+        } else {
+            changes.extend(registry.missed(&mac));
+        }
+    }
+    drop(registry);
+
+    send_events(senders, changes).await;
+}
+
+async fn send_events(senders: &Senders, events: Vec<BulbEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    senders.lock().await.retain(|tx| {
+        events
+            .iter()
+            .all(|event| tx.unbounded_send(event.clone()).is_ok())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: &str = "a8bb50aabbcc";
+
+    #[test]
+    fn test_online_and_ip_change() {
+        let mut registry = Registry::new();
+        let first = Ipv4Addr::new(192, 168, 1, 20);
+        let second = Ipv4Addr::new(192, 168, 1, 21);
+
+        assert_eq!(
+            registry.observe(MAC, first, SystemTime::now()),
+            vec![BulbEvent::Online {
+                mac: MAC.into(),
+                ip: first
+            }]
+        );
+        assert!(registry.observe(MAC, first, SystemTime::now()).is_empty());
+        assert_eq!(
+            registry.observe("A8BB50AABBCC", second, SystemTime::now()),
+            vec![BulbEvent::IpChanged(IpChanged {
+                mac: MAC.into(),
+                old: first,
+                new: second
+            })]
+        );
+        assert_eq!(registry.resolve(MAC), Some(second));
+    }
+
+    #[test]
+    fn test_offline_after_missed_checks() {
+        let mut registry = Registry::with_offline_after(2);
+        let ip = Ipv4Addr::new(192, 168, 1, 20);
+        registry.observe(MAC, ip, SystemTime::now());
+
+        assert_eq!(registry.missed(MAC), None);
+        assert_eq!(
+            registry.missed(MAC),
+            Some(BulbEvent::Offline {
+                mac: MAC.into(),
+                ip
+            })
+        );
+        assert_eq!(registry.missed(MAC), None);
+        assert_eq!(registry.online().count(), 0);
+
+        let events = registry.observe(MAC, ip, SystemTime::now());
+        assert!(matches!(events[..], [BulbEvent::Online { .. }]));
+    }
+
+    #[test]
+    fn test_added_bulbs_start_offline() {
+        let mut registry = Registry::new();
+        assert!(registry.add(MAC, Ipv4Addr::new(192, 168, 1, 20)));
+        assert!(!registry.add(MAC, Ipv4Addr::new(192, 168, 1, 21)));
+        assert_eq!(registry.missed(MAC), None);
+        assert!(!registry.get(MAC).unwrap().online);
+    }
+}
@@ -1,12 +1,23 @@
 //! Configuration payload for Wiz lights.
 
+use alloc::string::{String, ToString};
+use core::time::Duration;
+
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
+use crate::errors::Error;
 use crate::types::{
     Brightness, Color, ColorRGBW, ColorRGBWW, FanDirection, FanMode, FanSpeed, FanState,
     HueSaturation, Kelvin, Ratio, SceneMode, Speed, White,
 };
 
+type Result<T> = core::result::Result<T, Error>;
+
+fn duration_ms(duration: Duration) -> u32 {
+    duration.as_millis().min(u128::from(u32::MAX)) as u32
+}
+
 /// A configuration payload to send to Wiz lights.
 ///
 /// Payloads can contain multiple lighting attributes (color, brightness, scene, etc.)
@@ -22,17 +33,28 @@ use crate::types::{
 ///    let payload = Payload::from(&SceneMode::Sunset);
 ///    ```
 ///
-/// 2. **Builder pattern** for combining multiple attributes:
+/// 2. **Builder pattern** for combining multiple attributes. Setters return
+///    `&mut Self`, so they can be called one by one or chained and finished
+///    with [`Payload::build`]:
 ///    ```
 ///    use std::str::FromStr;
 ///    use wiz_lights_rs::{Payload, Brightness, Color};
 ///    let mut payload = Payload::new();
 ///    payload.brightness(&Brightness::create(80).unwrap());
 ///    payload.color(&Color::from_str("255,128,0").unwrap());
+///
+///    let chained = Payload::new()
+///        .brightness(&Brightness::create(80).unwrap())
+///        .color(&Color::rgb(255, 128, 0))
+///        .build();
+///    assert!(chained.is_ok());
 ///    ```
 #[serde_with::skip_serializing_none]
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Payload {
+    /// Power state, sent along with the pilot so a bulb turns on straight
+    /// into the requested look. Set by [`Light::turn_on_with`](crate::Light::turn_on_with).
+    pub(crate) state: Option<bool>,
     #[serde(rename = "sceneId")]
     pub(crate) scene: Option<u16>,
     pub(crate) dimming: Option<u8>,
@@ -58,6 +80,16 @@ pub struct Payload {
     pub(crate) fan_speed: Option<u8>,
     #[serde(rename = "fanRevrs")]
     pub(crate) fan_reverse: Option<u8>,
+    // Transition parameters, honoured by newer firmware only
+    #[serde(rename = "fadeIn")]
+    pub(crate) fade_in: Option<u32>,
+    #[serde(rename = "fadeOut")]
+    pub(crate) fade_out: Option<u32>,
+    #[serde(rename = "dimmingDelta")]
+    pub(crate) dimming_delta: Option<i8>,
+    /// Fields not modelled above, added via [`Payload::set_raw`].
+    #[serde(flatten)]
+    pub(crate) raw: Map<String, Value>,
 }
 
 impl Payload {
@@ -67,81 +99,199 @@ impl Payload {
 
     /// Returns true if at least one lighting attribute is set.
     pub fn is_valid(&self) -> bool {
-        self.scene.is_some()
+        self.state.is_some()
+            || self.scene.is_some()
             || self.dimming.is_some()
             || self.temp.is_some()
             || (self.red.is_some() && self.green.is_some() && self.blue.is_some())
             || self.cool.is_some()
             || self.warm.is_some()
+            || self.dimming_delta.is_some()
+            || !self.raw.is_empty()
     }
 
-    pub fn scene(&mut self, scene: &SceneMode) {
+    /// Returns a copy of the payload if it is valid, for ending a setter chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{Brightness, Color, Payload};
+    ///
+    /// let payload = Payload::new()
+    ///     .color(&Color::rgb(255, 128, 0))
+    ///     .brightness(&Brightness::create(80).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(payload.is_valid());
+    /// assert!(Payload::new().build().is_err());
+    /// ```
+    pub fn build(&self) -> Result<Self> {
+        if self.is_valid() {
+            Ok(self.clone())
+        } else {
+            Err(Error::NoAttribute)
+        }
+    }
+
+    pub fn scene(&mut self, scene: &SceneMode) -> &mut Self {
         self.scene = Some(scene.id());
+        self
     }
 
-    pub fn brightness(&mut self, brightness: &Brightness) {
+    pub fn brightness(&mut self, brightness: &Brightness) -> &mut Self {
         self.dimming = Some(brightness.value);
+        self
     }
 
-    pub fn speed(&mut self, speed: &Speed) {
+    pub fn speed(&mut self, speed: &Speed) -> &mut Self {
         self.speed = Some(speed.value);
+        self
     }
 
-    pub fn temp(&mut self, temp: &Kelvin) {
+    pub fn temp(&mut self, temp: &Kelvin) -> &mut Self {
         self.temp = Some(temp.kelvin);
+        self
+    }
+
+    /// Sets the color temperature from mireds, as sent by Home Assistant
+    /// or Zigbee, converted with [`Kelvin::try_from_mireds`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{Kelvin, Payload};
+    ///
+    /// let mut payload = Payload::new();
+    /// payload.temp_mireds(370)?;
+    /// assert_eq!(payload, Payload::from(&Kelvin::from_mireds(370).unwrap()));
+    /// assert!(payload.temp_mireds(50).is_err());
+    /// # Ok::<(), wiz_lights_rs::Error>(())
+    /// ```
+    pub fn temp_mireds(&mut self, mireds: u16) -> Result<&mut Self> {
+        Ok(self.temp(&Kelvin::try_from_mireds(mireds)?))
     }
 
-    pub fn color(&mut self, color: &Color) {
+    pub fn color(&mut self, color: &Color) -> &mut Self {
         self.red = Some(color.red);
         self.green = Some(color.green);
         self.blue = Some(color.blue);
+        self
     }
 
-    pub fn color_rgbw(&mut self, color: &ColorRGBW) {
+    pub fn color_rgbw(&mut self, color: &ColorRGBW) -> &mut Self {
         self.red = Some(color.red);
         self.green = Some(color.green);
         self.blue = Some(color.blue);
         self.warm = Some(color.warm);
+        self
     }
 
-    pub fn color_rgbww(&mut self, color: &ColorRGBWW) {
+    pub fn color_rgbww(&mut self, color: &ColorRGBWW) -> &mut Self {
         self.red = Some(color.red);
         self.green = Some(color.green);
         self.blue = Some(color.blue);
         self.cool = Some(color.cool);
         self.warm = Some(color.warm);
+        self
     }
 
-    pub fn hue_saturation(&mut self, hs: &HueSaturation) {
+    pub fn hue_saturation(&mut self, hs: &HueSaturation) -> &mut Self {
         self.color(&hs.to_color());
+        self
     }
 
-    pub fn cool(&mut self, cool: &White) {
+    pub fn cool(&mut self, cool: &White) -> &mut Self {
         self.cool = Some(cool.value);
+        self
     }
 
-    pub fn warm(&mut self, warm: &White) {
+    pub fn warm(&mut self, warm: &White) -> &mut Self {
         self.warm = Some(warm.value);
+        self
     }
 
-    pub fn ratio(&mut self, ratio: &Ratio) {
+    pub fn ratio(&mut self, ratio: &Ratio) -> &mut Self {
         self.ratio = Some(ratio.value);
+        self
     }
 
-    pub fn fan_state(&mut self, state: &FanState) {
+    pub fn fan_state(&mut self, state: &FanState) -> &mut Self {
         self.fan_state = Some(state.value());
+        self
     }
 
-    pub fn fan_mode(&mut self, mode: &FanMode) {
+    pub fn fan_mode(&mut self, mode: &FanMode) -> &mut Self {
         self.fan_mode = Some(mode.value());
+        self
     }
 
-    pub fn fan_speed(&mut self, speed: &FanSpeed) {
+    pub fn fan_speed(&mut self, speed: &FanSpeed) -> &mut Self {
         self.fan_speed = Some(speed.value());
+        self
     }
 
-    pub fn fan_direction(&mut self, direction: &FanDirection) {
+    pub fn fan_direction(&mut self, direction: &FanDirection) -> &mut Self {
         self.fan_reverse = Some(direction.value());
+        self
+    }
+
+    /// Run the fan in breeze mode, which varies its speed like natural wind.
+    ///
+    /// In breeze mode the fan speed sets the intensity of the gusts; `None`
+    /// keeps the fixture's current speed.
+    pub fn fan_breeze(&mut self, intensity: Option<&FanSpeed>) -> &mut Self {
+        self.fan_state(&FanState::On);
+        self.fan_mode(&FanMode::Breeze);
+        if let Some(intensity) = intensity {
+            self.fan_speed(intensity);
+        }
+        self
+    }
+
+    /// Fade into the new state over `duration` instead of switching instantly.
+    ///
+    /// Sent as `fadeIn` in milliseconds. Firmware that does not know the
+    /// field ignores it and applies the change immediately.
+    pub fn fade_in(&mut self, duration: Duration) -> &mut Self {
+        self.fade_in = Some(duration_ms(duration));
+        self
+    }
+
+    /// Fade out of the previous state over `duration`. Sent as `fadeOut`.
+    pub fn fade_out(&mut self, duration: Duration) -> &mut Self {
+        self.fade_out = Some(duration_ms(duration));
+        self
+    }
+
+    /// Change brightness relative to the bulb's current level, in percent.
+    ///
+    /// Sent as `dimmingDelta`; the bulb clamps the result to its range.
+    /// Firmware without support rejects or ignores the field, so prefer an
+    /// absolute [`Payload::brightness`] when portability matters.
+    pub fn dimming_delta(&mut self, delta: i8) -> &mut Self {
+        self.dimming_delta = Some(delta);
+        self
+    }
+
+    /// Add an arbitrary `setPilot` parameter.
+    ///
+    /// Escape hatch for firmware fields this crate does not model yet. Raw
+    /// parameters are merged into the serialized params and take precedence
+    /// over typed setters using the same key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use wiz_lights_rs::Payload;
+    ///
+    /// let mut payload = Payload::new();
+    /// payload.set_raw("schdPsetId", json!(9));
+    /// assert_eq!(serde_json::to_value(&payload).unwrap(), json!({"schdPsetId": 9}));
+    /// ```
+    pub fn set_raw(&mut self, key: &str, value: Value) -> &mut Self {
+        self.raw.insert(key.to_string(), value);
+        self
     }
 
     pub(crate) fn get_color(&self) -> Option<Color> {
@@ -184,6 +334,14 @@ impl From<&Speed> for Payload {
     }
 }
 
+impl From<&Ratio> for Payload {
+    fn from(ratio: &Ratio) -> Self {
+        let mut p = Payload::new();
+        p.ratio(ratio);
+        p
+    }
+}
+
 impl From<&Brightness> for Payload {
     fn from(brightness: &Brightness) -> Self {
         let mut p = Payload::new();
@@ -0,0 +1,156 @@
+//! WiZ smart plugs.
+
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cancel::CancelToken;
+use crate::config::{BulbClass, BulbType, SystemConfig, UserConfig};
+use crate::diagnostics::Diagnostics;
+use crate::errors::Error;
+use crate::light::Light;
+use crate::response::LightingResponse;
+use crate::runtime::{self, JoinHandle};
+use crate::types::PowerMode;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A smart plug (`BulbClass::Socket`).
+///
+/// Plugs speak the same protocol as bulbs but only switch power, so this
+/// type exposes on/off, power metering and delayed switching, and has no
+/// way to send colors, scenes or brightness.
+///
+/// # Example
+///
+/// ```ignore
+/// use wiz_lights_rs::Plug;
+///
+/// let plug = Plug::new("192.168.1.40".parse()?, Some("Heater"));
+/// plug.turn_on().await?;
+/// println!("drawing {:?} W", plug.get_power().await?);
+///
+/// // Switch off in an hour
+/// plug.schedule(Duration::from_secs(3600), false);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Plug {
+    light: Light,
+}
+
+impl Plug {
+    pub fn new(ip: Ipv4Addr, name: Option<&str>) -> Self {
+        Plug {
+            light: Light::new(ip, name),
+        }
+    }
+
+    /// Wraps `light` after checking with the device that it is a plug.
+    ///
+    /// Returns `None` for bulbs and other fixtures.
+    pub async fn detect(light: Light) -> Result<Option<Self>> {
+        let bulb_type = light.get_bulb_type().await?;
+        Ok((bulb_type.bulb_class == BulbClass::Socket).then_some(Plug { light }))
+    }
+
+    pub fn ip(&self) -> Ipv4Addr {
+        self.light.ip()
+    }
+
+    pub fn mac(&self) -> Option<&str> {
+        self.light.mac()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.light.name()
+    }
+
+    /// The underlying light. Not public, so color payloads cannot reach a
+    /// plug; the diagnostic methods below forward to it instead.
+    pub(crate) fn light(&self) -> &Light {
+        &self.light
+    }
+
+    /// Returns diagnostics including state, configuration, and history.
+    pub async fn diagnostics(&self) -> Diagnostics {
+        self.light.diagnostics().await
+    }
+
+    pub async fn get_system_config(&self) -> Result<SystemConfig> {
+        self.light.get_system_config().await
+    }
+
+    pub async fn get_user_config(&self) -> Result<UserConfig> {
+        self.light.get_user_config().await
+    }
+
+    /// Returns model configuration (firmware >= 1.22).
+    pub async fn get_model_config(&self) -> Result<Value> {
+        self.light.get_model_config().await
+    }
+
+    pub async fn get_bulb_type(&self) -> Result<BulbType> {
+        self.light.get_bulb_type().await
+    }
+
+    /// Queries whether the outlet is switched on.
+    pub async fn is_on(&self) -> Result<bool> {
+        Ok(self.light.get_status().await?.emitting())
+    }
+
+    pub async fn turn_on(&self) -> Result<LightingResponse> {
+        self.light.set_power(&PowerMode::On).await
+    }
+
+    pub async fn turn_off(&self) -> Result<LightingResponse> {
+        self.light.set_power(&PowerMode::Off).await
+    }
+
+    pub async fn set_on(&self, on: bool) -> Result<LightingResponse> {
+        if on {
+            self.turn_on().await
+        } else {
+            self.turn_off().await
+        }
+    }
+
+    pub async fn toggle(&self) -> Result<LightingResponse> {
+        self.light.toggle().await
+    }
+
+    /// Returns the power drawn through the outlet in watts, if metered.
+    pub async fn get_power(&self) -> Result<Option<f32>> {
+        self.light.get_power().await
+    }
+
+    /// Switches the outlet on or off after `delay`.
+    ///
+    /// The switch happens in a background task; abort the returned handle
+    /// to cancel it, or use [`Plug::schedule_with_cancel`].
+    pub fn schedule(&self, delay: Duration, on: bool) -> JoinHandle<Result<LightingResponse>> {
+        self.schedule_with_cancel(delay, on, CancelToken::new())
+    }
+
+    /// Like [`Plug::schedule`], but the switch is skipped with
+    /// [`Error::Cancelled`] once `cancel` is cancelled, on every runtime.
+    pub fn schedule_with_cancel(
+        &self,
+        delay: Duration,
+        on: bool,
+        cancel: CancelToken,
+    ) -> JoinHandle<Result<LightingResponse>> {
+        let plug = self.clone();
+        runtime::spawn(async move {
+            cancel.run(runtime::sleep(delay)).await?;
+            plug.set_on(on).await
+        })
+    }
+}
+
+impl From<Light> for Plug {
+    fn from(light: Light) -> Self {
+        Plug { light }
+    }
+}
@@ -0,0 +1,190 @@
+//! Background status polling.
+//!
+//! Some firmware versions drop push registrations or never send them, so the
+//! only way to keep a cached [`LightStatus`](crate::LightStatus) current is to
+//! ask. [`StatusPoller`] calls [`Light::get_status`] on an interval for each
+//! light, which refreshes the cache shared by every clone of it. Start one
+//! with [`Light::start_polling`] or [`Room::start_polling`](crate::Room::start_polling).
+//!
+//! Each light is polled by its own task. Waits are spread by a random jitter
+//! so the lights of a room do not answer in lockstep, and a light that stops
+//! answering is polled less often until it comes back.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::Duration;
+//! use futures::StreamExt;
+//!
+//! let poller = room.start_polling(Duration::from_secs(10));
+//! let mut updates = poller.updates();
+//! while let Some(change) = updates.next().await {
+//!     println!("{} is now {:?}", change.ip, change.current);
+//! }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::future;
+use futures::stream::BoxStream;
+use log::debug;
+
+use crate::light::{ChangeSubscription, Light, StatusChange};
+use crate::retry;
+use crate::runtime::{self, JoinHandle, Mutex};
+
+/// Longest wait between polls of a light that keeps failing.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Fraction of the interval added at random to each wait.
+const JITTER: f64 = 0.1;
+
+type Senders = Arc<StdMutex<Vec<UnboundedSender<StatusChange>>>>;
+
+/// Keeps the cached status of a set of lights fresh in the background.
+pub struct StatusPoller {
+    lights: Vec<Light>,
+    subscription: ChangeSubscription,
+    senders: Senders,
+    running: Arc<AtomicBool>,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl StatusPoller {
+    /// Start polling every light in `lights` about every `interval`.
+    pub fn start(lights: Vec<Light>, interval: Duration) -> Self {
+        let senders: Senders = Arc::new(StdMutex::new(Vec::new()));
+        let running = Arc::new(AtomicBool::new(true));
+        let subscription = ChangeSubscription::next();
+
+        let mut tasks = Vec::with_capacity(lights.len());
+        for light in &lights {
+            let task_senders = Arc::clone(&senders);
+            light.add_change_listener(
+                subscription,
+                Arc::new(move |change| {
+                    task_senders
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .retain(|tx| tx.unbounded_send(change.clone()).is_ok());
+                }),
+            );
+
+            let light = light.clone();
+            let task_running = Arc::clone(&running);
+            tasks.push(runtime::spawn(async move {
+                // Spread the first polls over part of the interval
+                let mut wait = jitter(interval);
+                let mut failures = 0;
+                loop {
+                    // Sleep in short slices so stop() is not delayed by the interval
+                    let start = runtime::Instant::now();
+                    while task_running.load(Ordering::SeqCst) && start.elapsed() < wait {
+                        runtime::sleep(wait.min(Duration::from_millis(500))).await;
+                    }
+                    if !task_running.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    match light.get_status().await {
+                        Ok(_) => failures = 0,
+                        Err(e) => {
+                            debug!("Status poll of {} failed: {}", light.ip(), e);
+                            failures += 1;
+                        }
+                    }
+                    wait = backoff(interval, failures) + jitter(interval);
+                }
+            }));
+        }
+
+        StatusPoller {
+            lights,
+            subscription,
+            senders,
+            running,
+            tasks: Mutex::new(tasks),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Get a stream of status changes of the polled lights.
+    ///
+    /// Changes are reported whatever caused them, not only polls. The
+    /// stream ends when the poller is stopped.
+    pub fn updates(&self) -> BoxStream<'static, StatusChange> {
+        let (tx, rx) = mpsc::unbounded();
+        self.senders
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(tx);
+        rx.boxed()
+    }
+
+    /// Stop polling and wait for the background tasks to finish.
+    pub async fn stop(&self) {
+        self.shutdown();
+        let tasks: Vec<_> = self.tasks.lock().await.drain(..).collect();
+        future::join_all(tasks).await;
+    }
+
+    fn shutdown(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        for light in &self.lights {
+            light.remove_change_listener(self.subscription);
+        }
+        self.senders
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+impl Drop for StatusPoller {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// The wait after `failures` consecutive failed polls: the interval,
+/// doubled per failure up to [`MAX_BACKOFF`].
+fn backoff(interval: Duration, failures: u32) -> Duration {
+    if failures == 0 {
+        return interval;
+    }
+    interval
+        .saturating_mul(1 << failures.min(16))
+        .min(MAX_BACKOFF.max(interval))
+}
+
+/// A random duration up to [`JITTER`] of `interval`.
+fn jitter(interval: Duration) -> Duration {
+    interval.mul_f64(JITTER * retry::random_fraction())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let interval = Duration::from_secs(10);
+        assert_eq!(backoff(interval, 0), interval);
+        assert_eq!(backoff(interval, 1), Duration::from_secs(20));
+        assert_eq!(backoff(interval, 3), Duration::from_secs(80));
+        assert_eq!(backoff(interval, 40), MAX_BACKOFF);
+
+        // An interval longer than the cap is never shortened
+        let slow = Duration::from_secs(600);
+        assert_eq!(backoff(slow, 2), slow);
+
+        assert!(jitter(interval) <= interval.mul_f64(JITTER));
+    }
+}
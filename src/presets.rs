@@ -0,0 +1,133 @@
+//! Named presets and state snapshots.
+//!
+//! A [`Preset`] is a named [`Payload`] that can be stored and applied to any
+//! light. A [`Snapshot`] records a light's current pilot state so it can be
+//! put back after a temporary change, such as a doorbell alert.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::{Color, Payload, Snapshot};
+//!
+//! let snapshot = Snapshot::capture(&light).await?;
+//! light.set(&Payload::from(&Color::rgb(255, 0, 0))).await?;
+//! // ... alert ...
+//! light.restore(&snapshot).await?;
+//! ```
+
+use std::net::Ipv4Addr;
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::Error;
+use crate::light::Light;
+use crate::payload::Payload;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// A named payload that can be applied to lights.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub payload: Payload,
+}
+
+impl Preset {
+    pub fn new(name: &str, payload: Payload) -> Self {
+        Preset {
+            name: name.to_string(),
+            payload,
+        }
+    }
+}
+
+/// A light's pilot state at a point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub ip: Ipv4Addr,
+    pub captured_at: SystemTime,
+    /// Whether the light was on.
+    pub emitting: bool,
+    /// Settings that reproduce the captured look.
+    pub payload: Payload,
+}
+
+impl Snapshot {
+    /// Query the light with `getPilot` and record its current state.
+    pub async fn capture(light: &Light) -> Result<Self> {
+        let resp = light.send_raw_method("getPilot", Value::Null).await?;
+        let result = resp.get("result").unwrap_or(&Value::Null);
+        Ok(Snapshot {
+            ip: light.ip(),
+            captured_at: SystemTime::now(),
+            emitting: result.get("state").and_then(Value::as_bool).unwrap_or(true),
+            payload: payload_from_pilot(result),
+        })
+    }
+}
+
+/// Rebuild the settings of a `getPilot` result.
+///
+/// Only the active mode is kept: a running scene wins over an explicit
+/// color, which wins over a white temperature, so restoring does not mix
+/// attributes the bulb reports but is not currently showing.
+fn payload_from_pilot(result: &Value) -> Payload {
+    let get = |key: &str| result.get(key).and_then(Value::as_u64);
+    let get_u8 = |key: &str| get(key).and_then(|v| u8::try_from(v).ok());
+
+    let mut payload = Payload::new();
+    payload.dimming = get_u8("dimming");
+
+    match get("sceneId").filter(|id| *id != 0) {
+        Some(scene) => {
+            payload.scene = u16::try_from(scene).ok();
+            payload.speed = get_u8("speed");
+        }
+        None => match (get_u8("r"), get_u8("g"), get_u8("b")) {
+            (Some(r), Some(g), Some(b)) => {
+                payload.red = Some(r);
+                payload.green = Some(g);
+                payload.blue = Some(b);
+                payload.cool = get_u8("c");
+                payload.warm = get_u8("w");
+            }
+            _ => match get("temp").and_then(|t| u16::try_from(t).ok()) {
+                Some(temp) => payload.temp = Some(temp),
+                None => {
+                    payload.cool = get_u8("c");
+                    payload.warm = get_u8("w");
+                }
+            },
+        },
+    }
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scene_takes_precedence() {
+        let payload = payload_from_pilot(&json!({
+            "state": true, "sceneId": 4, "speed": 120, "dimming": 60, "r": 255, "g": 0, "b": 0
+        }));
+        assert_eq!(payload.scene, Some(4));
+        assert_eq!(payload.speed, Some(120));
+        assert_eq!(payload.dimming, Some(60));
+        assert!(payload.get_color().is_none());
+    }
+
+    #[test]
+    fn test_temperature_when_no_color() {
+        let payload = payload_from_pilot(&json!({
+            "state": true, "sceneId": 0, "temp": 2700, "dimming": 40
+        }));
+        assert_eq!(payload.temp, Some(2700));
+        assert_eq!(payload.dimming, Some(40));
+        assert!(payload.scene.is_none());
+    }
+}
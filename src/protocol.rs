@@ -0,0 +1,243 @@
+//! Encoding and decoding of the WiZ JSON-RPC protocol, without any I/O.
+//!
+//! Everything here turns values into datagrams and datagrams back into
+//! values; nothing opens a socket, spawns a task or reads a clock. It is the
+//! part of the crate an embedded controller with its own UDP stack needs:
+//! build a request with [`set_pilot`] or [`request`], send the bytes to
+//! [`PORT`], and hand the reply to [`decode`] and [`parse_status`]. Tag
+//! requests with [`with_id`] and check replies with [`is_reply_to`] to tell
+//! late answers to earlier requests apart.
+//!
+//! The module only uses `core` and `alloc` APIs and is available without the
+//! `std` feature, see [the crate docs](crate#no_std).
+//!
+//! # Example
+//!
+//! ```
+//! use wiz_lights_rs::{Color, Payload, protocol};
+//!
+//! let datagram = protocol::set_pilot(&Payload::from(&Color::rgb(255, 0, 0))).unwrap();
+//! // ... send `datagram` to the bulb on protocol::PORT and receive `reply` ...
+//! let reply = br#"{"method":"getPilot","env":"pro","result":{"mac":"a8bb50aabbcc","state":true,"r":255,"g":0,"b":0}}"#;
+//! let status = protocol::parse_status(&protocol::decode(reply).unwrap()).unwrap();
+//! assert!(status.emitting());
+//! # assert!(!datagram.is_empty());
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde_json::{Value, json};
+
+use crate::errors::Error;
+use crate::payload::Payload;
+use crate::status::{BulbStatus, LightStatus};
+
+type Result<T> = core::result::Result<T, Error>;
+
+/// UDP port bulbs listen on for commands.
+pub const PORT: u16 = 38899;
+
+/// UDP port bulbs send push notifications to once registered.
+pub const PUSH_PORT: u16 = 38900;
+
+/// Whether a request can be sent again when its reply was lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryClass {
+    /// Applying the request twice has the same effect as once.
+    Idempotent,
+    /// The request must not be repeated once it may have reached the bulb,
+    /// e.g. a reboot, a firmware update, new WiFi credentials or a relative
+    /// brightness change.
+    NonIdempotent,
+}
+
+/// The retry class of a request.
+pub fn retry_class(msg: &Value) -> RetryClass {
+    let method = msg.get("method").and_then(Value::as_str).unwrap_or("");
+    let relative = msg
+        .get("params")
+        .is_some_and(|params| params.get("dimmingDelta").is_some());
+    match method {
+        "reboot" | "reset" | "updateOta" | "setWifiConfig" => RetryClass::NonIdempotent,
+        "setPilot" if relative => RetryClass::NonIdempotent,
+        _ => RetryClass::Idempotent,
+    }
+}
+
+/// Sets the JSON-RPC `id` of a request. Bulbs echo it in their reply.
+pub fn with_id(msg: &Value, id: u64) -> Value {
+    let mut msg = msg.clone();
+    if let Some(object) = msg.as_object_mut() {
+        object.insert("id".into(), json!(id));
+    }
+    msg
+}
+
+/// The JSON-RPC `id` of a request or reply, if any.
+pub fn message_id(msg: &Value) -> Option<u64> {
+    msg.get("id").and_then(Value::as_u64)
+}
+
+/// Whether `resp` answers `request`.
+///
+/// Replies are matched by `id`. Firmware that does not echo it is matched
+/// by method instead, so a late reply to a different command is still told
+/// apart.
+pub fn is_reply_to(resp: &Value, request: &Value) -> bool {
+    if let (Some(reply), Some(id)) = (message_id(resp), message_id(request)) {
+        return reply == id;
+    }
+    match (resp.get("method"), request.get("method")) {
+        (Some(reply), Some(method)) => reply == method,
+        _ => true,
+    }
+}
+
+/// A request for `method`, with `params` if given.
+pub fn request(method: &str, params: Option<Value>) -> Value {
+    match params {
+        Some(params) => json!({"method": method, "params": params}),
+        None => json!({"method": method}),
+    }
+}
+
+/// The datagram for a JSON message.
+pub fn encode(msg: &Value) -> Result<Vec<u8>> {
+    serde_json::to_vec(msg).map_err(Error::JsonDump)
+}
+
+/// The `setPilot` datagram applying `payload`.
+pub fn set_pilot(payload: &Payload) -> Result<Vec<u8>> {
+    if !payload.is_valid() {
+        return Err(Error::NoAttribute);
+    }
+    let params = serde_json::to_value(payload).map_err(Error::JsonDump)?;
+    encode(&request("setPilot", Some(params)))
+}
+
+/// The `getPilot` datagram.
+pub fn get_pilot() -> Vec<u8> {
+    br#"{"method":"getPilot"}"#.to_vec()
+}
+
+/// Parses a datagram received from a bulb.
+///
+/// A JSON-RPC error in the reply is returned as [`Error::Bulb`].
+pub fn decode(datagram: &[u8]) -> Result<Value> {
+    let resp = parse(datagram)?;
+    let method = resp.get("method").and_then(Value::as_str).unwrap_or("");
+    match bulb_error(method, &resp) {
+        Some(err) => Err(err),
+        None => Ok(resp),
+    }
+}
+
+/// Parses a datagram as JSON without looking for a JSON-RPC error.
+pub(crate) fn parse(datagram: &[u8]) -> Result<Value> {
+    let text = String::from_utf8(datagram.to_vec()).map_err(Error::Utf8Decode)?;
+    serde_json::from_str(&text).map_err(Error::JsonLoad)
+}
+
+/// The state reported by a decoded `getPilot` reply.
+pub fn parse_status(resp: &Value) -> Result<LightStatus> {
+    let status: BulbStatus = serde_json::from_value(resp.clone()).map_err(Error::JsonLoad)?;
+    Ok(LightStatus::from(&status))
+}
+
+/// Returns the bulb's JSON-RPC error, if the response carries one.
+pub(crate) fn bulb_error(method: &str, resp: &Value) -> Option<Error> {
+    let error = resp.get("error")?;
+    let code = error.get("code").and_then(Value::as_i64).unwrap_or(0);
+    let message = error
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown error");
+    Some(Error::bulb(code, message, method))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Brightness;
+
+    #[test]
+    fn test_set_pilot_datagram() {
+        let datagram = set_pilot(&Payload::from(&Brightness::create(40).unwrap())).unwrap();
+        let msg: Value = serde_json::from_slice(&datagram).unwrap();
+        assert_eq!(
+            msg,
+            json!({"method": "setPilot", "params": {"dimming": 40}})
+        );
+
+        assert!(matches!(
+            set_pilot(&Payload::default()),
+            Err(Error::NoAttribute)
+        ));
+        assert_eq!(
+            serde_json::from_slice::<Value>(&get_pilot()).unwrap(),
+            request("getPilot", None)
+        );
+    }
+
+    #[test]
+    fn test_retry_class_and_ids() {
+        let get_pilot = request("getPilot", None);
+        assert_eq!(retry_class(&get_pilot), RetryClass::Idempotent);
+        assert_eq!(
+            retry_class(&request("reboot", None)),
+            RetryClass::NonIdempotent
+        );
+        assert_eq!(
+            retry_class(&request("updateOta", None)),
+            RetryClass::NonIdempotent
+        );
+        let wifi = request("setWifiConfig", Some(json!({"ssid": "Home", "psk": "x"})));
+        assert_eq!(retry_class(&wifi), RetryClass::NonIdempotent);
+        let relative = request("setPilot", Some(json!({"dimmingDelta": 10})));
+        assert_eq!(retry_class(&relative), RetryClass::NonIdempotent);
+
+        let msg = with_id(&get_pilot, 7);
+        assert_eq!(msg["id"], 7);
+        assert_eq!(message_id(&msg), Some(7));
+        assert_eq!(message_id(&get_pilot), None);
+    }
+
+    #[test]
+    fn test_reply_correlation() {
+        let request = with_id(&request("getPilot", None), 7);
+        assert!(is_reply_to(
+            &json!({"method": "getPilot", "id": 7}),
+            &request
+        ));
+        assert!(!is_reply_to(
+            &json!({"method": "getPilot", "id": 6}),
+            &request
+        ));
+        // Without an echoed id, the method has to match
+        assert!(is_reply_to(&json!({"method": "getPilot"}), &request));
+        assert!(!is_reply_to(&json!({"method": "setPilot"}), &request));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let err = decode(
+            br#"{"method":"getPower","error":{"code":-32601,"message":"Method not found"}}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::Bulb { code: -32601, .. }));
+        assert!(matches!(decode(b"not json"), Err(Error::JsonLoad(_))));
+    }
+
+    #[test]
+    fn test_parse_status_scene_speed() {
+        let resp = json!({
+            "method": "getPilot",
+            "env": "pro",
+            "result": {"mac": "a8bb50aabbcc", "state": true, "sceneId": 4, "speed": 150, "temp": 2700}
+        });
+        let status = parse_status(&resp).unwrap();
+        assert_eq!(status.speed().map(|s| s.value()), Some(150));
+        assert_eq!(status.temp().map(|t| t.kelvin()), Some(2700));
+    }
+}
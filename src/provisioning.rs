@@ -0,0 +1,180 @@
+//! Onboarding new bulbs onto a WiFi network without the WiZ app.
+//!
+//! A bulb that has not been set up (or was factory reset) opens its own
+//! access point, named like `WiZ_a1b2c3`. Once this machine has joined that
+//! network, the bulb answers discovery and commands as usual, and
+//! [`provision`] hands it the SSID and passphrase of the home network. The
+//! bulb then leaves setup mode and reconnects as a regular client.
+//!
+//! Joining the bulb's access point is left to the operating system.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::provisioning::{self, WifiCredentials};
+//!
+//! let credentials = WifiCredentials::new("HomeNetwork", "correct horse battery")?;
+//! for bulb in provisioning::find_setup_bulbs(Duration::from_secs(3)).await? {
+//!     provisioning::provision(&bulb.into_light(None), &credentials).await?;
+//! }
+//! ```
+
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::discovery::{DiscoveredBulb, DiscoveryOptions, discover_bulbs_with};
+use crate::errors::Error;
+use crate::light::Light;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Prefix of the access point names bulbs open in setup mode.
+pub const SETUP_SSID_PREFIX: &str = "WiZ_";
+
+/// Network a bulb should join.
+#[derive(Clone, PartialEq, Eq)]
+pub struct WifiCredentials {
+    ssid: String,
+    psk: String,
+}
+
+impl std::fmt::Debug for WifiCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WifiCredentials")
+            .field("ssid", &self.ssid)
+            .field("psk", &"<redacted>")
+            .finish()
+    }
+}
+
+impl WifiCredentials {
+    /// Credentials for a WPA network.
+    ///
+    /// The SSID must be 1 to 32 bytes and the passphrase 8 to 63 characters,
+    /// or empty for an open network.
+    pub fn new(ssid: &str, psk: &str) -> Result<Self> {
+        if ssid.is_empty() || ssid.len() > 32 {
+            return Err(Error::InvalidCredentials(
+                "ssid must be 1 to 32 bytes".to_string(),
+            ));
+        }
+        if !psk.is_empty() && !(8..=63).contains(&psk.len()) {
+            return Err(Error::InvalidCredentials(
+                "passphrase must be 8 to 63 characters".to_string(),
+            ));
+        }
+        Ok(WifiCredentials {
+            ssid: ssid.to_string(),
+            psk: psk.to_string(),
+        })
+    }
+
+    pub fn ssid(&self) -> &str {
+        &self.ssid
+    }
+}
+
+/// Whether an access point name belongs to a bulb in setup mode.
+pub fn is_setup_ssid(ssid: &str) -> bool {
+    ssid.starts_with(SETUP_SSID_PREFIX)
+}
+
+/// Whether the bulb has not been added to a home yet.
+///
+/// Bulbs in setup mode report a `homeId` of 0, or none at all.
+pub async fn is_setup_mode(light: &Light) -> Result<bool> {
+    let config = light.get_system_config().await?;
+    Ok(config.home_id.unwrap_or(0) == 0)
+}
+
+/// Discover bulbs that are waiting to be set up.
+///
+/// Run this while connected to a bulb's access point, or on a network where
+/// reset bulbs may still be reachable.
+pub async fn find_setup_bulbs(timeout: Duration) -> Result<Vec<DiscoveredBulb>> {
+    let bulbs = discover_bulbs_with(&DiscoveryOptions {
+        timeout,
+        probe_config: true,
+        ..Default::default()
+    })
+    .await?;
+    Ok(bulbs
+        .into_iter()
+        .filter(|bulb| bulb.home_id.unwrap_or(0) == 0)
+        .collect())
+}
+
+/// Send WiFi credentials to a bulb in setup mode.
+///
+/// The bulb acknowledges before it leaves its access point, so this machine
+/// loses its connection to the bulb shortly after this returns.
+pub async fn provision(light: &Light, credentials: &WifiCredentials) -> Result<()> {
+    let resp = light
+        .send_raw_method(
+            "setWifiConfig",
+            json!({
+                "ssid": credentials.ssid,
+                "psk": credentials.psk,
+            }),
+        )
+        .await?;
+
+    let success = resp
+        .get("result")
+        .and_then(|r| r.get("success"))
+        .and_then(Value::as_bool);
+    if success != Some(true) {
+        return Err(Error::NotApplied("setWifiConfig".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockBulb, MockBulbConfig};
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_credentials_validation() {
+        assert!(WifiCredentials::new("Home", "password1").is_ok());
+        assert!(WifiCredentials::new("Open", "").is_ok());
+        assert!(WifiCredentials::new("", "password1").is_err());
+        assert!(WifiCredentials::new(&"x".repeat(33), "password1").is_err());
+        assert!(WifiCredentials::new("Home", "short").is_err());
+        assert!(is_setup_ssid("WiZ_a1b2c3"));
+        assert!(!is_setup_ssid("HomeNetwork"));
+    }
+
+    #[tokio::test]
+    async fn test_provision_mock_bulb() {
+        let config = MockBulbConfig {
+            home_id: 0,
+            ..Default::default()
+        };
+        let bulb = MockBulb::start_with(Ipv4Addr::new(127, 0, 0, 23), config)
+            .await
+            .unwrap();
+        let light = bulb.light();
+        assert!(is_setup_mode(&light).await.unwrap());
+
+        let credentials = WifiCredentials::new("Home", "password1").unwrap();
+        provision(&light, &credentials).await.unwrap();
+
+        let requests = bulb.requests().await;
+        let request = requests.last().unwrap();
+        assert_eq!(request["method"], "setWifiConfig");
+        assert_eq!(request["params"]["ssid"], "Home");
+        assert_eq!(request["params"]["psk"], "password1");
+
+        // The passphrase reaches the bulb but never the history
+        let history = light.history().await;
+        let mut export = Vec::new();
+        history.export_jsonl(&mut export).unwrap();
+        assert!(!String::from_utf8(export).unwrap().contains("password1"));
+        let diagnostics = serde_json::to_string(&light.diagnostics().await).unwrap();
+        assert!(!diagnostics.contains("password1"));
+        bulb.stop().await;
+    }
+}
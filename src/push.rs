@@ -1,32 +1,312 @@
 //! Push notification support for real-time state updates via syncPilot.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::stream::BoxStream;
+use futures::{StreamExt, future};
 use log::{debug, error};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 
 use crate::discovery::DiscoveredBulb;
 use crate::errors::Error;
-use crate::runtime::{self, AsyncUdpSocket, Instant, JoinHandle, Mutex, UdpSocket};
+use crate::light::Light;
+use crate::protocol;
+use crate::response::LightingResponse;
+use crate::room::Room;
+use crate::runtime::{self, Instant, JoinHandle, Mutex, Transport};
+use crate::types::{
+    Brightness, Color, FanDirection, FanMode, FanSpeed, FanState, Kelvin, Ratio, SceneMode, Speed,
+    White,
+};
 
 type Result<T> = std::result::Result<T, Error>;
 
-pub const LISTEN_PORT: u16 = 38900;
-pub const RESPOND_PORT: u16 = 38899;
+pub const LISTEN_PORT: u16 = protocol::PUSH_PORT;
+pub const RESPOND_PORT: u16 = protocol::PORT;
+
+/// Default interval between keep-alive re-registrations.
+///
+/// Bulbs forget push registrations after roughly 30 seconds.
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Shortest accepted keep-alive interval, so bulbs are not flooded with
+/// registrations.
+pub const MIN_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Callback type for state updates (syncPilot messages).
-/// Takes the MAC address and the params value from the message.
-pub type StateCallback = Arc<dyn Fn(&str, &Value) + Send + Sync + 'static>;
+pub type StateCallback = Arc<dyn Fn(&SyncPilotEvent) + Send + Sync + 'static>;
+
+/// Callback type for accessory events (remotes, motion sensors).
+pub type AccessoryCallback = Arc<dyn Fn(&AccessoryEvent) + Send + Sync + 'static>;
 
 /// Callback type for discovery events (firstBeat messages).
 /// Takes the discovered bulb information.
 pub type DiscoveryCallback = Arc<dyn Fn(DiscoveredBulb) + Send + Sync + 'static>;
 
+/// A state update pushed by a bulb via `syncPilot`.
+///
+/// Fields the bulb did not include (or that are out of range) are `None`.
+/// Use [`SyncPilotEvent::raw`] for parameters not modelled here.
+#[derive(Debug, Clone)]
+pub struct SyncPilotEvent {
+    /// MAC address of the bulb, uppercased.
+    pub mac: String,
+    /// IP address the update was received from.
+    pub ip: Ipv4Addr,
+    pub rssi: Option<i32>,
+    pub state: Option<bool>,
+    pub dimming: Option<Brightness>,
+    pub scene: Option<SceneMode>,
+    pub speed: Option<Speed>,
+    pub temp: Option<Kelvin>,
+    pub color: Option<Color>,
+    pub cool: Option<White>,
+    pub warm: Option<White>,
+    pub ratio: Option<Ratio>,
+    pub fan_state: Option<FanState>,
+    pub fan_mode: Option<FanMode>,
+    pub fan_speed: Option<FanSpeed>,
+    pub fan_direction: Option<FanDirection>,
+    raw: Value,
+}
+
+#[derive(Deserialize)]
+struct SyncPilotParams {
+    mac: String,
+    rssi: Option<i32>,
+    state: Option<bool>,
+    dimming: Option<u8>,
+    #[serde(rename = "sceneId")]
+    scene: Option<u16>,
+    speed: Option<u8>,
+    temp: Option<u16>,
+    r: Option<u8>,
+    g: Option<u8>,
+    b: Option<u8>,
+    c: Option<u8>,
+    w: Option<u8>,
+    ratio: Option<u8>,
+    #[serde(rename = "fanState")]
+    fan_state: Option<u8>,
+    #[serde(rename = "fanMode")]
+    fan_mode: Option<u8>,
+    #[serde(rename = "fanSpeed")]
+    fan_speed: Option<u8>,
+    #[serde(rename = "fanRevrs")]
+    fan_reverse: Option<u8>,
+}
+
+impl SyncPilotEvent {
+    /// Parse the `params` object of a `syncPilot` message.
+    pub fn parse(ip: Ipv4Addr, params: &Value) -> Result<Self> {
+        let p: SyncPilotParams = serde_json::from_value(params.clone()).map_err(Error::JsonLoad)?;
+
+        Ok(SyncPilotEvent {
+            mac: p.mac.to_uppercase(),
+            ip,
+            rssi: p.rssi,
+            state: p.state,
+            dimming: p.dimming.and_then(Brightness::create),
+            scene: p.scene.and_then(SceneMode::create),
+            speed: p.speed.and_then(Speed::create),
+            temp: p.temp.and_then(Kelvin::create),
+            color: match (p.r, p.g, p.b) {
+                (Some(r), Some(g), Some(b)) => Some(Color::rgb(r, g, b)),
+                _ => None,
+            },
+            cool: p.c.and_then(White::create),
+            warm: p.w.and_then(White::create),
+            ratio: p.ratio.and_then(Ratio::create),
+            fan_state: p.fan_state.and_then(FanState::create),
+            fan_mode: p.fan_mode.and_then(FanMode::create),
+            fan_speed: p.fan_speed.and_then(|v| FanSpeed::create(v, Some(u8::MAX))),
+            fan_direction: p.fan_reverse.and_then(FanDirection::create),
+            raw: params.clone(),
+        })
+    }
+
+    /// The unparsed `params` object as sent by the bulb.
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+/// What an accessory reported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessoryEventKind {
+    /// A remote or switch button was pressed.
+    Button { button: String },
+    /// A motion/occupancy sensor changed state.
+    Motion { detected: bool },
+    /// The message only carried a battery report.
+    Battery,
+    /// A message that could not be classified; see [`AccessoryEvent::raw`].
+    Other,
+}
+
+/// An event pushed by a Wiz accessory such as a remote or motion sensor.
+///
+/// Accessory messages are not formally documented, so the kind is inferred
+/// from commonly seen parameter names (`button`/`btn`/`key`,
+/// `motion`/`pir`/`occupancy`, `battery`/`batteryLevel`).
+#[derive(Debug, Clone)]
+pub struct AccessoryEvent {
+    /// MAC address of the accessory, uppercased.
+    pub mac: String,
+    /// IP address the event was received from.
+    pub ip: Ipv4Addr,
+    /// The JSON-RPC method of the message.
+    pub method: String,
+    pub kind: AccessoryEventKind,
+    /// Battery level in percent, if reported.
+    pub battery: Option<u8>,
+    raw: Value,
+}
+
+impl AccessoryEvent {
+    /// Build an accessory event from a push message's method and `params`.
+    pub fn parse(ip: Ipv4Addr, method: &str, params: &Value) -> Option<Self> {
+        let mac = params.get("mac")?.as_str()?.to_uppercase();
+        let field = |keys: &[&str]| keys.iter().find_map(|k| params.get(*k));
+
+        let battery = field(&["battery", "batteryLevel"])
+            .and_then(Value::as_u64)
+            .map(|b| b.min(100) as u8);
+
+        let kind = if let Some(button) = field(&["button", "btn", "key"]) {
+            AccessoryEventKind::Button {
+                button: match button {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                },
+            }
+        } else if let Some(motion) = field(&["motion", "pir", "occupancy"]) {
+            AccessoryEventKind::Motion {
+                detected: motion
+                    .as_bool()
+                    .or_else(|| motion.as_u64().map(|v| v != 0))
+                    .unwrap_or(false),
+            }
+        } else if battery.is_some() {
+            AccessoryEventKind::Battery
+        } else {
+            AccessoryEventKind::Other
+        };
+
+        Some(AccessoryEvent {
+            mac,
+            ip,
+            method: method.to_string(),
+            kind,
+            battery,
+            raw: params.clone(),
+        })
+    }
+
+    /// The unparsed `params` object as sent by the accessory.
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+}
+
+/// An event received by the push listener.
+#[derive(Debug, Clone)]
+pub enum PushEvent {
+    /// A bulb reported a state change (`syncPilot`).
+    State(SyncPilotEvent),
+    /// A bulb announced itself on the network (`firstBeat`).
+    Discovered(DiscoveredBulb),
+    /// An accessory such as a remote or motion sensor sent an event.
+    Accessory(AccessoryEvent),
+}
+
+impl PushEvent {
+    /// MAC address of the bulb that sent the event, uppercased.
+    pub fn mac(&self) -> &str {
+        match self {
+            PushEvent::State(event) => &event.mac,
+            PushEvent::Discovered(bulb) => &bulb.mac,
+            PushEvent::Accessory(event) => &event.mac,
+        }
+    }
+}
+
+/// A light or room whose cached status is kept in sync with push updates.
+#[derive(Clone)]
+enum Attachment {
+    Light(Arc<Light>),
+    Room(Arc<Mutex<Room>>),
+}
+
+impl Attachment {
+    async fn process_reply(&self, resp: &LightingResponse) -> bool {
+        match self {
+            Attachment::Light(light) => light.process_reply(resp),
+            Attachment::Room(room) => room.lock().await.process_reply(resp),
+        }
+    }
+
+    async fn record_push(&self, mac: &str, ip: Ipv4Addr, msg: &Value) {
+        match self {
+            Attachment::Light(light) => {
+                light.record_push(mac, ip, msg).await;
+            }
+            Attachment::Room(room) => {
+                for light in room.lock().await.members() {
+                    light.record_push(mac, ip, msg).await;
+                }
+            }
+        }
+    }
+}
+
+/// Identifies a registered state callback so it can be removed later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// State callbacks keyed by MAC address, plus wildcard callbacks.
+#[derive(Default)]
+struct Subscriptions {
+    next_id: u64,
+    by_mac: HashMap<String, Vec<(SubscriptionId, StateCallback)>>,
+    wildcard: Vec<(SubscriptionId, StateCallback)>,
+}
+
+impl Subscriptions {
+    fn next_id(&mut self) -> SubscriptionId {
+        self.next_id += 1;
+        SubscriptionId(self.next_id)
+    }
+
+    fn matching(&self, mac: &str) -> Vec<StateCallback> {
+        self.by_mac
+            .get(mac)
+            .into_iter()
+            .flatten()
+            .chain(&self.wildcard)
+            .map(|(_, cb)| Arc::clone(cb))
+            .collect()
+    }
+
+    fn remove_id(&mut self, id: SubscriptionId) {
+        self.wildcard.retain(|(sub, _)| *sub != id);
+        for callbacks in self.by_mac.values_mut() {
+            callbacks.retain(|(sub, _)| *sub != id);
+        }
+        self.by_mac.retain(|_, callbacks| !callbacks.is_empty());
+    }
+
+    fn len(&self) -> usize {
+        self.by_mac.values().map(Vec::len).sum::<usize>() + self.wildcard.len()
+    }
+}
+
 /// Diagnostics for the push manager.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PushDiagnostics {
@@ -34,6 +314,7 @@ pub struct PushDiagnostics {
     pub subscription_count: usize,
     pub time_since_last_push: Option<f64>,
     pub last_error: Option<String>,
+    pub registered_count: usize,
 }
 
 /// Manages push notification subscriptions for multiple bulbs.
@@ -51,8 +332,8 @@ pub struct PushDiagnostics {
 /// let manager = PushManager::new();
 ///
 /// // Subscribe to updates for a specific bulb
-/// manager.subscribe("AABBCCDDEEFF", |mac, params| {
-///     println!("Bulb {} updated: {:?}", mac, params);
+/// manager.subscribe("AABBCCDDEEFF", |event| {
+///     println!("Bulb {} updated: {:?}", event.mac, event.dimming);
 /// }).await;
 ///
 /// // Start listening (provide your local IP for registration)
@@ -63,12 +344,20 @@ pub struct PushDiagnostics {
 /// ```
 pub struct PushManager {
     running: Arc<AtomicBool>,
-    subscriptions: Arc<Mutex<HashMap<String, StateCallback>>>,
+    subscriptions: Arc<Mutex<Subscriptions>>,
     discovery_callback: Arc<Mutex<Option<DiscoveryCallback>>>,
+    accessory_callback: Arc<Mutex<Option<AccessoryCallback>>>,
+    attachments: Arc<Mutex<Vec<Attachment>>>,
+    event_senders: Arc<Mutex<Vec<UnboundedSender<PushEvent>>>>,
     listener_task: Mutex<Option<JoinHandle<()>>>,
+    keepalive_task: Mutex<Option<JoinHandle<()>>>,
     last_push: Arc<Mutex<Option<Instant>>>,
     last_error: Arc<Mutex<Option<String>>>,
     register_msg: Arc<Mutex<Option<Value>>>,
+    registered: Arc<Mutex<HashSet<Ipv4Addr>>>,
+    bind_addr: Option<Ipv4Addr>,
+    keepalive_interval: Duration,
+    transport: Arc<dyn Transport>,
 }
 
 impl Default for PushManager {
@@ -82,15 +371,68 @@ impl PushManager {
     pub fn new() -> Self {
         Self {
             running: Arc::new(AtomicBool::new(false)),
-            subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            subscriptions: Arc::new(Mutex::new(Subscriptions::default())),
             discovery_callback: Arc::new(Mutex::new(None)),
+            accessory_callback: Arc::new(Mutex::new(None)),
+            attachments: Arc::new(Mutex::new(Vec::new())),
+            event_senders: Arc::new(Mutex::new(Vec::new())),
             listener_task: Mutex::new(None),
+            keepalive_task: Mutex::new(None),
             last_push: Arc::new(Mutex::new(None)),
             last_error: Arc::new(Mutex::new(None)),
             register_msg: Arc::new(Mutex::new(None)),
+            registered: Arc::new(Mutex::new(HashSet::new())),
+            bind_addr: None,
+            keepalive_interval: DEFAULT_KEEPALIVE_INTERVAL,
+            transport: runtime::default_transport(),
         }
     }
 
+    /// Set how often registered bulbs are re-registered while running.
+    ///
+    /// Defaults to [`DEFAULT_KEEPALIVE_INTERVAL`]. Intervals shorter than
+    /// [`MIN_KEEPALIVE_INTERVAL`], including zero, are raised to it.
+    pub fn with_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = interval.max(MIN_KEEPALIVE_INTERVAL);
+        self
+    }
+
+    /// Send registration messages from the given local address.
+    ///
+    /// On multi-homed hosts this ensures registrations leave through the
+    /// interface that shares a network with the bulbs.
+    pub fn with_bind_addr(mut self, bind_addr: Ipv4Addr) -> Self {
+        self.bind_addr = Some(bind_addr);
+        self
+    }
+
+    /// Send registration messages from the IPv4 address of the named network
+    /// interface, resolved like [`DiscoveryOptions::for_interface`].
+    ///
+    /// Pass [`PushManager::bind_addr`] to [`PushManager::start`] so bulbs push
+    /// to the same address.
+    ///
+    /// Not available on `wasm32`, where there are no interfaces to list.
+    ///
+    /// [`DiscoveryOptions::for_interface`]: crate::DiscoveryOptions::for_interface
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_interface(self, name: &str) -> Result<Self> {
+        let addr = crate::discovery::interface_addr(name)?;
+        Ok(self.with_bind_addr(addr.ip))
+    }
+
+    /// The local address registrations are sent from, if one was set.
+    pub fn bind_addr(&self) -> Option<Ipv4Addr> {
+        self.bind_addr
+    }
+
+    /// Listen and send registrations through `transport` instead of the
+    /// runtime's sockets.
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
     /// Check if the push manager is currently running.
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::SeqCst)
@@ -107,27 +449,68 @@ impl PushManager {
                 .await
                 .map(|t| t.elapsed().as_secs_f64()),
             last_error: self.last_error.lock().await.clone(),
+            registered_count: self.registered.lock().await.len(),
         }
     }
 
     /// Subscribe to state updates for a specific bulb.
     ///
     /// The callback will be invoked whenever a `syncPilot` message is received
-    /// from the bulb with the specified MAC address.
-    pub async fn subscribe<F: Fn(&str, &Value) + Send + Sync + 'static>(
+    /// from the bulb with the specified MAC address. Several callbacks may be
+    /// registered for the same bulb.
+    pub async fn subscribe<F: Fn(&SyncPilotEvent) + Send + Sync + 'static>(
         &self,
         mac: &str,
         callback: F,
-    ) {
+    ) -> SubscriptionId {
+        self.subscribe_many(&[mac], callback).await
+    }
+
+    /// Subscribe one callback to state updates from several bulbs.
+    ///
+    /// The returned id removes the callback for all of them.
+    pub async fn subscribe_many<F: Fn(&SyncPilotEvent) + Send + Sync + 'static>(
+        &self,
+        macs: &[&str],
+        callback: F,
+    ) -> SubscriptionId {
+        let callback: StateCallback = Arc::new(callback);
+        let mut subs = self.subscriptions.lock().await;
+        let id = subs.next_id();
+        for mac in macs {
+            subs.by_mac
+                .entry(mac.to_uppercase())
+                .or_default()
+                .push((id, Arc::clone(&callback)));
+        }
+        id
+    }
+
+    /// Subscribe to state updates from every bulb, regardless of MAC address.
+    pub async fn subscribe_all<F: Fn(&SyncPilotEvent) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> SubscriptionId {
+        let mut subs = self.subscriptions.lock().await;
+        let id = subs.next_id();
+        subs.wildcard.push((id, Arc::new(callback)));
+        id
+    }
+
+    /// Unsubscribe all callbacks for a specific bulb.
+    ///
+    /// Wildcard callbacks registered with [`PushManager::subscribe_all`] are kept.
+    pub async fn unsubscribe(&self, mac: &str) {
         self.subscriptions
             .lock()
             .await
-            .insert(mac.to_uppercase(), Arc::new(callback));
+            .by_mac
+            .remove(&mac.to_uppercase());
     }
 
-    /// Unsubscribe from state updates for a specific bulb.
-    pub async fn unsubscribe(&self, mac: &str) {
-        self.subscriptions.lock().await.remove(&mac.to_uppercase());
+    /// Remove a single callback by the id returned when subscribing.
+    pub async fn unsubscribe_id(&self, id: SubscriptionId) {
+        self.subscriptions.lock().await.remove_id(id);
     }
 
     /// Set a callback for discovery events.
@@ -141,6 +524,83 @@ impl PushManager {
         *self.discovery_callback.lock().await = Some(Arc::new(callback));
     }
 
+    /// Set a callback for accessory events.
+    ///
+    /// The callback will be invoked for push messages other than `syncPilot`
+    /// and `firstBeat`, such as remote button presses or motion sensor
+    /// triggers.
+    pub async fn set_accessory_callback<F: Fn(&AccessoryEvent) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) {
+        *self.accessory_callback.lock().await = Some(Arc::new(callback));
+    }
+
+    /// Get a stream of all push events.
+    ///
+    /// Each call returns an independent stream. Events are buffered until
+    /// consumed, and the stream ends when the manager is stopped or dropped.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use futures::StreamExt;
+    ///
+    /// let mut events = manager.events().await;
+    /// while let Some(event) = events.next().await {
+    ///     println!("{}: {:?}", event.mac(), event);
+    /// }
+    /// ```
+    pub async fn events(&self) -> BoxStream<'static, PushEvent> {
+        let (tx, rx) = mpsc::unbounded();
+        self.event_senders.lock().await.push(tx);
+        rx.boxed()
+    }
+
+    /// Get a stream of push events from a specific bulb.
+    ///
+    /// See [`PushManager::events`].
+    pub async fn events_for(&self, mac: &str) -> BoxStream<'static, PushEvent> {
+        let mac = mac.to_uppercase();
+        self.events()
+            .await
+            .filter(move |event| future::ready(event.mac() == mac))
+            .boxed()
+    }
+
+    /// Keep a light's cached status updated from push notifications.
+    ///
+    /// Incoming `syncPilot` messages are parsed into a [`LightStatus`] and
+    /// applied with [`Light::process_reply`] when they come from the light's IP.
+    /// They are also recorded as [`MessageType::Push`](crate::MessageType::Push)
+    /// in the light's history when they carry its MAC, or come from its IP if
+    /// the MAC is unknown.
+    ///
+    /// # Example
+    ///
+    /// The light is updated through a shared reference, so other tasks can
+    /// keep using it (or clones of it) while the listener runs.
+    ///
+    /// ```ignore
+    /// use std::sync::Arc;
+    ///
+    /// let light = Arc::new(Light::new(ip, None));
+    /// manager.attach(Arc::clone(&light)).await;
+    /// manager.register_bulb(ip).await?;
+    /// // ... later, the cached status reflects the latest push
+    /// let status = light.status();
+    /// ```
+    pub async fn attach(&self, light: Arc<Light>) {
+        self.attachments.lock().await.push(Attachment::Light(light));
+    }
+
+    /// Keep the cached status of every light in a room updated from push notifications.
+    ///
+    /// See [`PushManager::attach`].
+    pub async fn attach_room(&self, room: Arc<Mutex<Room>>) {
+        self.attachments.lock().await.push(Attachment::Room(room));
+    }
+
     /// Start the push listener on port 38900.
     ///
     /// # Arguments
@@ -152,7 +612,9 @@ impl PushManager {
             return Ok(());
         }
 
-        let socket = UdpSocket::bind(&format!("0.0.0.0:{LISTEN_PORT}"))
+        let socket = self
+            .transport
+            .bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, LISTEN_PORT)))
             .await
             .map_err(|e| Error::socket("bind push socket", e))?;
 
@@ -170,6 +632,9 @@ impl PushManager {
         let running = Arc::clone(&self.running);
         let subscriptions = Arc::clone(&self.subscriptions);
         let discovery_callback = Arc::clone(&self.discovery_callback);
+        let accessory_callback = Arc::clone(&self.accessory_callback);
+        let attachments = Arc::clone(&self.attachments);
+        let event_senders = Arc::clone(&self.event_senders);
         let last_push = Arc::clone(&self.last_push);
         let last_error = Arc::clone(&self.last_error);
 
@@ -206,28 +671,55 @@ impl PushManager {
 
                         match (method, &mac) {
                             (Some("syncPilot"), Some(mac_addr)) => {
-                                let subs = subscriptions.lock().await;
-                                if let Some(cb) = subs.get(mac_addr) {
-                                    let cb = Arc::clone(cb);
-                                    let mac_addr = mac_addr.clone();
-                                    let params = msg.get("params").cloned().unwrap_or(Value::Null);
+                                let params = msg.get("params").cloned().unwrap_or(Value::Null);
+                                #[cfg(feature = "metrics")]
+                                crate::metrics::observe(source_ip, &params);
+                                let event = match SyncPilotEvent::parse(source_ip, &params) {
+                                    Ok(event) => event,
+                                    Err(e) => {
+                                        debug!("Unparseable syncPilot params: {}", e);
+                                        continue;
+                                    }
+                                };
+
+                                // Clone out of the map to release the lock before the callbacks
+                                let callbacks = subscriptions.lock().await.matching(mac_addr);
+                                for cb in callbacks {
                                     // Execute callback - we don't spawn here to keep it simple
                                     // and maintain ordering of callbacks
-                                    drop(subs); // Release lock before callback
-                                    cb(&mac_addr, &params);
+                                    cb(&event);
+                                }
+
+                                send_event(&event_senders, PushEvent::State(event.clone())).await;
+
+                                let targets = attachments.lock().await.clone();
+                                if !targets.is_empty() {
+                                    let resp = LightingResponse::push(source_ip, event);
+                                    for target in &targets {
+                                        target.record_push(mac_addr, source_ip, &msg).await;
+                                        target.process_reply(&resp).await;
+                                    }
                                 }
                             }
                             (Some("firstBeat"), Some(mac_addr)) => {
-                                let disc_cb = discovery_callback.lock().await;
-                                if let Some(ref cb) = *disc_cb {
-                                    let cb = Arc::clone(cb);
-                                    let bulb = DiscoveredBulb {
-                                        ip: source_ip,
-                                        mac: mac_addr.clone(),
-                                    };
-                                    drop(disc_cb); // Release lock before callback
-                                    cb(bulb);
+                                let bulb = DiscoveredBulb::new(source_ip, mac_addr.clone());
+                                let cb = discovery_callback.lock().await.clone();
+                                if let Some(cb) = cb {
+                                    cb(bulb.clone());
+                                }
+                                send_event(&event_senders, PushEvent::Discovered(bulb)).await;
+                            }
+                            (Some(method), Some(_)) => {
+                                let params = msg.get("params").cloned().unwrap_or(Value::Null);
+                                let Some(event) = AccessoryEvent::parse(source_ip, method, &params)
+                                else {
+                                    continue;
+                                };
+                                let cb = accessory_callback.lock().await.clone();
+                                if let Some(cb) = cb {
+                                    cb(&event);
                                 }
+                                send_event(&event_senders, PushEvent::Accessory(event)).await;
                             }
                             _ => debug!("Unknown push method: {:?}", method),
                         }
@@ -245,16 +737,65 @@ impl PushManager {
         });
 
         *self.listener_task.lock().await = Some(handle);
+
+        let running = Arc::clone(&self.running);
+        let registered = Arc::clone(&self.registered);
+        let register_msg = Arc::clone(&self.register_msg);
+        let last_error = Arc::clone(&self.last_error);
+        let bind_addr = self.bind_addr;
+        let interval = self.keepalive_interval;
+        let transport = Arc::clone(&self.transport);
+
+        let keepalive = runtime::spawn(async move {
+            let mut last_refresh = Instant::now();
+
+            while running.load(Ordering::SeqCst) {
+                // Sleep in short slices so stop() is not delayed by the interval
+                runtime::sleep(interval.min(Duration::from_millis(500))).await;
+                if last_refresh.elapsed() < interval {
+                    continue;
+                }
+                last_refresh = Instant::now();
+
+                let Some(msg) = register_msg.lock().await.clone() else {
+                    continue;
+                };
+                let bulbs: Vec<Ipv4Addr> = registered.lock().await.iter().copied().collect();
+                for bulb_ip in bulbs {
+                    if let Err(e) = send_registration(&*transport, &msg, bulb_ip, bind_addr).await {
+                        *last_error.lock().await = Some(e.to_string());
+                        error!("Push keep-alive to {} failed: {}", bulb_ip, e);
+                    }
+                }
+            }
+        });
+
+        *self.keepalive_task.lock().await = Some(keepalive);
         Ok(())
     }
 
     /// Stop the push listener.
+    ///
+    /// Every registered bulb is sent a `register: false` message so it stops
+    /// pushing updates to this host.
     pub async fn stop(&self) {
         self.running.store(false, Ordering::SeqCst);
+        // Wait for the tasks to complete (they will exit due to running flag)
         if let Some(h) = self.listener_task.lock().await.take() {
-            // Wait for the task to complete (it will exit due to running flag)
             let _ = h.await;
         }
+        if let Some(h) = self.keepalive_task.lock().await.take() {
+            let _ = h.await;
+        }
+
+        let bulbs: Vec<Ipv4Addr> = self.registered.lock().await.drain().collect();
+        for bulb_ip in bulbs {
+            if let Err(e) = self.send_unregistration(bulb_ip).await {
+                debug!("Failed to unregister from {}: {}", bulb_ip, e);
+            }
+        }
+        // Dropping the senders ends all event streams
+        self.event_senders.lock().await.clear();
     }
 
     /// Get the registration message for registering with bulbs.
@@ -265,34 +806,79 @@ impl PushManager {
     /// Register with a bulb to receive push notifications.
     ///
     /// This sends a registration message to the bulb at the specified IP address.
+    /// The bulb is remembered and re-registered periodically while the manager
+    /// is running, since bulbs drop registrations after a short time.
     pub async fn register_bulb(&self, bulb_ip: Ipv4Addr) -> Result<()> {
         let reg_msg = self
             .registration_message()
             .await
             .ok_or(Error::NoAttribute)?;
 
-        let socket = UdpSocket::bind("0.0.0.0:0")
+        send_registration(&*self.transport, &reg_msg, bulb_ip, self.bind_addr).await?;
+        self.registered.lock().await.insert(bulb_ip);
+        Ok(())
+    }
+
+    /// Ask a bulb to stop sending push notifications to this host.
+    ///
+    /// The bulb is also removed from the keep-alive set.
+    pub async fn unregister_bulb(&self, bulb_ip: Ipv4Addr) -> Result<()> {
+        self.registered.lock().await.remove(&bulb_ip);
+        self.send_unregistration(bulb_ip).await
+    }
+
+    async fn send_unregistration(&self, bulb_ip: Ipv4Addr) -> Result<()> {
+        let mut msg = self
+            .registration_message()
             .await
-            .map_err(|e| Error::socket("bind", e))?;
+            .ok_or(Error::NoAttribute)?;
+        msg["params"]["register"] = Value::Bool(false);
+        send_registration(&*self.transport, &msg, bulb_ip, self.bind_addr).await
+    }
 
-        let msg_bytes = serde_json::to_vec(&reg_msg).map_err(Error::JsonDump)?;
+    /// Get the IP addresses of all bulbs kept registered by the keep-alive loop.
+    pub async fn registered_bulbs(&self) -> Vec<Ipv4Addr> {
+        self.registered.lock().await.iter().copied().collect()
+    }
+}
 
-        // Use runtime-agnostic timeout for the send operation
-        runtime::timeout(
-            Duration::from_secs(2),
-            socket.send_to(&msg_bytes, &format!("{bulb_ip}:{RESPOND_PORT}")),
-        )
+/// Deliver an event to every open stream, forgetting streams that were dropped.
+async fn send_event(senders: &Mutex<Vec<UnboundedSender<PushEvent>>>, event: PushEvent) {
+    senders
+        .lock()
         .await
-        .map_err(|_| {
-            Error::socket(
-                "send_to",
-                std::io::Error::new(std::io::ErrorKind::TimedOut, "send timeout"),
-            )
-        })?
-        .map_err(|e| Error::socket("send_to", e))?;
+        .retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+}
 
-        Ok(())
-    }
+async fn send_registration(
+    transport: &dyn Transport,
+    reg_msg: &Value,
+    bulb_ip: Ipv4Addr,
+    bind_addr: Option<Ipv4Addr>,
+) -> Result<()> {
+    let bind_addr = bind_addr.unwrap_or(Ipv4Addr::UNSPECIFIED);
+    let socket = transport
+        .bind(SocketAddr::from((bind_addr, 0)))
+        .await
+        .map_err(|e| Error::socket("bind", e))?;
+
+    let msg_bytes = serde_json::to_vec(reg_msg).map_err(Error::JsonDump)?;
+
+    // Use runtime-agnostic timeout for the send operation
+    runtime::timeout(
+        Duration::from_secs(2),
+        socket.send_to(&msg_bytes, SocketAddr::from((bulb_ip, RESPOND_PORT))),
+    )
+    .await
+    .map_err(|_| {
+        Error::socket(
+            "send_to",
+            std::io::Error::new(std::io::ErrorKind::TimedOut, "send timeout"),
+        )
+    })?
+    .map_err(|e| Error::socket("send_to", e))?;
+
+    Ok(())
 }
 
 impl Drop for PushManager {
@@ -324,6 +910,8 @@ fn generate_mac() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::history::MessageType;
+    use crate::status::LightStatus;
 
     #[test]
     fn test_push_manager_new() {
@@ -331,15 +919,200 @@ mod tests {
         assert!(!manager.is_running());
     }
 
+    #[test]
+    fn test_keepalive_interval_clamped() {
+        let manager = PushManager::new().with_keepalive_interval(Duration::ZERO);
+        assert_eq!(manager.keepalive_interval, MIN_KEEPALIVE_INTERVAL);
+        let manager = PushManager::new().with_keepalive_interval(Duration::from_secs(5));
+        assert_eq!(manager.keepalive_interval, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_with_interface() {
+        // Named `lo` on Linux and `lo0` on macOS
+        let loopback = if_addrs::get_if_addrs()
+            .unwrap()
+            .into_iter()
+            .find(|iface| iface.ip() == std::net::IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .unwrap();
+        let manager = PushManager::new().with_interface(&loopback.name).unwrap();
+        assert_eq!(manager.bind_addr(), Some(Ipv4Addr::LOCALHOST));
+        assert!(matches!(
+            PushManager::new().with_interface("no-such-iface0"),
+            Err(Error::InterfaceNotFound(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_subscribe_unsubscribe() {
         let manager = PushManager::new();
-        manager.subscribe("AABBCCDDEEFF", |_, _| {}).await;
+        manager.subscribe("AABBCCDDEEFF", |_| {}).await;
         assert_eq!(manager.subscriptions.lock().await.len(), 1);
         manager.unsubscribe("AABBCCDDEEFF").await;
         assert_eq!(manager.subscriptions.lock().await.len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_multiple_subscriptions() {
+        let manager = PushManager::new();
+        let first = manager.subscribe("AABBCCDDEEFF", |_| {}).await;
+        manager.subscribe("aabbccddeeff", |_| {}).await;
+        manager
+            .subscribe_many(&["AABBCCDDEEFF", "112233445566"], |_| {})
+            .await;
+        manager.subscribe_all(|_| {}).await;
+
+        let subs = manager.subscriptions.lock().await;
+        assert_eq!(subs.matching("AABBCCDDEEFF").len(), 4);
+        assert_eq!(subs.matching("112233445566").len(), 2);
+        assert_eq!(subs.matching("000000000000").len(), 1);
+        drop(subs);
+
+        manager.unsubscribe_id(first).await;
+        let subs = manager.subscriptions.lock().await;
+        assert_eq!(subs.matching("AABBCCDDEEFF").len(), 3);
+        assert_eq!(subs.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_events_end_on_stop() {
+        let manager = PushManager::new();
+        let mut events = manager.events_for("aabbccddeeff").await;
+        assert_eq!(manager.event_senders.lock().await.len(), 1);
+        manager.stop().await;
+        assert!(events.next().await.is_none());
+    }
+
+    #[test]
+    fn test_sync_pilot_status() {
+        let params = json!({
+            "mac": "aabbccddeeff",
+            "state": true,
+            "ratio": 30,
+            "fanState": 1,
+            "fanSpeed": 4,
+            "fanRevrs": 1
+        });
+        let event = SyncPilotEvent::parse(Ipv4Addr::new(192, 168, 1, 20), &params).unwrap();
+        let status = LightStatus::from(&event);
+        assert_eq!(status.ratio().map(Ratio::down), Some(70));
+        assert_eq!(status.fan_state(), Some(FanState::On));
+        assert_eq!(status.fan_speed().map(FanSpeed::value), Some(4));
+        assert_eq!(status.fan_direction(), Some(FanDirection::Reverse));
+        assert!(status.fan_mode().is_none());
+    }
+
+    #[test]
+    fn test_partial_sync_pilot_keeps_cache() {
+        let ip = Ipv4Addr::new(192, 168, 1, 20);
+        let light = Light::new(ip, None);
+        let full = json!({"mac": "aabbccddeeff", "state": true, "sceneId": 4, "dimming": 50});
+        let event = SyncPilotEvent::parse(ip, &full).unwrap();
+        light.process_reply(&LightingResponse::push(ip, event));
+
+        // Only the brightness changed; power and scene stay as cached
+        let partial = json!({"mac": "aabbccddeeff", "dimming": 80});
+        let event = SyncPilotEvent::parse(ip, &partial).unwrap();
+        light.process_reply(&LightingResponse::push(ip, event));
+        let status = light.status().unwrap();
+        assert!(status.emitting());
+        assert_eq!(status.scene(), Some(&SceneMode::Party));
+        assert_eq!(status.brightness().map(Brightness::value), Some(80));
+
+        // A color ends the scene
+        let color = json!({"mac": "aabbccddeeff", "r": 255, "g": 0, "b": 0});
+        let event = SyncPilotEvent::parse(ip, &color).unwrap();
+        light.process_reply(&LightingResponse::push(ip, event));
+        assert!(light.status().unwrap().scene().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_push_recorded_in_history() {
+        let mut light = Light::new(Ipv4Addr::new(192, 168, 1, 20), None);
+        light.set_mac(Some("aabbccddeeff"));
+        let light = Arc::new(light);
+        let target = Attachment::Light(Arc::clone(&light));
+        let msg = json!({"method": "syncPilot", "params": {"mac": "aabbccddeeff"}});
+
+        target
+            .record_push("AABBCCDDEEFF", Ipv4Addr::new(192, 168, 1, 99), &msg)
+            .await;
+        target.record_push("112233445566", light.ip(), &msg).await;
+
+        let history = light.history().await;
+        assert_eq!(history.summary().push_count, 1);
+        assert_eq!(history.entries()[0].msg_type, MessageType::Push);
+    }
+
+    #[tokio::test]
+    async fn test_register_bulb_requires_start() {
+        let manager = PushManager::new();
+        assert!(manager.register_bulb(Ipv4Addr::LOCALHOST).await.is_err());
+        assert!(manager.registered_bulbs().await.is_empty());
+    }
+
+    #[test]
+    fn test_parse_sync_pilot() {
+        let params = json!({
+            "mac": "aabbccddeeff",
+            "rssi": -60,
+            "state": true,
+            "sceneId": 0,
+            "r": 255,
+            "g": 0,
+            "b": 0,
+            "dimming": 50,
+            "src": "udp",
+        });
+        let event = SyncPilotEvent::parse(Ipv4Addr::LOCALHOST, &params).unwrap();
+        assert_eq!(event.mac, "AABBCCDDEEFF");
+        assert_eq!(event.state, Some(true));
+        assert_eq!(event.dimming.as_ref().map(|d| d.value()), Some(50));
+        assert_eq!(event.color, Some(Color::rgb(255, 0, 0)));
+        assert!(event.scene.is_none());
+        assert_eq!(event.raw()["src"], "udp");
+        assert!(LightStatus::from(&event).fan().is_none());
+    }
+
+    #[test]
+    fn test_parse_sync_pilot_fan() {
+        let params = json!({
+            "mac": "aabbccddeeff",
+            "state": false,
+            "fanState": 1,
+            "fanMode": 2,
+            "fanSpeed": 4,
+        });
+        let event = SyncPilotEvent::parse(Ipv4Addr::LOCALHOST, &params).unwrap();
+        let status = LightStatus::from(&event);
+        let fan = status.fan().unwrap();
+        assert!(fan.is_on());
+        assert_eq!(fan.mode, Some(FanMode::Breeze));
+        assert_eq!(fan.speed.map(FanSpeed::value), Some(4));
+        assert!(fan.direction.is_none());
+    }
+
+    #[test]
+    fn test_parse_accessory_event() {
+        let ip = Ipv4Addr::LOCALHOST;
+        let button = json!({"mac": "a8bb50000001", "button": "on", "battery": 87});
+        let event = AccessoryEvent::parse(ip, "syncRemote", &button).unwrap();
+        assert_eq!(event.mac, "A8BB50000001");
+        assert_eq!(
+            event.kind,
+            AccessoryEventKind::Button {
+                button: "on".into()
+            }
+        );
+        assert_eq!(event.battery, Some(87));
+
+        let motion = json!({"mac": "a8bb50000002", "pir": 1});
+        let event = AccessoryEvent::parse(ip, "syncSensor", &motion).unwrap();
+        assert_eq!(event.kind, AccessoryEventKind::Motion { detected: true });
+
+        assert!(AccessoryEvent::parse(ip, "syncSensor", &json!({"pir": 1})).is_none());
+    }
+
     #[test]
     fn test_generate_mac() {
         let mac = generate_mac();
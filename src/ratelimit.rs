@@ -0,0 +1,352 @@
+//! Rate limiting for outgoing commands.
+//!
+//! Bulbs start dropping packets when sent more than roughly ten messages per
+//! second. A [`RateLimiter`] spaces out datagrams; attach one to a single
+//! [`Light`](crate::Light) for a per-light limit, or share the same `Arc`
+//! between several lights (e.g. everything on one subnet or access point).
+//!
+//! [`Light::set_coalesced`](crate::Light::set_coalesced) builds on the limiter
+//! for animations: payloads that arrive while a send is waiting for its slot
+//! replace each other, so only the latest one reaches the bulb.
+//!
+//! Independently of any limiter, every light runs its commands one at a
+//! time through a [`CommandQueue`], so the packets and retries of two
+//! concurrent commands never interleave.
+
+use std::collections::VecDeque;
+use std::future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::task::{Poll, Waker};
+use std::time::Duration;
+
+use crate::payload::Payload;
+use crate::runtime::{self, Instant, Mutex};
+
+/// Spaces outgoing messages to at most a fixed number per second.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use wiz_lights_rs::RateLimiter;
+///
+/// let limiter = Arc::new(RateLimiter::new(10.0));
+/// assert_eq!(limiter.interval().as_millis(), 100);
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// A conservative default that bulbs handle reliably.
+    pub const DEFAULT_MESSAGES_PER_SECOND: f64 = 10.0;
+
+    /// Create a limiter allowing `messages_per_second` messages.
+    ///
+    /// Non-positive rates disable limiting.
+    pub fn new(messages_per_second: f64) -> Self {
+        let interval = if messages_per_second > 0.0 {
+            Duration::from_secs_f64(1.0 / messages_per_second)
+        } else {
+            Duration::ZERO
+        };
+        Self {
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Minimum time between two messages.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Wait for a slot and claim it.
+    ///
+    /// Concurrent callers are served one at a time.
+    pub async fn acquire(&self) {
+        let mut last = self.last.lock().await;
+        if let Some(wait) = Self::remaining(&last, self.interval) {
+            runtime::sleep(wait).await;
+        }
+        *last = Some(Instant::now());
+    }
+
+    /// Wait until a slot is available without claiming it.
+    pub async fn wait_ready(&self) {
+        let wait = Self::remaining(&*self.last.lock().await, self.interval);
+        if let Some(wait) = wait {
+            runtime::sleep(wait).await;
+        }
+    }
+
+    fn remaining(last: &Option<Instant>, interval: Duration) -> Option<Duration> {
+        let elapsed = last.as_ref()?.elapsed();
+        (elapsed < interval).then(|| interval - elapsed)
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MESSAGES_PER_SECOND)
+    }
+}
+
+/// Latest-wins slot shared by concurrent coalesced sends to one light.
+///
+/// The first caller becomes the driver and keeps sending until the slot is
+/// empty; everyone else just drops their payload into the slot.
+#[derive(Debug, Default)]
+pub(crate) struct Coalescer {
+    pending: Mutex<Option<Payload>>,
+    driving: AtomicBool,
+}
+
+impl Coalescer {
+    /// Store `payload` as the latest. Returns true if the caller must drive.
+    pub(crate) async fn offer(&self, payload: Payload) -> bool {
+        *self.pending.lock().await = Some(payload);
+        !self.driving.swap(true, Ordering::SeqCst)
+    }
+
+    /// Take the latest payload, releasing the driver role once none is left.
+    pub(crate) async fn next(&self) -> Option<Payload> {
+        loop {
+            if let Some(payload) = self.pending.lock().await.take() {
+                return Some(payload);
+            }
+            self.driving.store(false, Ordering::SeqCst);
+            // A payload may have landed after the check above but before its
+            // sender saw the driver leave; reclaim the role to send it.
+            if self.pending.lock().await.is_none() || self.driving.swap(true, Ordering::SeqCst) {
+                return None;
+            }
+        }
+    }
+
+    /// Give up the driver role without sending, e.g. after an error.
+    pub(crate) fn release(&self) {
+        self.driving.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Runs the commands sent to one light one at a time, in the order they
+/// were issued.
+///
+/// At most `depth` commands are running or waiting; more are refused.
+///
+/// Commands wait on an explicit ticket list rather than the runtime's mutex,
+/// since only tokio's mutex hands out the lock first come, first served.
+#[derive(Debug)]
+pub(crate) struct CommandQueue {
+    turns: std::sync::Mutex<Turns>,
+    queued: AtomicUsize,
+    depth: AtomicUsize,
+}
+
+/// Who runs now and who waits, in arrival order.
+#[derive(Debug, Default)]
+struct Turns {
+    busy: bool,
+    next_ticket: u64,
+    waiting: VecDeque<(u64, Option<Waker>)>,
+}
+
+impl Turns {
+    /// Wakes the first waiter if nothing is running.
+    fn wake_next(&self) {
+        if !self.busy {
+            if let Some((_, Some(waker))) = self.waiting.front() {
+                waker.wake_by_ref();
+            }
+        }
+    }
+}
+
+impl CommandQueue {
+    pub(crate) fn new(depth: usize) -> Self {
+        CommandQueue {
+            turns: std::sync::Mutex::default(),
+            queued: AtomicUsize::new(0),
+            depth: AtomicUsize::new(depth.max(1)),
+        }
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.depth.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_depth(&self, depth: usize) {
+        self.depth.store(depth.max(1), Ordering::SeqCst);
+    }
+
+    /// Runs `command` once the commands queued before it finished, or
+    /// returns `None` without running it if the queue is full.
+    pub(crate) async fn run<T>(&self, command: impl Future<Output = T>) -> Option<T> {
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.depth() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+
+        // Leave the queue even if the caller drops the command midway
+        struct Leave<'a>(&'a AtomicUsize);
+        impl Drop for Leave<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+        let _leave = Leave(&self.queued);
+
+        let ticket = {
+            let mut turns = self.turns();
+            let ticket = turns.next_ticket;
+            turns.next_ticket += 1;
+            turns.waiting.push_back((ticket, None));
+            ticket
+        };
+
+        // Gives up the ticket, or the turn once started, even if dropped midway
+        struct Turn<'a> {
+            queue: &'a CommandQueue,
+            ticket: u64,
+            started: bool,
+        }
+        impl Drop for Turn<'_> {
+            fn drop(&mut self) {
+                let mut turns = self.queue.turns();
+                if self.started {
+                    turns.busy = false;
+                } else {
+                    turns.waiting.retain(|(ticket, _)| *ticket != self.ticket);
+                }
+                turns.wake_next();
+            }
+        }
+        let mut turn = Turn {
+            queue: self,
+            ticket,
+            started: false,
+        };
+
+        future::poll_fn(|cx| {
+            let mut turns = self.turns();
+            if !turns.busy && turns.waiting.front().is_some_and(|(t, _)| *t == ticket) {
+                turns.waiting.pop_front();
+                turns.busy = true;
+                return Poll::Ready(());
+            }
+            if let Some((_, waker)) = turns.waiting.iter_mut().find(|(t, _)| *t == ticket) {
+                *waker = Some(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await;
+        turn.started = true;
+        Some(command.await)
+    }
+
+    fn turns(&self) -> std::sync::MutexGuard<'_, Turns> {
+        self.turns.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spaces_messages() {
+        let limiter = RateLimiter::new(20.0);
+        let start = std::time::Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_zero_rate_is_unlimited() {
+        let limiter = RateLimiter::new(0.0);
+        assert_eq!(limiter.interval(), Duration::ZERO);
+        let start = std::time::Instant::now();
+        for _ in 0..100 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_command_queue() {
+        let queue = CommandQueue::new(2);
+        let order = std::sync::Mutex::new(Vec::new());
+        let command = |n: u32| {
+            let order = &order;
+            async move {
+                runtime::sleep(Duration::from_millis(u64::from(30 - n * 10))).await;
+                order.lock().unwrap().push(n);
+                n
+            }
+        };
+
+        // The second command waits for the first; the third does not fit
+        let (first, second, third) = futures::join!(
+            queue.run(command(1)),
+            queue.run(command(2)),
+            queue.run(command(3))
+        );
+        assert_eq!((first, second, third), (Some(1), Some(2), None));
+        assert_eq!(*order.lock().unwrap(), [1, 2]);
+        assert_eq!(queue.run(command(1)).await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_command_queue_runs_in_order() {
+        let queue = CommandQueue::new(16);
+        let order = std::sync::Mutex::new(Vec::new());
+        let command = |n: u64| {
+            let order = &order;
+            async move {
+                // Later commands finish their work sooner if run concurrently
+                runtime::sleep(Duration::from_millis(20 - n)).await;
+                order.lock().unwrap().push(n);
+            }
+        };
+
+        let running = queue.run(command(0));
+        futures::pin_mut!(running);
+        assert!(futures::poll!(running.as_mut()).is_pending());
+        // A waiter that gives up does not hold up the ones behind it
+        let mut abandoned = Box::pin(queue.run(command(10)));
+        assert!(futures::poll!(abandoned.as_mut()).is_pending());
+        let waiting = futures::future::join_all((1..10).map(|n| queue.run(command(n))));
+        drop(abandoned);
+
+        futures::join!(running, waiting);
+        assert_eq!(*order.lock().unwrap(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_coalescer_keeps_latest() {
+        use crate::types::Brightness;
+
+        let coalescer = Coalescer::default();
+        let mut first = Payload::new();
+        first.brightness(&Brightness::create(10).unwrap());
+        let mut second = Payload::new();
+        second.brightness(&Brightness::create(90).unwrap());
+
+        assert!(coalescer.offer(first).await);
+        assert!(!coalescer.offer(second.clone()).await);
+
+        let sent = coalescer.next().await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&sent).unwrap(),
+            serde_json::to_value(&second).unwrap()
+        );
+        assert!(coalescer.next().await.is_none());
+        // The driver role is free again
+        assert!(coalescer.offer(Payload::new()).await);
+    }
+}
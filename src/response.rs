@@ -2,7 +2,9 @@
 
 use std::net::Ipv4Addr;
 
+use crate::errors::Error;
 use crate::payload::Payload;
+use crate::push::SyncPilotEvent;
 use crate::status::LightStatus;
 use crate::types::PowerMode;
 
@@ -14,6 +16,17 @@ use crate::types::PowerMode;
 pub struct LightingResponse {
     pub(crate) ip: Ipv4Addr,
     pub(crate) response: LightingResponseType,
+    pub(crate) adjustments: Vec<Adjustment>,
+}
+
+/// A value changed before sending because the bulb could not show it as
+/// requested, e.g. by [`Light::set_checked`](crate::Light::set_checked).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adjustment {
+    /// Payload field that was changed, e.g. `temp`.
+    pub field: &'static str,
+    pub requested: u16,
+    pub applied: u16,
 }
 
 impl LightingResponse {
@@ -22,6 +35,7 @@ impl LightingResponse {
         LightingResponse {
             ip,
             response: LightingResponseType::Payload(payload),
+            adjustments: Vec::new(),
         }
     }
 
@@ -30,6 +44,7 @@ impl LightingResponse {
         LightingResponse {
             ip,
             response: LightingResponseType::Power(power),
+            adjustments: Vec::new(),
         }
     }
 
@@ -38,10 +53,126 @@ impl LightingResponse {
         LightingResponse {
             ip,
             response: LightingResponseType::Status(status),
+            adjustments: Vec::new(),
+        }
+    }
+
+    /// A push from the bulb, which may report only some fields.
+    pub(crate) fn push(ip: Ipv4Addr, event: SyncPilotEvent) -> Self {
+        LightingResponse {
+            ip,
+            response: LightingResponseType::Push(Box::new(event)),
+            adjustments: Vec::new(),
+        }
+    }
+
+    /// Values changed before sending, empty if the command went out as
+    /// requested.
+    pub fn adjustments(&self) -> &[Adjustment] {
+        &self.adjustments
+    }
+
+    pub(crate) fn with_adjustments(mut self, adjustments: Vec<Adjustment>) -> Self {
+        self.adjustments = adjustments;
+        self
+    }
+}
+
+/// Per-light outcomes of a batch operation, such as
+/// [`Room::set`](crate::Room::set) or [`broadcast_set`](crate::broadcast_set),
+/// in the order the lights were addressed.
+///
+/// A light that fails does not stop the others, so callers can retry only
+/// the lights in [`failures`](BatchResults::failures). `K` identifies a
+/// light: its id in a room, or its IP.
+///
+/// # Example
+///
+/// ```ignore
+/// let results = room.set(&payload).await;
+/// if !results.all_ok() {
+///     for (id, err) in results.failures() {
+///         println!("{id} failed: {err}");
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BatchResults<K> {
+    results: Vec<(K, Result<LightingResponse, Error>)>,
+}
+
+impl<K> BatchResults<K> {
+    /// Whether every light succeeded. True for an empty batch.
+    pub fn all_ok(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// The lights that succeeded, with their responses.
+    pub fn successes(&self) -> impl Iterator<Item = (&K, &LightingResponse)> {
+        self.results
+            .iter()
+            .filter_map(|(id, result)| Some((id, result.as_ref().ok()?)))
+    }
+
+    /// The lights that failed, with their errors.
+    pub fn failures(&self) -> impl Iterator<Item = (&K, &Error)> {
+        self.results
+            .iter()
+            .filter_map(|(id, result)| Some((id, result.as_ref().err()?)))
+    }
+
+    /// Number of lights in the batch.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Whether the batch has no lights.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Every light with its result, in batch order.
+    pub fn iter(&self) -> std::slice::Iter<'_, (K, Result<LightingResponse, Error>)> {
+        self.results.iter()
+    }
+
+    /// The results as a vector of key and result pairs, in batch order.
+    pub fn into_inner(self) -> Vec<(K, Result<LightingResponse, Error>)> {
+        self.results
+    }
+
+    /// Every response, or the first error in batch order.
+    pub fn into_result(self) -> Result<Vec<LightingResponse>, Error> {
+        self.results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+impl<K> FromIterator<(K, Result<LightingResponse, Error>)> for BatchResults<K> {
+    fn from_iter<I: IntoIterator<Item = (K, Result<LightingResponse, Error>)>>(iter: I) -> Self {
+        BatchResults {
+            results: iter.into_iter().collect(),
         }
     }
 }
 
+impl<K> IntoIterator for BatchResults<K> {
+    type Item = (K, Result<LightingResponse, Error>);
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+impl<'a, K> IntoIterator for &'a BatchResults<K> {
+    type Item = &'a (K, Result<LightingResponse, Error>);
+    type IntoIter = std::slice::Iter<'a, (K, Result<LightingResponse, Error>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.iter()
+    }
+}
+
 /// The type of lighting response.
 #[derive(Debug)]
 pub(crate) enum LightingResponseType {
@@ -51,4 +182,6 @@ pub(crate) enum LightingResponseType {
     Power(PowerMode),
     /// Response from a status query
     Status(LightStatus),
+    /// State pushed by the bulb
+    Push(Box<SyncPilotEvent>),
 }
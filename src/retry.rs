@@ -0,0 +1,211 @@
+//! Retry policies for commands whose reply was lost.
+//!
+//! UDP gives no delivery guarantee, so a [`Light`](crate::Light) resends a
+//! command that got no reply. A [`RetryPolicy`] decides how often and how
+//! long to wait in between. [`FixedRetry`] is the default; [`ExponentialRetry`]
+//! spreads retries out with random jitter, which helps when many lights
+//! retry at once, and [`NoRetry`] suits callers with their own retry logic.
+//!
+//! A policy can be set for one light with
+//! [`Light::set_retry_policy`](crate::Light::set_retry_policy) or for every
+//! light with [`set_default_policy`]. Each retry is logged at debug level
+//! and passed to the hook set with [`set_retry_hook`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//! use wiz_lights_rs::retry::{self, ExponentialRetry, NoRetry};
+//!
+//! retry::set_default_policy(Arc::new(ExponentialRetry::default()));
+//! light.set_retry_policy(Some(Arc::new(NoRetry)));
+//!
+//! retry::set_retry_hook(Some(Arc::new(|attempt: &retry::RetryAttempt| {
+//!     eprintln!("retrying {} on {}: {}", attempt.method, attempt.ip, attempt.error);
+//! })));
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher};
+use std::net::Ipv4Addr;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+
+use log::debug;
+
+/// Decides whether and when a command without a reply is sent again.
+pub trait RetryPolicy: Debug + Send + Sync {
+    /// The wait before retry number `retry`, counting from 1, or `None` to
+    /// give up.
+    ///
+    /// Must eventually return `None`, or a bulb that is gone is retried
+    /// forever.
+    fn delay(&self, retry: u32) -> Option<Duration>;
+}
+
+/// Retries after a fixed list of delays, one per retry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedRetry {
+    delays: Vec<Duration>,
+}
+
+impl FixedRetry {
+    pub fn new(delays: impl IntoIterator<Item = Duration>) -> Self {
+        FixedRetry {
+            delays: delays.into_iter().collect(),
+        }
+    }
+}
+
+impl Default for FixedRetry {
+    /// Three retries after 750 ms, 1.5 s and 3 s.
+    fn default() -> Self {
+        Self::new([750, 1500, 3000].map(Duration::from_millis))
+    }
+}
+
+impl RetryPolicy for FixedRetry {
+    fn delay(&self, retry: u32) -> Option<Duration> {
+        self.delays.get(retry.checked_sub(1)? as usize).copied()
+    }
+}
+
+/// Doubles the delay on every retry, up to a cap, plus random jitter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialRetry {
+    /// Delay before the first retry.
+    pub initial: Duration,
+    /// Longest delay before jitter is added.
+    pub max_delay: Duration,
+    pub max_retries: u32,
+    /// Fraction of the delay added at random, e.g. 0.2 for up to 20%.
+    pub jitter: f64,
+}
+
+impl Default for ExponentialRetry {
+    fn default() -> Self {
+        ExponentialRetry {
+            initial: Duration::from_millis(500),
+            max_delay: Duration::from_secs(5),
+            max_retries: 3,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl ExponentialRetry {
+    /// The delay before retry `retry` without jitter.
+    fn base_delay(&self, retry: u32) -> Duration {
+        self.initial
+            .saturating_mul(1 << retry.saturating_sub(1).min(16))
+            .min(self.max_delay)
+    }
+}
+
+impl RetryPolicy for ExponentialRetry {
+    fn delay(&self, retry: u32) -> Option<Duration> {
+        if retry == 0 || retry > self.max_retries {
+            return None;
+        }
+        let base = self.base_delay(retry);
+        Some(base + base.mul_f64(self.jitter.max(0.0) * random_fraction()))
+    }
+}
+
+/// Never retries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoRetry;
+
+impl RetryPolicy for NoRetry {
+    fn delay(&self, _retry: u32) -> Option<Duration> {
+        None
+    }
+}
+
+/// A retry about to be made, passed to the hook set with [`set_retry_hook`].
+#[derive(Debug, Clone)]
+pub struct RetryAttempt {
+    pub ip: Ipv4Addr,
+    pub method: String,
+    /// Number of this retry, counting from 1.
+    pub retry: u32,
+    /// How long until the command is sent again.
+    pub delay: Duration,
+    /// Why the previous attempt failed.
+    pub error: String,
+}
+
+pub type RetryHook = Arc<dyn Fn(&RetryAttempt) + Send + Sync>;
+
+fn default_slot() -> &'static RwLock<Arc<dyn RetryPolicy>> {
+    static POLICY: OnceLock<RwLock<Arc<dyn RetryPolicy>>> = OnceLock::new();
+    POLICY.get_or_init(|| RwLock::new(Arc::new(FixedRetry::default())))
+}
+
+fn hook_slot() -> &'static RwLock<Option<RetryHook>> {
+    static HOOK: OnceLock<RwLock<Option<RetryHook>>> = OnceLock::new();
+    HOOK.get_or_init(RwLock::default)
+}
+
+/// Use `policy` for every light that has no policy of its own.
+pub fn set_default_policy(policy: Arc<dyn RetryPolicy>) {
+    *default_slot().write().unwrap_or_else(|e| e.into_inner()) = policy;
+}
+
+/// The policy used by lights that have no policy of their own.
+pub fn default_policy() -> Arc<dyn RetryPolicy> {
+    Arc::clone(&default_slot().read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Call `hook` before every retry of every light; `None` removes it.
+pub fn set_retry_hook(hook: Option<RetryHook>) {
+    *hook_slot().write().unwrap_or_else(|e| e.into_inner()) = hook;
+}
+
+pub(crate) fn notify(attempt: &RetryAttempt) {
+    debug!(
+        "Retrying {} on {} in {:?} (retry {}): {}",
+        attempt.method, attempt.ip, attempt.delay, attempt.retry, attempt.error
+    );
+    let hook = hook_slot()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if let Some(hook) = hook {
+        hook(attempt);
+    }
+}
+
+/// A random number in `0.0..=1.0`.
+pub(crate) fn random_fraction() -> f64 {
+    // RandomState is seeded randomly, which is all the randomness needed here
+    let random = RandomState::new().build_hasher().finish();
+    random as f64 / u64::MAX as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policies() {
+        let fixed = FixedRetry::default();
+        assert_eq!(fixed.delay(1), Some(Duration::from_millis(750)));
+        assert_eq!(fixed.delay(3), Some(Duration::from_secs(3)));
+        assert_eq!(fixed.delay(4), None);
+
+        let exponential = ExponentialRetry {
+            max_retries: 5,
+            ..Default::default()
+        };
+        assert_eq!(exponential.base_delay(1), Duration::from_millis(500));
+        assert_eq!(exponential.base_delay(3), Duration::from_secs(2));
+        assert_eq!(exponential.base_delay(5), exponential.max_delay);
+        let delay = exponential.delay(2).unwrap();
+        assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_millis(1200));
+        assert_eq!(exponential.delay(6), None);
+
+        assert_eq!(NoRetry.delay(1), None);
+    }
+}
@@ -1,23 +1,109 @@
 //! Room grouping for batch operations.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, hash_map};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
 
 use futures::future;
+use futures::stream::{self, BoxStream, StreamExt};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::broadcast::{BroadcastOptions, broadcast_keyed};
+use crate::diagnostics::{DiagnosticsSummary, RoomDiagnostics};
 use crate::errors::Error;
-use crate::light::Light;
-use crate::response::LightingResponse;
+use crate::light::{ChangeSubscription, Light, StatusChange};
+use crate::payload::Payload;
+use crate::polling::StatusPoller;
+use crate::ratelimit::RateLimiter;
+use crate::response::{BatchResults, LightingResponse};
+use crate::runtime::{self, JoinHandle};
+use crate::types::{Color, PowerMode};
 
 type Result<T> = std::result::Result<T, Error>;
 
+/// A named state for several lights in a room, e.g. "Movie night".
+///
+/// # Example
+///
+/// ```
+/// use uuid::Uuid;
+/// use wiz_lights_rs::{Brightness, Color, Payload, RoomScene};
+///
+/// let (lamp, ceiling) = (Uuid::new_v4(), Uuid::new_v4());
+/// let mut lamp_state = Payload::from(&Color::rgb(255, 0, 0));
+/// lamp_state.brightness(&Brightness::create(20).unwrap());
+///
+/// let mut scene = RoomScene::new("Movie night");
+/// scene.set(&lamp, lamp_state).off(&ceiling);
+/// assert_eq!(scene.lights().count(), 2);
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoomScene {
+    name: String,
+    lights: HashMap<Uuid, Payload>,
+}
+
+impl RoomScene {
+    pub fn new(name: &str) -> Self {
+        RoomScene {
+            name: name.to_string(),
+            lights: HashMap::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Turn the light on with `payload` when the scene is applied.
+    pub fn set(&mut self, light_id: &Uuid, payload: Payload) -> &mut Self {
+        self.lights.insert(*light_id, payload);
+        self
+    }
+
+    /// Turn the light off when the scene is applied.
+    pub fn off(&mut self, light_id: &Uuid) -> &mut Self {
+        let payload = Payload {
+            state: Some(false),
+            ..Default::default()
+        };
+        self.lights.insert(*light_id, payload);
+        self
+    }
+
+    pub fn lights(&self) -> impl Iterator<Item = (&Uuid, &Payload)> {
+        self.lights.iter()
+    }
+}
+
 /// A grouping of lights for batch operations.
+///
+/// Iterating over a room yields each light with its id:
+///
+/// ```
+/// use std::net::Ipv4Addr;
+/// use wiz_lights_rs::{Light, Room};
+///
+/// let mut room = Room::new("Office");
+/// room.new_light(Light::new(Ipv4Addr::new(192, 168, 1, 20), Some("Desk")))?;
+/// room.new_light(Light::new(Ipv4Addr::new(192, 168, 1, 21), None))?;
+///
+/// assert_eq!(room.len(), 2);
+/// for (id, light) in &room {
+///     println!("{id}: {}", light.ip());
+/// }
+/// let (_, desk) = room.find_by_name("Desk").unwrap();
+/// assert_eq!(desk.ip(), Ipv4Addr::new(192, 168, 1, 20));
+/// # Ok::<(), wiz_lights_rs::Error>(())
+/// ```
 #[serde_with::skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Room {
     name: String,
     lights: Option<HashMap<Uuid, Light>>,
+    scenes: Option<HashMap<String, RoomScene>>,
     #[serde(skip)]
     id: Uuid,
     #[serde(skip)]
@@ -29,47 +115,145 @@ impl Room {
         Room {
             name: String::from(name),
             lights: None,
+            scenes: None,
             id: Uuid::new_v4(),
             linked: false,
         }
     }
 
-    pub fn link(&mut self, id: &Uuid) {
-        assert!(!self.linked, "refusing to overwrite id!");
+    /// Gives the room a stable id, e.g. the key it is stored under.
+    ///
+    /// Fails with [`Error::AlreadyLinked`] if the room was linked before.
+    ///
+    /// ```
+    /// use uuid::Uuid;
+    /// use wiz_lights_rs::{Error, Room};
+    ///
+    /// let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+    /// let mut room = Room::new("Office");
+    /// room.link(&first)?;
+    /// assert!(matches!(room.link(&second), Err(Error::AlreadyLinked(id)) if id == first));
+    ///
+    /// room.relink(&second, true)?;
+    /// assert_eq!(room.id(), second);
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn link(&mut self, id: &Uuid) -> Result<()> {
+        self.relink(id, false)
+    }
+
+    /// Like [`Room::link`], but replaces an existing link if `overwrite` is set.
+    pub fn relink(&mut self, id: &Uuid, overwrite: bool) -> Result<()> {
+        if self.linked && !overwrite {
+            return Err(Error::AlreadyLinked(self.id));
+        }
         self.id = *id;
         self.linked = true;
+        Ok(())
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
 
-    pub async fn get_status(&self) -> Result<Vec<LightingResponse>> {
-        let Some(lights) = &self.lights else {
-            return Ok(Vec::new());
-        };
+    /// Applies a payload to every light concurrently.
+    ///
+    /// A light that fails does not stop the others; see [`BatchResults`].
+    pub async fn set(&self, payload: &Payload) -> BatchResults<Uuid> {
+        self.each(|light| light.set(payload)).await
+    }
 
-        // Create futures for concurrent execution
-        let futures: Vec<_> = lights
-            .values()
-            .map(|light| async move {
-                let ip = light.ip();
-                light
-                    .get_status()
-                    .await
-                    .map(|status| LightingResponse::status(ip, status))
-            })
-            .collect();
+    pub async fn set_power(&self, power: &PowerMode) -> BatchResults<Uuid> {
+        self.each(|light| light.set_power(power)).await
+    }
+
+    pub async fn get_status(&self) -> BatchResults<Uuid> {
+        self.each(|light| async move {
+            let status = light.get_status().await?;
+            Ok(LightingResponse::status(light.ip(), status))
+        })
+        .await
+    }
 
-        // Execute all queries concurrently using join_all
-        let results = future::join_all(futures).await;
+    /// Returns [`Light::diagnostics`] for every light, queried concurrently,
+    /// with totals for the room.
+    pub async fn diagnostics(&self) -> RoomDiagnostics {
+        let lights: Vec<_> = self.lights.iter().flatten().collect();
+        let reports = future::join_all(lights.iter().map(|(_, light)| light.diagnostics())).await;
+        let lights: BTreeMap<_, _> = lights.iter().map(|(id, _)| **id).zip(reports).collect();
 
-        // Collect successful responses and return first error if any
-        let mut responses = Vec::new();
-        for result in results {
-            responses.push(result?);
+        RoomDiagnostics {
+            id: self.id,
+            name: self.name.clone(),
+            summary: DiagnosticsSummary::from_reports(lights.values()),
+            lights,
         }
-        Ok(responses)
+    }
+
+    /// Sends `payload` to every light at once, like [`broadcast_set`](crate::broadcast_set).
+    ///
+    /// Much faster than setting the lights one by one in large rooms, but
+    /// without retries, rate limiting or calibration, and outside each
+    /// light's command queue. The cached status of every light that answered
+    /// is updated.
+    pub async fn set_fast(&self, payload: &Payload) -> Result<BatchResults<Uuid>> {
+        let lights: Vec<_> = self.lights.iter().flatten().collect();
+        let targets = lights
+            .iter()
+            .map(|(id, light)| (**id, light.ip()))
+            .collect();
+        let results = broadcast_keyed(targets, payload, &BroadcastOptions::default()).await?;
+
+        for ((_, result), (_, light)) in results.iter().zip(&lights) {
+            if let Ok(response) = result {
+                light.process_reply(response);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Raises or lowers the brightness of every light by `delta` percentage points.
+    ///
+    /// See [`Light::adjust_brightness`]. Lights are adjusted concurrently,
+    /// each from its own current level.
+    pub async fn adjust_brightness(&self, delta: i8) -> BatchResults<Uuid> {
+        self.each(|light| light.adjust_brightness(delta)).await
+    }
+
+    /// Blinks every light in `color` and restores each one's prior state.
+    ///
+    /// See [`Light::flash`]. All lights blink concurrently.
+    pub async fn flash(&self, color: &Color, times: u32, interval: Duration) -> BatchResults<Uuid> {
+        self.each(|light| light.flash(color, times, interval)).await
+    }
+
+    /// Turns every light off after `duration`.
+    ///
+    /// See [`Light::turn_off_after`]: each light's timer replaces the one it
+    /// had. The handle resolves once every light was turned off, or to the
+    /// first error, which is [`Error::Cancelled`] if a timer was replaced or
+    /// cancelled.
+    pub fn turn_off_after(&self, duration: Duration) -> JoinHandle<Result<Vec<LightingResponse>>> {
+        let timers: Vec<_> = self
+            .members()
+            .map(|light| light.turn_off_after(duration))
+            .collect();
+        runtime::spawn(async move { future::join_all(timers).await.into_iter().collect() })
+    }
+
+    /// Cancels the timers set with [`Room::turn_off_after`] or on the
+    /// lights themselves.
+    ///
+    /// Returns whether any timer was pending.
+    pub fn cancel_turn_off(&self) -> bool {
+        self.members()
+            .filter(|light| light.cancel_turn_off())
+            .count()
+            > 0
     }
 
     pub fn new_light(&mut self, light: Light) -> Result<Uuid> {
@@ -114,6 +298,63 @@ impl Room {
         }
     }
 
+    /// Shares one rate limiter between every light currently in the room.
+    pub fn set_rate_limiter(&mut self, limiter: Option<Arc<RateLimiter>>) {
+        for light in self
+            .lights
+            .iter_mut()
+            .flat_map(|lights| lights.values_mut())
+        {
+            light.set_rate_limiter(limiter.clone());
+        }
+    }
+
+    /// Stores a scene, replacing any existing scene with the same name.
+    pub fn add_scene(&mut self, scene: RoomScene) {
+        self.scenes
+            .get_or_insert_with(HashMap::new)
+            .insert(scene.name.clone(), scene);
+    }
+
+    pub fn remove_scene(&mut self, name: &str) -> Option<RoomScene> {
+        self.scenes.as_mut().and_then(|scenes| scenes.remove(name))
+    }
+
+    pub fn scene(&self, name: &str) -> Option<&RoomScene> {
+        self.scenes.as_ref().and_then(|scenes| scenes.get(name))
+    }
+
+    pub fn scenes(&self) -> impl Iterator<Item = &RoomScene> {
+        self.scenes.iter().flat_map(|scenes| scenes.values())
+    }
+
+    /// Applies a stored scene to its lights concurrently.
+    ///
+    /// Lights in the room that the scene does not mention are left alone.
+    pub async fn apply_scene(&self, name: &str) -> Result<BatchResults<Uuid>> {
+        let scene = self
+            .scene(name)
+            .ok_or_else(|| Error::SceneNotFound(name.to_string()))?;
+
+        let mut targets = Vec::new();
+        for (id, payload) in scene.lights() {
+            let light = self
+                .read(id)
+                .ok_or_else(|| Error::light_not_found(&self.id, id))?;
+            targets.push((*id, light, payload));
+        }
+
+        let results = future::join_all(targets.iter().map(|(_, light, payload)| async move {
+            if payload.state == Some(false) {
+                light.set_power(&PowerMode::Off).await
+            } else {
+                light.turn_on_with(payload).await
+            }
+        }))
+        .await;
+        Ok(targets.iter().map(|(id, _, _)| *id).zip(results).collect())
+    }
+
     pub fn list(&self) -> Option<Vec<&Uuid>> {
         self.lights.as_ref().map(|lights| lights.keys().collect())
     }
@@ -128,12 +369,76 @@ impl Room {
             .and_then(|lights| lights.get_mut(light_id))
     }
 
-    pub fn process_reply(&mut self, resp: &LightingResponse) -> bool {
-        let Some(lights) = &mut self.lights else {
-            return false;
-        };
+    /// Iterates over the lights with their ids, in no particular order.
+    pub fn iter(&self) -> RoomIter<'_> {
+        RoomIter(self.lights.as_ref().map(HashMap::iter))
+    }
+
+    pub fn iter_mut(&mut self) -> RoomIterMut<'_> {
+        RoomIterMut(self.lights.as_mut().map(HashMap::iter_mut))
+    }
+
+    /// Number of lights in the room.
+    pub fn len(&self) -> usize {
+        self.lights.as_ref().map_or(0, HashMap::len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-        lights.values_mut().any(|light| light.process_reply(resp))
+    pub fn find_by_ip(&self, ip: Ipv4Addr) -> Option<(Uuid, &Light)> {
+        self.iter().find(|(_, light)| light.ip() == ip)
+    }
+
+    /// Finds the first light with the given name.
+    pub fn find_by_name(&self, name: &str) -> Option<(Uuid, &Light)> {
+        self.iter().find(|(_, light)| light.name() == Some(name))
+    }
+
+    pub fn process_reply(&self, resp: &LightingResponse) -> bool {
+        self.members().any(|light| light.process_reply(resp))
+    }
+
+    /// Calls `callback` with the light's id whenever a member's cached
+    /// status changes. Lights added to the room afterwards are not covered.
+    pub fn on_change<F: Fn(&Uuid, &StatusChange) + Send + Sync + 'static>(
+        &self,
+        callback: F,
+    ) -> ChangeSubscription {
+        let id = ChangeSubscription::next();
+        let callback = Arc::new(callback);
+        for (light_id, light) in self.lights.iter().flatten() {
+            let light_id = *light_id;
+            let callback = Arc::clone(&callback);
+            light.add_change_listener(id, Arc::new(move |change| callback(&light_id, change)));
+        }
+        id
+    }
+
+    /// Removes a callback added with [`Room::on_change`] from every member.
+    pub fn remove_change_listener(&self, id: ChangeSubscription) -> bool {
+        // Not `any`: every member must drop the listener
+        self.members()
+            .filter(|light| light.remove_change_listener(id))
+            .count()
+            > 0
+    }
+
+    /// Status changes of the current members, tagged with the light's id.
+    pub fn changes(&self) -> BoxStream<'static, (Uuid, StatusChange)> {
+        let streams = self.lights.iter().flatten().map(|(light_id, light)| {
+            let light_id = *light_id;
+            light.changes().map(move |change| (light_id, change))
+        });
+        stream::select_all(streams).boxed()
+    }
+
+    /// Polls every current member about every `interval` in the background.
+    ///
+    /// See [`Light::start_polling`]. Lights added afterwards are not polled.
+    pub fn start_polling(&self, interval: Duration) -> StatusPoller {
+        StatusPoller::start(self.members().cloned().collect(), interval)
     }
 
     pub fn update(&mut self, other: &Self) -> bool {
@@ -144,6 +449,27 @@ impl Room {
         true
     }
 
+    /// Runs `command` on every light concurrently, keyed by light id.
+    async fn each<'a, F, Fut>(&'a self, command: F) -> BatchResults<Uuid>
+    where
+        F: Fn(&'a Light) -> Fut,
+        Fut: Future<Output = Result<LightingResponse>>,
+    {
+        let lights: Vec<_> = self.lights.iter().flatten().collect();
+        let results = future::join_all(lights.iter().map(|(_, light)| command(light))).await;
+        lights.iter().map(|(id, _)| **id).zip(results).collect()
+    }
+
+    pub(crate) fn members(&self) -> impl Iterator<Item = &Light> {
+        self.lights.iter().flat_map(|lights| lights.values())
+    }
+
+    pub(crate) fn members_mut(&mut self) -> impl Iterator<Item = &mut Light> {
+        self.lights
+            .iter_mut()
+            .flat_map(|lights| lights.values_mut())
+    }
+
     fn validate_light(&self, light: &Light, exclude_id: Option<&Uuid>) -> Result<()> {
         let Some(lights) = &self.lights else {
             return Ok(());
@@ -161,3 +487,85 @@ impl Room {
         Ok(())
     }
 }
+
+/// Iterator over the lights of a [`Room`], returned by [`Room::iter`].
+#[derive(Debug, Clone)]
+pub struct RoomIter<'a>(Option<hash_map::Iter<'a, Uuid, Light>>);
+
+impl<'a> Iterator for RoomIter<'a> {
+    type Item = (Uuid, &'a Light);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.as_mut()?.next().map(|(id, light)| (*id, light))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.as_ref().map_or((0, Some(0)), Iterator::size_hint)
+    }
+}
+
+impl ExactSizeIterator for RoomIter<'_> {}
+
+/// Mutable iterator over the lights of a [`Room`], returned by
+/// [`Room::iter_mut`].
+#[derive(Debug)]
+pub struct RoomIterMut<'a>(Option<hash_map::IterMut<'a, Uuid, Light>>);
+
+impl<'a> Iterator for RoomIterMut<'a> {
+    type Item = (Uuid, &'a mut Light);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.as_mut()?.next().map(|(id, light)| (*id, light))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.as_ref().map_or((0, Some(0)), Iterator::size_hint)
+    }
+}
+
+impl ExactSizeIterator for RoomIterMut<'_> {}
+
+/// Owning iterator over the lights of a [`Room`].
+#[derive(Debug)]
+pub struct RoomIntoIter(Option<hash_map::IntoIter<Uuid, Light>>);
+
+impl Iterator for RoomIntoIter {
+    type Item = (Uuid, Light);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.as_mut()?.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.as_ref().map_or((0, Some(0)), Iterator::size_hint)
+    }
+}
+
+impl ExactSizeIterator for RoomIntoIter {}
+
+impl<'a> IntoIterator for &'a Room {
+    type Item = (Uuid, &'a Light);
+    type IntoIter = RoomIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Room {
+    type Item = (Uuid, &'a mut Light);
+    type IntoIter = RoomIterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for Room {
+    type Item = (Uuid, Light);
+    type IntoIter = RoomIntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RoomIntoIter(self.lights.map(HashMap::into_iter))
+    }
+}
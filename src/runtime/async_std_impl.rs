@@ -5,9 +5,13 @@ use std::io;
 use std::net::SocketAddr;
 use std::time::Duration;
 
-use async_std::net::UdpSocket as AsyncStdUdpSocket;
+use async_std::net::{
+    TcpListener as AsyncStdTcpListener, TcpStream as AsyncStdTcpStream,
+    UdpSocket as AsyncStdUdpSocket,
+};
+use futures::io::{AsyncReadExt, AsyncWriteExt};
 
-use super::{AsyncUdpSocket, Spawner, TimedOut};
+use super::{AsyncTcpListener, AsyncTcpStream, AsyncUdpSocket, Spawner, TimedOut};
 
 /// async-std-based UDP socket.
 pub struct UdpSocket(AsyncStdUdpSocket);
@@ -42,6 +46,39 @@ impl AsyncUdpSocket for UdpSocket {
     }
 }
 
+/// async-std-based TCP listener.
+pub struct TcpListener(AsyncStdTcpListener);
+
+impl AsyncTcpListener for TcpListener {
+    type Stream = TcpStream;
+
+    async fn bind(addr: &str) -> io::Result<Self> {
+        AsyncStdTcpListener::bind(addr).await.map(TcpListener)
+    }
+
+    async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (stream, addr) = self.0.accept().await?;
+        Ok((TcpStream(stream), addr))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr()
+    }
+}
+
+/// async-std-based TCP stream.
+pub struct TcpStream(AsyncStdTcpStream);
+
+impl AsyncTcpStream for TcpStream {
+    async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).await
+    }
+
+    async fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.write_all(buf).await
+    }
+}
+
 /// async-std task spawner.
 pub struct AsyncStdSpawner;
 
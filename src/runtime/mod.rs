@@ -10,6 +10,7 @@
 //! - `runtime-tokio` (default) - Use the tokio runtime
 //! - `runtime-async-std` - Use the async-std runtime
 //! - `runtime-smol` - Use the smol runtime
+//! - `wasm` - Delegate to a user-supplied `Bridge`, for WebAssembly hosts
 //!
 //! # Example
 //!
@@ -22,10 +23,12 @@
 //! wiz-lights-rs = { version = "0.1", default-features = false, features = ["runtime-smol"] }
 //! ```
 
+use std::fmt::Debug;
 use std::future::Future;
 use std::io;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 
 #[cfg(feature = "runtime-tokio")]
@@ -37,6 +40,9 @@ mod async_std_impl;
 #[cfg(feature = "runtime-smol")]
 mod smol_impl;
 
+#[cfg(feature = "wasm")]
+mod wasm_impl;
+
 // Re-export the active runtime's types
 #[cfg(feature = "runtime-tokio")]
 pub use tokio_impl::*;
@@ -47,6 +53,9 @@ pub use async_std_impl::*;
 #[cfg(feature = "runtime-smol")]
 pub use smol_impl::*;
 
+#[cfg(feature = "wasm")]
+pub use wasm_impl::*;
+
 /// A boxed future type for runtime abstraction.
 pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
@@ -80,6 +89,105 @@ pub trait AsyncUdpSocket: Send + Sync + Sized {
     fn set_broadcast(&self, broadcast: bool) -> io::Result<()>;
 }
 
+/// A bound datagram socket handed out by a [`Transport`].
+///
+/// Unlike [`AsyncUdpSocket`], this trait is object safe, so a custom
+/// implementation can be swapped in at runtime.
+pub trait Datagram: Send + Sync {
+    /// Send data to a specific address.
+    fn send_to<'a>(&'a self, buf: &'a [u8], addr: SocketAddr) -> BoxFuture<'a, io::Result<usize>>;
+
+    /// Receive data and the source address.
+    fn recv_from<'a>(&'a self, buf: &'a mut [u8])
+    -> BoxFuture<'a, io::Result<(usize, SocketAddr)>>;
+
+    /// Enable or disable broadcast mode.
+    fn set_broadcast(&self, broadcast: bool) -> io::Result<()>;
+}
+
+/// Creates the UDP sockets used to talk to bulbs.
+///
+/// [`Light`](crate::Light), [`PushManager`](crate::push::PushManager) and
+/// discovery use [`RuntimeTransport`] unless given another transport, so a
+/// custom network stack or a test double can be plugged in without a new
+/// runtime feature.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::sync::Arc;
+/// use wiz_lights_rs::runtime::Transport;
+///
+/// let transport: Arc<dyn Transport> = Arc::new(MyStack::new());
+/// let mut light = Light::new(ip, None);
+/// light.set_transport(Arc::clone(&transport));
+/// ```
+pub trait Transport: Debug + Send + Sync {
+    /// Bind a socket to `addr`. Port 0 picks any free port.
+    fn bind(&self, addr: SocketAddr) -> BoxFuture<'_, io::Result<Box<dyn Datagram>>>;
+}
+
+/// The transport backed by the selected runtime's [`UdpSocket`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RuntimeTransport;
+
+impl Transport for RuntimeTransport {
+    fn bind(&self, addr: SocketAddr) -> BoxFuture<'_, io::Result<Box<dyn Datagram>>> {
+        Box::pin(async move {
+            let socket = UdpSocket::bind(&addr.to_string()).await?;
+            Ok(Box::new(socket) as Box<dyn Datagram>)
+        })
+    }
+}
+
+impl Datagram for UdpSocket {
+    fn send_to<'a>(&'a self, buf: &'a [u8], addr: SocketAddr) -> BoxFuture<'a, io::Result<usize>> {
+        Box::pin(async move { AsyncUdpSocket::send_to(self, buf, &addr.to_string()).await })
+    }
+
+    fn recv_from<'a>(
+        &'a self,
+        buf: &'a mut [u8],
+    ) -> BoxFuture<'a, io::Result<(usize, SocketAddr)>> {
+        Box::pin(AsyncUdpSocket::recv_from(self, buf))
+    }
+
+    fn set_broadcast(&self, broadcast: bool) -> io::Result<()> {
+        AsyncUdpSocket::set_broadcast(self, broadcast)
+    }
+}
+
+/// The transport used when none is configured.
+pub fn default_transport() -> Arc<dyn Transport> {
+    Arc::new(RuntimeTransport)
+}
+
+/// Trait for async TCP listener operations.
+///
+/// Used by the embedded HTTP server; bulbs themselves only speak UDP.
+pub trait AsyncTcpListener: Send + Sync + Sized {
+    /// The stream type of accepted connections.
+    type Stream: AsyncTcpStream;
+
+    /// Bind to the specified address.
+    fn bind(addr: &str) -> impl Future<Output = io::Result<Self>> + Send;
+
+    /// Accept a connection and return it with the peer address.
+    fn accept(&self) -> impl Future<Output = io::Result<(Self::Stream, SocketAddr)>> + Send;
+
+    /// The address the listener is bound to.
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+}
+
+/// Trait for async TCP stream operations.
+pub trait AsyncTcpStream: Send + Sized {
+    /// Read data into `buf`, returning 0 at end of stream.
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = io::Result<usize>> + Send;
+
+    /// Write all of `buf`.
+    fn write_all(&mut self, buf: &[u8]) -> impl Future<Output = io::Result<()>> + Send;
+}
+
 /// Trait for async task spawning.
 ///
 /// This trait abstracts over different async runtime's task spawning mechanisms.
@@ -147,6 +255,9 @@ pub use async_std::sync::Mutex;
 #[cfg(feature = "runtime-smol")]
 pub use async_lock::Mutex;
 
+#[cfg(feature = "wasm")]
+pub use futures::lock::Mutex;
+
 // JoinHandle type alias for task spawning
 #[cfg(feature = "runtime-tokio")]
 pub type JoinHandle<T> = tokio_impl::TokioJoinHandle<T>;
@@ -157,14 +268,18 @@ pub type JoinHandle<T> = async_std_impl::AsyncStdJoinHandle<T>;
 #[cfg(feature = "runtime-smol")]
 pub type JoinHandle<T> = smol_impl::SmolJoinHandle<T>;
 
+#[cfg(feature = "wasm")]
+pub type JoinHandle<T> = wasm_impl::BridgeJoinHandle<T>;
+
 // Compile-time check to ensure exactly one runtime is selected
 #[cfg(not(any(
     feature = "runtime-tokio",
     feature = "runtime-async-std",
-    feature = "runtime-smol"
+    feature = "runtime-smol",
+    feature = "wasm"
 )))]
 compile_error!(
-    "One of \"runtime-tokio\", \"runtime-async-std\", or \"runtime-smol\" features must be enabled"
+    "One of \"runtime-tokio\", \"runtime-async-std\", \"runtime-smol\", or \"wasm\" features must be enabled"
 );
 
 #[cfg(all(feature = "runtime-tokio", feature = "runtime-async-std"))]
@@ -175,3 +290,12 @@ compile_error!("Features \"runtime-tokio\" and \"runtime-smol\" are mutually exc
 
 #[cfg(all(feature = "runtime-async-std", feature = "runtime-smol"))]
 compile_error!("Features \"runtime-async-std\" and \"runtime-smol\" are mutually exclusive");
+
+#[cfg(all(feature = "wasm", feature = "runtime-tokio"))]
+compile_error!("Features \"wasm\" and \"runtime-tokio\" are mutually exclusive");
+
+#[cfg(all(feature = "wasm", feature = "runtime-async-std"))]
+compile_error!("Features \"wasm\" and \"runtime-async-std\" are mutually exclusive");
+
+#[cfg(all(feature = "wasm", feature = "runtime-smol"))]
+compile_error!("Features \"wasm\" and \"runtime-smol\" are mutually exclusive");
@@ -6,8 +6,9 @@ use std::net::SocketAddr;
 use std::time::Duration;
 
 use async_io::Async;
+use futures::io::{AsyncReadExt, AsyncWriteExt};
 
-use super::{AsyncUdpSocket, Spawner, TimedOut};
+use super::{AsyncTcpListener, AsyncTcpStream, AsyncUdpSocket, Spawner, TimedOut};
 
 /// smol-based UDP socket using async-io.
 pub struct UdpSocket(Async<std::net::UdpSocket>);
@@ -52,6 +53,42 @@ impl AsyncUdpSocket for UdpSocket {
     }
 }
 
+/// smol-based TCP listener using async-io.
+pub struct TcpListener(Async<std::net::TcpListener>);
+
+impl AsyncTcpListener for TcpListener {
+    type Stream = TcpStream;
+
+    async fn bind(addr: &str) -> io::Result<Self> {
+        let addr: SocketAddr = addr
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Async::<std::net::TcpListener>::bind(addr).map(TcpListener)
+    }
+
+    async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (stream, addr) = self.0.accept().await?;
+        Ok((TcpStream(stream), addr))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.get_ref().local_addr()
+    }
+}
+
+/// smol-based TCP stream using async-io.
+pub struct TcpStream(Async<std::net::TcpStream>);
+
+impl AsyncTcpStream for TcpStream {
+    async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).await
+    }
+
+    async fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.write_all(buf).await
+    }
+}
+
 /// smol task spawner.
 pub struct SmolSpawner;
 
@@ -5,9 +5,12 @@ use std::io;
 use std::net::SocketAddr;
 use std::time::Duration;
 
-use tokio::net::UdpSocket as TokioUdpSocket;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{
+    TcpListener as TokioTcpListener, TcpStream as TokioTcpStream, UdpSocket as TokioUdpSocket,
+};
 
-use super::{AsyncUdpSocket, Spawner, TimedOut};
+use super::{AsyncTcpListener, AsyncTcpStream, AsyncUdpSocket, Spawner, TimedOut};
 
 /// Tokio-based UDP socket.
 pub struct UdpSocket(TokioUdpSocket);
@@ -42,6 +45,39 @@ impl AsyncUdpSocket for UdpSocket {
     }
 }
 
+/// Tokio-based TCP listener.
+pub struct TcpListener(TokioTcpListener);
+
+impl AsyncTcpListener for TcpListener {
+    type Stream = TcpStream;
+
+    async fn bind(addr: &str) -> io::Result<Self> {
+        TokioTcpListener::bind(addr).await.map(TcpListener)
+    }
+
+    async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let (stream, addr) = self.0.accept().await?;
+        Ok((TcpStream(stream), addr))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.0.local_addr()
+    }
+}
+
+/// Tokio-based TCP stream.
+pub struct TcpStream(TokioTcpStream);
+
+impl AsyncTcpStream for TcpStream {
+    async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).await
+    }
+
+    async fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.write_all(buf).await
+    }
+}
+
 /// Tokio task spawner.
 pub struct TokioSpawner;
 
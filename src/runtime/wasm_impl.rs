@@ -0,0 +1,308 @@
+//! Runtime that delegates to a user-supplied [`Bridge`].
+//!
+//! WebAssembly has no UDP sockets and no thread-based executor, so with the
+//! `wasm` feature every runtime operation (spawning, timers, the clock and
+//! datagrams) is forwarded to a bridge registered with [`set_bridge`]. A
+//! Tauri command, a WebRTC data channel or a small native proxy can carry the
+//! datagrams, while the crate keeps its protocol handling and state.
+//!
+//! Bridge futures must be `Send`. On single-threaded wasm targets, wrap
+//! JavaScript-backed futures (e.g. with `send_wrapper`) before returning them.
+//!
+//! Until a bridge is registered, binding a socket fails with
+//! [`io::ErrorKind::NotConnected`], so every light command returns an error.
+//! Sleeps and timeouts end at once, the clock stands still, and spawned
+//! tasks only run while their handle is awaited.
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use futures::FutureExt;
+use futures::channel::oneshot;
+use futures::future::{AbortHandle, Abortable};
+
+use super::{
+    AsyncTcpListener, AsyncTcpStream, AsyncUdpSocket, BoxFuture, Datagram, Spawner, TimedOut,
+    Transport,
+};
+
+/// The host environment the `wasm` runtime runs on.
+///
+/// [`Transport::bind`] provides the datagram sockets; the other methods
+/// stand in for an executor and a timer.
+///
+/// # Example
+///
+/// ```ignore
+/// use wiz_lights_rs::runtime::{self, Bridge};
+///
+/// #[derive(Debug)]
+/// struct TauriBridge;
+///
+/// impl Transport for TauriBridge { /* forward datagrams to the native side */ }
+///
+/// impl Bridge for TauriBridge {
+///     fn spawn(&self, task: BoxFuture<'static, ()>) {
+///         wasm_bindgen_futures::spawn_local(task);
+///     }
+///     fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+///         Box::pin(SendWrapper::new(gloo_timers::future::sleep(duration)))
+///     }
+///     fn now(&self) -> Duration {
+///         Duration::from_secs_f64(web_sys::window().unwrap().performance().unwrap().now() / 1000.0)
+///     }
+/// }
+///
+/// runtime::set_bridge(Arc::new(TauriBridge));
+/// ```
+pub trait Bridge: Transport {
+    /// Run a task to completion in the background.
+    fn spawn(&self, task: BoxFuture<'static, ()>);
+
+    /// A future that completes after `duration`.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+
+    /// Time elapsed since an arbitrary fixed point, never going backwards.
+    fn now(&self) -> Duration;
+}
+
+static BRIDGE: OnceLock<Arc<dyn Bridge>> = OnceLock::new();
+
+/// Register the bridge used by every runtime operation.
+///
+/// Must be called once before any light is used. Returns false if a bridge
+/// was already registered.
+pub fn set_bridge(bridge: Arc<dyn Bridge>) -> bool {
+    BRIDGE.set(bridge).is_ok()
+}
+
+fn bridge() -> io::Result<&'static Arc<dyn Bridge>> {
+    BRIDGE.get().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotConnected,
+            "no runtime bridge registered; call runtime::set_bridge first",
+        )
+    })
+}
+
+/// The bridge timer, or one that has already fired without a bridge.
+fn timer(duration: Duration) -> BoxFuture<'static, ()> {
+    match bridge() {
+        Ok(bridge) => bridge.sleep(duration),
+        Err(_) => Box::pin(futures::future::ready(())),
+    }
+}
+
+/// UDP socket provided by the bridge.
+pub struct UdpSocket {
+    socket: Box<dyn Datagram>,
+    peer: std::sync::Mutex<Option<SocketAddr>>,
+}
+
+impl UdpSocket {
+    fn peer(&self) -> io::Result<SocketAddr> {
+        self.peer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "socket not connected"))
+    }
+}
+
+impl AsyncUdpSocket for UdpSocket {
+    async fn bind(addr: &str) -> io::Result<Self> {
+        let socket = bridge()?.bind(parse(addr)?).await?;
+        Ok(UdpSocket {
+            socket,
+            peer: std::sync::Mutex::new(None),
+        })
+    }
+
+    /// Remembers the peer; datagrams from other addresses are dropped by `recv`.
+    async fn connect(&self, addr: &str) -> io::Result<()> {
+        *self.peer.lock().unwrap_or_else(|e| e.into_inner()) = Some(parse(addr)?);
+        Ok(())
+    }
+
+    async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.socket.send_to(buf, self.peer()?).await
+    }
+
+    async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let peer = self.peer()?;
+        loop {
+            let (size, from) = self.socket.recv_from(buf).await?;
+            if from == peer {
+                return Ok(size);
+            }
+        }
+    }
+
+    async fn send_to(&self, buf: &[u8], addr: &str) -> io::Result<usize> {
+        self.socket.send_to(buf, parse(addr)?).await
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.socket.recv_from(buf).await
+    }
+
+    fn set_broadcast(&self, broadcast: bool) -> io::Result<()> {
+        self.socket.set_broadcast(broadcast)
+    }
+}
+
+fn parse(addr: &str) -> io::Result<SocketAddr> {
+    addr.parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// TCP is not available through the bridge; binding always fails.
+pub struct TcpListener(Infallible);
+
+impl AsyncTcpListener for TcpListener {
+    type Stream = TcpStream;
+
+    async fn bind(_addr: &str) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "TCP is not supported by the wasm runtime",
+        ))
+    }
+
+    async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        match self.0 {}
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self.0 {}
+    }
+}
+
+/// TCP stream type of the wasm runtime; never constructed.
+pub struct TcpStream(Infallible);
+
+impl AsyncTcpStream for TcpStream {
+    async fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        match self.0 {}
+    }
+
+    async fn write_all(&mut self, _buf: &[u8]) -> io::Result<()> {
+        match self.0 {}
+    }
+}
+
+/// Spawner that hands tasks to the bridge.
+pub struct BridgeSpawner;
+
+impl Spawner for BridgeSpawner {
+    type JoinHandle<T: Send + 'static> = BridgeJoinHandle<T>;
+
+    fn spawn<F, T>(future: F) -> Self::JoinHandle<T>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let (abort, registration) = AbortHandle::new_pair();
+        let task = Abortable::new(
+            async move {
+                let _ = tx.send(future.await);
+            },
+            registration,
+        );
+        let task: BoxFuture<'static, ()> = Box::pin(task.map(drop));
+        let local = match bridge() {
+            Ok(bridge) => {
+                bridge.spawn(task);
+                None
+            }
+            Err(_) => Some(task),
+        };
+        BridgeJoinHandle { rx, abort, local }
+    }
+}
+
+/// Handle to a task spawned on the bridge.
+pub struct BridgeJoinHandle<T> {
+    rx: oneshot::Receiver<T>,
+    abort: AbortHandle,
+    /// The task itself if no bridge could take it, run by awaiting the handle.
+    local: Option<BoxFuture<'static, ()>>,
+}
+
+impl<T> Future for BridgeJoinHandle<T> {
+    type Output = T;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        use std::task::Poll;
+        if let Some(task) = &mut self.local {
+            if task.poll_unpin(cx).is_ready() {
+                self.local = None;
+            }
+        }
+        match self.rx.poll_unpin(cx) {
+            Poll::Ready(Ok(v)) => Poll::Ready(v),
+            // Task was aborted or dropped by the bridge
+            Poll::Ready(Err(_)) => panic!("Task was cancelled unexpectedly"),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T: Send + 'static> BridgeJoinHandle<T> {
+    /// Abort the task.
+    pub fn abort(&self) {
+        self.abort.abort();
+    }
+}
+
+/// Internal instant type for the wasm runtime, read from the bridge clock.
+#[derive(Debug, Clone, Copy)]
+pub struct InstantInner(Duration);
+
+impl InstantInner {
+    pub fn now() -> Self {
+        InstantInner(bridge().map_or(Duration::ZERO, |bridge| bridge.now()))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Self::now().0.saturating_sub(self.0)
+    }
+}
+
+/// Sleep for the specified duration using the bridge timer.
+pub async fn sleep_impl(duration: Duration) {
+    timer(duration).await
+}
+
+/// Run a future with a timeout using the bridge timer.
+pub async fn timeout_impl<F, T>(duration: Duration, future: F) -> Result<T, TimedOut>
+where
+    F: Future<Output = T>,
+{
+    use futures::future::Either;
+
+    let timeout_future = timer(duration);
+
+    futures::pin_mut!(future);
+
+    match futures::future::select(future, timeout_future).await {
+        Either::Left((result, _)) => Ok(result),
+        Either::Right((_, _)) => Err(TimedOut),
+    }
+}
+
+/// Spawn a task on the bridge.
+pub fn spawn<F, T>(future: F) -> BridgeJoinHandle<T>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    BridgeSpawner::spawn(future)
+}
@@ -0,0 +1,179 @@
+//! Crate-wide protocol statistics.
+//!
+//! Once [`enable`]d, every [`Light`](crate::Light) adds to a shared
+//! [`Stats`] aggregate: commands by method, retries, timeouts, bytes on the
+//! wire and round-trip times per bulb. Long-running bridges can poll
+//! [`snapshot`] to spot bulbs that time out or answer slowly. Collection is
+//! off by default and costs a single atomic load per command while off.
+//!
+//! Unlike the `metrics` feature, which exports per-light gauges for
+//! Prometheus, this needs no feature flag and is meant to be read in-process.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use wiz_lights_rs::stats;
+//!
+//! stats::enable();
+//! // ... run for a while ...
+//! let stats = stats::snapshot();
+//! for (ip, bulb) in &stats.bulbs {
+//!     println!("{ip}: {} timeouts, avg rtt {:?}", bulb.timeouts, bulb.avg_rtt);
+//! }
+//! println!("{}", serde_json::to_string_pretty(&stats)?);
+//! ```
+
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Statistics for one bulb.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BulbStats {
+    /// Commands sent, not counting retries.
+    pub commands: u64,
+    /// Extra attempts made after a command got no reply.
+    pub retries: u64,
+    /// Attempts that timed out waiting for a reply.
+    pub timeouts: u64,
+    /// Replies received.
+    pub replies: u64,
+    /// Mean time from sending a datagram to receiving the reply.
+    pub avg_rtt: Option<Duration>,
+    #[serde(skip)]
+    total_rtt: Duration,
+}
+
+/// Protocol statistics aggregated over every light.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Stats {
+    /// Commands sent per JSON-RPC method, not counting retries.
+    pub commands: BTreeMap<String, u64>,
+    pub retries: u64,
+    pub timeouts: u64,
+    /// Payload bytes of every datagram sent, retries included.
+    pub bytes_sent: u64,
+    /// Payload bytes of every reply received.
+    pub bytes_received: u64,
+    pub bulbs: BTreeMap<Ipv4Addr, BulbStats>,
+}
+
+impl Stats {
+    fn bulb(&mut self, ip: Ipv4Addr) -> &mut BulbStats {
+        self.bulbs.entry(ip).or_default()
+    }
+
+    fn record_command(&mut self, ip: Ipv4Addr, method: &str) {
+        *self.commands.entry(method.to_string()).or_default() += 1;
+        self.bulb(ip).commands += 1;
+    }
+
+    fn record_retry(&mut self, ip: Ipv4Addr) {
+        self.retries += 1;
+        self.bulb(ip).retries += 1;
+    }
+
+    fn record_timeout(&mut self, ip: Ipv4Addr) {
+        self.timeouts += 1;
+        self.bulb(ip).timeouts += 1;
+    }
+
+    fn record_reply(&mut self, ip: Ipv4Addr, bytes: usize, rtt: Duration) {
+        self.bytes_received += bytes as u64;
+        let bulb = self.bulb(ip);
+        bulb.replies += 1;
+        bulb.total_rtt += rtt;
+        bulb.avg_rtt = Some(bulb.total_rtt / bulb.replies as u32);
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+fn global() -> &'static Mutex<Stats> {
+    static STATS: OnceLock<Mutex<Stats>> = OnceLock::new();
+    STATS.get_or_init(Mutex::default)
+}
+
+fn with(f: impl FnOnce(&mut Stats)) {
+    if !is_enabled() {
+        return;
+    }
+    let mut stats = global().lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut stats);
+}
+
+/// Start collecting statistics.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stop collecting. Statistics gathered so far are kept.
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Statistics collected so far.
+pub fn snapshot() -> Stats {
+    global().lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Forget all collected statistics.
+pub fn reset() {
+    *global().lock().unwrap_or_else(|e| e.into_inner()) = Stats::default();
+}
+
+pub(crate) fn record_command(ip: Ipv4Addr, method: &str) {
+    with(|stats| stats.record_command(ip, method));
+}
+
+pub(crate) fn record_retry(ip: Ipv4Addr) {
+    with(|stats| stats.record_retry(ip));
+}
+
+pub(crate) fn record_timeout(ip: Ipv4Addr) {
+    with(|stats| stats.record_timeout(ip));
+}
+
+pub(crate) fn record_sent(bytes: usize) {
+    with(|stats| stats.bytes_sent += bytes as u64);
+}
+
+pub(crate) fn record_reply(ip: Ipv4Addr, bytes: usize, rtt: Duration) {
+    with(|stats| stats.record_reply(ip, bytes, rtt));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate() {
+        let ip = Ipv4Addr::new(192, 168, 1, 20);
+        let mut stats = Stats::default();
+        stats.record_command(ip, "getPilot");
+        stats.record_command(ip, "getPilot");
+        stats.record_command(ip, "setPilot");
+        stats.record_timeout(ip);
+        stats.record_retry(ip);
+        stats.record_reply(ip, 100, Duration::from_millis(20));
+        stats.record_reply(ip, 50, Duration::from_millis(40));
+
+        assert_eq!(stats.commands["getPilot"], 2);
+        assert_eq!(stats.bytes_received, 150);
+        let bulb = &stats.bulbs[&ip];
+        assert_eq!((bulb.commands, bulb.retries, bulb.timeouts), (3, 1, 1));
+        assert_eq!(bulb.avg_rtt, Some(Duration::from_millis(30)));
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["bulbs"]["192.168.1.20"]["replies"], 2);
+        assert!(json["bulbs"]["192.168.1.20"].get("total_rtt").is_none());
+    }
+}
@@ -1,11 +1,39 @@
 //! Light status tracking.
 
+use alloc::string::String;
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
 use serde::{Deserialize, Serialize};
 
 use crate::payload::Payload;
-use crate::types::{Brightness, Color, Kelvin, PowerMode, SceneMode, Speed, White};
+#[cfg(feature = "std")]
+use crate::push::SyncPilotEvent;
+use crate::types::{
+    Brightness, Color, FanDirection, FanMode, FanSpeed, FanState, Kelvin, PowerMode, Ratio,
+    SceneMode, Speed, White,
+};
 
 /// The last context set on the light that the API is aware of.
+///
+/// Lighting settings take precedence: a payload that changes both the color
+/// and the fan counts as a color change.
+///
+/// # Examples
+///
+/// ```
+/// use wiz_lights_rs::{FanState, LastSet, LightStatus, Payload, PowerMode};
+///
+/// let mut payload = Payload::new();
+/// payload.fan_state(&FanState::On);
+/// let mut status = LightStatus::from(&payload);
+/// assert_eq!(status.last(), Some(&LastSet::Fan));
+///
+/// status.update(&LightStatus::from(&PowerMode::Off));
+/// assert_eq!(status.last(), Some(&LastSet::Power));
+/// ```
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum LastSet {
     /// The last set context was an RGB color
@@ -18,6 +46,10 @@ pub enum LastSet {
     Cool,
     /// The last set context was a warm white value
     Warm,
+    /// The last change only switched the light on or off
+    Power,
+    /// The last change only touched the fan of a fan-equipped fixture
+    Fan,
 }
 
 impl LastSet {
@@ -37,11 +69,67 @@ impl LastSet {
         if payload.warm.is_some() {
             return Some(LastSet::Warm);
         }
+        if fan_from_payload(payload).is_some() {
+            return Some(LastSet::Fan);
+        }
+        if payload.state.is_some() {
+            return Some(LastSet::Power);
+        }
         None
     }
 }
 
+/// Fan settings of a fan-equipped fixture.
+///
+/// Fields the bulb did not report are `None`.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FanStatus {
+    pub state: Option<FanState>,
+    pub mode: Option<FanMode>,
+    pub speed: Option<FanSpeed>,
+    pub direction: Option<FanDirection>,
+}
+
+impl FanStatus {
+    /// Parses the `fanState`, `fanMode`, `fanSpeed` and `fanRevrs` values.
+    ///
+    /// Returns `None` when none of them is present, as for plain bulbs.
+    pub(crate) fn from_raw(
+        state: Option<u8>,
+        mode: Option<u8>,
+        speed: Option<u8>,
+        direction: Option<u8>,
+    ) -> Option<Self> {
+        let status = FanStatus {
+            state: state.and_then(FanState::create),
+            mode: mode.and_then(FanMode::create),
+            speed: speed.and_then(|v| FanSpeed::create(v, Some(u8::MAX))),
+            direction: direction.and_then(FanDirection::create),
+        };
+        (status != FanStatus::default()).then_some(status)
+    }
+
+    /// Whether the fan is spinning.
+    pub fn is_on(&self) -> bool {
+        self.state == Some(FanState::On)
+    }
+
+    /// Overwrites fields with those set in `other`.
+    pub fn update(&mut self, other: &Self) {
+        self.state = other.state.or(self.state);
+        self.mode = other.mode.or(self.mode);
+        self.speed = other.speed.or(self.speed);
+        self.direction = other.direction.or(self.direction);
+    }
+}
+
 /// Tracks the last known settings for a light bulb.
+///
+/// A status built from a payload the crate sent is optimistic: it shows what
+/// was requested until the bulb reports its state through `getPilot` or a
+/// push, which confirms it. Equality compares the settings and whether they
+/// are confirmed, not when they were observed.
 #[serde_with::skip_serializing_none]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LightStatus {
@@ -53,10 +141,85 @@ pub struct LightStatus {
     temp: Option<Kelvin>,
     cool: Option<White>,
     warm: Option<White>,
+    ratio: Option<Ratio>,
     last: Option<LastSet>,
+    fan: Option<FanStatus>,
+    #[serde(default)]
+    confirmed: bool,
+    #[cfg(feature = "std")]
+    requested_at: Option<SystemTime>,
+    #[cfg(feature = "std")]
+    confirmed_at: Option<SystemTime>,
+}
+
+impl PartialEq for LightStatus {
+    fn eq(&self, other: &Self) -> bool {
+        self.color == other.color
+            && self.brightness == other.brightness
+            && self.emitting == other.emitting
+            && self.scene == other.scene
+            && self.speed == other.speed
+            && self.temp == other.temp
+            && self.cool == other.cool
+            && self.warm == other.warm
+            && self.ratio == other.ratio
+            && self.last == other.last
+            && self.fan == other.fan
+            && self.confirmed == other.confirmed
+    }
 }
 
 impl LightStatus {
+    /// Whether the bulb has reported these settings since they were last
+    /// requested.
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed
+    }
+
+    /// When settings were last requested through a light's cache.
+    #[cfg(feature = "std")]
+    pub fn requested_at(&self) -> Option<SystemTime> {
+        self.requested_at
+    }
+
+    /// When the bulb last reported its state to a light's cache.
+    #[cfg(feature = "std")]
+    pub fn confirmed_at(&self) -> Option<SystemTime> {
+        self.confirmed_at
+    }
+
+    /// When the status last changed hands: the later of
+    /// [`LightStatus::requested_at`] and [`LightStatus::confirmed_at`].
+    #[cfg(feature = "std")]
+    pub fn last_updated(&self) -> Option<SystemTime> {
+        self.requested_at.max(self.confirmed_at)
+    }
+
+    /// Time since [`LightStatus::last_updated`], zero if the clock went
+    /// backwards. `None` for statuses that never went through a light's cache.
+    #[cfg(feature = "std")]
+    pub fn age(&self) -> Option<Duration> {
+        self.last_updated()
+            .map(|at| SystemTime::now().duration_since(at).unwrap_or_default())
+    }
+
+    /// Whether the status is older than `max_age` or was never timestamped.
+    #[cfg(feature = "std")]
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        self.age().is_none_or(|age| age > max_age)
+    }
+
+    /// Records `at` as the request or confirmation time, whichever this
+    /// status is.
+    #[cfg(feature = "std")]
+    pub(crate) fn stamp(&mut self, at: SystemTime) {
+        if self.confirmed {
+            self.confirmed_at = Some(at);
+        } else {
+            self.requested_at = Some(at);
+        }
+    }
+
     /// Get the last set context.
     pub fn last(&self) -> Option<&LastSet> {
         self.last.as_ref()
@@ -102,6 +265,36 @@ impl LightStatus {
         self.warm.as_ref()
     }
 
+    /// Get the last set up/down ratio, for dual-head fixtures.
+    pub fn ratio(&self) -> Option<&Ratio> {
+        self.ratio.as_ref()
+    }
+
+    /// Get the fan settings, for fan-equipped fixtures.
+    pub fn fan(&self) -> Option<&FanStatus> {
+        self.fan.as_ref()
+    }
+
+    /// Get the fan power state, for fan-equipped fixtures.
+    pub fn fan_state(&self) -> Option<FanState> {
+        self.fan?.state
+    }
+
+    /// Get the fan mode, for fan-equipped fixtures.
+    pub fn fan_mode(&self) -> Option<FanMode> {
+        self.fan?.mode
+    }
+
+    /// Get the fan speed, for fan-equipped fixtures.
+    pub fn fan_speed(&self) -> Option<FanSpeed> {
+        self.fan?.speed
+    }
+
+    /// Get the fan direction, for fan-equipped fixtures.
+    pub fn fan_direction(&self) -> Option<FanDirection> {
+        self.fan?.direction
+    }
+
     /// Update this status with values from another status.
     ///
     /// Values set in `other` overwrite values in `self`.
@@ -140,12 +333,32 @@ impl LightStatus {
         if let Some(warm) = &other.warm {
             self.warm = Some(warm.clone());
         }
+        if let Some(ratio) = &other.ratio {
+            self.ratio = Some(ratio.clone());
+        }
         if let Some(last) = &other.last {
             self.last = Some(last.clone());
         }
+        if let Some(fan) = &other.fan {
+            self.fan.get_or_insert_default().update(fan);
+        }
+        self.confirmed = other.confirmed;
+        #[cfg(feature = "std")]
+        {
+            if other.requested_at.is_some() {
+                self.requested_at = other.requested_at;
+            }
+            if other.confirmed_at.is_some() {
+                self.confirmed_at = other.confirmed_at;
+            }
+        }
     }
 
+    #[cfg(feature = "std")]
     pub(crate) fn update_from_payload(&mut self, payload: &Payload) {
+        if let Some(state) = payload.state {
+            self.emitting = state;
+        }
         if let Some(color) = payload.get_color() {
             self.color = Some(color);
             self.last = Some(LastSet::Color);
@@ -172,10 +385,61 @@ impl LightStatus {
             self.warm = White::create(warm);
             self.last = Some(LastSet::Warm);
         }
+        if let Some(ratio) = payload.ratio {
+            self.ratio = Ratio::create(ratio);
+        }
+        if let Some(fan) = fan_from_payload(payload) {
+            self.fan.get_or_insert_default().update(&fan);
+        }
+        // Lighting contexts were recorded above
+        if let Some(last @ (LastSet::Fan | LastSet::Power)) = LastSet::from_payload(payload) {
+            self.last = Some(last);
+        }
+        self.confirmed = false;
     }
 
+    /// Merges a push, which may report only some fields. Absent fields keep
+    /// their cached values; a reported color or temperature ends the scene.
+    #[cfg(feature = "std")]
+    pub(crate) fn update_from_push(&mut self, event: &SyncPilotEvent) {
+        if let Some(state) = event.state {
+            self.emitting = state;
+        }
+        if let Some(color) = &event.color {
+            self.color = Some(color.clone());
+        }
+        if let Some(dimming) = &event.dimming {
+            self.brightness = Some(dimming.clone());
+        }
+        if let Some(speed) = &event.speed {
+            self.speed = Some(speed.clone());
+        }
+        if let Some(temp) = &event.temp {
+            self.temp = Some(temp.clone());
+        }
+        if event.scene.is_some() || event.color.is_some() || event.temp.is_some() {
+            self.scene.clone_from(&event.scene);
+        }
+        if let Some(cool) = &event.cool {
+            self.cool = Some(cool.clone());
+        }
+        if let Some(warm) = &event.warm {
+            self.warm = Some(warm.clone());
+        }
+        if let Some(ratio) = &event.ratio {
+            self.ratio = Some(ratio.clone());
+        }
+        if let Some(fan) = LightStatus::from(event).fan {
+            self.fan.get_or_insert_default().update(&fan);
+        }
+        self.confirmed = true;
+    }
+
+    #[cfg(feature = "std")]
     pub(crate) fn update_from_power(&mut self, power: &PowerMode) {
         self.emitting = !matches!(power, PowerMode::Off);
+        self.last = Some(LastSet::Power);
+        self.confirmed = false;
     }
 }
 
@@ -184,13 +448,20 @@ impl From<&Payload> for LightStatus {
         LightStatus {
             color: payload.get_color(),
             brightness: payload.dimming.and_then(Brightness::create),
-            emitting: true,
+            emitting: payload.state.unwrap_or(true),
             scene: payload.scene.and_then(SceneMode::create),
             speed: payload.speed.and_then(Speed::create),
             temp: payload.temp.and_then(Kelvin::create),
             cool: payload.cool.and_then(White::create),
             warm: payload.warm.and_then(White::create),
+            ratio: payload.ratio.and_then(Ratio::create),
             last: LastSet::from_payload(payload),
+            fan: fan_from_payload(payload),
+            confirmed: false,
+            #[cfg(feature = "std")]
+            requested_at: None,
+            #[cfg(feature = "std")]
+            confirmed_at: None,
         }
     }
 }
@@ -206,29 +477,86 @@ impl From<&PowerMode> for LightStatus {
             temp: None,
             cool: None,
             warm: None,
-            last: None,
+            ratio: None,
+            last: Some(LastSet::Power),
+            fan: None,
+            confirmed: false,
+            #[cfg(feature = "std")]
+            requested_at: None,
+            #[cfg(feature = "std")]
+            confirmed_at: None,
         }
     }
 }
 
 impl From<&BulbStatus> for LightStatus {
     fn from(bulb: &BulbStatus) -> Self {
-        let res = &bulb.result;
+        LightStatus::from(&bulb.result)
+    }
+}
 
+impl From<&BulbStatusResult> for LightStatus {
+    fn from(res: &BulbStatusResult) -> Self {
         LightStatus {
             color: res.get_color(),
             brightness: res.dimming.and_then(Brightness::create),
             cool: res.cool.and_then(White::create),
             warm: res.warm.and_then(White::create),
+            ratio: res.ratio.and_then(Ratio::create),
             emitting: res.emitting,
             scene: SceneMode::create(res.scene),
-            speed: None,
-            temp: None,
+            speed: res.speed.and_then(Speed::create),
+            temp: res.temp.and_then(Kelvin::create),
+            last: None,
+            fan: FanStatus::from_raw(res.fan_state, res.fan_mode, res.fan_speed, res.fan_reverse),
+            confirmed: true,
+            #[cfg(feature = "std")]
+            requested_at: None,
+            #[cfg(feature = "std")]
+            confirmed_at: None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&SyncPilotEvent> for LightStatus {
+    fn from(event: &SyncPilotEvent) -> Self {
+        LightStatus {
+            color: event.color.clone(),
+            brightness: event.dimming.clone(),
+            emitting: event.state.unwrap_or(false),
+            scene: event.scene.clone(),
+            speed: event.speed.clone(),
+            temp: event.temp.clone(),
+            cool: event.cool.clone(),
+            warm: event.warm.clone(),
+            ratio: event.ratio.clone(),
             last: None,
+            fan: Some(FanStatus {
+                state: event.fan_state,
+                mode: event.fan_mode,
+                speed: event.fan_speed,
+                direction: event.fan_direction,
+            })
+            .filter(|fan| *fan != FanStatus::default()),
+            confirmed: true,
+            #[cfg(feature = "std")]
+            requested_at: None,
+            #[cfg(feature = "std")]
+            confirmed_at: None,
         }
     }
 }
 
+fn fan_from_payload(payload: &Payload) -> Option<FanStatus> {
+    FanStatus::from_raw(
+        payload.fan_state,
+        payload.fan_mode,
+        payload.fan_speed,
+        payload.fan_reverse,
+    )
+}
+
 /// Bulb status as reported by the bulb via getPilot.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub(crate) struct BulbStatus {
@@ -249,13 +577,26 @@ pub(crate) struct BulbStatusResult {
     pub mac: String,
     #[serde(rename = "state")]
     pub emitting: bool,
-    #[serde(rename = "sceneId")]
+    #[serde(rename = "sceneId", default)]
     pub scene: u16,
+    /// Animation speed of a dynamic scene.
+    pub speed: Option<u8>,
+    pub temp: Option<u16>,
+    #[serde(default)]
     pub rssi: i32,
     #[serde(rename = "c")]
     pub cool: Option<u8>,
     #[serde(rename = "w")]
     pub warm: Option<u8>,
+    pub ratio: Option<u8>,
+    #[serde(rename = "fanState")]
+    pub fan_state: Option<u8>,
+    #[serde(rename = "fanMode")]
+    pub fan_mode: Option<u8>,
+    #[serde(rename = "fanSpeed")]
+    pub fan_speed: Option<u8>,
+    #[serde(rename = "fanRevrs")]
+    pub fan_reverse: Option<u8>,
 }
 
 impl BulbStatusResult {
@@ -0,0 +1,1045 @@
+//! A simulated bulb for tests that run without hardware.
+//!
+//! [`MockBulb`] binds UDP port 38899 on a local address and answers the
+//! JSON-RPC methods the crate uses: `getPilot`, `setPilot`, `setState`,
+//! `getSystemConfig`, `getPower`, `setWifiConfig` and `registration`. It
+//! keeps the simulated pilot state, records every request, and sends
+//! `syncPilot` pushes to a registered listener when its state changes.
+//!
+//! Bulbs are always addressed on port 38899, so each mock needs its own IP.
+//! On Linux every address in `127.0.0.0/8` is local, so mocks can use
+//! `127.0.0.2`, `127.0.0.3`, and so on; other platforms may need loopback
+//! aliases configured.
+//!
+//! Requires the `testing` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::net::Ipv4Addr;
+//! use wiz_lights_rs::testing::MockBulb;
+//! use wiz_lights_rs::{Brightness, Payload};
+//!
+//! let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 2)).await?;
+//! let light = bulb.light();
+//! light.set(&Payload::from(&Brightness::create(40).unwrap())).await?;
+//! assert_eq!(bulb.pilot().await["dimming"], 40);
+//! bulb.stop().await;
+//! ```
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use log::debug;
+use serde_json::{Map, Value, json};
+
+use crate::errors::Error;
+use crate::light::Light;
+use crate::runtime::{self, AsyncUdpSocket, JoinHandle, Mutex, UdpSocket};
+
+type Result<T> = std::result::Result<T, Error>;
+
+const BULB_PORT: u16 = 38899;
+const PUSH_PORT: u16 = 38900;
+
+/// Identity and capabilities reported by a [`MockBulb`].
+#[derive(Debug, Clone)]
+pub struct MockBulbConfig {
+    pub mac: String,
+    pub module_name: String,
+    pub fw_version: String,
+    pub home_id: u64,
+    pub room_id: u64,
+    pub group_id: u64,
+    pub rssi: i32,
+    /// Reported by `getPower`; `None` answers like a bulb without metering.
+    pub power: Option<f32>,
+}
+
+impl Default for MockBulbConfig {
+    fn default() -> Self {
+        MockBulbConfig {
+            mac: "a8bb50000001".into(),
+            module_name: "ESP01_SHRGB1C_31".into(),
+            fw_version: "1.25.0".into(),
+            home_id: 1,
+            room_id: 1,
+            group_id: 0,
+            rssi: -55,
+            power: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MockState {
+    config: MockBulbConfig,
+    pilot: Map<String, Value>,
+    user_config: Map<String, Value>,
+    requests: Vec<Value>,
+    push_target: Option<Ipv4Addr>,
+    unresponsive: bool,
+}
+
+/// A simulated WiZ bulb listening on a local address.
+pub struct MockBulb {
+    ip: Ipv4Addr,
+    state: Arc<Mutex<MockState>>,
+    running: Arc<AtomicBool>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl MockBulb {
+    /// Start a bulb with the default identity on `ip`.
+    pub async fn start(ip: Ipv4Addr) -> Result<Self> {
+        Self::start_with(ip, MockBulbConfig::default()).await
+    }
+
+    /// Start a bulb with the given identity on `ip`.
+    pub async fn start_with(ip: Ipv4Addr, config: MockBulbConfig) -> Result<Self> {
+        let socket = UdpSocket::bind(&format!("{ip}:{BULB_PORT}"))
+            .await
+            .map_err(|e| Error::socket("bind mock bulb", e))?;
+
+        let mut pilot = Map::new();
+        pilot.insert("state".into(), json!(true));
+        pilot.insert("sceneId".into(), json!(0));
+        pilot.insert("temp".into(), json!(4200));
+        pilot.insert("dimming".into(), json!(100));
+
+        let state = Arc::new(Mutex::new(MockState {
+            config,
+            pilot,
+            user_config: Map::new(),
+            requests: Vec::new(),
+            push_target: None,
+            unresponsive: false,
+        }));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let task_state = Arc::clone(&state);
+        let task_running = Arc::clone(&running);
+        let handle = runtime::spawn(async move {
+            let mut buffer = [0u8; 4096];
+            while task_running.load(Ordering::SeqCst) {
+                // Short timeout so stop() is noticed promptly
+                let received =
+                    runtime::timeout(Duration::from_millis(200), socket.recv_from(&mut buffer))
+                        .await;
+                let Ok(Ok((size, peer))) = received else {
+                    continue;
+                };
+                let Ok(request) = serde_json::from_slice::<Value>(&buffer[..size]) else {
+                    continue;
+                };
+
+                let (reply, push) = handle_request(&task_state, &request, peer).await;
+                if let Some(mut reply) = reply {
+                    // Bulbs echo the request id
+                    if let Some(id) = request.get("id") {
+                        reply["id"] = id.clone();
+                    }
+                    let reply = reply.to_string();
+                    if let Err(e) = socket.send_to(reply.as_bytes(), &peer.to_string()).await {
+                        debug!("Mock bulb reply to {} failed: {}", peer, e);
+                    }
+                }
+                if push {
+                    send_sync_pilot(&task_state, ip).await;
+                }
+            }
+        });
+
+        Ok(MockBulb {
+            ip,
+            state,
+            running,
+            task: Mutex::new(Some(handle)),
+        })
+    }
+
+    pub fn ip(&self) -> Ipv4Addr {
+        self.ip
+    }
+
+    pub async fn mac(&self) -> String {
+        self.state.lock().await.config.mac.clone()
+    }
+
+    /// A [`Light`] addressing this bulb.
+    pub fn light(&self) -> Light {
+        Light::new(self.ip, None)
+    }
+
+    /// The simulated pilot state, as `getPilot` would report it.
+    pub async fn pilot(&self) -> Value {
+        Value::Object(self.state.lock().await.pilot.clone())
+    }
+
+    /// Change the state as if from the WiZ app or a remote.
+    ///
+    /// A `syncPilot` push is sent if a listener registered.
+    pub async fn set_pilot(&self, params: Value) {
+        apply_pilot(&mut self.state.lock().await.pilot, &params);
+        send_sync_pilot(&self.state, self.ip).await;
+    }
+
+    /// Every request received so far, oldest first.
+    pub async fn requests(&self) -> Vec<Value> {
+        self.state.lock().await.requests.clone()
+    }
+
+    /// Stop answering requests, to simulate a bulb that dropped off WiFi.
+    pub async fn set_unresponsive(&self, unresponsive: bool) {
+        self.state.lock().await.unresponsive = unresponsive;
+    }
+
+    /// IP of the push listener registered with this bulb, if any.
+    pub async fn push_target(&self) -> Option<Ipv4Addr> {
+        self.state.lock().await.push_target
+    }
+
+    /// Stop the bulb and release its socket.
+    pub async fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(h) = self.task.lock().await.take() {
+            let _ = h.await;
+        }
+    }
+}
+
+impl Drop for MockBulb {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Builds the reply to `request`, and whether the state changed.
+async fn handle_request(
+    state: &Mutex<MockState>,
+    request: &Value,
+    peer: SocketAddr,
+) -> (Option<Value>, bool) {
+    let mut state = state.lock().await;
+    state.requests.push(request.clone());
+    if state.unresponsive {
+        return (None, false);
+    }
+
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let config = &state.config;
+    let (result, changed) = match method {
+        "getPilot" => {
+            let mut result = state.pilot.clone();
+            result.insert("mac".into(), json!(config.mac));
+            result.insert("rssi".into(), json!(config.rssi));
+            (Value::Object(result), false)
+        }
+        "setPilot" | "setState" => {
+            apply_pilot(&mut state.pilot, &params);
+            (json!({"success": true}), true)
+        }
+        "getSystemConfig" => (
+            json!({
+                "mac": config.mac,
+                "homeId": config.home_id,
+                "roomId": config.room_id,
+                "groupId": config.group_id,
+                "moduleName": config.module_name,
+                "fwVersion": config.fw_version,
+                "typeId": 0,
+                "ping": 0,
+            }),
+            false,
+        ),
+        "getPower" => match config.power {
+            Some(power) => (json!({"power": power}), false),
+            None => return (Some(method_not_found(method)), false),
+        },
+        "getUserConfig" => (Value::Object(state.user_config.clone()), false),
+        "setUserConfig" => {
+            if let Some(params) = params.as_object() {
+                state.user_config.extend(params.clone());
+            }
+            (json!({"success": true}), false)
+        }
+        "setWifiConfig" => (json!({"success": true}), false),
+        "registration" => {
+            let mac = config.mac.clone();
+            if params.get("register").and_then(Value::as_bool) == Some(true) {
+                state.push_target = params
+                    .get("phoneIp")
+                    .and_then(Value::as_str)
+                    .and_then(|ip| ip.parse().ok());
+            } else if let SocketAddr::V4(peer) = peer {
+                // Discovery probes also unregister; only forget the sender's own registration
+                if state.push_target == Some(*peer.ip()) {
+                    state.push_target = None;
+                }
+            }
+            (json!({"mac": mac, "success": true}), false)
+        }
+        _ => return (Some(method_not_found(method)), false),
+    };
+
+    let reply = json!({"method": method, "env": "pro", "result": result});
+    (Some(reply), changed)
+}
+
+fn method_not_found(method: &str) -> Value {
+    json!({
+        "method": method,
+        "env": "pro",
+        "error": {"code": -32601, "message": "Method not found"},
+    })
+}
+
+/// Merges `setPilot` params into the pilot state the way a bulb does.
+fn apply_pilot(pilot: &mut Map<String, Value>, params: &Value) {
+    let Some(params) = params.as_object() else {
+        return;
+    };
+    // Choosing a color mode clears the others
+    let modes: [&[&str]; 4] = [&["r", "g", "b"], &["temp"], &["sceneId"], &["c", "w"]];
+    if let Some(mode) = modes
+        .iter()
+        .find(|keys| keys.iter().any(|key| params.contains_key(*key)))
+    {
+        for other in modes
+            .iter()
+            .filter(|keys| *keys != mode)
+            .flat_map(|keys| keys.iter())
+        {
+            pilot.remove(*other);
+        }
+        if !mode.contains(&"sceneId") {
+            pilot.insert("sceneId".into(), json!(0));
+        }
+    }
+    for (key, value) in params {
+        // Transition parameters shape the change but are not reported back
+        if matches!(key.as_str(), "fadeIn" | "fadeOut" | "dimmingDelta") {
+            continue;
+        }
+        pilot.insert(key.clone(), value.clone());
+    }
+    if let Some(delta) = params.get("dimmingDelta").and_then(Value::as_i64) {
+        let current = pilot.get("dimming").and_then(Value::as_i64).unwrap_or(100);
+        pilot.insert("dimming".into(), json!((current + delta).clamp(10, 100)));
+    }
+}
+
+async fn send_sync_pilot(state: &Mutex<MockState>, ip: Ipv4Addr) {
+    let (target, msg) = {
+        let state = state.lock().await;
+        let Some(target) = state.push_target else {
+            return;
+        };
+        let mut params = state.pilot.clone();
+        params.insert("mac".into(), json!(state.config.mac));
+        params.insert("rssi".into(), json!(state.config.rssi));
+        params.insert("src".into(), json!("udp"));
+        (target, json!({"method": "syncPilot", "params": params}))
+    };
+
+    let Ok(socket) = UdpSocket::bind(&format!("{ip}:0")).await else {
+        return;
+    };
+    let msg = msg.to_string();
+    if let Err(e) = socket
+        .send_to(msg.as_bytes(), &format!("{target}:{PUSH_PORT}"))
+        .await
+    {
+        debug!("Mock bulb push to {} failed: {}", target, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::Payload;
+    use crate::types::{Brightness, Color, Kelvin, PowerMode};
+
+    #[tokio::test]
+    async fn test_set_and_get_pilot() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 21)).await.unwrap();
+        let light = bulb.light();
+
+        let mut payload = Payload::from(&Color::rgb(255, 0, 0));
+        payload.brightness(&Brightness::create(40).unwrap());
+        light.set(&payload).await.unwrap();
+
+        let pilot = bulb.pilot().await;
+        assert_eq!(pilot["r"], 255);
+        assert_eq!(pilot["dimming"], 40);
+        assert!(pilot.get("temp").is_none());
+
+        let status = light.get_status().await.unwrap();
+        assert_eq!(status.color(), Some(&Color::rgb(255, 0, 0)));
+        assert_eq!(bulb.requests().await.len(), 2);
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_change_notifications() {
+        use futures::StreamExt;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 24)).await.unwrap();
+        let light = bulb.light();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let id = light.on_change(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let mut changes = light.changes();
+
+        let payload = Payload::from(&Color::rgb(0, 0, 255));
+        light.process_reply(&light.set(&payload).await.unwrap());
+        let change = changes.next().await.unwrap();
+        assert!(change.previous.is_none());
+        assert_eq!(change.current.color(), Some(&Color::rgb(0, 0, 255)));
+        assert!(!change.current.is_confirmed());
+        assert!(change.current.requested_at().is_some());
+
+        // Re-applying the same state is not a change
+        light.process_reply(&light.set(&payload).await.unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        assert!(light.remove_change_listener(id));
+        light.get_status().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let confirmed = changes.next().await.unwrap().current;
+        assert!(confirmed.is_confirmed() && confirmed.confirmed_at().is_some());
+        assert_eq!(confirmed.color(), Some(&Color::rgb(0, 0, 255)));
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_status_polling() {
+        use futures::StreamExt;
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 25)).await.unwrap();
+        let light = bulb.light();
+        let poller = light.start_polling(Duration::from_millis(50));
+        let mut updates = poller.updates();
+
+        let change = updates.next().await.unwrap();
+        assert_eq!(change.ip, light.ip());
+        assert_eq!(light.status(), Some(change.current));
+
+        poller.stop().await;
+        assert!(!poller.is_running());
+        assert!(updates.next().await.is_none());
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_cached_status() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 26)).await.unwrap();
+        let light = bulb.light();
+        assert!(light.is_status_stale());
+
+        let max_age = Duration::from_secs(60);
+        light.get_status_cached(max_age).await.unwrap();
+        let status = light.get_status_cached(max_age).await.unwrap();
+        assert!(status.age().unwrap() < max_age);
+        assert!(!light.is_status_stale());
+        assert_eq!(bulb.requests().await.len(), 1);
+
+        // Optimistic state is not served from the cache
+        light
+            .set(&Payload::from(&Color::rgb(9, 9, 9)))
+            .await
+            .unwrap();
+        light.get_status_cached(max_age).await.unwrap();
+        assert_eq!(bulb.requests().await.len(), 3);
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_non_idempotent_not_retried() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 27)).await.unwrap();
+        let light = bulb.light();
+        light.get_status().await.unwrap();
+        assert!(bulb.requests().await[0]["id"].is_u64());
+
+        // A reboot whose reply is lost may still have happened
+        bulb.set_unresponsive(true).await;
+        assert!(light.set_power(&PowerMode::Reboot).await.is_err());
+        assert_eq!(bulb.requests().await.len(), 2);
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_system_config_and_errors() {
+        let config = MockBulbConfig {
+            mac: "a8bb50000022".into(),
+            room_id: 7,
+            ..Default::default()
+        };
+        let bulb = MockBulb::start_with(Ipv4Addr::new(127, 0, 0, 22), config)
+            .await
+            .unwrap();
+        let light = bulb.light();
+
+        let system = light.get_system_config().await.unwrap();
+        assert_eq!(system.mac, "a8bb50000022");
+        assert_eq!(system.room_id, Some(7));
+
+        let err = light.get_power().await.unwrap_err();
+        assert!(matches!(err, Error::Bulb { code: -32601, .. }));
+        bulb.stop().await;
+    }
+
+    /// Answers every datagram with a fixed `getPilot` reply, without sockets.
+    #[derive(Debug, Default)]
+    struct CannedTransport {
+        sent: Arc<std::sync::Mutex<Vec<(SocketAddr, Value)>>>,
+    }
+
+    struct CannedSocket {
+        sent: Arc<std::sync::Mutex<Vec<(SocketAddr, Value)>>>,
+    }
+
+    impl runtime::Transport for CannedTransport {
+        fn bind(
+            &self,
+            _addr: SocketAddr,
+        ) -> runtime::BoxFuture<'_, std::io::Result<Box<dyn runtime::Datagram>>> {
+            let socket = CannedSocket {
+                sent: Arc::clone(&self.sent),
+            };
+            Box::pin(async move { Ok(Box::new(socket) as Box<dyn runtime::Datagram>) })
+        }
+    }
+
+    impl runtime::Datagram for CannedSocket {
+        fn send_to<'a>(
+            &'a self,
+            buf: &'a [u8],
+            addr: SocketAddr,
+        ) -> runtime::BoxFuture<'a, std::io::Result<usize>> {
+            let msg = serde_json::from_slice(buf).unwrap_or(Value::Null);
+            self.sent.lock().unwrap().push((addr, msg));
+            Box::pin(async move { Ok(buf.len()) })
+        }
+
+        fn recv_from<'a>(
+            &'a self,
+            buf: &'a mut [u8],
+        ) -> runtime::BoxFuture<'a, std::io::Result<(usize, SocketAddr)>> {
+            let peer = self.sent.lock().unwrap().last().map(|(addr, _)| *addr);
+            Box::pin(async move {
+                let Some(peer) = peer else {
+                    return futures::future::pending().await;
+                };
+                let reply = json!({
+                    "method": "getPilot",
+                    "env": "pro",
+                    "result": {"mac": "a8bb50000005", "state": true, "dimming": 50}
+                });
+                let bytes = reply.to_string().into_bytes();
+                buf[..bytes.len()].copy_from_slice(&bytes);
+                Ok((bytes.len(), peer))
+            })
+        }
+
+        fn set_broadcast(&self, _broadcast: bool) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_transport() {
+        let transport = Arc::new(CannedTransport::default());
+        let mut light = Light::new(Ipv4Addr::new(10, 0, 0, 5), None);
+        light.set_transport(transport.clone());
+
+        let status = light.get_status().await.unwrap();
+        assert!(status.emitting());
+
+        let sent = transport.sent.lock().unwrap();
+        assert_eq!(sent[0].0, "10.0.0.5:38899".parse().unwrap());
+        assert_eq!(sent[0].1["method"], "getPilot");
+    }
+
+    #[tokio::test]
+    async fn test_discover_in_subnet_with_options() {
+        use crate::discovery::{DiscoveryOptions, discover_in_subnet_with};
+
+        let ip = Ipv4Addr::new(127, 0, 0, 48);
+        let bulb = MockBulb::start(ip).await.unwrap();
+        let options = DiscoveryOptions {
+            timeout: Duration::from_millis(300),
+            probe_config: true,
+            bind_addr: Some(Ipv4Addr::LOCALHOST),
+            ..Default::default()
+        };
+        let bulbs = discover_in_subnet_with("127.0.0.48/32".parse().unwrap(), &options)
+            .await
+            .unwrap();
+        assert_eq!(bulbs.len(), 1);
+        assert_eq!(bulbs[0].ip, ip);
+        assert!(bulbs[0].module_name.is_some());
+
+        // A cancelled sweep sends nothing and returns at once
+        let cancel = crate::cancel::CancelToken::new();
+        cancel.cancel();
+        let options = DiscoveryOptions {
+            timeout: Duration::from_secs(5),
+            cancel: Some(cancel),
+            ..options
+        };
+        let start = std::time::Instant::now();
+        let bulbs = discover_in_subnet_with("127.0.0.48/32".parse().unwrap(), &options)
+            .await
+            .unwrap();
+        assert!(bulbs.is_empty());
+        assert!(start.elapsed() < Duration::from_secs(1));
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_room_diagnostics() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 28)).await.unwrap();
+        let mut room = crate::Room::new("Office");
+        let id = room.new_light(bulb.light()).unwrap();
+
+        let diag = room.diagnostics().await;
+        assert_eq!(diag.name, "Office");
+        assert_eq!(diag.summary.reachable, 1);
+        assert_eq!(diag.summary.fw_versions["1.25.0"], 1);
+        let light = &diag.lights[&id];
+        assert!(light.reachable);
+        assert_eq!(light.rssi, Some(-55));
+        assert_eq!(diag.to_json()["lights"][id.to_string()]["rssi"], -55);
+
+        // An unresponsive bulb yields a partial report within the timeout
+        bulb.set_unresponsive(true).await;
+        let start = std::time::Instant::now();
+        let light = bulb.light().diagnostics().await;
+        assert!(start.elapsed() < Light::DIAGNOSTICS_TIMEOUT * 2);
+        assert!(!light.reachable && !light.is_complete());
+        assert!(light.errors["status"].contains("timed out"));
+        assert!(light.errors.contains_key("system_config"));
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_alias_sync() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 29)).await.unwrap();
+        let mut light = Light::new(bulb.ip, Some("Desk"));
+        assert_eq!(light.get_alias().await.unwrap(), None);
+
+        // A local name is pushed to a bulb without an alias
+        assert_eq!(light.sync_name().await.unwrap(), Some("Desk"));
+        assert_eq!(light.get_alias().await.unwrap().as_deref(), Some("Desk"));
+
+        // A stored alias replaces the local name
+        light.set_alias("Reading lamp").await.unwrap();
+        let mut other = bulb.light();
+        assert_eq!(other.sync_name().await.unwrap(), Some("Reading lamp"));
+        assert!(light.set_alias("").await.is_err());
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_convenience_setters() {
+        use crate::types::{Kelvin, SceneMode, Speed};
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 30)).await.unwrap();
+        let light = bulb.light();
+        light
+            .set_scene(SceneMode::Ocean, Speed::create(150), Brightness::create(30))
+            .await
+            .unwrap();
+        let pilot = bulb.pilot().await;
+        assert_eq!(
+            (pilot["speed"].clone(), pilot["dimming"].clone()),
+            (json!(150), json!(30))
+        );
+        assert!(pilot["sceneId"].is_u64());
+
+        light.set_color(Color::rgb(0, 255, 0), None).await.unwrap();
+        assert_eq!(bulb.pilot().await["g"], 255);
+        light
+            .set_temperature(Kelvin::create(2700).unwrap(), Brightness::create(80))
+            .await
+            .unwrap();
+        let pilot = bulb.pilot().await;
+        assert_eq!(
+            (pilot["temp"].clone(), pilot["dimming"].clone()),
+            (json!(2700), json!(80))
+        );
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_brightness_or_off() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 35)).await.unwrap();
+        let light = bulb.light();
+        light.set_brightness_or_off(5).await.unwrap();
+        let pilot = bulb.pilot().await;
+        assert_eq!(
+            (pilot["state"].clone(), pilot["dimming"].clone()),
+            (json!(true), json!(10))
+        );
+
+        light.set_brightness_or_off(0).await.unwrap();
+        assert_eq!(bulb.pilot().await["state"], false);
+        assert!(!light.status().unwrap().emitting());
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_set_checked_clamps_temp() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 36)).await.unwrap();
+        let light = bulb.light();
+        let mut payload = Payload::new();
+        payload.temp(&Kelvin::create(8000).unwrap());
+        let response = light.set_checked(&payload).await.unwrap();
+        assert_eq!(bulb.pilot().await["temp"], 6500);
+        let adjustment = &response.adjustments()[0];
+        assert_eq!((adjustment.requested, adjustment.applied), (8000, 6500));
+
+        payload.temp(&Kelvin::create(4000).unwrap());
+        let response = light.set_checked(&payload).await.unwrap();
+        assert!(response.adjustments().is_empty());
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_set_with_confirmation_updates_cache() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 43)).await.unwrap();
+        let light = bulb.light();
+        let payload = Payload::from(&Brightness::create(40).unwrap());
+        light.set_with_confirmation(&payload, true).await.unwrap();
+        let status = light.status().unwrap();
+        assert!(status.is_confirmed());
+        assert_eq!(status.brightness().map(Brightness::value), Some(40));
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_effect_frames_skip_history() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 44)).await.unwrap();
+        let light = bulb.light();
+        let effect = crate::effects::Effect::new()
+            .keyframe(crate::effects::Keyframe::new(Duration::ZERO).color(Color::rgb(0, 0, 255)));
+        effect.run_on_light(&light).wait().await;
+
+        assert_eq!(bulb.pilot().await["b"], 255);
+        assert!(light.history().await.is_empty());
+        bulb.stop().await;
+    }
+
+    #[cfg(feature = "audio-sync")]
+    #[tokio::test]
+    async fn test_audio_sync_sends_in_background() {
+        use crate::audio::{AudioSync, AudioSyncConfig};
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 45)).await.unwrap();
+        let mut sync = AudioSync::new(vec![bulb.light()], AudioSyncConfig::default());
+        for _ in 0..5 {
+            sync.feed_samples(&[0.5; 256]).await;
+        }
+        runtime::sleep(Duration::from_millis(200)).await;
+
+        // Frames fed while the first was in flight collapse into the latest
+        let sent = bulb.requests().await.len();
+        assert!((1..=5).contains(&sent));
+        assert_eq!(bulb.pilot().await["dimming"], 100);
+        bulb.stop().await;
+    }
+
+    #[cfg(feature = "audio-sync")]
+    #[tokio::test]
+    async fn test_audio_sync_drop_cancels_frames() {
+        use crate::audio::{AudioSync, AudioSyncConfig};
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 49)).await.unwrap();
+        let mut sync = AudioSync::new(vec![bulb.light()], AudioSyncConfig::default());
+        // The test runtime is single threaded, so the frame has not started yet
+        sync.feed_samples(&[0.5; 256]).await;
+        drop(sync);
+        runtime::sleep(Duration::from_millis(200)).await;
+
+        assert!(bulb.requests().await.is_empty());
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_command_queue() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 37)).await.unwrap();
+        let mut light = bulb.light();
+        let dim =
+            [10, 20, 30, 40, 50].map(|value| Payload::from(&Brightness::create(value).unwrap()));
+
+        let (first, second, third) =
+            futures::join!(light.set(&dim[0]), light.set(&dim[1]), light.set(&dim[2]));
+        assert!(first.is_ok() && second.is_ok() && third.is_ok());
+        let dimming: Vec<_> = bulb
+            .requests()
+            .await
+            .iter()
+            .map(|r| r["params"]["dimming"].clone())
+            .collect();
+        assert_eq!(dimming, [10, 20, 30]);
+
+        // Clones share the queue, so a full queue refuses their commands too
+        light.set_queue_depth(1);
+        let clone = light.clone();
+        let (first, second) = futures::join!(light.set(&dim[3]), clone.set(&dim[4]));
+        assert!(first.is_ok());
+        assert!(matches!(second, Err(Error::QueueFull { depth: 1, .. })));
+        assert_eq!(bulb.pilot().await["dimming"], 40);
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_set() {
+        use crate::broadcast::{BroadcastOptions, broadcast_set_with};
+
+        let first = MockBulb::start(Ipv4Addr::new(127, 0, 0, 38)).await.unwrap();
+        let second = MockBulb::start(Ipv4Addr::new(127, 0, 0, 39)).await.unwrap();
+        let payload = Payload::from(&Brightness::create(70).unwrap());
+        let options = BroadcastOptions {
+            timeout: Duration::from_millis(300),
+            ..Default::default()
+        };
+
+        let ips = [first.ip(), second.ip(), first.ip()];
+        let responses = broadcast_set_with(&ips, &payload, &options).await.unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(first.pilot().await["dimming"], 70);
+        assert_eq!(second.pilot().await["dimming"], 70);
+
+        // Nothing answers on .40, which times out without failing the rest
+        let silent = Ipv4Addr::new(127, 0, 0, 40);
+        let ips = [first.ip(), silent];
+        let results = broadcast_set_with(&ips, &payload, &options).await.unwrap();
+        assert!(!results.all_ok());
+        assert_eq!(results.successes().count(), 1);
+        let failures: Vec<_> = results.failures().collect();
+        assert!(matches!(failures[..], [(ip, Error::Socket { .. })] if *ip == silent));
+        first.stop().await;
+        second.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_set_fast_keys_results_by_light() {
+        use crate::room::Room;
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 46)).await.unwrap();
+        let mut room = Room::new("Hall");
+        let first = room.new_light(bulb.light()).unwrap();
+        let moved = Ipv4Addr::new(127, 0, 0, 47);
+        let second = room.new_light(Light::new(moved, None)).unwrap();
+        // As if the second handle re-resolved to the same bulb
+        for light in room.members_mut().filter(|light| light.ip() == moved) {
+            light.set_ip(bulb.ip());
+        }
+
+        let payload = Payload::from(&Brightness::create(30).unwrap());
+        let results = room.set_fast(&payload).await.unwrap();
+        assert!(results.all_ok());
+        let mut ids: Vec<_> = results.iter().map(|(id, _)| *id).collect();
+        ids.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(ids, expected);
+        bulb.stop().await;
+    }
+
+    /// Sends fine but fails every receive, like a socket hit by WSAECONNRESET.
+    #[derive(Debug)]
+    struct ResetTransport;
+
+    impl runtime::Transport for ResetTransport {
+        fn bind(
+            &self,
+            _addr: SocketAddr,
+        ) -> runtime::BoxFuture<'_, std::io::Result<Box<dyn runtime::Datagram>>> {
+            Box::pin(async move { Ok(Box::new(ResetTransport) as Box<dyn runtime::Datagram>) })
+        }
+    }
+
+    impl runtime::Datagram for ResetTransport {
+        fn send_to<'a>(
+            &'a self,
+            buf: &'a [u8],
+            _addr: SocketAddr,
+        ) -> runtime::BoxFuture<'a, std::io::Result<usize>> {
+            Box::pin(async move { Ok(buf.len()) })
+        }
+
+        fn recv_from<'a>(
+            &'a self,
+            _buf: &'a mut [u8],
+        ) -> runtime::BoxFuture<'a, std::io::Result<(usize, SocketAddr)>> {
+            Box::pin(async move { Err(std::io::ErrorKind::ConnectionReset.into()) })
+        }
+
+        fn set_broadcast(&self, _broadcast: bool) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_stops_on_receive_error() {
+        use crate::broadcast::{BroadcastOptions, broadcast_set_with};
+
+        let options = BroadcastOptions {
+            timeout: Duration::from_secs(5),
+            transport: Arc::new(ResetTransport),
+        };
+        let payload = Payload::from(&Brightness::create(70).unwrap());
+        let start = std::time::Instant::now();
+        let results = broadcast_set_with(&[Ipv4Addr::new(10, 0, 0, 9)], &payload, &options)
+            .await
+            .unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let failures: Vec<_> = results.failures().collect();
+        assert!(matches!(
+            failures[..],
+            [(_, Error::Socket { err, .. })] if err.kind() == std::io::ErrorKind::ConnectionReset
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_room_batch_results() {
+        use crate::retry::NoRetry;
+        use crate::room::Room;
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 41)).await.unwrap();
+        let mut room = Room::new("Office");
+        let present = room.new_light(bulb.light()).unwrap();
+        let mut gone = Light::new(Ipv4Addr::new(127, 0, 0, 42), None);
+        gone.set_retry_policy(Some(Arc::new(NoRetry)));
+        let gone = room.new_light(gone).unwrap();
+
+        let results = room
+            .set(&Payload::from(&Brightness::create(30).unwrap()))
+            .await;
+        assert_eq!(results.len(), 2);
+        assert!(!results.all_ok());
+        let failed: Vec<_> = results.failures().map(|(id, _)| *id).collect();
+        assert_eq!(failed, [gone]);
+        assert_eq!(
+            results.successes().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [present]
+        );
+        assert_eq!(bulb.pilot().await["dimming"], 30);
+        assert!(results.into_result().is_err());
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_toggle_with_cache() {
+        use crate::light::ToggleOptions;
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 31)).await.unwrap();
+        let light = bulb.light();
+        let options = ToggleOptions::default();
+
+        // The first toggle queries the bulb, later ones use the cache
+        light.toggle_with_cache(&options).await.unwrap();
+        let on = bulb.pilot().await["state"].as_bool().unwrap();
+        light.toggle_with_cache(&options).await.unwrap();
+        assert_eq!(bulb.pilot().await["state"], !on);
+        let methods: Vec<_> = bulb
+            .requests()
+            .await
+            .iter()
+            .map(|r| r["method"].clone())
+            .collect();
+        assert_eq!(methods, ["getPilot", "setState", "setState"]);
+
+        // A quick second press is ignored
+        let debounced = ToggleOptions {
+            debounce: Some(Duration::from_secs(60)),
+            ..options
+        };
+        assert!(light.toggle_with_cache(&debounced).await.unwrap().is_some());
+        assert!(light.toggle_with_cache(&debounced).await.unwrap().is_none());
+        assert_eq!(bulb.pilot().await["state"], on);
+
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_turn_off_after() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 32)).await.unwrap();
+        let mut room = crate::Room::new("Hall");
+        let id = room.new_light(bulb.light()).unwrap();
+        let light = room.read(&id).unwrap().clone();
+        light.set_power(&PowerMode::On).await.unwrap();
+
+        // A new timer replaces the previous one
+        let first = light.turn_off_after(Duration::from_secs(60));
+        let second = room.turn_off_after(Duration::from_millis(50));
+        assert!(matches!(first.await, Err(Error::Cancelled)));
+        assert_eq!(second.await.unwrap().len(), 1);
+        assert_eq!(bulb.pilot().await["state"], false);
+        assert!(!room.cancel_turn_off());
+
+        let timer = light.turn_off_after(Duration::from_secs(60));
+        assert!(room.cancel_turn_off());
+        assert!(matches!(timer.await, Err(Error::Cancelled)));
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_shared_membership() {
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 34)).await.unwrap();
+        let light = bulb.light();
+        let mut room = crate::Room::new("Kitchen");
+        let id = room.new_light(light.share()).unwrap();
+        let mut zone = crate::Group::new("Downstairs");
+        assert!(zone.attach(light.share()));
+        assert!(!zone.attach(light.share()));
+        assert!(room.read(&id).unwrap().is_shared_with(&light));
+        assert!(!light.clone().is_shared_with(&light));
+
+        // Commands through the group update the room member's cached status
+        zone.set_power(&PowerMode::On).await.unwrap();
+        assert!(room.read(&id).unwrap().status().unwrap().emitting());
+        assert_eq!(bulb.pilot().await["state"], true);
+        bulb.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy() {
+        use crate::retry::{self, FixedRetry, NoRetry, RetryAttempt};
+        use std::sync::Mutex as StdMutex;
+
+        let bulb = MockBulb::start(Ipv4Addr::new(127, 0, 0, 33)).await.unwrap();
+        bulb.set_unresponsive(true).await;
+        let mut light = bulb.light();
+
+        light.set_retry_policy(Some(Arc::new(NoRetry)));
+        assert!(light.get_status().await.is_err());
+        assert_eq!(bulb.requests().await.len(), 1);
+
+        // Other tests retry too, so only count this bulb's retries
+        let seen = Arc::new(StdMutex::new(Vec::new()));
+        let hook_seen = Arc::clone(&seen);
+        retry::set_retry_hook(Some(Arc::new(move |attempt: &RetryAttempt| {
+            if attempt.ip == Ipv4Addr::new(127, 0, 0, 33) {
+                hook_seen.lock().unwrap().push(attempt.retry);
+            }
+        })));
+        let delays = [Duration::from_millis(10); 2];
+        light.set_retry_policy(Some(Arc::new(FixedRetry::new(delays))));
+        assert!(light.get_status().await.is_err());
+        retry::set_retry_hook(None);
+        assert_eq!(bulb.requests().await.len(), 4);
+        assert_eq!(*seen.lock().unwrap(), [1, 2]);
+        bulb.stop().await;
+    }
+}
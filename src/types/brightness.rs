@@ -1,9 +1,13 @@
 //! Brightness control for Wiz lights.
 
+use core::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use super::RangeError;
+
 /// Brightness level from 10 to 100 percent.
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Brightness {
     pub(crate) value: u8,
 }
@@ -22,11 +26,12 @@ impl Brightness {
 
     /// Returns None if value is outside valid range (10-100).
     pub fn create(value: u8) -> Option<Self> {
-        if Self::is_valid(value) {
-            Some(Brightness { value })
-        } else {
-            None
-        }
+        Self::try_create(value).ok()
+    }
+
+    /// Like [`Brightness::create`], but says why the value was rejected.
+    pub fn try_create(value: u8) -> Result<Self, RangeError> {
+        RangeError::check(value, Self::MIN, Self::MAX).map(|value| Brightness { value })
     }
 
     /// Returns default (100%) if value is invalid.
@@ -38,7 +43,61 @@ impl Brightness {
         }
     }
 
+    /// Maps a 0-100 slider value, where 0 means off, to what bulbs accept.
+    ///
+    /// Bulbs do not dim below 10%, so 1-9 are raised to 10; values above
+    /// 100 are capped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{Brightness, BrightnessOrOff};
+    ///
+    /// assert_eq!(Brightness::from_percent_or_off(0), BrightnessOrOff::Off);
+    /// assert_eq!(
+    ///     Brightness::from_percent_or_off(5),
+    ///     BrightnessOrOff::On(Brightness::create(10).unwrap())
+    /// );
+    /// assert_eq!(Brightness::from_percent_or_off(150), BrightnessOrOff::On(Brightness::new()));
+    /// ```
+    pub fn from_percent_or_off(percent: u8) -> BrightnessOrOff {
+        if percent == 0 {
+            return BrightnessOrOff::Off;
+        }
+        BrightnessOrOff::On(Brightness {
+            value: percent.clamp(Self::MIN, Self::MAX),
+        })
+    }
+
     fn is_valid(value: u8) -> bool {
         (Self::MIN..=Self::MAX).contains(&value)
     }
 }
+
+/// A brightness from a slider where 0 means off, see
+/// [`Brightness::from_percent_or_off`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BrightnessOrOff {
+    Off,
+    On(Brightness),
+}
+
+impl TryFrom<u8> for Brightness {
+    type Error = RangeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::try_create(value)
+    }
+}
+
+impl From<Brightness> for u8 {
+    fn from(value: Brightness) -> Self {
+        value.value
+    }
+}
+
+impl fmt::Display for Brightness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.value)
+    }
+}
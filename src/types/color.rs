@@ -1,10 +1,20 @@
 //! RGB, RGBW, and RGBWW color representations.
 
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+
+use super::Kelvin;
+use crate::errors::Error;
+#[cfg(not(feature = "std"))]
+use crate::float::FloatExt;
 
 /// An RGB color with red, green, and blue components (0-255 each).
-#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct Color {
     pub(crate) red: u8,
     pub(crate) green: u8,
@@ -33,24 +43,331 @@ impl Color {
     pub fn blue(&self) -> u8 {
         self.blue
     }
+
+    /// Approximate the RGB appearance of a white at the given temperature.
+    ///
+    /// Uses a curve fit of the Planckian locus, accurate enough to emulate
+    /// white temperatures on RGB-only code paths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{Color, Kelvin};
+    ///
+    /// let warm = Color::from_kelvin(&Kelvin::create(2700).unwrap());
+    /// assert_eq!(warm.red(), 255);
+    /// assert!(warm.blue() < warm.green());
+    /// ```
+    pub fn from_kelvin(kelvin: &Kelvin) -> Self {
+        let t = f64::from(kelvin.kelvin()) / 100.0;
+        let red = if t <= 66.0 {
+            255.0
+        } else {
+            329.698727446 * (t - 60.0).powf(-0.1332047592)
+        };
+        let green = if t <= 66.0 {
+            99.4708025861 * t.ln() - 161.1195681661
+        } else {
+            288.1221695283 * (t - 60.0).powf(-0.0755148492)
+        };
+        let blue = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.5177312231 * (t - 10.0).ln() - 305.0447927307
+        };
+        let channel = |c: f64| c.round().clamp(0.0, 255.0) as u8;
+        Self::rgb(channel(red), channel(green), channel(blue))
+    }
+
+    /// Create a color from hue (degrees), saturation and value (`0.0..=1.0`).
+    ///
+    /// Hue wraps around, so `-30.0` and `330.0` are the same. Keeping the
+    /// value from [`Color::to_hsv`] while changing hue preserves brightness.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Color;
+    ///
+    /// assert_eq!(Color::from_hsv(120.0, 1.0, 0.5), Color::rgb(0, 128, 0));
+    /// ```
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let s = saturation.clamp(0.0, 1.0);
+        let v = value.clamp(0.0, 1.0);
+        let chroma = v * s;
+        Self::from_hue_chroma(hue, chroma, v - chroma)
+    }
+
+    /// Convert to hue (degrees, `0.0..360.0`), saturation and value (`0.0..=1.0`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Color;
+    ///
+    /// let (h, s, v) = Color::rgb(0, 128, 0).to_hsv();
+    /// assert_eq!(h, 120.0);
+    /// assert_eq!(s, 1.0);
+    /// assert!((v - 0.5).abs() < 0.01);
+    /// assert_eq!(Color::from_hsv(h, s, v), Color::rgb(0, 128, 0));
+    /// ```
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    /// Create a color from hue (degrees), saturation and lightness (`0.0..=1.0`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Color;
+    ///
+    /// assert_eq!(Color::from_hsl(0.0, 1.0, 0.5), Color::rgb(255, 0, 0));
+    /// assert_eq!(Color::from_hsl(0.0, 0.0, 1.0), Color::rgb(255, 255, 255));
+    /// ```
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let s = saturation.clamp(0.0, 1.0);
+        let l = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        Self::from_hue_chroma(hue, chroma, l - chroma / 2.0)
+    }
+
+    /// Convert to hue (degrees, `0.0..360.0`), saturation and lightness (`0.0..=1.0`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Color;
+    ///
+    /// let (h, s, l) = Color::rgb(255, 0, 0).to_hsl();
+    /// assert_eq!((h, s, l), (0.0, 1.0, 0.5));
+    /// ```
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// Create a color from CIE 1931 xy chromaticity and brightness
+    /// (`0.0..=1.0`), as used by Hue-style APIs.
+    ///
+    /// Assumes sRGB primaries. Colors outside the sRGB gamut are moved to its
+    /// edge, and colors too bright to show at the requested brightness are
+    /// scaled down to keep their hue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Color;
+    ///
+    /// assert_eq!(Color::from_xy(0.3127, 0.3290, 1.0), Color::rgb(255, 255, 255));
+    /// assert_eq!(Color::from_xy(0.64, 0.33, 1.0), Color::rgb(255, 0, 0));
+    /// ```
+    pub fn from_xy(x: f32, y: f32, brightness: f32) -> Self {
+        if y <= 0.0 {
+            return Self::new();
+        }
+        let luminance = brightness.clamp(0.0, 1.0);
+        let big_x = luminance / y * x;
+        let big_z = luminance / y * (1.0 - x - y);
+
+        let r = 3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z;
+        let g = -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z;
+        let b = 0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z;
+        let (r, g, b) = (r.max(0.0), g.max(0.0), b.max(0.0));
+        let scale = r.max(g).max(b).max(1.0);
+
+        let channel = |c: f32| {
+            let c = c / scale;
+            let encoded = if c <= 0.0031308 {
+                12.92 * c
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (encoded * 255.0).round().clamp(0.0, 255.0) as u8
+        };
+        Self::rgb(channel(r), channel(g), channel(b))
+    }
+
+    /// Convert to CIE 1931 xy chromaticity, assuming sRGB primaries.
+    ///
+    /// Brightness is lost; black maps to the D65 white point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Color;
+    ///
+    /// let (x, y) = Color::rgb(255, 0, 0).to_xy();
+    /// assert!((x - 0.64).abs() < 0.001 && (y - 0.33).abs() < 0.001);
+    /// ```
+    pub fn to_xy(&self) -> (f32, f32) {
+        let linear = |c: u8| {
+            let c = f32::from(c) / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        let (r, g, b) = (linear(self.red), linear(self.green), linear(self.blue));
+        let big_x = 0.4124 * r + 0.3576 * g + 0.1805 * b;
+        let big_y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let big_z = 0.0193 * r + 0.1192 * g + 0.9505 * b;
+        let sum = big_x + big_y + big_z;
+        if sum <= 0.0 {
+            return (0.3127, 0.3290);
+        }
+        (big_x / sum, big_y / sum)
+    }
+
+    /// Hue in degrees plus the largest and smallest channel in `0.0..=1.0`.
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let r = f32::from(self.red) / 255.0;
+        let g = f32::from(self.green) / 255.0;
+        let b = f32::from(self.blue) / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, max, min)
+    }
+
+    fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Self {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |c: f32| ((c + offset) * 255.0).round().clamp(0.0, 255.0) as u8;
+        Self::rgb(channel(r), channel(g), channel(b))
+    }
+
+    /// Format as a lowercase `#rrggbb` hex string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Color;
+    ///
+    /// assert_eq!(Color::rgb(255, 128, 0).to_hex(), "#ff8000");
+    /// ```
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    /// Look up a CSS named color such as `"coral"`, ignoring case.
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        NAMED_COLORS
+            .binary_search_by(|(n, _)| (*n).cmp(name.as_str()))
+            .ok()
+            .map(|i| Self::from_u32(NAMED_COLORS[i].1))
+    }
+
+    fn from_u32(value: u32) -> Self {
+        Self::rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
+    }
+
+    fn parse_hex(hex: &str) -> Option<Self> {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        match hex.len() {
+            6 => u32::from_str_radix(hex, 16).ok().map(Self::from_u32),
+            // Shorthand #rgb expands each digit, as in CSS
+            3 => {
+                let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).ok().map(|d| d * 17);
+                Some(Self::rgb(digit(0)?, digit(1)?, digit(2)?))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_components(s: &str) -> Option<Self> {
+        let parts = s
+            .split(',')
+            .map(|c| c.trim().parse::<u8>().ok())
+            .collect::<Option<Vec<u8>>>()?;
+        match parts[..] {
+            [red, green, blue] => Some(Self::rgb(red, green, blue)),
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for Color {
-    type Err = String;
+    type Err = Error;
 
-    /// Parse from comma-separated string (e.g., "255,128,0").
-    fn from_str(s: &str) -> Result<Self, String> {
-        let parts: Vec<u8> = s.split(',').map(|c| c.parse().unwrap_or(0)).collect();
-        if parts.len() == 3 {
-            Ok(Self::rgb(parts[0], parts[1], parts[2]))
+    /// Parse a color from any of these forms:
+    ///
+    /// - comma-separated components: `"255,128,0"`
+    /// - hex: `"#ff8000"`, `"0xff8000"` or shorthand `"#f80"`
+    /// - CSS color names: `"orange"`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::str::FromStr;
+    /// use wiz_lights_rs::Color;
+    ///
+    /// assert_eq!(Color::from_str("255,128,0").unwrap(), Color::rgb(255, 128, 0));
+    /// assert_eq!(Color::from_str("#FF8000").unwrap(), Color::rgb(255, 128, 0));
+    /// assert_eq!(Color::from_str("0xff8000").unwrap(), Color::rgb(255, 128, 0));
+    /// assert_eq!(Color::from_str("DarkOrange").unwrap(), Color::rgb(255, 140, 0));
+    /// assert!(Color::from_str("256,0,0").is_err());
+    /// assert!(Color::from_str("#12345").is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let trimmed = s.trim();
+        let parsed = if let Some(hex) = trimmed.strip_prefix('#') {
+            Self::parse_hex(hex)
+        } else if let Some(hex) = trimmed
+            .strip_prefix("0x")
+            .or_else(|| trimmed.strip_prefix("0X"))
+        {
+            Self::parse_hex(hex)
+        } else if trimmed.contains(',') {
+            Self::parse_components(trimmed)
         } else {
-            Err("Expected format: r,g,b".into())
-        }
+            Self::named(trimmed)
+        };
+        parsed.ok_or_else(|| Error::InvalidColorString(s.to_string()))
+    }
+}
+
+impl fmt::Display for Color {
+    /// Formats as `#rrggbb`, which [`Color::from_str`] parses back.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
     }
 }
 
 /// An RGBW color (RGB + warm white, 0-255 each).
-#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct ColorRGBW {
     pub red: u8,
     pub green: u8,
@@ -86,8 +403,15 @@ impl FromStr for ColorRGBW {
     }
 }
 
+impl fmt::Display for ColorRGBW {
+    /// Formats as `r,g,b,w`, which [`ColorRGBW::from_str`] parses back.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.red, self.green, self.blue, self.warm)
+    }
+}
+
 /// An RGBWW color (RGB + cool white + warm white, 0-255 each).
-#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct ColorRGBWW {
     pub red: u8,
     pub green: u8,
@@ -128,3 +452,166 @@ impl FromStr for ColorRGBWW {
         }
     }
 }
+
+impl fmt::Display for ColorRGBWW {
+    /// Formats as `r,g,b,c,w`, which [`ColorRGBWW::from_str`] parses back.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{}",
+            self.red, self.green, self.blue, self.cool, self.warm
+        )
+    }
+}
+
+/// CSS named colors, sorted by name for binary search.
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xF0F8FF),
+    ("antiquewhite", 0xFAEBD7),
+    ("aqua", 0x00FFFF),
+    ("aquamarine", 0x7FFFD4),
+    ("azure", 0xF0FFFF),
+    ("beige", 0xF5F5DC),
+    ("bisque", 0xFFE4C4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xFFEBCD),
+    ("blue", 0x0000FF),
+    ("blueviolet", 0x8A2BE2),
+    ("brown", 0xA52A2A),
+    ("burlywood", 0xDEB887),
+    ("cadetblue", 0x5F9EA0),
+    ("chartreuse", 0x7FFF00),
+    ("chocolate", 0xD2691E),
+    ("coral", 0xFF7F50),
+    ("cornflowerblue", 0x6495ED),
+    ("cornsilk", 0xFFF8DC),
+    ("crimson", 0xDC143C),
+    ("cyan", 0x00FFFF),
+    ("darkblue", 0x00008B),
+    ("darkcyan", 0x008B8B),
+    ("darkgoldenrod", 0xB8860B),
+    ("darkgray", 0xA9A9A9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xA9A9A9),
+    ("darkkhaki", 0xBDB76B),
+    ("darkmagenta", 0x8B008B),
+    ("darkolivegreen", 0x556B2F),
+    ("darkorange", 0xFF8C00),
+    ("darkorchid", 0x9932CC),
+    ("darkred", 0x8B0000),
+    ("darksalmon", 0xE9967A),
+    ("darkseagreen", 0x8FBC8F),
+    ("darkslateblue", 0x483D8B),
+    ("darkslategray", 0x2F4F4F),
+    ("darkslategrey", 0x2F4F4F),
+    ("darkturquoise", 0x00CED1),
+    ("darkviolet", 0x9400D3),
+    ("deeppink", 0xFF1493),
+    ("deepskyblue", 0x00BFFF),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1E90FF),
+    ("firebrick", 0xB22222),
+    ("floralwhite", 0xFFFAF0),
+    ("forestgreen", 0x228B22),
+    ("fuchsia", 0xFF00FF),
+    ("gainsboro", 0xDCDCDC),
+    ("ghostwhite", 0xF8F8FF),
+    ("gold", 0xFFD700),
+    ("goldenrod", 0xDAA520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xADFF2F),
+    ("grey", 0x808080),
+    ("honeydew", 0xF0FFF0),
+    ("hotpink", 0xFF69B4),
+    ("indianred", 0xCD5C5C),
+    ("indigo", 0x4B0082),
+    ("ivory", 0xFFFFF0),
+    ("khaki", 0xF0E68C),
+    ("lavender", 0xE6E6FA),
+    ("lavenderblush", 0xFFF0F5),
+    ("lawngreen", 0x7CFC00),
+    ("lemonchiffon", 0xFFFACD),
+    ("lightblue", 0xADD8E6),
+    ("lightcoral", 0xF08080),
+    ("lightcyan", 0xE0FFFF),
+    ("lightgoldenrodyellow", 0xFAFAD2),
+    ("lightgray", 0xD3D3D3),
+    ("lightgreen", 0x90EE90),
+    ("lightgrey", 0xD3D3D3),
+    ("lightpink", 0xFFB6C1),
+    ("lightsalmon", 0xFFA07A),
+    ("lightseagreen", 0x20B2AA),
+    ("lightskyblue", 0x87CEFA),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xB0C4DE),
+    ("lightyellow", 0xFFFFE0),
+    ("lime", 0x00FF00),
+    ("limegreen", 0x32CD32),
+    ("linen", 0xFAF0E6),
+    ("magenta", 0xFF00FF),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66CDAA),
+    ("mediumblue", 0x0000CD),
+    ("mediumorchid", 0xBA55D3),
+    ("mediumpurple", 0x9370DB),
+    ("mediumseagreen", 0x3CB371),
+    ("mediumslateblue", 0x7B68EE),
+    ("mediumspringgreen", 0x00FA9A),
+    ("mediumturquoise", 0x48D1CC),
+    ("mediumvioletred", 0xC71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xF5FFFA),
+    ("mistyrose", 0xFFE4E1),
+    ("moccasin", 0xFFE4B5),
+    ("navajowhite", 0xFFDEAD),
+    ("navy", 0x000080),
+    ("oldlace", 0xFDF5E6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6B8E23),
+    ("orange", 0xFFA500),
+    ("orangered", 0xFF4500),
+    ("orchid", 0xDA70D6),
+    ("palegoldenrod", 0xEEE8AA),
+    ("palegreen", 0x98FB98),
+    ("paleturquoise", 0xAFEEEE),
+    ("palevioletred", 0xDB7093),
+    ("papayawhip", 0xFFEFD5),
+    ("peachpuff", 0xFFDAB9),
+    ("peru", 0xCD853F),
+    ("pink", 0xFFC0CB),
+    ("plum", 0xDDA0DD),
+    ("powderblue", 0xB0E0E6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xFF0000),
+    ("rosybrown", 0xBC8F8F),
+    ("royalblue", 0x4169E1),
+    ("saddlebrown", 0x8B4513),
+    ("salmon", 0xFA8072),
+    ("sandybrown", 0xF4A460),
+    ("seagreen", 0x2E8B57),
+    ("seashell", 0xFFF5EE),
+    ("sienna", 0xA0522D),
+    ("silver", 0xC0C0C0),
+    ("skyblue", 0x87CEEB),
+    ("slateblue", 0x6A5ACD),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xFFFAFA),
+    ("springgreen", 0x00FF7F),
+    ("steelblue", 0x4682B4),
+    ("tan", 0xD2B48C),
+    ("teal", 0x008080),
+    ("thistle", 0xD8BFD8),
+    ("tomato", 0xFF6347),
+    ("turquoise", 0x40E0D0),
+    ("violet", 0xEE82EE),
+    ("wheat", 0xF5DEB3),
+    ("white", 0xFFFFFF),
+    ("whitesmoke", 0xF5F5F5),
+    ("yellow", 0xFFFF00),
+    ("yellowgreen", 0x9ACD32),
+];
@@ -2,6 +2,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::RangeError;
+
 /// Fan power state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum FanState {
@@ -11,6 +13,24 @@ pub enum FanState {
 }
 
 impl FanState {
+    /// Returns None if value is not a known protocol value.
+    pub fn create(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(FanState::Off),
+            1 => Some(FanState::On),
+            _ => None,
+        }
+    }
+
+    /// Like [`FanState::create`], but says why the value was rejected.
+    pub fn try_create(value: u8) -> Result<Self, RangeError> {
+        Self::create(value).ok_or(RangeError {
+            got: value.into(),
+            min: 0,
+            max: 1,
+        })
+    }
+
     pub fn value(self) -> u8 {
         self as u8
     }
@@ -31,6 +51,24 @@ pub enum FanMode {
 }
 
 impl FanMode {
+    /// Returns None if value is not a known protocol value.
+    pub fn create(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(FanMode::Normal),
+            2 => Some(FanMode::Breeze),
+            _ => None,
+        }
+    }
+
+    /// Like [`FanMode::create`], but says why the value was rejected.
+    pub fn try_create(value: u8) -> Result<Self, RangeError> {
+        Self::create(value).ok_or(RangeError {
+            got: value.into(),
+            min: 1,
+            max: 2,
+        })
+    }
+
     pub fn value(self) -> u8 {
         self as u8
     }
@@ -45,6 +83,24 @@ pub enum FanDirection {
 }
 
 impl FanDirection {
+    /// Returns None if value is not a known protocol value.
+    pub fn create(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(FanDirection::Forward),
+            1 => Some(FanDirection::Reverse),
+            _ => None,
+        }
+    }
+
+    /// Like [`FanDirection::create`], but says why the value was rejected.
+    pub fn try_create(value: u8) -> Result<Self, RangeError> {
+        Self::create(value).ok_or(RangeError {
+            got: value.into(),
+            min: 0,
+            max: 1,
+        })
+    }
+
     pub fn value(self) -> u8 {
         self as u8
     }
@@ -62,12 +118,13 @@ impl FanSpeed {
 
     /// Create a fan speed. Returns None if out of range (1 to max_speed).
     pub fn create(value: u8, max_speed: Option<u8>) -> Option<Self> {
+        Self::try_create(value, max_speed).ok()
+    }
+
+    /// Like [`FanSpeed::create`], but says why the value was rejected.
+    pub fn try_create(value: u8, max_speed: Option<u8>) -> Result<Self, RangeError> {
         let max = max_speed.unwrap_or(Self::DEFAULT_MAX);
-        if (1..=max).contains(&value) {
-            Some(FanSpeed { value })
-        } else {
-            None
-        }
+        RangeError::check(value, 1, max).map(|value| FanSpeed { value })
     }
 
     pub fn value(self) -> u8 {
@@ -1,6 +1,8 @@
 //! Hue and Saturation color representation.
 
-use super::Color;
+use super::{Color, RangeError};
+#[cfg(not(feature = "std"))]
+use crate::float::FloatExt;
 
 /// Hue and Saturation color representation.
 ///
@@ -37,11 +39,15 @@ impl HueSaturation {
     /// assert!(HueSaturation::create(180, 101).is_none()); // Invalid saturation
     /// ```
     pub fn create(hue: u16, saturation: u8) -> Option<Self> {
-        if hue <= 360 && saturation <= 100 {
-            Some(HueSaturation { hue, saturation })
-        } else {
-            None
-        }
+        Self::try_create(hue, saturation).ok()
+    }
+
+    /// Like [`HueSaturation::create`], but says which value was rejected,
+    /// checking the hue first.
+    pub fn try_create(hue: u16, saturation: u8) -> Result<Self, RangeError> {
+        let hue = RangeError::check(hue, 0, 360)?;
+        let saturation = RangeError::check(saturation, 0, 100)?;
+        Ok(HueSaturation { hue, saturation })
     }
 
     /// Get the hue value.
@@ -97,6 +103,45 @@ impl HueSaturation {
 
         Color::rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
     }
+
+    /// Convert to RGB Color at the given HSV value (`0.0..=1.0`).
+    ///
+    /// Pass the value from [`Color::to_hsv`] of the current color to change
+    /// hue without changing brightness.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::HueSaturation;
+    ///
+    /// let hs = HueSaturation::create(240, 100).unwrap();
+    /// let color = hs.to_color_with_value(0.5);
+    /// assert_eq!((color.red(), color.green(), color.blue()), (0, 0, 128));
+    /// ```
+    pub fn to_color_with_value(&self, value: f32) -> Color {
+        Color::from_hsv(self.hue as f32, self.saturation as f32 / 100.0, value)
+    }
+}
+
+impl From<&Color> for HueSaturation {
+    /// Takes hue and saturation from the color's HSV form, dropping its value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{Color, HueSaturation};
+    ///
+    /// let hs = HueSaturation::from(&Color::rgb(0, 64, 128));
+    /// assert_eq!(hs.hue(), 210);
+    /// assert_eq!(hs.saturation(), 100);
+    /// ```
+    fn from(color: &Color) -> Self {
+        let (hue, saturation, _) = color.to_hsv();
+        HueSaturation {
+            hue: (hue.round() as u16) % 360,
+            saturation: (saturation * 100.0).round() as u8,
+        }
+    }
 }
 
 impl From<&HueSaturation> for Color {
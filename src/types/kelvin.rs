@@ -1,7 +1,14 @@
 //! Color temperature control.
 
+use core::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use super::{Color, RangeError};
+use crate::config::KelvinRange;
+#[cfg(not(feature = "std"))]
+use crate::float::FloatExt;
+
 /// Color temperature in Kelvin, with valid values from 1000K to 8000K.
 ///
 /// Lower values produce warmer (more yellow/orange) light, while higher
@@ -9,7 +16,7 @@ use serde::{Deserialize, Serialize};
 /// - 2700K: Warm white (incandescent-like)
 /// - 4000K: Neutral white
 /// - 6500K: Daylight
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Kelvin {
     pub(crate) kelvin: u16,
 }
@@ -17,6 +24,9 @@ pub struct Kelvin {
 impl Kelvin {
     const MIN: u16 = 1000;
     const MAX: u16 = 8000;
+    /// Mireds of [`Kelvin::MAX`] and [`Kelvin::MIN`].
+    const MIN_MIREDS: u16 = 125;
+    const MAX_MIREDS: u16 = 1000;
 
     /// Create a new Kelvin with the default value (1000K).
     ///
@@ -49,12 +59,150 @@ impl Kelvin {
     /// assert!(Kelvin::create(1000).is_some());
     /// assert!(Kelvin::create(8000).is_some());
     /// assert!(Kelvin::create(8001).is_none());
+    ///
+    /// let warm = Kelvin::try_from(2700).unwrap();
+    /// assert_eq!(warm.to_string(), "2700K");
+    /// assert!(warm < Kelvin::create(6500).unwrap());
     /// ```
     pub fn create(kelvin: u16) -> Option<Self> {
-        if (Self::MIN..=Self::MAX).contains(&kelvin) {
-            Some(Kelvin { kelvin })
-        } else {
-            None
+        Self::try_create(kelvin).ok()
+    }
+
+    /// Like [`Kelvin::create`], but says why the value was rejected.
+    pub fn try_create(kelvin: u16) -> Result<Self, RangeError> {
+        RangeError::check(kelvin, Self::MIN, Self::MAX).map(|kelvin| Kelvin { kelvin })
+    }
+
+    /// Create a Kelvin from a temperature in mireds (micro reciprocal
+    /// degrees), the unit used by Home Assistant and Zigbee.
+    ///
+    /// Returns `None` if value is outside the valid range (125-1000).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Kelvin;
+    ///
+    /// assert_eq!(Kelvin::from_mireds(370).unwrap().kelvin(), 2703);
+    /// assert_eq!(Kelvin::from_mireds(125).unwrap().kelvin(), 8000);
+    /// assert!(Kelvin::from_mireds(124).is_none());
+    /// assert!(Kelvin::from_mireds(1001).is_none());
+    /// ```
+    pub fn from_mireds(mireds: u16) -> Option<Self> {
+        Self::try_from_mireds(mireds).ok()
+    }
+
+    /// Like [`Kelvin::from_mireds`], but says why the value was rejected.
+    /// The range in the error is in mireds.
+    pub fn try_from_mireds(mireds: u16) -> Result<Self, RangeError> {
+        RangeError::check(mireds, Self::MIN_MIREDS, Self::MAX_MIREDS).map(|mireds: u16| Kelvin {
+            kelvin: reciprocal(mireds),
+        })
+    }
+
+    /// The temperature in mireds, rounded to the nearest whole mired.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::Kelvin;
+    ///
+    /// assert_eq!(Kelvin::create(2700).unwrap().to_mireds(), 370);
+    /// assert_eq!(Kelvin::create(6500).unwrap().to_mireds(), 154);
+    /// ```
+    pub fn to_mireds(&self) -> u16 {
+        reciprocal(self.kelvin)
+    }
+
+    /// The nearest temperature within `range`, such as the range a bulb
+    /// reports in its [`BulbType`](crate::BulbType).
+    ///
+    /// An unknown or inverted range, e.g. `0-0`, leaves the value as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{Kelvin, KelvinRange};
+    ///
+    /// let range = KelvinRange { min: 2700, max: 6500 };
+    /// assert_eq!(Kelvin::create(8000).unwrap().clamp_to(&range).kelvin(), 6500);
+    /// assert_eq!(Kelvin::create(4000).unwrap().clamp_to(&range).kelvin(), 4000);
+    /// ```
+    pub fn clamp_to(&self, range: &KelvinRange) -> Self {
+        if range.min == 0 || range.min > range.max {
+            return self.clone();
+        }
+        let min = range.min.clamp(Self::MIN, Self::MAX);
+        let max = range.max.clamp(Self::MIN, Self::MAX);
+        Kelvin {
+            kelvin: self.kelvin.clamp(min, max),
         }
     }
+
+    /// Estimate the temperature of a white given as RGB.
+    ///
+    /// Finds the temperature whose [`Color::from_kelvin`] rendering has the
+    /// closest channel ratios, to the nearest 10K. Only meaningful for
+    /// whitish colors; saturated colors still map to the nearest white.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{Color, Kelvin};
+    ///
+    /// let color = Color::from_kelvin(&Kelvin::create(3500).unwrap());
+    /// let estimate = Kelvin::approximate_from_color(&color).kelvin();
+    /// assert!((3400..=3600).contains(&estimate));
+    /// ```
+    pub fn approximate_from_color(color: &Color) -> Self {
+        let normalize = |c: &Color| {
+            let max = f64::from(c.red().max(c.green()).max(c.blue()).max(1));
+            [
+                f64::from(c.red()) / max,
+                f64::from(c.green()) / max,
+                f64::from(c.blue()) / max,
+            ]
+        };
+        let target = normalize(color);
+
+        let distance = |kelvin: u16| {
+            let candidate = normalize(&Color::from_kelvin(&Kelvin { kelvin }));
+            (0..3)
+                .map(|i| (candidate[i] - target[i]).powi(2))
+                .sum::<f64>()
+        };
+
+        let kelvin = (Self::MIN..=Self::MAX)
+            .step_by(10)
+            .min_by(|a, b| distance(*a).total_cmp(&distance(*b)))
+            .unwrap_or(Self::MIN);
+        Kelvin { kelvin }
+    }
+}
+
+/// Converts between kelvin and mireds, which are each other's reciprocal
+/// scaled by one million, rounding to the nearest integer.
+fn reciprocal(value: u16) -> u16 {
+    let value = u32::from(value.max(1));
+    ((1_000_000 + value / 2) / value).min(u32::from(u16::MAX)) as u16
+}
+
+impl TryFrom<u16> for Kelvin {
+    type Error = RangeError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        Self::try_create(value)
+    }
+}
+
+impl From<Kelvin> for u16 {
+    fn from(value: Kelvin) -> Self {
+        value.kelvin
+    }
+}
+
+impl fmt::Display for Kelvin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}K", self.kelvin)
+    }
 }
@@ -6,17 +6,19 @@ mod fan;
 mod hue_saturation;
 mod kelvin;
 mod power;
+mod range;
 mod ratio;
 mod scene;
 mod speed;
 mod white;
 
-pub use brightness::Brightness;
+pub use brightness::{Brightness, BrightnessOrOff};
 pub use color::{Color, ColorRGBW, ColorRGBWW};
 pub use fan::{FanDirection, FanMode, FanSpeed, FanState};
 pub use hue_saturation::HueSaturation;
 pub use kelvin::Kelvin;
 pub use power::PowerMode;
+pub use range::RangeError;
 pub use ratio::Ratio;
 pub use scene::SceneMode;
 pub use speed::Speed;
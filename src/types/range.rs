@@ -0,0 +1,37 @@
+//! Errors for values outside the range bulbs accept.
+
+use thiserror::Error;
+
+/// A value was outside the range a type accepts.
+///
+/// # Examples
+///
+/// ```
+/// use wiz_lights_rs::{Brightness, RangeError};
+///
+/// let err = Brightness::try_from(5).unwrap_err();
+/// assert_eq!(err, RangeError { got: 5, min: 10, max: 100 });
+/// assert_eq!(err.to_string(), "5 is out of range 10-100");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[error("{got} is out of range {min}-{max}")]
+pub struct RangeError {
+    pub got: u16,
+    pub min: u16,
+    pub max: u16,
+}
+
+impl RangeError {
+    pub(crate) fn check<T: Into<u16> + Copy>(got: T, min: T, max: T) -> Result<T, Self> {
+        let (value, low, high) = (got.into(), min.into(), max.into());
+        if (low..=high).contains(&value) {
+            Ok(got)
+        } else {
+            Err(RangeError {
+                got: value,
+                min: low,
+                max: high,
+            })
+        }
+    }
+}
@@ -1,7 +1,11 @@
 //! Ratio control for dual-head fixtures.
 
+use core::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use super::RangeError;
+
 /// Ratio for dual-head fixtures, controlling the balance between up and down lights.
 ///
 /// Valid values are 0 to 100, where:
@@ -11,7 +15,7 @@ use serde::{Deserialize, Serialize};
 ///
 /// This only applies to fixtures with dual-head lighting (e.g., floor lamps with
 /// both up-lighting and down-lighting capabilities).
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Ratio {
     pub(crate) value: u8,
 }
@@ -32,6 +36,16 @@ impl Ratio {
         self.value
     }
 
+    /// Share of the light sent through the up head, in percent.
+    pub fn up(&self) -> u8 {
+        self.value
+    }
+
+    /// Share of the light sent through the down head, in percent.
+    pub fn down(&self) -> u8 {
+        Self::MAX - self.value
+    }
+
     /// Create a new Ratio with the given value.
     ///
     /// Returns `None` if value exceeds 100.
@@ -47,10 +61,32 @@ impl Ratio {
     /// assert!(Ratio::create(101).is_none());
     /// ```
     pub fn create(value: u8) -> Option<Self> {
-        if value <= Self::MAX {
-            Some(Ratio { value })
-        } else {
-            None
-        }
+        Self::try_create(value).ok()
+    }
+
+    /// Like [`Ratio::create`], but says why the value was rejected.
+    pub fn try_create(value: u8) -> Result<Self, RangeError> {
+        RangeError::check(value, 0, Self::MAX).map(|value| Ratio { value })
+    }
+}
+
+impl TryFrom<u8> for Ratio {
+    type Error = RangeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::try_create(value)
+    }
+}
+
+impl From<Ratio> for u8 {
+    fn from(value: Ratio) -> Self {
+        value.value
+    }
+}
+
+impl fmt::Display for Ratio {
+    /// Formats the share sent through the up head, e.g. `30%`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.value)
     }
 }
@@ -1,9 +1,17 @@
 //! Preset lighting scenes.
 
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
+use crate::config::{BulbClass, BulbType};
+use crate::errors::Error;
+
 /// Preset lighting scenes with static colors or dynamic animations.
 #[derive(Debug, Serialize, Deserialize, Clone, EnumIter, PartialEq)]
 pub enum SceneMode {
@@ -50,7 +58,156 @@ impl SceneMode {
         SceneMode::iter().find(|scene| scene.clone() as u16 == value)
     }
 
+    /// Like [`SceneMode::create`], but fails with [`Error::UnknownScene`].
+    ///
+    /// Scene ids have gaps, so an unknown id is not reported as a
+    /// [`RangeError`](super::RangeError).
+    pub fn try_create(value: u16) -> Result<Self, Error> {
+        Self::create(value).ok_or_else(|| Error::UnknownScene(value.to_string()))
+    }
+
     pub fn id(&self) -> u16 {
         self.clone() as u16
     }
+
+    /// Every scene, in id order.
+    pub fn all() -> Vec<Self> {
+        SceneMode::iter().collect()
+    }
+
+    /// Human-readable name, as shown in the WiZ app.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SceneMode::Ocean => "Ocean",
+            SceneMode::Romance => "Romance",
+            SceneMode::Sunset => "Sunset",
+            SceneMode::Party => "Party",
+            SceneMode::Fireplace => "Fireplace",
+            SceneMode::Cozy => "Cozy",
+            SceneMode::Forest => "Forest",
+            SceneMode::PastelColors => "Pastel Colors",
+            SceneMode::WakeUp => "Wake Up",
+            SceneMode::Bedtime => "Bedtime",
+            SceneMode::WarmWhite => "Warm White",
+            SceneMode::Daylight => "Daylight",
+            SceneMode::CoolWhite => "Cool White",
+            SceneMode::NightLight => "Night Light",
+            SceneMode::Focus => "Focus",
+            SceneMode::Relax => "Relax",
+            SceneMode::TrueColors => "True Colors",
+            SceneMode::TvTime => "TV Time",
+            SceneMode::Plantgrowth => "Plant Growth",
+            SceneMode::Spring => "Spring",
+            SceneMode::Summer => "Summer",
+            SceneMode::Fall => "Fall",
+            SceneMode::Deepdive => "Deep Dive",
+            SceneMode::Jungle => "Jungle",
+            SceneMode::Mojito => "Mojito",
+            SceneMode::Club => "Club",
+            SceneMode::Christmas => "Christmas",
+            SceneMode::Halloween => "Halloween",
+            SceneMode::Candlelight => "Candlelight",
+            SceneMode::GoldenWhite => "Golden White",
+            SceneMode::Pulse => "Pulse",
+            SceneMode::Steampunk => "Steampunk",
+            SceneMode::Diwali => "Diwali",
+            SceneMode::Alarm => "Alarm",
+            SceneMode::WarmFeeling => "Warm Feeling",
+            SceneMode::Rhythm => "Rhythm",
+        }
+    }
+
+    /// Scenes the given bulb can show.
+    ///
+    /// Mirrors the scene lists of the WiZ app: color bulbs support every
+    /// scene, tunable- and dimmable-white bulbs only those built from white
+    /// light, and sockets none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{BulbType, SceneMode};
+    ///
+    /// let dw = BulbType::from_module_name("ESP01_SHDW_01", None);
+    /// let scenes = SceneMode::supported_by(&dw);
+    /// assert!(scenes.contains(&SceneMode::Bedtime));
+    /// assert!(!scenes.contains(&SceneMode::Ocean));
+    /// ```
+    pub fn supported_by(bulb_type: &BulbType) -> Vec<Self> {
+        SceneMode::iter()
+            .filter(|scene| scene.is_supported_by(bulb_type))
+            .collect()
+    }
+
+    /// Whether the given bulb can show this scene.
+    pub fn is_supported_by(&self, bulb_type: &BulbType) -> bool {
+        const TW_SCENES: [u16; 14] = [6, 9, 10, 11, 12, 13, 14, 15, 16, 18, 29, 30, 31, 32];
+        const DW_SCENES: [u16; 8] = [9, 10, 13, 14, 29, 30, 31, 32];
+
+        match bulb_type.bulb_class {
+            BulbClass::RGB => true,
+            BulbClass::TW => TW_SCENES.contains(&self.id()),
+            BulbClass::DW | BulbClass::FanDim => DW_SCENES.contains(&self.id()),
+            BulbClass::Socket => false,
+        }
+    }
+
+    /// Whether the scene animates. Only dynamic scenes honour
+    /// [`Speed`](crate::Speed).
+    pub fn is_dynamic(&self) -> bool {
+        !matches!(
+            self,
+            SceneMode::Cozy
+                | SceneMode::WarmWhite
+                | SceneMode::Daylight
+                | SceneMode::CoolWhite
+                | SceneMode::NightLight
+                | SceneMode::Focus
+                | SceneMode::Relax
+                | SceneMode::TrueColors
+                | SceneMode::TvTime
+                | SceneMode::Plantgrowth
+                | SceneMode::GoldenWhite
+        )
+    }
+}
+
+impl fmt::Display for SceneMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SceneMode {
+    type Err = Error;
+
+    /// Parse a scene from its name or numeric id.
+    ///
+    /// Names are matched ignoring case, spaces, `-` and `_`, so `"Wake Up"`,
+    /// `"wakeup"` and `"wake_up"` are all accepted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::SceneMode;
+    ///
+    /// assert_eq!("pastel colors".parse::<SceneMode>().unwrap(), SceneMode::PastelColors);
+    /// assert_eq!("TvTime".parse::<SceneMode>().unwrap(), SceneMode::TvTime);
+    /// assert_eq!("3".parse::<SceneMode>().unwrap(), SceneMode::Sunset);
+    /// assert!("disco".parse::<SceneMode>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let normalize = |name: &str| {
+            name.chars()
+                .filter(|c| !matches!(c, ' ' | '-' | '_'))
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        };
+        let wanted = normalize(s);
+
+        let by_id = s.trim().parse::<u16>().ok().and_then(SceneMode::create);
+        by_id
+            .or_else(|| SceneMode::iter().find(|scene| normalize(scene.name()) == wanted))
+            .ok_or_else(|| Error::UnknownScene(s.to_string()))
+    }
 }
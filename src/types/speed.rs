@@ -1,13 +1,17 @@
 //! Animation speed for dynamic scenes.
 
+use core::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use super::RangeError;
+
 /// Animation speed for dynamic scenes, with valid values from 20 to 200 percent.
 ///
 /// Speed only affects scenes with animation (like Party, Ocean, etc.).
 /// A value of 100 is the default speed; lower values slow the animation,
 /// higher values speed it up.
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Speed {
     pub(crate) value: u8,
 }
@@ -52,11 +56,20 @@ impl Speed {
     /// assert!(Speed::create(201).is_none());
     /// ```
     pub fn create(value: u8) -> Option<Self> {
-        if Self::is_valid(value) {
-            Some(Speed { value })
-        } else {
-            None
-        }
+        Self::try_create(value).ok()
+    }
+
+    /// Like [`Speed::create`], but says why the value was rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wiz_lights_rs::{RangeError, Speed};
+    ///
+    /// assert_eq!(Speed::try_create(250), Err(RangeError { got: 250, min: 20, max: 200 }));
+    /// ```
+    pub fn try_create(value: u8) -> Result<Self, RangeError> {
+        RangeError::check(value, Self::MIN, Self::MAX).map(|value| Speed { value })
     }
 
     /// Create a Speed, using default if value is invalid.
@@ -83,3 +96,23 @@ impl Speed {
         (Self::MIN..=Self::MAX).contains(&value)
     }
 }
+
+impl TryFrom<u8> for Speed {
+    type Error = RangeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::try_create(value)
+    }
+}
+
+impl From<Speed> for u8 {
+    fn from(value: Speed) -> Self {
+        value.value
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.value)
+    }
+}
@@ -1,13 +1,17 @@
 //! White LED channel control.
 
+use core::fmt;
+
 use serde::{Deserialize, Serialize};
 
+use super::RangeError;
+
 /// White LED intensity for cool or warm white channels, from 1 to 100 percent.
 ///
 /// Some Wiz bulbs have separate cool and warm white LED channels that can be
 /// controlled independently of the RGB LEDs. This provides more accurate
 /// white light reproduction than mixing RGB.
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct White {
     pub(crate) value: u8,
 }
@@ -41,10 +45,31 @@ impl White {
     /// assert!(White::create(101).is_none());
     /// ```
     pub fn create(value: u8) -> Option<Self> {
-        if (Self::MIN..=Self::MAX).contains(&value) {
-            Some(White { value })
-        } else {
-            None
-        }
+        Self::try_create(value).ok()
+    }
+
+    /// Like [`White::create`], but says why the value was rejected.
+    pub fn try_create(value: u8) -> Result<Self, RangeError> {
+        RangeError::check(value, Self::MIN, Self::MAX).map(|value| White { value })
+    }
+}
+
+impl TryFrom<u8> for White {
+    type Error = RangeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::try_create(value)
+    }
+}
+
+impl From<White> for u8 {
+    fn from(value: White) -> Self {
+        value.value
+    }
+}
+
+impl fmt::Display for White {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.value)
     }
 }